@@ -0,0 +1,261 @@
+//! Access Control Lists
+//!
+//! Per-user permissions (operation categories and key patterns) checked by
+//! the ACL middleware before requests reach the handlers.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+// == Permission ==
+/// Operation categories a user can be granted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    /// Reading keys and statistics
+    Read,
+    /// Creating, updating and deleting keys
+    Write,
+    /// `/admin` endpoints
+    Admin,
+    /// Publish/subscribe channels
+    PubSub,
+}
+
+impl Permission {
+    /// All permissions, as granted to the default user.
+    pub const ALL: [Permission; 4] = [
+        Permission::Read,
+        Permission::Write,
+        Permission::Admin,
+        Permission::PubSub,
+    ];
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "read" => Ok(Permission::Read),
+            "write" => Ok(Permission::Write),
+            "admin" => Ok(Permission::Admin),
+            "pubsub" => Ok(Permission::PubSub),
+            other => Err(format!(
+                "unknown permission '{}', expected read, write, admin or pubsub",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Permission::Read => "read",
+            Permission::Write => "write",
+            Permission::Admin => "admin",
+            Permission::PubSub => "pubsub",
+        };
+        f.write_str(name)
+    }
+}
+
+// == ACL User ==
+/// A named user with a token, granted permissions and allowed key patterns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AclUser {
+    /// User name reported by whoami and in logs
+    pub name: String,
+    /// Bearer token identifying the user
+    #[serde(skip_serializing)]
+    pub token: String,
+    /// Granted operation categories
+    pub permissions: Vec<Permission>,
+    /// Glob patterns (`*`, `?`) of keys the user may touch
+    pub key_patterns: Vec<String>,
+}
+
+impl AclUser {
+    /// Name of the implicit user with full access.
+    pub const DEFAULT_USER: &'static str = "default";
+
+    /// The unrestricted user, used when authenticating with a plain
+    /// `AUTH_TOKENS` token or when authentication is disabled.
+    pub fn default_user() -> Self {
+        Self {
+            name: Self::DEFAULT_USER.to_string(),
+            token: String::new(),
+            permissions: Permission::ALL.to_vec(),
+            key_patterns: vec!["*".to_string()],
+        }
+    }
+
+    /// Returns true if the user was granted `permission`.
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    /// Returns true if `key` matches one of the user's key patterns.
+    pub fn can_access_key(&self, key: &str) -> bool {
+        self.key_patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, key))
+    }
+
+    /// Parses a `;`-separated list of users.
+    ///
+    /// Each user is `name|token|permissions|patterns`, with comma-separated
+    /// permissions and key patterns, e.g.
+    /// `billing|s3cret|read,write|billing:*;ops|t0ken|read,admin|*`.
+    pub fn parse_list(value: &str) -> Result<Vec<AclUser>, String> {
+        value
+            .split(';')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for AclUser {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = spec.split('|').map(str::trim).collect();
+        let [name, token, permissions, patterns] = parts[..] else {
+            return Err(format!(
+                "invalid ACL user '{}', expected name|token|permissions|patterns",
+                spec
+            ));
+        };
+
+        if name.is_empty() || token.is_empty() {
+            return Err(format!("ACL user '{}' needs a name and a token", spec));
+        }
+
+        let permissions = permissions
+            .split(',')
+            .filter(|p| !p.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Permission>, String>>()?;
+
+        let key_patterns = patterns
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Ok(Self {
+            name: name.to_string(),
+            token: token.to_string(),
+            permissions,
+            key_patterns,
+        })
+    }
+}
+
+// == Identity ==
+/// The authenticated user attached to a request by the auth middleware.
+#[derive(Debug, Clone)]
+pub struct Identity(pub Arc<AclUser>);
+
+impl Identity {
+    /// Identity with full access.
+    pub fn default_user() -> Self {
+        Self(Arc::new(AclUser::default_user()))
+    }
+}
+
+// == Glob Matching ==
+/// Matches `text` against a glob pattern where `*` matches any sequence
+/// and `?` matches exactly one character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was matched against
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` absorb one more character
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("billing:*", "billing:invoice:42"));
+        assert!(!glob_match("billing:*", "users:1"));
+        assert!(glob_match("user:?", "user:7"));
+        assert!(!glob_match("user:?", "user:42"));
+        assert!(glob_match("*:cache:*", "svc:cache:item"));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("exact", "exactly"));
+    }
+
+    #[test]
+    fn test_parse_acl_user() {
+        let user: AclUser = "billing|s3cret|read,write|billing:*,invoices:*"
+            .parse()
+            .unwrap();
+
+        assert_eq!(user.name, "billing");
+        assert_eq!(user.token, "s3cret");
+        assert_eq!(user.permissions, vec![Permission::Read, Permission::Write]);
+        assert!(user.can_access_key("invoices:1"));
+        assert!(!user.can_access_key("users:1"));
+        assert!(!user.has_permission(Permission::Admin));
+    }
+
+    #[test]
+    fn test_parse_acl_list() {
+        let users = AclUser::parse_list("a|t1|read|*; b|t2|admin,pubsub|x:*;").unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(
+            users[1].permissions,
+            vec![Permission::Admin, Permission::PubSub]
+        );
+    }
+
+    #[test]
+    fn test_parse_acl_errors() {
+        assert!("missing|fields".parse::<AclUser>().is_err());
+        assert!("a|t|fly|*".parse::<AclUser>().is_err());
+        assert!("|t|read|*".parse::<AclUser>().is_err());
+    }
+
+    #[test]
+    fn test_default_user_allows_everything() {
+        let user = AclUser::default_user();
+        for permission in Permission::ALL {
+            assert!(user.has_permission(permission));
+        }
+        assert!(user.can_access_key("any:key"));
+    }
+}
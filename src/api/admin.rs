@@ -0,0 +1,437 @@
+//! Admin Handlers
+//!
+//! HTTP handlers for operational endpoints under `/admin`.
+
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+use tracing::info;
+
+use super::database::Database;
+use super::handlers::AppState;
+use crate::acl::{AclUser, Identity};
+use crate::cache::KeyInfo;
+use crate::config::ConfigFile;
+use crate::error::{CacheError, Result};
+use crate::models::{
+    BigKeysResponse, ConfigPatchRequest, ConfigResponse, ConfigRewriteResponse, CountQuery,
+    HotKeysResponse, SlowLogResetResponse, SlowLogResponse,
+};
+
+/// Handler for GET /admin/slowlog
+///
+/// Returns the most recent slow operations, newest first.
+pub async fn slowlog_get_handler(
+    State(state): State<AppState>,
+    Query(query): Query<CountQuery>,
+) -> Json<SlowLogResponse> {
+    let slowlog = state.slowlog.read().await;
+
+    Json(SlowLogResponse {
+        entries: slowlog.latest(query.count()),
+        len: slowlog.len(),
+        threshold_us: slowlog.threshold_us(),
+        max_len: slowlog.max_len(),
+    })
+}
+
+/// Handler for DELETE /admin/slowlog
+///
+/// Clears all slow log entries.
+pub async fn slowlog_reset_handler(State(state): State<AppState>) -> Json<SlowLogResetResponse> {
+    let cleared = state.slowlog.write().await.reset();
+
+    Json(SlowLogResetResponse::new(cleared))
+}
+
+/// Handler for GET /admin/hotkeys
+///
+/// Returns the most frequently accessed keys of the selected database.
+pub async fn hotkeys_handler(
+    db: Database,
+    Query(query): Query<CountQuery>,
+) -> Json<HotKeysResponse> {
+    let keys = db.store.read().await.hot_keys(query.count());
+
+    Json(HotKeysResponse { keys })
+}
+
+/// Handler for GET /admin/bigkeys
+///
+/// Returns the keys of the selected database holding the largest values.
+pub async fn bigkeys_handler(
+    db: Database,
+    Query(query): Query<CountQuery>,
+) -> Json<BigKeysResponse> {
+    let keys = db.store.read().await.big_keys(query.count());
+
+    Json(BigKeysResponse { keys })
+}
+
+/// Handler for GET /admin/key/:key
+///
+/// Returns a key's internal metadata without counting as a hit or
+/// changing its eviction order.
+pub async fn key_info_handler(db: Database, Path(key): Path<String>) -> Result<Json<KeyInfo>> {
+    let info = db.store.read().await.inspect(&key);
+
+    info.map(Json).ok_or(CacheError::NotFound(key))
+}
+
+/// Handler for GET /admin/acl/whoami
+///
+/// Returns the user the request authenticated as, with its permissions
+/// and key patterns. Requires no permission beyond authenticating.
+pub async fn whoami_handler(identity: Option<Extension<Identity>>) -> Result<Json<AclUser>> {
+    let Extension(Identity(user)) = identity
+        .ok_or_else(|| CacheError::Unauthorized("Missing bearer token".to_string()))?;
+
+    Ok(Json(user.as_ref().clone()))
+}
+
+/// Handler for GET /admin/config
+///
+/// Returns the current runtime settings along with the static ones the
+/// server was started with. `max_entries` and `default_ttl` are those of
+/// the selected database.
+pub async fn config_get_handler(
+    State(state): State<AppState>,
+    db: Database,
+) -> Json<ConfigResponse> {
+    Json(config_response(&state, &db).await)
+}
+
+/// Handler for PATCH /admin/config
+///
+/// Changes runtime settings without a restart. `max_entries` and
+/// `default_ttl` apply to the selected database. Shrinking `max_entries`
+/// evicts least recently used entries immediately, and a new
+/// `cleanup_interval` reschedules the cleanup task.
+pub async fn config_set_handler(
+    State(state): State<AppState>,
+    db: Database,
+    Json(req): Json<ConfigPatchRequest>,
+) -> Result<Json<ConfigResponse>> {
+    if let Some(error_msg) = req.validate() {
+        return Err(CacheError::InvalidRequest(error_msg));
+    }
+
+    if req.max_entries.is_some() || req.default_ttl.is_some() {
+        if let Some(follower) = state.leader_link() {
+            return Err(CacheError::ReadOnly(format!(
+                "max_entries and default_ttl follow the leader at {}",
+                follower.leader()
+            )));
+        }
+        let mut cache = db.store.write().await;
+        if let Some(max_entries) = req.max_entries {
+            let evicted = cache.set_max_entries(max_entries);
+            if evicted > 0 {
+                info!(
+                    "Evicted {} entries from database '{}' to fit max_entries {}",
+                    evicted, db.name, max_entries
+                );
+            }
+        }
+        if let Some(default_ttl) = req.default_ttl {
+            cache.set_default_ttl(default_ttl);
+        }
+    }
+
+    if req.slowlog_threshold_us.is_some() || req.slowlog_max_len.is_some() {
+        let mut slowlog = state.slowlog.write().await;
+        if let Some(threshold_us) = req.slowlog_threshold_us {
+            slowlog.set_threshold_us(threshold_us);
+        }
+        if let Some(max_len) = req.slowlog_max_len {
+            slowlog.set_max_len(max_len);
+        }
+    }
+
+    if let Some(cleanup_interval) = req.cleanup_interval {
+        state.cleanup_interval.send_replace(cleanup_interval);
+    }
+
+    let response = config_response(&state, &db).await;
+    info!(
+        "Runtime configuration of database '{}' changed: {:?}",
+        db.name, response.tunables
+    );
+    Ok(Json(response))
+}
+
+/// Handler for POST /admin/config/rewrite
+///
+/// Writes the current runtime settings back to the config file the server
+/// was started with, keeping its other settings and comments.
+pub async fn config_rewrite_handler(
+    State(state): State<AppState>,
+) -> Result<Json<ConfigRewriteResponse>> {
+    let Some(path) = state.config.config_file.clone() else {
+        return Err(CacheError::InvalidRequest(
+            "The server was not started with a config file".to_string(),
+        ));
+    };
+
+    let tunables = state.tunables().await;
+    let written = path.clone();
+    tokio::task::spawn_blocking(move || ConfigFile::rewrite(&written, &tunables))
+        .await
+        .map_err(|e| CacheError::Internal(e.to_string()))?
+        .map_err(|e| CacheError::Internal(e.to_string()))?;
+
+    info!("Configuration written to {}", path.display());
+    Ok(Json(ConfigRewriteResponse::new(path.display().to_string())))
+}
+
+/// Builds the config response from the live tunables of `db` and the
+/// startup config.
+async fn config_response(state: &AppState, db: &Database) -> ConfigResponse {
+    let config = &state.config;
+
+    ConfigResponse {
+        tunables: state.tunables_of(&db.store).await,
+        server_port: config.server_port,
+        bind_addresses: config
+            .bind_addresses
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect(),
+        unix_socket: config
+            .unix_socket
+            .as_ref()
+            .map(|path| path.display().to_string()),
+        log_format: config.log_format.to_string(),
+        auth_enabled: config.auth_enabled(),
+        tls_enabled: config.tls_settings().is_some(),
+        rate_limit: config.rate_limit.map(|rule| rule.to_string()),
+        replica_of: config.replica_of.clone(),
+        config_file: config
+            .config_file
+            .as_ref()
+            .map(|path| path.display().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{CacheStore, DatabaseConfig};
+    use crate::config::Config;
+    use crate::monitor::SlowLog;
+    use std::time::Duration;
+
+    /// State with a `staging` database, and that database selected
+    fn staging() -> (AppState, Database) {
+        let config = Config {
+            databases: DatabaseConfig::parse_list("staging").unwrap(),
+            ..Default::default()
+        };
+        let state = AppState::from_config(&config);
+        let db = Database {
+            name: "staging".to_string(),
+            store: state.databases.get("staging").unwrap().clone(),
+        };
+        (state, db)
+    }
+
+    #[tokio::test]
+    async fn test_slowlog_get_and_reset() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        *state.slowlog.write().await = SlowLog::new(0, 10);
+        for key in ["a", "b", "c"] {
+            state
+                .record_slow("get", Some(key), None, Duration::from_millis(1))
+                .await;
+        }
+
+        let query = CountQuery { count: Some(2) };
+        let response = slowlog_get_handler(State(state.clone()), Query(query)).await;
+        assert_eq!(response.entries.len(), 2);
+        assert_eq!(response.len, 3);
+        assert_eq!(response.entries[0].key.as_deref(), Some("c"));
+
+        let response = slowlog_reset_handler(State(state.clone())).await;
+        assert_eq!(response.cleared, 3);
+        assert!(state.slowlog.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hotkeys_and_bigkeys_handlers() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        {
+            let mut cache = state.cache.write().await;
+            cache.set("big".to_string(), "x".repeat(500), None).unwrap();
+            cache.set("hot".to_string(), "x".to_string(), None).unwrap();
+            cache.get("hot").unwrap();
+        }
+
+        let db = Database::default_for(&state);
+        let response = hotkeys_handler(db.clone(), Query(CountQuery::default())).await;
+        assert_eq!(response.keys[0].key, "hot");
+
+        let response = bigkeys_handler(db, Query(CountQuery { count: Some(1) })).await;
+        assert_eq!(response.keys.len(), 1);
+        assert_eq!(response.keys[0].key, "big");
+        assert_eq!(response.keys[0].size, 500);
+    }
+
+    #[tokio::test]
+    async fn test_hotkeys_and_bigkeys_of_named_database() {
+        let (state, db) = staging();
+        {
+            let mut store = db.store.write().await;
+            store
+                .set("staged".to_string(), "x".repeat(50), None)
+                .unwrap();
+            store.get("staged").unwrap();
+        }
+        state
+            .cache
+            .write()
+            .await
+            .set("default".to_string(), "x".repeat(500), None)
+            .unwrap();
+
+        let response = hotkeys_handler(db.clone(), Query(CountQuery::default())).await;
+        let keys: Vec<&str> = response.keys.iter().map(|key| key.key.as_str()).collect();
+        assert_eq!(keys, vec!["staged"]);
+
+        let response = bigkeys_handler(db, Query(CountQuery::default())).await;
+        let keys: Vec<&str> = response.keys.iter().map(|key| key.key.as_str()).collect();
+        assert_eq!(keys, vec!["staged"]);
+    }
+
+    #[tokio::test]
+    async fn test_key_info_handler() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        state
+            .cache
+            .write()
+            .await
+            .set("key".to_string(), "value".to_string(), None)
+            .unwrap();
+
+        let response = key_info_handler(Database::default_for(&state), Path("key".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.key, "key");
+        assert_eq!(response.access_count, 0);
+        assert_eq!(state.cache.read().await.stats().hits, 0);
+
+        let result =
+            key_info_handler(Database::default_for(&state), Path("missing".to_string())).await;
+        assert!(matches!(result, Err(CacheError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_key_info_of_named_database() {
+        let (state, db) = staging();
+        db.store
+            .write()
+            .await
+            .set("staged".to_string(), "value".to_string(), None)
+            .unwrap();
+
+        let response = key_info_handler(db.clone(), Path("staged".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.key, "staged");
+
+        let result =
+            key_info_handler(Database::default_for(&state), Path("staged".to_string())).await;
+        assert!(matches!(result, Err(CacheError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_whoami_handler() {
+        let response = whoami_handler(Some(Extension(Identity::default_user())))
+            .await
+            .unwrap();
+        assert_eq!(response.name, AclUser::DEFAULT_USER);
+
+        let result = whoami_handler(None).await;
+        assert!(matches!(result, Err(CacheError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_config_set_handler() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        {
+            let mut cache = state.cache.write().await;
+            for i in 0..5 {
+                cache
+                    .set(format!("key{}", i), "v".to_string(), None)
+                    .unwrap();
+            }
+        }
+        let mut interval = state.cleanup_interval.subscribe();
+
+        let patch = ConfigPatchRequest {
+            max_entries: Some(2),
+            cleanup_interval: Some(30),
+            slowlog_max_len: Some(0),
+            ..Default::default()
+        };
+        let response = config_set_handler(
+            State(state.clone()),
+            Database::default_for(&state),
+            Json(patch),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.tunables.max_entries, 2);
+        assert_eq!(response.tunables.default_ttl, 300);
+        assert_eq!(response.tunables.slowlog_max_len, 0);
+        assert_eq!(state.cache.read().await.len(), 2);
+        assert!(interval.has_changed().unwrap());
+        assert_eq!(*interval.borrow_and_update(), 30);
+
+        let db = Database::default_for(&state);
+        let result =
+            config_set_handler(State(state), db, Json(ConfigPatchRequest::default())).await;
+        assert!(matches!(result, Err(CacheError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_config_set_of_named_database() {
+        let (state, db) = staging();
+        {
+            let mut store = db.store.write().await;
+            for i in 0..5 {
+                store
+                    .set(format!("key{}", i), "v".to_string(), None)
+                    .unwrap();
+            }
+        }
+
+        let patch = ConfigPatchRequest {
+            max_entries: Some(2),
+            default_ttl: Some(60),
+            ..Default::default()
+        };
+        let response = config_set_handler(State(state.clone()), db.clone(), Json(patch))
+            .await
+            .unwrap();
+        assert_eq!(response.tunables.max_entries, 2);
+        assert_eq!(response.tunables.default_ttl, 60);
+        assert_eq!(db.store.read().await.len(), 2);
+
+        // The default database keeps its limits
+        let default = config_get_handler(State(state.clone()), Database::default_for(&state)).await;
+        assert_eq!(default.tunables.max_entries, state.config.max_entries);
+        assert_eq!(default.tunables.default_ttl, state.config.default_ttl);
+        let staged = config_get_handler(State(state), db).await;
+        assert_eq!(staged.tunables.max_entries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_config_rewrite_requires_config_file() {
+        let state = AppState::new(CacheStore::new(100, 300));
+
+        let result = config_rewrite_handler(State(state)).await;
+        assert!(matches!(result, Err(CacheError::InvalidRequest(_))));
+    }
+}
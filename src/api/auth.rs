@@ -0,0 +1,303 @@
+//! Authentication and Access Control Middleware
+//!
+//! `require_auth` resolves the bearer token to an `Identity`; `enforce_acl`
+//! then checks that identity's permissions and key patterns against the
+//! matched route.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{FromRequestParts, MatchedPath, Path, Request, State},
+    http::{header, request::Parts, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+use tracing::warn;
+
+use super::handlers::AppState;
+use crate::acl::{AclUser, Identity, Permission};
+use crate::cache::MAX_VALUE_SIZE;
+use crate::config::Config;
+use crate::error::{CacheError, Result};
+
+/// Path left open for load balancer health checks when configured
+const HEALTH_PATH: &str = "/health";
+
+/// Largest request body buffered to read the key of a write
+const MAX_BUFFERED_BODY: usize = 2 * MAX_VALUE_SIZE;
+
+/// Requires a configured bearer token on every request.
+///
+/// Passes everything through as the default user when authentication is
+/// disabled, and lets `/health` through unauthenticated if
+/// `auth_exempt_health` is set. Otherwise attaches the resolved `Identity`
+/// to the request.
+pub async fn require_auth(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response> {
+    let config = &state.config;
+    if !config.auth_enabled() {
+        req.extensions_mut().insert(Identity::default_user());
+        return Ok(next.run(req).await);
+    }
+    if config.auth_exempt_health && req.uri().path() == HEALTH_PATH {
+        return Ok(next.run(req).await);
+    }
+
+    let token = bearer_token(req.headers())
+        .ok_or_else(|| CacheError::Unauthorized("Missing bearer token".to_string()))?;
+
+    let Some(identity) = resolve_identity(config, token) else {
+        warn!(
+            "Rejected request with invalid token: {} {}",
+            req.method(),
+            req.uri().path()
+        );
+        return Err(CacheError::Unauthorized("Invalid bearer token".to_string()));
+    };
+
+    req.extensions_mut().insert(identity);
+    Ok(next.run(req).await)
+}
+
+/// Maps a token to its ACL user, or to the default user for `auth_tokens`.
+fn resolve_identity(config: &Config, token: &str) -> Option<Identity> {
+    let acl_user = config
+        .acl_users
+        .iter()
+        .find(|user| constant_time_eq(user.token.as_bytes(), token.as_bytes()));
+    if let Some(user) = acl_user {
+        return Some(Identity(Arc::new(user.clone())));
+    }
+
+    config
+        .auth_tokens
+        .iter()
+        .any(|expected| constant_time_eq(expected.as_bytes(), token.as_bytes()))
+        .then(Identity::default_user)
+}
+
+/// Permission needed for a route, by its matched path template.
+///
+/// Routes not listed here require `admin`, so new endpoints are closed
+/// until explicitly categorised.
+pub fn required_permission(route: &str) -> Option<Permission> {
+    match route {
+        "/health" | "/admin/acl/whoami" => None,
+        "/get/:key" | "/ttl/:key" | "/scan" | "/stats" | "/info" => Some(Permission::Read),
+        "/cluster/slots" | "/cluster/nodes" | "/cluster/keyslot/:key" => Some(Permission::Read),
+        "/db/:db/get/:key" | "/db/:db/ttl/:key" | "/db/:db/scan" | "/db/:db/stats" => {
+            Some(Permission::Read)
+        }
+        "/set" | "/del/:key" => Some(Permission::Write),
+        "/db/:db/set" | "/db/:db/del/:key" | "/db/:db/move/:key" => Some(Permission::Write),
+        _ => Some(Permission::Admin),
+    }
+}
+
+/// Only the key is needed from a SET body
+#[derive(Deserialize)]
+struct KeyOnly {
+    key: String,
+}
+
+/// Enforces the identity's permissions and key patterns.
+///
+/// Installed as a route layer so the matched route and its `:key`
+/// parameter are available. For SET the key is read from the JSON body,
+/// which is buffered and handed on unchanged; users whose patterns allow
+/// every key skip this.
+pub async fn enforce_acl(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    let (mut parts, body) = req.into_parts();
+
+    let route = MatchedPath::from_request_parts(&mut parts, &state)
+        .await
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let Some(permission) = required_permission(&route) else {
+        return Ok(next.run(Request::from_parts(parts, body)).await);
+    };
+
+    let Some(Identity(user)) = parts.extensions.get::<Identity>().cloned() else {
+        return Err(CacheError::Unauthorized("Missing bearer token".to_string()));
+    };
+
+    if !user.has_permission(permission) {
+        return Err(deny(&state, &user, &route, permission, None));
+    }
+
+    let unrestricted = user.key_patterns.iter().any(|p| p == "*");
+    let (key, body) = if unrestricted {
+        (None, body)
+    } else {
+        request_key(&mut parts, body, &route, &state).await?
+    };
+
+    if let Some(key) = key {
+        if !user.can_access_key(&key) {
+            return Err(deny(&state, &user, &route, permission, Some(&key)));
+        }
+    }
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// Returns the key a request operates on: the `:key` path parameter, or
+/// for SET the `key` of the JSON body. The body is buffered in that case,
+/// so it is returned to be handed on unchanged.
+pub(super) async fn request_key(
+    parts: &mut Parts,
+    body: Body,
+    route: &str,
+    state: &AppState,
+) -> Result<(Option<String>, Body)> {
+    match Path::<HashMap<String, String>>::from_request_parts(parts, state).await {
+        Ok(Path(mut params)) if params.contains_key("key") => Ok((params.remove("key"), body)),
+        _ if route == "/set" || route == "/db/:db/set" => {
+            let bytes = to_bytes(body, MAX_BUFFERED_BODY)
+                .await
+                .map_err(|_| CacheError::InvalidRequest("Request body too large".to_string()))?;
+            let key = serde_json::from_slice::<KeyOnly>(&bytes)
+                .ok()
+                .map(|k| k.key);
+            Ok((key, Body::from(bytes)))
+        }
+        _ => Ok((None, body)),
+    }
+}
+
+/// Logs and counts an ACL denial, returning the error to send.
+fn deny(
+    state: &AppState,
+    user: &AclUser,
+    route: &str,
+    permission: Permission,
+    key: Option<&str>,
+) -> CacheError {
+    state.metrics.record_acl_denial();
+    match key {
+        Some(key) => {
+            warn!(
+                "ACL denied: user '{}' may not access key '{}' on {}",
+                user.name, key, route
+            );
+            CacheError::Forbidden(format!("User '{}' may not access key '{}'", user.name, key))
+        }
+        None => {
+            warn!(
+                "ACL denied: user '{}' lacks '{}' permission for {}",
+                user.name, permission, route
+            );
+            CacheError::Forbidden(format!(
+                "User '{}' lacks '{}' permission",
+                user.name, permission
+            ))
+        }
+    }
+}
+
+/// Extracts the token from an `Authorization: Bearer <token>` header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+    let token = token.trim();
+    (!token.is_empty()).then_some(token)
+}
+
+/// Compares two byte strings in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers_with(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_bearer_token_parsing() {
+        assert_eq!(bearer_token(&headers_with("Bearer abc123")), Some("abc123"));
+        assert_eq!(
+            bearer_token(&headers_with("bearer  abc123 ")),
+            Some("abc123")
+        );
+        assert_eq!(bearer_token(&headers_with("Basic dXNlcjpwYXNz")), None);
+        assert_eq!(bearer_token(&headers_with("Bearer ")), None);
+        assert_eq!(bearer_token(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_required_permission() {
+        assert_eq!(required_permission("/health"), None);
+        assert_eq!(required_permission("/admin/acl/whoami"), None);
+        assert_eq!(required_permission("/get/:key"), Some(Permission::Read));
+        assert_eq!(required_permission("/set"), Some(Permission::Write));
+        assert_eq!(required_permission("/del/:key"), Some(Permission::Write));
+        assert_eq!(required_permission("/scan"), Some(Permission::Read));
+        assert_eq!(
+            required_permission("/db/:db/get/:key"),
+            Some(Permission::Read)
+        );
+        assert_eq!(
+            required_permission("/db/:db/move/:key"),
+            Some(Permission::Write)
+        );
+        assert_eq!(
+            required_permission("/db/:db/flush"),
+            Some(Permission::Admin)
+        );
+        assert_eq!(
+            required_permission("/admin/slowlog"),
+            Some(Permission::Admin)
+        );
+        assert_eq!(
+            required_permission("/something/new"),
+            Some(Permission::Admin)
+        );
+    }
+
+    #[test]
+    fn test_resolve_identity() {
+        let config = Config {
+            auth_tokens: vec!["plain".to_string()],
+            acl_users: vec!["reader|r-token|read|public:*".parse().unwrap()],
+            ..Config::default()
+        };
+
+        let Identity(user) = resolve_identity(&config, "r-token").unwrap();
+        assert_eq!(user.name, "reader");
+
+        let Identity(user) = resolve_identity(&config, "plain").unwrap();
+        assert_eq!(user.name, AclUser::DEFAULT_USER);
+
+        assert!(resolve_identity(&config, "unknown").is_none());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+}
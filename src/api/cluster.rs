@@ -0,0 +1,419 @@
+//! Cluster Handlers
+//!
+//! Slot table and node table lookups for clients that route keys to the
+//! serving node themselves, and the admin endpoints migrating slots
+//! between nodes.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use tracing::info;
+
+use super::handlers::AppState;
+use crate::cache::{current_timestamp_ms, CacheEntry};
+use crate::cluster::{
+    key_slot, start_migration, Cluster, MigrationStatus, SlotState, DEFAULT_MIGRATION_BATCH_SIZE,
+};
+use crate::error::{CacheError, Result};
+use crate::models::{
+    ClusterNodeInfo, ClusterNodesResponse, ClusterSlotRange, ClusterSlotsResponse, KeyPath,
+    KeySlotResponse, MigrateRequest, MigrationsResponse, RestoreRequest, RestoreResponse,
+    SetSlotRequest, SetSlotResponse, SetSlotState,
+};
+
+/// Returns the slot table, or an error when cluster mode is off.
+fn cluster(state: &AppState) -> Result<&Cluster> {
+    state
+        .cluster
+        .as_deref()
+        .ok_or_else(|| CacheError::InvalidRequest("Cluster mode is disabled".to_string()))
+}
+
+/// Handler for GET /cluster/slots
+///
+/// Lists the served slot ranges and the node serving each (CLUSTER SLOTS).
+pub async fn cluster_slots_handler(
+    State(state): State<AppState>,
+) -> Result<Json<ClusterSlotsResponse>> {
+    let slots = cluster(&state)?
+        .slot_ranges()
+        .into_iter()
+        .map(|(range, node)| ClusterSlotRange {
+            start: range.start,
+            end: range.end,
+            node: node.id.clone(),
+            address: node.address.clone(),
+        })
+        .collect();
+
+    Ok(Json(ClusterSlotsResponse { slots }))
+}
+
+/// Handler for GET /cluster/nodes
+///
+/// Lists every known node with its address, slot ranges, role and health as
+/// seen by this node (CLUSTER NODES), and the recent membership changes.
+pub async fn cluster_nodes_handler(
+    State(state): State<AppState>,
+) -> Result<Json<ClusterNodesResponse>> {
+    let cluster = cluster(&state)?;
+    let nodes = cluster
+        .node_statuses()
+        .into_iter()
+        .map(|status| ClusterNodeInfo {
+            myself: status.node.id == cluster.myself_id(),
+            role: if status.leader.is_some() {
+                "follower"
+            } else {
+                "leader"
+            },
+            slots: status.node.slots.iter().map(ToString::to_string).collect(),
+            id: status.node.id,
+            address: status.node.address,
+            bus_address: status.bus_address,
+            leader: status.leader,
+            health: status.health,
+            config_epoch: status.config_epoch,
+            last_seen_ms: status.last_seen_ms,
+            fail_reports: status.fail_reports,
+        })
+        .collect();
+
+    Ok(Json(ClusterNodesResponse {
+        nodes,
+        current_epoch: cluster.current_epoch(),
+        migrating: cluster.migrating(),
+        importing: cluster.importing(),
+        events: cluster.events(),
+    }))
+}
+
+/// Handler for GET /cluster/keyslot/:key
+///
+/// Returns the hash slot of a key (CLUSTER KEYSLOT) and, in cluster mode,
+/// the node serving it.
+pub async fn cluster_keyslot_handler(
+    State(state): State<AppState>,
+    Path(KeyPath { key }): Path<KeyPath>,
+) -> Json<KeySlotResponse> {
+    let slot = key_slot(&key);
+    let owner = state
+        .cluster
+        .as_deref()
+        .and_then(|cluster| cluster.owner(slot));
+
+    Json(KeySlotResponse {
+        node: owner.as_ref().map(|node| node.id.clone()),
+        address: owner.map(|node| node.address),
+        key,
+        slot,
+    })
+}
+
+/// Handler for POST /admin/cluster/setslot
+///
+/// Marks slots as migrating to or importing from another node, assigns
+/// them to a node, or cancels their migration (CLUSTER SETSLOT).
+pub async fn cluster_setslot_handler(
+    State(state): State<AppState>,
+    Json(request): Json<SetSlotRequest>,
+) -> Result<Json<SetSlotResponse>> {
+    let cluster = cluster(&state)?;
+    if request.slots.is_empty() {
+        return Err(CacheError::InvalidRequest("No slots given".to_string()));
+    }
+    let node = || {
+        request.node.clone().ok_or_else(|| {
+            CacheError::InvalidRequest(format!("State {:?} needs a node", request.state))
+        })
+    };
+    let slot_state = match request.state {
+        SetSlotState::Migrating => SlotState::Migrating(node()?),
+        SetSlotState::Importing => SlotState::Importing(node()?),
+        SetSlotState::Node => SlotState::Node(node()?),
+        SetSlotState::Stable => SlotState::Stable,
+    };
+    cluster
+        .set_slots(&request.slots, &slot_state)
+        .map_err(CacheError::InvalidRequest)?;
+
+    let ranges: Vec<String> = request.slots.iter().map(ToString::to_string).collect();
+    info!("Slots {} set to {:?}", ranges.join(","), slot_state);
+    Ok(Json(SetSlotResponse {
+        message: format!("Slots {} set to {:?}", ranges.join(","), slot_state),
+        slots: request.slots.iter().map(|range| range.count()).sum(),
+    }))
+}
+
+/// Handler for POST /admin/cluster/restore
+///
+/// Stores keys migrated from another node with their remaining TTL, and
+/// deletes keys removed there after they were sent, unless they were
+/// written here since. Every key must be in a slot this node serves or is
+/// importing.
+pub async fn cluster_restore_handler(
+    State(state): State<AppState>,
+    Json(request): Json<RestoreRequest>,
+) -> Result<Json<RestoreResponse>> {
+    let cluster = cluster(&state)?;
+    let keys = request
+        .entries
+        .iter()
+        .map(|entry| (&entry.db, &entry.key))
+        .chain(request.deleted.iter().map(|key| (&key.db, &key.key)));
+    for (db, key) in keys {
+        if state.databases.get(db).is_none() {
+            return Err(CacheError::InvalidRequest(format!(
+                "Unknown database '{}'",
+                db
+            )));
+        }
+        let slot = key_slot(key);
+        if !cluster.accepts(slot) {
+            return Err(CacheError::InvalidRequest(format!(
+                "Slot {} of key '{}' is neither served nor imported here",
+                slot, key
+            )));
+        }
+    }
+
+    let mut restored = 0;
+    for entry in request.entries {
+        let expires_at = entry
+            .ttl_ms
+            .map(|ttl| current_timestamp_ms().saturating_add(ttl));
+        let store = &state.databases.get(&entry.db).expect("checked above");
+        let mut store = store.write().await;
+        if request.keep_existing && store.contains_key(&entry.key) {
+            continue;
+        }
+        store.insert_entry(
+            entry.key,
+            CacheEntry {
+                grace_ms: entry.grace_ms,
+                compute_ms: entry.compute_ms,
+                ..CacheEntry::with_expires_at(entry.value, expires_at)
+            },
+        )?;
+        restored += 1;
+    }
+    let mut deleted = 0;
+    for key in request.deleted {
+        let store = &state.databases.get(&key.db).expect("checked above");
+        let unchanged = |entry: &CacheEntry| key.value.as_ref().is_none_or(|v| *v == entry.value);
+        if store.write().await.take_if(&key.key, unchanged).is_some() {
+            deleted += 1;
+        }
+    }
+
+    Ok(Json(RestoreResponse { restored, deleted }))
+}
+
+/// Handler for POST /admin/cluster/migrate
+///
+/// Starts moving slots served by this node to another node in the
+/// background and returns 202 with the migration's status.
+pub async fn cluster_migrate_handler(
+    State(state): State<AppState>,
+    Json(request): Json<MigrateRequest>,
+) -> Result<(StatusCode, Json<MigrationStatus>)> {
+    let cluster = state
+        .cluster
+        .clone()
+        .ok_or_else(|| CacheError::InvalidRequest("Cluster mode is disabled".to_string()))?;
+    let status = start_migration(
+        cluster,
+        state.databases.clone(),
+        &request.target,
+        request.slots,
+        request.batch_size.unwrap_or(DEFAULT_MIGRATION_BATCH_SIZE),
+        state.config.cluster_auth_token.clone(),
+        state.cluster_tls.clone(),
+    )
+    .map_err(CacheError::InvalidRequest)?;
+
+    Ok((StatusCode::ACCEPTED, Json(status)))
+}
+
+/// Handler for GET /admin/cluster/migrations
+///
+/// Reports the progress of the running migration and the recent ones.
+pub async fn cluster_migrations_handler(
+    State(state): State<AppState>,
+) -> Result<Json<MigrationsResponse>> {
+    Ok(Json(MigrationsResponse {
+        migrations: cluster(&state)?.migrations().list(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{CacheStore, DEFAULT_DATABASE};
+    use crate::cluster::{ClusterNode, NodeHealth, SlotRange};
+    use crate::models::{RestoreEntry, RestoreKey};
+    use std::sync::Arc;
+
+    fn cluster_state() -> AppState {
+        let nodes =
+            ClusterNode::parse_list("a|127.0.0.1:7000|0-8191;b|127.0.0.1:7001|8192-16383").unwrap();
+        AppState {
+            cluster: Some(Arc::new(Cluster::new("a", nodes).unwrap())),
+            ..AppState::new(CacheStore::new(100, 300))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cluster_slots_and_nodes() {
+        let state = cluster_state();
+
+        let slots = cluster_slots_handler(State(state.clone())).await.unwrap();
+        assert_eq!(slots.slots.len(), 2);
+        assert_eq!(slots.slots[1].start, 8192);
+        assert_eq!(slots.slots[1].end, 16383);
+        assert_eq!(slots.slots[1].address, "127.0.0.1:7001");
+
+        let nodes = cluster_nodes_handler(State(state)).await.unwrap();
+        assert!(nodes.nodes[0].myself);
+        assert!(!nodes.nodes[1].myself);
+        assert_eq!(nodes.nodes[0].slots, vec!["0-8191"]);
+        assert_eq!(nodes.nodes[0].role, "leader");
+        assert_eq!(nodes.nodes[0].last_seen_ms, None);
+        assert_eq!(nodes.nodes[1].health, NodeHealth::Online);
+        assert_eq!(nodes.nodes[1].bus_address, "127.0.0.1:17001");
+        assert_eq!(nodes.current_epoch, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_keyslot() {
+        let response =
+            cluster_keyslot_handler(State(cluster_state()), Path(KeyPath::new("foo"))).await;
+        assert_eq!(response.slot, 12182);
+        assert_eq!(response.node.as_deref(), Some("b"));
+
+        // Without cluster mode only the slot is known
+        let state = AppState::new(CacheStore::new(100, 300));
+        let response =
+            cluster_keyslot_handler(State(state.clone()), Path(KeyPath::new("foo"))).await;
+        assert_eq!(response.slot, 12182);
+        assert!(response.node.is_none());
+        assert!(matches!(
+            cluster_slots_handler(State(state)).await,
+            Err(CacheError::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_setslot_and_restore() {
+        let state = cluster_state();
+        let slots = SlotRange::parse_list("12182").unwrap();
+
+        // "foo" (slot 12182) is served by b, so it cannot be restored here
+        let restore = || RestoreRequest {
+            entries: vec![RestoreEntry {
+                db: DEFAULT_DATABASE.to_string(),
+                key: "foo".to_string(),
+                value: "bar".to_string(),
+                ttl_ms: Some(60_000),
+                grace_ms: None,
+                compute_ms: None,
+            }],
+            keep_existing: false,
+            deleted: Vec::new(),
+        };
+        assert!(
+            cluster_restore_handler(State(state.clone()), Json(restore()))
+                .await
+                .is_err()
+        );
+
+        let request = SetSlotRequest {
+            slots,
+            state: SetSlotState::Importing,
+            node: Some("b".to_string()),
+        };
+        let response = cluster_setslot_handler(State(state.clone()), Json(request))
+            .await
+            .unwrap();
+        assert_eq!(response.slots, 1);
+        let response = cluster_restore_handler(State(state.clone()), Json(restore()))
+            .await
+            .unwrap();
+        assert_eq!(response.restored, 1);
+        let ttl = state.cache.read().await.inspect("foo").unwrap().ttl_ms;
+        assert!(ttl.is_some_and(|ttl| ttl <= 60_000));
+
+        let nodes = cluster_nodes_handler(State(state.clone())).await.unwrap();
+        assert_eq!(nodes.importing.get(&12182).map(String::as_str), Some("b"));
+
+        // Keys written here since they were sent are kept
+        let restore_existing = RestoreRequest {
+            keep_existing: true,
+            ..restore()
+        };
+        state
+            .cache
+            .write()
+            .await
+            .set("foo".to_string(), "newer".to_string(), None)
+            .unwrap();
+        let response = cluster_restore_handler(State(state.clone()), Json(restore_existing))
+            .await
+            .unwrap();
+        assert_eq!(response.restored, 0);
+        let deleted = |value: &str| RestoreRequest {
+            deleted: vec![RestoreKey {
+                db: DEFAULT_DATABASE.to_string(),
+                key: "foo".to_string(),
+                value: Some(value.to_string()),
+            }],
+            ..RestoreRequest::default()
+        };
+        let response = cluster_restore_handler(State(state.clone()), Json(deleted("bar")))
+            .await
+            .unwrap();
+        assert_eq!(response.deleted, 0);
+        assert!(state.cache.read().await.contains_key("foo"));
+
+        let response = cluster_restore_handler(State(state.clone()), Json(deleted("newer")))
+            .await
+            .unwrap();
+        assert_eq!(response.deleted, 1);
+        assert!(!state.cache.read().await.contains_key("foo"));
+
+        // A TTL beyond the clock's range saturates instead of overflowing
+        let mut forever = restore();
+        forever.entries[0].ttl_ms = Some(u64::MAX);
+        let response = cluster_restore_handler(State(state.clone()), Json(forever))
+            .await
+            .unwrap();
+        assert_eq!(response.restored, 1);
+        let entry = state.cache.read().await.entry("foo").cloned().unwrap();
+        assert_eq!(entry.expires_at, Some(u64::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_validates_request() {
+        let state = cluster_state();
+        let migrate = |target: &str, slots: &str| MigrateRequest {
+            target: target.to_string(),
+            slots: SlotRange::parse_list(slots).unwrap(),
+            batch_size: None,
+        };
+
+        for (target, slots) in [("b", "8192"), ("a", "0"), ("z", "0"), ("b", "")] {
+            let result =
+                cluster_migrate_handler(State(state.clone()), Json(migrate(target, slots))).await;
+            assert!(
+                matches!(result, Err(CacheError::InvalidRequest(_))),
+                "{} {}",
+                target,
+                slots
+            );
+        }
+        let migrations = cluster_migrations_handler(State(state)).await.unwrap();
+        assert!(migrations.migrations.is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! Database Selection and Handlers
+//!
+//! Requests address a database with a `/db/:db` path prefix or the
+//! `X-Database` header, falling back to the default database. This module
+//! provides the extractor resolving it and the FLUSHDB, FLUSHALL, MOVE and
+//! SWAPDB handlers.
+
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query, State},
+    http::request::Parts,
+    Json,
+};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use super::handlers::AppState;
+use crate::cache::{CacheStore, DEFAULT_DATABASE};
+use crate::error::{CacheError, Result};
+use crate::models::{
+    DatabaseInfo, DatabasesResponse, FlushQuery, FlushResponse, KeyPath, MoveRequest, MoveResponse,
+    SwapDbRequest, SwapDbResponse,
+};
+
+/// Header selecting the database for routes without a `/db/:db` prefix
+pub const DATABASE_HEADER: &str = "x-database";
+
+// == Database Extractor ==
+/// The database a request operates on.
+#[derive(Debug, Clone)]
+pub struct Database {
+    /// Database name
+    pub name: String,
+    /// The database's store
+    pub store: Arc<RwLock<CacheStore>>,
+}
+
+impl Database {
+    /// The default database of `state`.
+    pub fn default_for(state: &AppState) -> Self {
+        Self {
+            name: DEFAULT_DATABASE.to_string(),
+            store: state.databases.default_store().clone(),
+        }
+    }
+}
+
+/// Only the `:db` segment is needed from the path
+#[derive(Deserialize)]
+struct DatabasePath {
+    #[serde(default)]
+    db: Option<String>,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Database {
+    type Rejection = CacheError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self> {
+        let from_path = Path::<DatabasePath>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .and_then(|Path(path)| path.db);
+        let name = match from_path {
+            Some(name) => name,
+            None => match parts.headers.get(DATABASE_HEADER) {
+                Some(value) => value
+                    .to_str()
+                    .map_err(|_| {
+                        CacheError::InvalidRequest("Invalid X-Database header".to_string())
+                    })?
+                    .to_string(),
+                None => return Ok(Self::default_for(state)),
+            },
+        };
+
+        match state.databases.get(&name) {
+            Some(store) => Ok(Self {
+                store: store.clone(),
+                name,
+            }),
+            None => Err(CacheError::InvalidRequest(format!(
+                "Unknown database '{}'",
+                name
+            ))),
+        }
+    }
+}
+
+// == Handlers ==
+/// Handler for GET /admin/databases
+///
+/// Lists every database with its size, limits and hit statistics.
+pub async fn databases_handler(State(state): State<AppState>) -> Json<DatabasesResponse> {
+    let mut databases = Vec::with_capacity(state.databases.len());
+    for (name, store) in state.databases.iter() {
+        let store = store.read().await;
+        let stats = store.stats();
+        databases.push(DatabaseInfo {
+            name: name.to_string(),
+            keys: store.len(),
+            max_entries: store.max_entries(),
+            default_ttl: store.default_ttl(),
+            hits: stats.hits,
+            misses: stats.misses,
+            evictions: stats.evictions,
+        });
+    }
+
+    Json(DatabasesResponse { databases })
+}
+
+/// Handler for POST /db/:db/flush
+///
+/// Removes every key of one database (FLUSHDB).
+pub async fn flushdb_handler(
+    State(state): State<AppState>,
+    db: Database,
+    Query(query): Query<FlushQuery>,
+) -> Result<Json<FlushResponse>> {
+    let removed = state.databases.flush(&db.name, query.reset_stats).await?;
+
+    info!(
+        "Flushed database '{}': {} entries removed",
+        db.name, removed
+    );
+    Ok(Json(FlushResponse::new(removed)))
+}
+
+/// Handler for POST /admin/flush
+///
+/// Removes every key of every database (FLUSHALL).
+pub async fn flushall_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FlushQuery>,
+) -> Json<FlushResponse> {
+    let removed = state.databases.flush_all(query.reset_stats).await;
+
+    info!("Flushed all databases: {} entries removed", removed);
+    Json(FlushResponse::new(removed))
+}
+
+/// Handler for POST /db/:db/move/:key
+///
+/// Moves a key with its TTL to another database (MOVE). Nothing is moved
+/// if the target already holds the key.
+pub async fn move_handler(
+    State(state): State<AppState>,
+    db: Database,
+    Path(path): Path<KeyPath>,
+    Json(req): Json<MoveRequest>,
+) -> Result<Json<MoveResponse>> {
+    let moved = state
+        .databases
+        .move_key(&db.name, &req.db, &path.key)
+        .await?;
+
+    Ok(Json(MoveResponse {
+        key: path.key,
+        db: req.db,
+        moved,
+    }))
+}
+
+/// Handler for POST /admin/swapdb
+///
+/// Swaps the contents of two databases (SWAPDB). Each keeps its own
+/// capacity and default TTL.
+pub async fn swapdb_handler(
+    State(state): State<AppState>,
+    Json(req): Json<SwapDbRequest>,
+) -> Result<Json<SwapDbResponse>> {
+    let evicted = state.databases.swap(&req.db1, &req.db2).await?;
+
+    info!(
+        "Swapped databases '{}' and '{}' ({} entries evicted)",
+        req.db1, req.db2, evicted
+    );
+    Ok(Json(SwapDbResponse {
+        message: format!("Swapped databases '{}' and '{}'", req.db1, req.db2),
+        evicted,
+    }))
+}
@@ -5,19 +5,30 @@
 //! # Requirements
 //! - Validates: Requirements 4.2, 4.3, 4.4, 4.5, 4.6
 
+use rustls::ClientConfig;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, RwLock};
 
 use axum::{
-    extract::{Path, State},
-    Json,
+    extract::{Path, Query, State},
+    Extension, Json,
 };
 
-use crate::cache::CacheStore;
+use super::database::Database;
+use crate::acl::Identity;
+use crate::cache::{CacheStore, CachedValue, Databases, SetOptions};
+use crate::cluster::Cluster;
+use crate::config::{Config, Tunables};
 use crate::error::{CacheError, Result};
 use crate::models::{
-    DeleteResponse, GetResponse, HealthResponse, SetRequest, SetResponse, StatsResponse,
+    DeleteResponse, GetResponse, HealthResponse, KeyPath, ScanQuery, ScanResponse, SetRequest,
+    SetResponse, StatsResponse, TtlResponse,
 };
+use crate::monitor::{ServerMetrics, SlowLog};
+use crate::origin::Origin;
+use crate::ratelimit::RateLimiter;
+use crate::replication::{FollowerStatus, ReplicationLog};
 
 /// Application state shared across all handlers.
 ///
@@ -27,24 +38,159 @@ use crate::models::{
 /// - Validates: Requirements 5.1, 5.2, 5.3
 #[derive(Clone)]
 pub struct AppState {
-    /// Thread-safe cache store
+    /// Thread-safe cache store of the default database
     pub cache: Arc<RwLock<CacheStore>>,
+    /// All databases, including the default one
+    pub databases: Arc<Databases>,
+    /// Log of operations exceeding the latency threshold
+    pub slowlog: Arc<RwLock<SlowLog>>,
+    /// Configuration the server was started with
+    pub config: Arc<Config>,
+    /// Uptime and request counters
+    pub metrics: Arc<ServerMetrics>,
+    /// Per-client request rate limits
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Current cleanup interval in seconds, watched by the cleanup task
+    pub cleanup_interval: Arc<watch::Sender<u64>>,
+    /// Write commands streamed to followers
+    pub replication: Arc<ReplicationLog>,
+    /// Link to the leader when this server is a follower
+    pub follower: Option<Arc<FollowerStatus>>,
+    /// Slot table when cluster mode is enabled
+    pub cluster: Option<Arc<Cluster>>,
+    /// TLS settings for reaching other nodes when they serve TLS
+    pub cluster_tls: Option<Arc<ClientConfig>>,
+    /// Origin fetched on a miss when origin mode is enabled
+    pub origin: Option<Arc<Origin>>,
 }
 
 impl AppState {
     /// Creates a new AppState with the given cache store.
     pub fn new(cache: CacheStore) -> Self {
+        let cache = Arc::new(RwLock::new(cache));
         Self {
-            cache: Arc::new(RwLock::new(cache)),
+            databases: Arc::new(Databases::new(cache.clone())),
+            cache,
+            slowlog: Arc::new(RwLock::new(SlowLog::default())),
+            config: Arc::new(Config::default()),
+            metrics: Arc::new(ServerMetrics::new()),
+            rate_limiter: Arc::new(RateLimiter::disabled()),
+            cleanup_interval: Arc::new(watch::channel(Config::default().cleanup_interval).0),
+            replication: Arc::new(ReplicationLog::new(Config::default().repl_backlog_size)),
+            follower: None,
+            cluster: None,
+            cluster_tls: None,
+            origin: None,
         }
     }
 
     /// Creates a new AppState from configuration.
     ///
-    /// Initializes the cache store with parameters from the Config.
-    pub fn from_config(config: &crate::config::Config) -> Self {
-        let cache = CacheStore::new(config.max_entries, config.default_ttl);
-        Self::new(cache)
+    /// Initializes the cache store with parameters from the Config, and one
+    /// more store per configured database.
+    pub fn from_config(config: &Config) -> Self {
+        // Cluster nodes index keys by slot so slots can be migrated
+        let new_store = |max_entries, default_ttl| {
+            let mut store = CacheStore::new(max_entries, default_ttl);
+            if config.cluster_node_id.is_some() {
+                store.enable_slot_index();
+            }
+            store
+        };
+        let state = Self::new(new_store(config.max_entries, config.default_ttl));
+        let mut databases = Databases::new(state.cache.clone());
+        for database in &config.databases {
+            let store = new_store(
+                database.max_entries.unwrap_or(config.max_entries),
+                database.default_ttl.unwrap_or(config.default_ttl),
+            );
+            databases
+                .add(&database.name, store)
+                .expect("database names are unique in a validated config");
+        }
+
+        Self {
+            databases: Arc::new(databases),
+            slowlog: Arc::new(RwLock::new(SlowLog::new(
+                config.slowlog_threshold_us,
+                config.slowlog_max_len,
+            ))),
+            config: Arc::new(config.clone()),
+            rate_limiter: Arc::new(RateLimiter::new(
+                config.rate_limit,
+                config.route_rate_limits.clone(),
+            )),
+            cleanup_interval: Arc::new(watch::channel(config.cleanup_interval).0),
+            replication: Arc::new(ReplicationLog::new(config.repl_backlog_size)),
+            follower: config
+                .replica_of
+                .as_ref()
+                .map(|leader| Arc::new(FollowerStatus::new(leader.clone()))),
+            cluster: config.cluster().map(|cluster| {
+                Arc::new(cluster.expect("the node table of a validated config is consistent"))
+            }),
+            origin: config.origin_url.as_ref().map(|template| {
+                Arc::new(Origin::new(
+                    template.clone(),
+                    Duration::from_millis(config.origin_timeout),
+                ))
+            }),
+            ..state
+        }
+    }
+
+    /// The link to the leader while this server is a follower; None for a
+    /// leader, including a follower promoted by a cluster failover.
+    pub fn leader_link(&self) -> Option<&Arc<FollowerStatus>> {
+        self.follower
+            .as_ref()
+            .filter(|follower| !follower.is_promoted())
+    }
+
+    /// Returns the runtime-adjustable settings at their current values.
+    ///
+    /// These may differ from `config`, which holds the startup values.
+    pub async fn tunables(&self) -> Tunables {
+        self.tunables_of(&self.cache).await
+    }
+
+    /// Like `tunables`, with the `max_entries` and `default_ttl` of `store`
+    /// instead of the default database.
+    pub async fn tunables_of(&self, store: &RwLock<CacheStore>) -> Tunables {
+        let (max_entries, default_ttl) = {
+            let cache = store.read().await;
+            (cache.max_entries(), cache.default_ttl())
+        };
+        let (slowlog_threshold_us, slowlog_max_len) = {
+            let slowlog = self.slowlog.read().await;
+            (slowlog.threshold_us(), slowlog.max_len())
+        };
+        Tunables {
+            max_entries,
+            default_ttl,
+            cleanup_interval: *self.cleanup_interval.borrow(),
+            slowlog_threshold_us,
+            slowlog_max_len,
+        }
+    }
+
+    /// Records an operation in the slow log if it exceeded the threshold.
+    ///
+    /// Only takes the write lock when the operation is actually slow.
+    pub async fn record_slow(
+        &self,
+        operation: &str,
+        key: Option<&str>,
+        value_size: Option<usize>,
+        duration: Duration,
+    ) {
+        if !self.slowlog.read().await.is_slow(duration) {
+            return;
+        }
+        self.slowlog
+            .write()
+            .await
+            .record(operation, key, value_size, duration);
     }
 }
 
@@ -56,36 +202,89 @@ impl AppState {
 /// - Validates: Requirement 4.2
 pub async fn set_handler(
     State(state): State<AppState>,
+    db: Database,
     Json(req): Json<SetRequest>,
 ) -> Result<Json<SetResponse>> {
+    let started = Instant::now();
+
     // Validate request
     if let Some(error_msg) = req.validate() {
         return Err(CacheError::InvalidRequest(error_msg));
     }
 
     // Acquire write lock and set the value
-    let mut cache = state.cache.write().await;
-    cache.set(req.key.clone(), req.value, req.ttl)?;
+    let value_size = req.value.len();
+    let result = {
+        let mut cache = db.store.write().await;
+        let options = SetOptions {
+            grace: req.grace,
+            compute_ms: req.compute_ms,
+        };
+        cache.set_with(req.key.clone(), req.value, req.ttl, options)
+    };
+
+    state
+        .record_slow("set", Some(&req.key), Some(value_size), started.elapsed())
+        .await;
+    result?;
 
     Ok(Json(SetResponse::new(req.key)))
 }
 
-
 /// Handler for GET /get/:key
 ///
-/// Retrieves a value from the cache by key.
+/// Retrieves a value from the cache by key. Values expired within their
+/// grace window are flagged as stale, and the first reader of a stale value
+/// is asked to refresh it. In origin mode a miss is fetched from the origin,
+/// and stale values are refreshed from it in the background instead.
 ///
 /// # Requirements
 /// - Validates: Requirement 4.3
 pub async fn get_handler(
     State(state): State<AppState>,
-    Path(key): Path<String>,
+    db: Database,
+    Path(KeyPath { key }): Path<KeyPath>,
 ) -> Result<Json<GetResponse>> {
+    let started = Instant::now();
+
     // Acquire write lock (needed for LRU touch and stats update)
-    let mut cache = state.cache.write().await;
-    let value = cache.get(&key)?;
+    let mut result = db.store.write().await.read(&key);
+    // Followers only take writes from their leader
+    let leader = state.leader_link().is_none();
+    match (&mut result, &state.origin) {
+        (Err(CacheError::NotFound(_) | CacheError::Expired(_)), Some(origin)) => {
+            let store = leader.then_some(&*db.store);
+            result = origin.load(&db.name, &key, store).await.and_then(|value| {
+                let value = value.ok_or_else(|| CacheError::NotFound(key.clone()))?;
+                Ok(CachedValue {
+                    value,
+                    stale: false,
+                    refresh: false,
+                })
+            });
+        }
+        (Ok(cached), Some(origin)) if cached.refresh => {
+            cached.refresh = false;
+            if leader {
+                let origin = origin.clone();
+                let (name, key, store) = (db.name.clone(), key.clone(), db.store.clone());
+                tokio::spawn(async move { origin.load(&name, &key, Some(&store)).await });
+            }
+        }
+        _ => {}
+    }
 
-    Ok(Json(GetResponse::new(key, value)))
+    let value_size = result.as_ref().ok().map(|cached| cached.value.len());
+    state
+        .record_slow("get", Some(&key), value_size, started.elapsed())
+        .await;
+    let cached = result?;
+
+    Ok(Json(GetResponse {
+        stale: cached.stale,
+        refresh: cached.refresh,
+        ..GetResponse::new(key, cached.value)
+    }))
 }
 
 /// Handler for DELETE /del/:key
@@ -96,32 +295,98 @@ pub async fn get_handler(
 /// - Validates: Requirement 4.4
 pub async fn delete_handler(
     State(state): State<AppState>,
-    Path(key): Path<String>,
+    db: Database,
+    Path(KeyPath { key }): Path<KeyPath>,
 ) -> Result<Json<DeleteResponse>> {
+    let started = Instant::now();
+
     // Acquire write lock
-    let mut cache = state.cache.write().await;
-    cache.delete(&key)?;
+    let result = db.store.write().await.delete(&key);
+
+    state
+        .record_slow("del", Some(&key), None, started.elapsed())
+        .await;
+    result?;
 
     Ok(Json(DeleteResponse::new(key)))
 }
 
+/// Handler for GET /ttl/:key
+///
+/// Returns the remaining time to live of a key without reading its value,
+/// so it does not count as a hit or touch the LRU order.
+pub async fn ttl_handler(
+    db: Database,
+    Path(KeyPath { key }): Path<KeyPath>,
+) -> Result<Json<TtlResponse>> {
+    let ttl_ms = db
+        .store
+        .read()
+        .await
+        .entry(&key)
+        .map(|entry| entry.ttl_remaining_ms())
+        .ok_or_else(|| CacheError::NotFound(key.clone()))?;
+
+    Ok(Json(TtlResponse { key, ttl_ms }))
+}
+
+/// Handler for GET /scan
+///
+/// Returns one page of keys and the cursor of the next, as Redis `SCAN`.
+/// Users restricted to some key patterns only see keys they may access.
+pub async fn scan_handler(
+    State(state): State<AppState>,
+    db: Database,
+    identity: Option<Extension<Identity>>,
+    Query(query): Query<ScanQuery>,
+) -> Json<ScanResponse> {
+    let started = Instant::now();
+
+    let (cursor, mut keys) =
+        db.store
+            .read()
+            .await
+            .scan(query.cursor, query.pattern.as_deref(), query.count());
+    if let Some(Extension(Identity(user))) = identity {
+        keys.retain(|key| user.can_access_key(key));
+    }
+
+    state
+        .record_slow("scan", None, None, started.elapsed())
+        .await;
+
+    Json(ScanResponse {
+        cursor: cursor.to_string(),
+        keys,
+    })
+}
+
 /// Handler for GET /stats
 ///
 /// Returns current cache statistics.
 ///
 /// # Requirements
 /// - Validates: Requirement 4.5
-pub async fn stats_handler(State(state): State<AppState>) -> Json<StatsResponse> {
+pub async fn stats_handler(State(state): State<AppState>, db: Database) -> Json<StatsResponse> {
+    let started = Instant::now();
+
     // Acquire read lock for stats
-    let cache = state.cache.read().await;
-    let stats = cache.stats();
+    let stats = db.store.read().await.stats();
+
+    state
+        .record_slow("stats", None, None, started.elapsed())
+        .await;
 
-    Json(StatsResponse::new(
+    let mut response = StatsResponse::new(
         stats.hits,
         stats.misses,
         stats.evictions,
         stats.total_entries,
-    ))
+    );
+    response.acl_denials = state.metrics.acl_denials();
+    response.rate_limited = state.metrics.rate_limited();
+
+    Json(response)
 }
 
 /// Handler for GET /health
@@ -137,81 +402,229 @@ pub async fn health_handler() -> Json<HealthResponse> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::acl::{AclUser, Permission};
 
     #[tokio::test]
     async fn test_set_and_get_handler() {
         let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
 
         // Set a value
         let req = SetRequest {
             key: "test_key".to_string(),
             value: "test_value".to_string(),
             ttl: None,
+            grace: None,
+            compute_ms: None,
         };
-        let result = set_handler(State(state.clone()), Json(req)).await;
+        let result = set_handler(State(state.clone()), db.clone(), Json(req)).await;
         assert!(result.is_ok());
 
         // Get the value
-        let result = get_handler(State(state.clone()), Path("test_key".to_string())).await;
+        let result = get_handler(
+            State(state.clone()),
+            db.clone(),
+            Path(KeyPath::new("test_key")),
+        )
+        .await;
         assert!(result.is_ok());
         let response = result.unwrap();
         assert_eq!(response.value, "test_value");
     }
 
+    #[tokio::test]
+    async fn test_get_stale_value() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
+
+        let req = SetRequest::new("stale_key", "old")
+            .with_ttl(0)
+            .with_grace(60);
+        let _ = set_handler(State(state.clone()), db.clone(), Json(req))
+            .await
+            .unwrap();
+
+        let get = || {
+            get_handler(
+                State(state.clone()),
+                db.clone(),
+                Path(KeyPath::new("stale_key")),
+            )
+        };
+        let first = get().await.unwrap();
+        assert_eq!(first.value, "old");
+        assert!(first.stale && first.refresh);
+        let second = get().await.unwrap();
+        assert!(second.stale && !second.refresh);
+
+        // A slow enough value is refreshed before it expires
+        let req = SetRequest::new("stale_key", "new")
+            .with_ttl(60)
+            .with_compute_ms(u64::MAX / 2);
+        let _ = set_handler(State(state.clone()), db.clone(), Json(req))
+            .await
+            .unwrap();
+        let early = get().await.unwrap();
+        assert_eq!(early.value, "new");
+        assert!(!early.stale && early.refresh);
+    }
+
     #[tokio::test]
     async fn test_get_nonexistent_key() {
         let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
 
-        let result = get_handler(State(state), Path("nonexistent".to_string())).await;
+        let result = get_handler(State(state), db.clone(), Path(KeyPath::new("nonexistent"))).await;
         assert!(result.is_err());
     }
 
     #[tokio::test]
     async fn test_delete_handler() {
         let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
 
         // Set a value first
         let req = SetRequest {
             key: "to_delete".to_string(),
             value: "value".to_string(),
             ttl: None,
+            grace: None,
+            compute_ms: None,
         };
-        set_handler(State(state.clone()), Json(req)).await.unwrap();
+        let _ = set_handler(State(state.clone()), db.clone(), Json(req))
+            .await
+            .unwrap();
 
         // Delete it
-        let result = delete_handler(State(state.clone()), Path("to_delete".to_string())).await;
+        let result = delete_handler(
+            State(state.clone()),
+            db.clone(),
+            Path(KeyPath::new("to_delete")),
+        )
+        .await;
         assert!(result.is_ok());
 
         // Verify it's gone
-        let result = get_handler(State(state), Path("to_delete".to_string())).await;
+        let result = get_handler(State(state), db.clone(), Path(KeyPath::new("to_delete"))).await;
         assert!(result.is_err());
     }
 
     #[tokio::test]
     async fn test_stats_handler() {
         let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
 
-        let response = stats_handler(State(state)).await;
+        let response = stats_handler(State(state.clone()), db.clone()).await;
         assert_eq!(response.hits, 0);
         assert_eq!(response.misses, 0);
     }
 
+    #[tokio::test]
+    async fn test_ttl_handler() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
+        db.store
+            .write()
+            .await
+            .set("key".to_string(), "value".to_string(), Some(60))
+            .unwrap();
+
+        let response = ttl_handler(db.clone(), Path(KeyPath::new("key")))
+            .await
+            .unwrap();
+        assert!(response
+            .ttl_ms
+            .is_some_and(|ttl| ttl > 59_000 && ttl <= 60_000));
+        assert_eq!(db.store.read().await.stats().hits, 0);
+
+        let result = ttl_handler(db, Path(KeyPath::new("missing"))).await;
+        assert!(matches!(result, Err(CacheError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_scan_handler() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
+        for key in ["user:1", "user:2", "item:1"] {
+            db.store
+                .write()
+                .await
+                .set(key.to_string(), "value".to_string(), None)
+                .unwrap();
+        }
+
+        let query = ScanQuery {
+            pattern: Some("user:*".to_string()),
+            count: Some(100),
+            ..ScanQuery::default()
+        };
+        let response =
+            scan_handler(State(state.clone()), db.clone(), None, Query(query.clone())).await;
+        assert_eq!(response.cursor, "0");
+        assert_eq!(response.keys.len(), 2);
+
+        // Keys outside the user's patterns are left out
+        let user = AclUser {
+            name: "reader".to_string(),
+            token: "token".to_string(),
+            permissions: vec![Permission::Read],
+            key_patterns: vec!["user:1".to_string()],
+        };
+        let identity = Some(Extension(Identity(Arc::new(user))));
+        let response = scan_handler(State(state), db, identity, Query(query)).await;
+        assert_eq!(response.keys, vec!["user:1"]);
+    }
+
     #[tokio::test]
     async fn test_health_handler() {
         let response = health_handler().await;
         assert_eq!(response.status, "healthy");
     }
 
+    #[tokio::test]
+    async fn test_handlers_record_slow_operations() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
+        *state.slowlog.write().await = SlowLog::new(0, 10);
+
+        let req = SetRequest {
+            key: "slow_key".to_string(),
+            value: "value".to_string(),
+            ttl: None,
+            grace: None,
+            compute_ms: None,
+        };
+        let _ = set_handler(State(state.clone()), db.clone(), Json(req))
+            .await
+            .unwrap();
+        let _ = get_handler(
+            State(state.clone()),
+            db.clone(),
+            Path(KeyPath::new("slow_key")),
+        )
+        .await;
+
+        let entries = state.slowlog.read().await.latest(10);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, "get");
+        assert_eq!(entries[1].operation, "set");
+        assert_eq!(entries[1].key.as_deref(), Some("slow_key"));
+        assert_eq!(entries[1].value_size, Some(5));
+    }
+
     #[tokio::test]
     async fn test_set_invalid_request() {
         let state = AppState::new(CacheStore::new(100, 300));
+        let db = Database::default_for(&state);
 
         let req = SetRequest {
             key: "".to_string(), // Empty key is invalid
             value: "value".to_string(),
             ttl: None,
+            grace: None,
+            compute_ms: None,
         };
-        let result = set_handler(State(state), Json(req)).await;
+        let result = set_handler(State(state.clone()), db.clone(), Json(req)).await;
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,407 @@
+//! INFO Handler
+//!
+//! Builds the Redis INFO-style report served by GET /info.
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use super::handlers::AppState;
+use crate::cache::KeyspaceInfo;
+use crate::error::{CacheError, Result};
+use crate::models::{InfoQuery, InfoReport, InfoSection};
+
+/// Sections reported by GET /info, in display order
+pub const INFO_SECTIONS: [&str; 8] = [
+    "server",
+    "clients",
+    "memory",
+    "persistence",
+    "stats",
+    "replication",
+    "cluster",
+    "keyspace",
+];
+
+/// Handler for GET /info
+///
+/// Returns server, client, memory, persistence, stats, replication,
+/// cluster and keyspace information. `section` limits the report to one section; `format=text`
+/// renders it in the Redis `key:value` format instead of JSON.
+pub async fn info_handler(
+    State(state): State<AppState>,
+    Query(query): Query<InfoQuery>,
+) -> Result<Response> {
+    let section = query
+        .section
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+        .filter(|s| s != "all" && s != "default" && s != "everything");
+
+    if let Some(name) = &section {
+        if !INFO_SECTIONS.contains(&name.as_str()) {
+            return Err(CacheError::InvalidRequest(format!(
+                "Unknown INFO section '{}', expected one of: {}",
+                name,
+                INFO_SECTIONS.join(", ")
+            )));
+        }
+    }
+
+    let text = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("text") => true,
+        Some(other) => {
+            return Err(CacheError::InvalidRequest(format!(
+                "Unknown INFO format '{}', expected 'json' or 'text'",
+                other
+            )))
+        }
+    };
+
+    let report = build_report(&state, section.as_deref()).await;
+
+    if text {
+        Ok((
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            report.to_text(),
+        )
+            .into_response())
+    } else {
+        Ok(Json(report.to_json()).into_response())
+    }
+}
+
+/// Collects the requested sections (all when `only` is None).
+pub async fn build_report(state: &AppState, only: Option<&str>) -> InfoReport {
+    let wanted = |name: &str| only.is_none_or(|only| only == name);
+
+    // Snapshot each database under a single read lock; counters and the
+    // keyspace are summed, limits and cleanup runs are the default database's
+    let mut databases = Vec::with_capacity(state.databases.len());
+    for (name, store) in state.databases.iter() {
+        let store = store.read().await;
+        databases.push((
+            name.to_string(),
+            store.stats(),
+            store.keyspace_info(),
+            store.memory_usage(),
+        ));
+    }
+    let (max_entries, default_ttl) = {
+        let cache = state.cache.read().await;
+        (cache.max_entries(), cache.default_ttl())
+    };
+    let mut stats = databases[0].1.clone();
+    let mut keyspace = KeyspaceInfo::default();
+    let mut used_memory = 0;
+    let mut ttl_total: u64 = 0;
+    for (index, (_, db_stats, db_keyspace, db_memory)) in databases.iter().enumerate() {
+        if index > 0 {
+            stats.hits += db_stats.hits;
+            stats.stale_hits += db_stats.stale_hits;
+            stats.misses += db_stats.misses;
+            stats.expired += db_stats.expired;
+            stats.evictions += db_stats.evictions;
+        }
+        keyspace.keys += db_keyspace.keys;
+        keyspace.expires += db_keyspace.expires;
+        keyspace.persistent += db_keyspace.persistent;
+        ttl_total += db_keyspace.avg_ttl_ms * db_keyspace.expires as u64;
+        used_memory += db_memory;
+    }
+    if keyspace.expires > 0 {
+        keyspace.avg_ttl_ms = ttl_total / keyspace.expires as u64;
+    }
+    let tunables = state.tunables().await;
+    let config = &state.config;
+    let metrics = &state.metrics;
+    let uptime = metrics.uptime().as_secs();
+
+    let mut report = InfoReport::new();
+
+    if wanted("server") {
+        let bind_addresses = config
+            .bind_addresses
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        report.push(
+            InfoSection::new("server")
+                .field("version", env!("CARGO_PKG_VERSION"))
+                .field(
+                    "mode",
+                    if state.cluster.is_some() {
+                        "cluster"
+                    } else {
+                        "standalone"
+                    },
+                )
+                .field("os", std::env::consts::OS)
+                .field("arch", std::env::consts::ARCH)
+                .field("process_id", std::process::id())
+                .field("tcp_port", config.server_port)
+                .field("bind_address", bind_addresses)
+                .field(
+                    "unix_socket",
+                    config
+                        .unix_socket
+                        .as_ref()
+                        .map(|path| path.display().to_string()),
+                )
+                .field(
+                    "config_file",
+                    config
+                        .config_file
+                        .as_ref()
+                        .map(|path| path.display().to_string()),
+                )
+                .field("tls_enabled", config.tls_settings().is_some())
+                .field("started_at", metrics.started_at_ms())
+                .field("uptime_in_seconds", uptime)
+                .field("uptime_in_days", uptime / 86_400)
+                .field("max_entries", max_entries)
+                .field("default_ttl", default_ttl)
+                .field("databases", databases.len())
+                .field("cleanup_interval", tunables.cleanup_interval)
+                .field("slowlog_threshold_us", tunables.slowlog_threshold_us)
+                .field("slowlog_max_len", tunables.slowlog_max_len),
+        );
+    }
+
+    if wanted("clients") {
+        report.push(
+            InfoSection::new("clients")
+                .field("connected_clients", metrics.connected_clients())
+                .field("total_connections_received", metrics.total_connections())
+                .field("active_requests", metrics.active_requests())
+                .field("total_requests", metrics.total_requests()),
+        );
+    }
+
+    if wanted("memory") {
+        let used_ratio = if max_entries > 0 {
+            stats.total_entries as f64 / max_entries as f64
+        } else {
+            0.0
+        };
+        report.push(
+            InfoSection::new("memory")
+                .field("used_memory", used_memory)
+                .field("used_memory_human", human_bytes(used_memory))
+                .field("max_entries", max_entries)
+                .field("entries_used_ratio", used_ratio)
+                .field("eviction_policy", "allkeys-lru"),
+        );
+    }
+
+    if wanted("persistence") {
+        report.push(
+            InfoSection::new("persistence")
+                .field("enabled", false)
+                .field("mode", "none"),
+        );
+    }
+
+    if wanted("stats") {
+        report.push(
+            InfoSection::new("stats")
+                .field("keyspace_hits", stats.hits)
+                .field("keyspace_misses", stats.misses)
+                .field("keyspace_stale_hits", stats.stale_hits)
+                .field("hit_rate", stats.hit_rate())
+                .field("expired_keys", stats.expired)
+                .field("evicted_keys", stats.evictions)
+                .field("acl_denials", metrics.acl_denials())
+                .field("rate_limited", metrics.rate_limited())
+                .field("cleanup_interval", tunables.cleanup_interval)
+                .field("cleanup_runs", stats.cleanup_runs)
+                .field("last_cleanup_at", stats.last_cleanup_at)
+                .field("last_cleanup_removed", stats.last_cleanup_removed),
+        );
+    }
+
+    if wanted("replication") {
+        let log = state.replication.info();
+        let role = if state.leader_link().is_some() {
+            "follower"
+        } else {
+            "leader"
+        };
+        let mut section = InfoSection::new("replication")
+            .field("role", role)
+            .field("connected_followers", log.connected_followers)
+            .field("replid", log.replid)
+            .field("repl_offset", log.offset)
+            .field("repl_backlog_size", log.backlog_size)
+            .field("repl_backlog_histlen", log.backlog_len)
+            .field("repl_backlog_first_offset", log.backlog_first_offset)
+            .field("sync_full", log.full_syncs)
+            .field("sync_partial_ok", log.partial_syncs)
+            .field("sync_partial_err", log.partial_sync_errors);
+        if let Some(follower) = state.leader_link() {
+            let link = follower.info();
+            section = section
+                .field("leader", link.leader)
+                .field(
+                    "leader_link_status",
+                    if link.link_up { "up" } else { "down" },
+                )
+                .field("leader_last_io_seconds_ago", link.last_io_secs)
+                .field("leader_sync_in_progress", link.syncing)
+                .field("leader_repl_offset", link.leader_offset)
+                .field("follower_repl_offset", link.offset)
+                .field("follower_lag", link.leader_offset - link.offset)
+                .field("leader_full_syncs", link.full_syncs)
+                .field("leader_partial_syncs", link.partial_syncs);
+        }
+        report.push(section);
+    }
+
+    if wanted("cluster") {
+        let mut section =
+            InfoSection::new("cluster").field("cluster_enabled", state.cluster.is_some());
+        if let Some(cluster) = &state.cluster {
+            section = section
+                .field(
+                    "cluster_state",
+                    if cluster.is_complete() { "ok" } else { "fail" },
+                )
+                .field("cluster_my_id", cluster.myself_id())
+                .field("cluster_known_nodes", cluster.nodes().len())
+                .field("cluster_failed_nodes", cluster.failed_nodes())
+                .field("cluster_current_epoch", cluster.current_epoch())
+                .field("cluster_slots_assigned", cluster.slots_assigned())
+                .field("cluster_slots_served", cluster.slots_served())
+                .field("cluster_slots_migrating", cluster.migrating().len())
+                .field("cluster_slots_importing", cluster.importing().len())
+                .field(
+                    "cluster_migration_in_progress",
+                    cluster.migrations().is_running(),
+                );
+        }
+        report.push(section);
+    }
+
+    if wanted("keyspace") {
+        let mut section = InfoSection::new("keyspace")
+            .field("keys", keyspace.keys)
+            .field("expires", keyspace.expires)
+            .field("persistent", keyspace.persistent)
+            .field("avg_ttl_ms", keyspace.avg_ttl_ms);
+        // One line per non-empty database, as in `db0:keys=1,expires=0,avg_ttl=0`
+        for (name, _, db_keyspace, _) in &databases {
+            if db_keyspace.keys > 0 {
+                section = section.field(
+                    format!("db{}", name),
+                    format!(
+                        "keys={},expires={},avg_ttl={}",
+                        db_keyspace.keys, db_keyspace.expires, db_keyspace.avg_ttl_ms
+                    ),
+                );
+            }
+        }
+        report.push(section);
+    }
+
+    report
+}
+
+/// Formats a byte count with a binary unit suffix (e.g. "1.50K").
+fn human_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "K", "M", "G"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else {
+        format!("{:.2}{}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheStore;
+
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(human_bytes(512), "512B");
+        assert_eq!(human_bytes(1536), "1.50K");
+        assert_eq!(human_bytes(3 * 1024 * 1024), "3.00M");
+    }
+
+    #[tokio::test]
+    async fn test_build_report_all_sections() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        state
+            .cache
+            .write()
+            .await
+            .set("key".to_string(), "value".to_string(), Some(60))
+            .unwrap();
+
+        let json = build_report(&state, None).await.to_json();
+
+        for section in INFO_SECTIONS {
+            assert!(json.get(section).is_some(), "missing section {}", section);
+        }
+        assert_eq!(json["keyspace"]["keys"], 1);
+        assert_eq!(json["keyspace"]["expires"], 1);
+        assert_eq!(json["server"]["max_entries"], 100);
+    }
+
+    #[tokio::test]
+    async fn test_build_report_sums_databases() {
+        let config = crate::config::Config {
+            databases: crate::cache::DatabaseConfig::parse_list("staging").unwrap(),
+            ..Default::default()
+        };
+        let state = AppState::from_config(&config);
+        for (name, store) in state.databases.iter() {
+            store
+                .write()
+                .await
+                .set(format!("{}-key", name), "value".to_string(), None)
+                .unwrap();
+        }
+
+        let json = build_report(&state, Some("keyspace")).await.to_json();
+
+        assert_eq!(json["keyspace"]["keys"], 2);
+        for field in ["db0", "dbstaging"] {
+            let line = json["keyspace"][field].as_str().unwrap();
+            assert!(line.starts_with("keys=1,expires=1,avg_ttl="), "{}", line);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_report_single_section() {
+        let state = AppState::new(CacheStore::new(100, 300));
+
+        let report = build_report(&state, Some("memory")).await;
+
+        assert_eq!(report.sections.len(), 1);
+        assert_eq!(report.sections[0].name, "memory");
+    }
+
+    #[tokio::test]
+    async fn test_info_handler_rejects_unknown_section() {
+        let state = AppState::new(CacheStore::new(100, 300));
+        let query = InfoQuery {
+            section: Some("replication-ish".to_string()),
+            format: None,
+        };
+
+        let result = info_handler(State(state), Query(query)).await;
+        assert!(matches!(result, Err(CacheError::InvalidRequest(_))));
+    }
+}
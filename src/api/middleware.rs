@@ -0,0 +1,231 @@
+//! API Middleware
+//!
+//! Request-level layers applied to every route in `create_router`.
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use tracing::debug;
+
+use super::auth::request_key;
+use super::database::Database;
+use super::handlers::AppState;
+use crate::acl::{AclUser, Identity};
+use crate::cluster::{ClusterNode, SlotRoute};
+use crate::error::{CacheError, Result};
+use crate::monitor::ServerMetrics;
+
+/// Decrements the active request count when dropped, so cancelled or
+/// panicking requests are still accounted for.
+struct ActiveRequestGuard<'a>(&'a ServerMetrics);
+
+impl Drop for ActiveRequestGuard<'_> {
+    fn drop(&mut self) {
+        self.0.request_finished();
+    }
+}
+
+/// Counts active and total requests in `ServerMetrics`.
+pub async fn track_requests(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    state.metrics.request_started();
+    let _guard = ActiveRequestGuard(&state.metrics);
+
+    next.run(req).await
+}
+
+/// Applies the per-client token-bucket limits from `RateLimiter`.
+///
+/// Installed as a route layer so per-route limits can match the route
+/// template. Rejected requests get 429 with a `Retry-After` header.
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    let limiter = &state.rate_limiter;
+    if !limiter.is_enabled() {
+        return Ok(next.run(req).await);
+    }
+
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or_else(|| req.uri().path());
+    let client = client_key(&req);
+
+    if let Err(retry_after) = limiter.check(&client, route) {
+        state.metrics.record_rate_limited();
+        debug!("Rate limited {} on {}", client, route);
+        return Err(CacheError::RateLimited {
+            retry_after_secs: retry_after.as_secs_f64().ceil().max(1.0) as u64,
+        });
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Routes that change the data set
+const WRITE_ROUTES: &[&str] = &[
+    "/set",
+    "/del/:key",
+    "/db/:db/set",
+    "/db/:db/del/:key",
+    "/db/:db/flush",
+    "/db/:db/move/:key",
+    "/admin/flush",
+    "/admin/swapdb",
+    "/admin/cluster/restore",
+];
+
+/// Rejects writes on a follower, whose data only changes through
+/// replication from its leader.
+pub async fn reject_follower_writes(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    if let Some(follower) = state.leader_link() {
+        let route = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str());
+        if route.is_some_and(|route| WRITE_ROUTES.contains(&route)) {
+            return Err(CacheError::ReadOnly(format!(
+                "writes go to the leader at {}",
+                follower.leader()
+            )));
+        }
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Routes that operate on a single key
+const KEY_ROUTES: &[&str] = &[
+    "/set",
+    "/get/:key",
+    "/del/:key",
+    "/ttl/:key",
+    "/db/:db/set",
+    "/db/:db/get/:key",
+    "/db/:db/del/:key",
+    "/db/:db/ttl/:key",
+    "/db/:db/move/:key",
+    "/admin/key/:key",
+];
+
+/// Redirects requests for keys in slots served by another cluster node.
+///
+/// The redirect is a 307, so clients repeat the same method and body at
+/// the `Location` given; the body also carries the slot and the node's
+/// address for clients that route by themselves. Keys in slots no node
+/// serves are rejected with 503.
+///
+/// While a slot is migrating, keys still on this node are served here and
+/// the rest are redirected with ASK to the node importing the slot, whose
+/// `Location` adds `asking=1` so that node serves them before it owns the
+/// slot.
+pub async fn redirect_cluster_keys(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    let Some(cluster) = &state.cluster else {
+        return Ok(next.run(req).await);
+    };
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    if !KEY_ROUTES.contains(&route.as_str()) {
+        return Ok(next.run(req).await);
+    }
+
+    let (mut parts, body) = req.into_parts();
+    let (key, body) = request_key(&mut parts, body, &route, &state).await?;
+    if let Some(key) = key {
+        let scheme = if state.config.tls_settings().is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        let path = parts
+            .uri
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or("/")
+            .to_string();
+        let has_query = parts.uri.query().is_some();
+        let location = |node: &ClusterNode, asking: bool| {
+            let asking = match (asking, has_query) {
+                (false, _) => "",
+                (true, true) => "&asking=1",
+                (true, false) => "?asking=1",
+            };
+            format!("{}://{}{}{}", scheme, node.address, path, asking)
+        };
+
+        match cluster.route(&key, is_asking(parts.uri.query())) {
+            SlotRoute::Local => {}
+            SlotRoute::Moved { slot, node } => {
+                debug!("Redirecting key '{}' (slot {}) to {}", key, slot, node.id);
+                return Err(CacheError::Moved {
+                    slot,
+                    location: location(&node, false),
+                    address: node.address,
+                });
+            }
+            SlotRoute::Migrating { slot, node } => {
+                let database = Database::from_request_parts(&mut parts, &state).await?;
+                if !database.store.read().await.contains_key(&key) {
+                    debug!("Asking {} for key '{}' (slot {})", node.id, key, slot);
+                    return Err(CacheError::Ask {
+                        slot,
+                        location: location(&node, true),
+                        address: node.address,
+                    });
+                }
+            }
+            SlotRoute::Unassigned { slot } => {
+                return Err(CacheError::ClusterDown(format!(
+                    "hash slot {} is not served by any node",
+                    slot
+                )));
+            }
+        }
+    }
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// Returns true if the query string has `asking=1`, set by clients
+/// following an ASK redirect.
+fn is_asking(query: Option<&str>) -> bool {
+    query.is_some_and(|query| {
+        query
+            .split('&')
+            .any(|param| param == "asking=1" || param == "asking=true")
+    })
+}
+
+/// Identifies the client for rate limiting.
+///
+/// Named ACL users share one budget across connections; everyone else
+/// (including callers using plain `AUTH_TOKENS`) is keyed by peer IP.
+fn client_key(req: &Request) -> String {
+    if let Some(Identity(user)) = req.extensions().get::<Identity>() {
+        if user.name != AclUser::DEFAULT_USER {
+            return format!("user:{}", user.name);
+        }
+    }
+    match req.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "unknown".to_string(),
+    }
+}
@@ -8,12 +8,49 @@
 //! - `DELETE /del/:key` - Delete a key
 //! - `GET /stats` - Get cache statistics
 //! - `GET /health` - Health check endpoint
+//! - `GET /info` - Server, memory and keyspace report
+//! - `GET /admin/slowlog` - List slow operations
+//! - `DELETE /admin/slowlog` - Reset the slow log
+//! - `GET /admin/hotkeys` - Most frequently accessed keys
+//! - `GET /admin/bigkeys` - Keys with the largest values
+//! - `GET /admin/key/:key` - Internal metadata for one key
+//! - `GET /admin/acl/whoami` - The authenticated user and its permissions
+//! - `PUT /db/:db/set`, `GET /db/:db/get/:key`, `DELETE /db/:db/del/:key`,
+//!   `GET /db/:db/stats` - The same operations on a named database
+//! - `POST /db/:db/flush` - Remove every key of a database (FLUSHDB)
+//! - `POST /db/:db/move/:key` - Move a key to another database (MOVE)
+//! - `GET /admin/databases` - List databases
+//! - `POST /admin/flush` - Remove every key of every database (FLUSHALL)
+//! - `POST /admin/swapdb` - Swap the contents of two databases (SWAPDB)
+//! - `GET /admin/config` - Current runtime and startup settings
+//! - `PATCH /admin/config` - Change runtime settings
+//! - `POST /admin/config/rewrite` - Persist runtime settings to the config file
+//! - `GET /admin/replication/sync` - Replication stream read by followers
+//! - `GET /cluster/slots` - Slot ranges and the nodes serving them
+//! - `GET /cluster/nodes` - Cluster nodes and their slots
+//! - `GET /cluster/keyslot/:key` - Hash slot of a key
+//! - `POST /admin/cluster/setslot` - Change slot assignments (CLUSTER SETSLOT)
+//! - `POST /admin/cluster/restore` - Store keys migrated from another node
+//! - `POST /admin/cluster/migrate` - Start migrating slots to another node
+//! - `GET /admin/cluster/migrations` - Progress of slot migrations
 //!
 //! # Requirements
 //! - Validates: Requirement 4.1
 
+pub mod admin;
+pub mod auth;
+pub mod cluster;
+pub mod database;
 pub mod handlers;
+pub mod info;
+pub mod middleware;
+pub mod replication;
 pub mod routes;
 
+pub use admin::*;
+pub use cluster::*;
+pub use database::*;
 pub use handlers::*;
+pub use info::info_handler;
+pub use replication::replication_sync_handler;
 pub use routes::create_router;
@@ -0,0 +1,37 @@
+//! Replication Handler
+//!
+//! Serves the replication stream followers read from their leader.
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use super::handlers::AppState;
+use crate::models::SyncQuery;
+use crate::replication::{start_sync, stream_body};
+
+/// Handler for GET /admin/replication/sync
+///
+/// Streams newline-delimited JSON: a snapshot, or only the missed commands
+/// when `replid` and `offset` name a position still in the backlog, then
+/// every write command as it happens. The response stays open until the
+/// follower disconnects.
+pub async fn replication_sync_handler(
+    State(state): State<AppState>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    Query(query): Query<SyncQuery>,
+) -> Response {
+    let resume = query.replid.as_deref().zip(query.offset);
+    let sync = start_sync(&state.databases, &state.replication, resume).await;
+
+    let follower = match peer {
+        Some(ConnectInfo(addr)) => addr.to_string(),
+        None => "unknown".to_string(),
+    };
+    let body = stream_body(sync, state.replication.clone(), follower);
+    ([(header::CONTENT_TYPE, "application/x-ndjson")], body).into_response()
+}
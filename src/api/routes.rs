@@ -6,7 +6,8 @@
 //! - Validates: Requirement 4.1
 
 use axum::{
-    routing::{delete, get, put},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
 use tower_http::{
@@ -14,9 +15,27 @@ use tower_http::{
     trace::TraceLayer,
 };
 
+use super::admin::{
+    bigkeys_handler, config_get_handler, config_rewrite_handler, config_set_handler,
+    hotkeys_handler, key_info_handler, slowlog_get_handler, slowlog_reset_handler, whoami_handler,
+};
+use super::auth::{enforce_acl, require_auth};
+use super::cluster::{
+    cluster_keyslot_handler, cluster_migrate_handler, cluster_migrations_handler,
+    cluster_nodes_handler, cluster_restore_handler, cluster_setslot_handler, cluster_slots_handler,
+};
+use super::database::{
+    databases_handler, flushall_handler, flushdb_handler, move_handler, swapdb_handler,
+};
 use super::handlers::{
-    delete_handler, get_handler, health_handler, set_handler, stats_handler, AppState,
+    delete_handler, get_handler, health_handler, scan_handler, set_handler, stats_handler,
+    ttl_handler, AppState,
 };
+use super::info::info_handler;
+use super::middleware::{
+    rate_limit, redirect_cluster_keys, reject_follower_writes, track_requests,
+};
+use super::replication::replication_sync_handler;
 
 /// Creates the main router with all endpoints configured.
 ///
@@ -24,12 +43,50 @@ use super::handlers::{
 /// - `PUT /set` - Store a key-value pair
 /// - `GET /get/:key` - Retrieve a value by key
 /// - `DELETE /del/:key` - Delete a key
+/// - `GET /ttl/:key` - Remaining time to live of a key
+/// - `GET /scan` - Iterate over keys with a cursor
 /// - `GET /stats` - Get cache statistics
 /// - `GET /health` - Health check endpoint
+/// - `GET /info` - Server, memory and keyspace report
+/// - `GET /admin/slowlog` - List slow operations
+/// - `DELETE /admin/slowlog` - Reset the slow log
+/// - `GET /admin/hotkeys` - Most frequently accessed keys
+/// - `GET /admin/bigkeys` - Keys with the largest values
+/// - `GET /admin/key/:key` - Internal metadata for one key
+/// - `GET /admin/acl/whoami` - The authenticated user and its permissions
+/// - `PUT /db/:db/set`, `GET /db/:db/get/:key`, `DELETE /db/:db/del/:key`,
+///   `GET /db/:db/ttl/:key`, `GET /db/:db/scan`, `GET /db/:db/stats` - The
+///   same operations on a named database
+/// - `POST /db/:db/flush` - Remove every key of a database (FLUSHDB)
+/// - `POST /db/:db/move/:key` - Move a key to another database (MOVE)
+/// - `GET /admin/databases` - List databases
+/// - `POST /admin/flush` - Remove every key of every database (FLUSHALL)
+/// - `POST /admin/swapdb` - Swap the contents of two databases (SWAPDB)
+/// - `GET /admin/config` - Current runtime and startup settings
+/// - `PATCH /admin/config` - Change runtime settings
+/// - `POST /admin/config/rewrite` - Persist runtime settings to the config file
+/// - `GET /admin/replication/sync` - Replication stream read by followers
+/// - `GET /cluster/slots` - Slot ranges and the nodes serving them
+/// - `GET /cluster/nodes` - Cluster nodes and their slots
+/// - `GET /cluster/keyslot/:key` - Hash slot of a key
+/// - `POST /admin/cluster/setslot` - Change slot assignments (CLUSTER SETSLOT)
+/// - `POST /admin/cluster/restore` - Store keys migrated from another node
+/// - `POST /admin/cluster/migrate` - Start migrating slots to another node
+/// - `GET /admin/cluster/migrations` - Progress of slot migrations
+///
+/// The unprefixed data routes use the database named by the `X-Database`
+/// header, or the default database.
 ///
 /// # Middleware
+/// - Auth: Requires a bearer token when `auth_tokens` or `acl_users` are configured
+/// - Rate limiting: Per-client token buckets, checked after auth and before ACL
+/// - ACL: Checks the user's permissions and key patterns for the matched route
+/// - Read-only: Followers reject routes that change the data set
+/// - Cluster: Keys in slots served by another node are redirected there,
+///   keys already migrated out of a migrating slot are redirected with ASK
 /// - CORS: Allows any origin (configurable for production)
 /// - Tracing: Logs all requests for debugging
+/// - Request tracking: Counts active and total requests for `/info`
 ///
 /// # Requirements
 /// - Validates: Requirement 4.1
@@ -45,8 +102,55 @@ pub fn create_router(state: AppState) -> Router {
         .route("/set", put(set_handler))
         .route("/get/:key", get(get_handler))
         .route("/del/:key", delete(delete_handler))
+        .route("/ttl/:key", get(ttl_handler))
+        .route("/scan", get(scan_handler))
         .route("/stats", get(stats_handler))
         .route("/health", get(health_handler))
+        .route("/info", get(info_handler))
+        .route("/db/:db/set", put(set_handler))
+        .route("/db/:db/get/:key", get(get_handler))
+        .route("/db/:db/del/:key", delete(delete_handler))
+        .route("/db/:db/ttl/:key", get(ttl_handler))
+        .route("/db/:db/scan", get(scan_handler))
+        .route("/db/:db/stats", get(stats_handler))
+        .route("/db/:db/flush", post(flushdb_handler))
+        .route("/db/:db/move/:key", post(move_handler))
+        .route("/admin/databases", get(databases_handler))
+        .route("/admin/flush", post(flushall_handler))
+        .route("/admin/swapdb", post(swapdb_handler))
+        .route(
+            "/admin/slowlog",
+            get(slowlog_get_handler).delete(slowlog_reset_handler),
+        )
+        .route("/admin/hotkeys", get(hotkeys_handler))
+        .route("/admin/bigkeys", get(bigkeys_handler))
+        .route("/admin/key/:key", get(key_info_handler))
+        .route("/admin/acl/whoami", get(whoami_handler))
+        .route(
+            "/admin/config",
+            get(config_get_handler).patch(config_set_handler),
+        )
+        .route("/admin/config/rewrite", post(config_rewrite_handler))
+        .route("/admin/replication/sync", get(replication_sync_handler))
+        .route("/cluster/slots", get(cluster_slots_handler))
+        .route("/cluster/nodes", get(cluster_nodes_handler))
+        .route("/cluster/keyslot/:key", get(cluster_keyslot_handler))
+        .route("/admin/cluster/setslot", post(cluster_setslot_handler))
+        .route("/admin/cluster/restore", post(cluster_restore_handler))
+        .route("/admin/cluster/migrate", post(cluster_migrate_handler))
+        .route("/admin/cluster/migrations", get(cluster_migrations_handler))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            reject_follower_writes,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            redirect_cluster_keys,
+        ))
+        .route_layer(middleware::from_fn_with_state(state.clone(), enforce_acl))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+        .layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        .layer(middleware::from_fn_with_state(state.clone(), track_requests))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(state)
@@ -137,4 +241,90 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn test_info_endpoint_text_format() {
+        let app = create_test_app();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/info?section=server&format=text")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.starts_with("# Server\r\n"));
+        assert!(text.contains("uptime_in_seconds:"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_required_when_tokens_configured() {
+        let config = crate::config::Config {
+            auth_tokens: vec!["secret".to_string()],
+            ..Default::default()
+        };
+        let app = create_router(AppState::from_config(&config));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/stats")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/stats")
+                    .header("authorization", "Bearer secret")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_slowlog_endpoint() {
+        let app = create_test_app();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/slowlog?count=5")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/admin/slowlog")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 }
@@ -0,0 +1,148 @@
+//! Latency Histogram
+//!
+//! Records latencies in nanoseconds into log-linear buckets: exact below
+//! 128ns, then 64 buckets per power of two, so any percentile is within
+//! about 1.6% of the true value while memory stays fixed however long the
+//! run. Workers keep their own histogram and they are merged at the end.
+
+use std::time::Duration;
+
+/// Values below this get a bucket each
+const LINEAR: u64 = 128;
+/// Buckets per power of two above `LINEAR`
+const SUB_BUCKETS: u64 = 64;
+/// Enough buckets for any u64
+const BUCKETS: usize = (LINEAR + (64 - 7) * SUB_BUCKETS) as usize;
+
+// == Histogram ==
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    sum: u128,
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            count: 0,
+            sum: 0,
+            max: 0,
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, latency: Duration) {
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        self.counts[bucket(nanos)] += 1;
+        self.count += 1;
+        self.sum += nanos as u128;
+        self.max = self.max.max(nanos);
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Mean latency in nanoseconds
+    pub fn mean(&self) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        (self.sum / self.count as u128) as u64
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Latency in nanoseconds below which `percentile` percent of the
+    /// samples fall, as the upper end of its bucket.
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((percentile / 100.0 * self.count as f64).ceil() as u64).clamp(1, self.count);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return upper_bound(index).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+fn bucket(nanos: u64) -> usize {
+    if nanos < LINEAR {
+        return nanos as usize;
+    }
+    let exponent = 63 - nanos.leading_zeros() as u64;
+    let shift = exponent - 6;
+    let mantissa = nanos >> shift;
+    (LINEAR + (exponent - 7) * SUB_BUCKETS + (mantissa - SUB_BUCKETS)) as usize
+}
+
+/// Largest value falling in bucket `index`
+fn upper_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < LINEAR {
+        return index;
+    }
+    let exponent = (index - LINEAR) / SUB_BUCKETS + 7;
+    let mantissa = (index - LINEAR) % SUB_BUCKETS + SUB_BUCKETS;
+    let shift = exponent - 6;
+    let next = ((mantissa + 1) as u128) << shift;
+    u64::try_from(next - 1).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_cover_their_values() {
+        for nanos in [0, 1, 127, 128, 129, 1000, 123_456, 10_000_000_000, u64::MAX] {
+            let index = bucket(nanos);
+            assert!(index < BUCKETS);
+            assert!(upper_bound(index) >= nanos);
+            if index > 0 {
+                assert!(upper_bound(index - 1) < nanos, "{}", nanos);
+            }
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut histogram = Histogram::default();
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+        let p50 = histogram.percentile(50.0) as f64;
+        assert!((p50 - 500_000.0).abs() < 500_000.0 * 0.02, "{}", p50);
+        let p99 = histogram.percentile(99.0) as f64;
+        assert!((p99 - 990_000.0).abs() < 990_000.0 * 0.02, "{}", p99);
+        assert_eq!(histogram.percentile(100.0), 1_000_000);
+        assert_eq!(histogram.mean(), 500_500);
+
+        let mut other = Histogram::default();
+        other.record(Duration::from_secs(1));
+        histogram.merge(&other);
+        assert_eq!(histogram.count(), 1001);
+        assert_eq!(histogram.max(), 1_000_000_000);
+        assert_eq!(Histogram::default().percentile(99.0), 0);
+    }
+}
@@ -0,0 +1,397 @@
+//! mini-redis-benchmark
+//!
+//! Drives a configurable workload against a running server, or against a
+//! `CacheStore` in this process, and reports throughput and latency
+//! percentiles. The in-process mode leaves out HTTP and the network, for
+//! regression benchmarks of the store itself.
+
+mod histogram;
+mod report;
+mod workload;
+
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use mini_redis::cache::CacheStore;
+use mini_redis::client::{Client, ClientOptions, RetryPolicy};
+use mini_redis::error::CacheError;
+use mini_redis::models::SetRequest;
+use tokio::sync::RwLock;
+
+use histogram::Histogram;
+use report::{Format, Report};
+use workload::{key_name, value_pool, Distribution, KeyChooser, Op, Rng, ValueSize, Workload};
+
+/// Command-line options
+#[derive(Debug, Parser)]
+#[command(
+    name = "mini-redis-benchmark",
+    version,
+    about = "Load generator and benchmark for mini_redis"
+)]
+struct Args {
+    /// Server to benchmark
+    #[arg(
+        short = 'u',
+        long,
+        env = "MINI_REDIS_URL",
+        default_value = "http://127.0.0.1:3000"
+    )]
+    url: String,
+
+    /// Bearer token to authenticate with
+    #[arg(short = 'a', long, env = "MINI_REDIS_AUTH_TOKEN")]
+    auth_token: Option<String>,
+
+    /// Named database to use instead of the default one
+    #[arg(long)]
+    db: Option<String>,
+
+    /// Benchmark a CacheStore in this process instead of a server
+    #[arg(long)]
+    in_process: bool,
+
+    /// Capacity of the in-process store
+    #[arg(long, default_value_t = 1_000_000)]
+    max_entries: usize,
+
+    /// Default TTL in seconds of the in-process store
+    #[arg(long, default_value_t = 3600)]
+    default_ttl: u64,
+
+    /// Clients sending requests, one at a time each over its own
+    /// connection. There is no pipelining: HTTP/1.1 answers in order, so
+    /// more requests in flight take more clients.
+    #[arg(short, long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+    concurrency: u32,
+
+    /// Requests to send
+    #[arg(short = 'n', long, default_value_t = 100_000)]
+    requests: u64,
+
+    /// Run for this many seconds instead of a number of requests
+    #[arg(short, long)]
+    duration: Option<u64>,
+
+    /// Share of reads, from 0 (only writes) to 1 (only reads)
+    #[arg(short, long, default_value_t = 0.9, value_parser = parse_ratio)]
+    read_ratio: f64,
+
+    /// Size of the key space
+    #[arg(short, long, default_value_t = 100_000, value_parser = clap::value_parser!(u64).range(1..))]
+    keys: u64,
+
+    /// How keys are picked from the key space
+    #[arg(long, value_enum, default_value_t = Distribution::Zipf)]
+    distribution: Distribution,
+
+    /// Skew of the Zipfian distribution; larger puts more load on hot keys
+    #[arg(long, default_value_t = 0.99, value_parser = parse_exponent)]
+    zipf_exponent: f64,
+
+    /// Value size in bytes, fixed (`100`) or a uniform range (`64-4096`)
+    #[arg(short, long, default_value = "100")]
+    value_size: ValueSize,
+
+    /// TTL in seconds of written keys, the server's default if not set
+    #[arg(long)]
+    ttl: Option<u64>,
+
+    /// Do not write every key before measuring, so early reads miss
+    #[arg(long)]
+    no_preload: bool,
+
+    /// Seed of the workload, for repeatable runs
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// How the report is printed
+    #[arg(short, long, value_enum, default_value_t = Format::Human)]
+    format: Format,
+}
+
+fn parse_ratio(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err(format!("'{}' is not a number between 0 and 1", s)),
+    }
+}
+
+fn parse_exponent(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(exponent) if exponent > 0.0 && exponent.is_finite() => Ok(exponent),
+        _ => Err(format!("'{}' is not a positive number", s)),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args).await {
+        Ok(report) => {
+            println!("{}", report.render(args.format));
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: &Args) -> Result<Report, String> {
+    let target = Target::new(args).await?;
+    let values = Arc::new(value_pool(args.value_size.max));
+    if !args.no_preload {
+        preload(&target, args, &values).await?;
+    }
+
+    let workload = Arc::new(Workload {
+        read_ratio: args.read_ratio,
+        keys: KeyChooser::new(args.distribution, args.keys, args.zipf_exponent),
+        value_size: args.value_size,
+    });
+    let started = Instant::now();
+    let plan = Arc::new(Plan {
+        requests: args.duration.is_none().then_some(args.requests),
+        deadline: args
+            .duration
+            .map(|secs| started + Duration::from_secs(secs)),
+        issued: AtomicU64::new(0),
+    });
+
+    let mut tasks = Vec::new();
+    for worker in 0..args.concurrency {
+        let connection = target.connect(worker as usize);
+        let workload = workload.clone();
+        let plan = plan.clone();
+        let values = values.clone();
+        let rng = Rng::new(args.seed.wrapping_add(worker as u64));
+        let ttl = args.ttl;
+        tasks.push(tokio::spawn(async move {
+            run_worker(connection, workload, plan, values, rng, ttl).await
+        }));
+    }
+    let mut totals = WorkerStats::default();
+    for task in tasks {
+        totals.merge(task.await.map_err(|e| e.to_string())?);
+    }
+
+    Ok(Report::new(
+        args,
+        target.describe(),
+        started.elapsed(),
+        totals,
+    ))
+}
+
+// == Targets ==
+/// What requests are sent to
+enum Target {
+    /// A server, with one client per concurrent client
+    Http(Vec<Client>),
+    /// A store in this process
+    InProcess(Arc<RwLock<CacheStore>>),
+}
+
+/// How one worker reaches the target
+enum Connection {
+    Http(Box<Client>),
+    /// Locked as the server's handlers lock it
+    InProcess(Arc<RwLock<CacheStore>>),
+}
+
+/// What an operation found
+enum Outcome {
+    Hit,
+    Miss,
+    Stored,
+}
+
+impl Target {
+    async fn new(args: &Args) -> Result<Self, String> {
+        if args.in_process {
+            let store = CacheStore::new(args.max_entries, args.default_ttl);
+            return Ok(Target::InProcess(Arc::new(RwLock::new(store))));
+        }
+        let options = ClientOptions {
+            auth_token: args.auth_token.clone(),
+            database: args.db.clone(),
+            // Failures are counted, not hidden behind retries
+            retry: RetryPolicy::none(),
+            max_idle_connections: 1,
+            ..ClientOptions::default()
+        };
+        let clients = (0..args.concurrency)
+            .map(|_| Client::with_options(&args.url, options.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        clients[0]
+            .health()
+            .await
+            .map_err(|e| format!("server not reachable: {}", e))?;
+        Ok(Target::Http(clients))
+    }
+
+    /// A connection through client number `client`.
+    fn connect(&self, client: usize) -> Connection {
+        match self {
+            Target::Http(clients) => Connection::Http(Box::new(clients[client].clone())),
+            Target::InProcess(store) => Connection::InProcess(store.clone()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Target::Http(clients) => clients[0].url().to_string(),
+            Target::InProcess(_) => "in-process".to_string(),
+        }
+    }
+}
+
+impl Connection {
+    async fn run(&self, op: Op, values: &str, ttl: Option<u64>) -> Result<Outcome, String> {
+        match (self, op) {
+            (Connection::Http(client), Op::Get(key)) => match client.get(&key).await {
+                Ok(Some(_)) => Ok(Outcome::Hit),
+                Ok(None) => Ok(Outcome::Miss),
+                Err(e) => Err(e.to_string()),
+            },
+            (Connection::Http(client), Op::Set(key, size)) => {
+                let mut request = SetRequest::new(key, &values[..size]);
+                request.ttl = ttl;
+                client
+                    .set(&request)
+                    .await
+                    .map(|_| Outcome::Stored)
+                    .map_err(|e| e.to_string())
+            }
+            (Connection::InProcess(store), Op::Get(key)) => match store.write().await.get(&key) {
+                Ok(_) => Ok(Outcome::Hit),
+                Err(CacheError::NotFound(_) | CacheError::Expired(_)) => Ok(Outcome::Miss),
+                Err(e) => Err(e.to_string()),
+            },
+            (Connection::InProcess(store), Op::Set(key, size)) => store
+                .write()
+                .await
+                .set(key, values[..size].to_string(), ttl)
+                .map(|_| Outcome::Stored)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Writes every key of the key space once.
+async fn preload(target: &Target, args: &Args, values: &Arc<String>) -> Result<(), String> {
+    let next = Arc::new(AtomicU64::new(0));
+    let mut tasks = Vec::new();
+    for worker in 0..args.concurrency {
+        let connection = target.connect(worker as usize);
+        let next = next.clone();
+        let values = values.clone();
+        let (keys, value_size, ttl) = (args.keys, args.value_size, args.ttl);
+        let mut rng = Rng::new(args.seed.wrapping_sub(worker as u64 + 1));
+        tasks.push(tokio::spawn(async move {
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= keys {
+                    return Ok::<(), String>(());
+                }
+                let op = Op::Set(key_name(index), value_size.next(&mut rng));
+                connection.run(op, &values, ttl).await?;
+            }
+        }));
+    }
+    for task in tasks {
+        task.await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("preload failed: {}", e))?;
+    }
+    Ok(())
+}
+
+// == Workers ==
+/// When the run ends: after a number of requests or at a deadline
+struct Plan {
+    requests: Option<u64>,
+    deadline: Option<Instant>,
+    issued: AtomicU64,
+}
+
+impl Plan {
+    /// Claims the next request; false once the run is over.
+    fn next(&self) -> bool {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return false;
+        }
+        match self.requests {
+            Some(requests) => self.issued.fetch_add(1, Ordering::Relaxed) < requests,
+            None => true,
+        }
+    }
+}
+
+/// What a worker measured
+#[derive(Debug, Default)]
+pub struct WorkerStats {
+    pub gets: Histogram,
+    pub sets: Histogram,
+    pub hits: u64,
+    pub misses: u64,
+    pub errors: u64,
+    pub first_error: Option<String>,
+}
+
+impl WorkerStats {
+    fn merge(&mut self, other: WorkerStats) {
+        self.gets.merge(&other.gets);
+        self.sets.merge(&other.sets);
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.errors += other.errors;
+        self.first_error = self.first_error.take().or(other.first_error);
+    }
+}
+
+async fn run_worker(
+    connection: Connection,
+    workload: Arc<Workload>,
+    plan: Arc<Plan>,
+    values: Arc<String>,
+    mut rng: Rng,
+    ttl: Option<u64>,
+) -> WorkerStats {
+    let mut stats = WorkerStats::default();
+    while plan.next() {
+        let op = workload.next(&mut rng);
+        let is_get = matches!(op, Op::Get(_));
+        let started = Instant::now();
+        let result = connection.run(op, &values, ttl).await;
+        let latency = started.elapsed();
+        match result {
+            Ok(outcome) => {
+                match outcome {
+                    Outcome::Hit => stats.hits += 1,
+                    Outcome::Miss => stats.misses += 1,
+                    Outcome::Stored => {}
+                }
+                if is_get {
+                    stats.gets.record(latency);
+                } else {
+                    stats.sets.record(latency);
+                }
+            }
+            Err(e) => {
+                stats.errors += 1;
+                stats.first_error.get_or_insert(e);
+            }
+        }
+    }
+    stats
+}
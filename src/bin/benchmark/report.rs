@@ -0,0 +1,205 @@
+//! Benchmark Report
+//!
+//! Summarizes a run as a table for people or as JSON for tracking results
+//! over time.
+
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::histogram::Histogram;
+use crate::workload::Distribution;
+use crate::{Args, WorkerStats};
+
+/// How the report is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+// == Report ==
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Server URL, or `in-process`
+    pub target: String,
+    pub workload: WorkloadSummary,
+    /// Length of the measured run, without the preload
+    pub elapsed_secs: f64,
+    /// Requests that succeeded
+    pub requests: u64,
+    pub errors: u64,
+    pub first_error: Option<String>,
+    /// Successful requests per second
+    pub throughput: f64,
+    /// Share of reads that found their key
+    pub hit_rate: f64,
+    pub latency: Latencies,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkloadSummary {
+    pub read_ratio: f64,
+    pub keys: u64,
+    pub distribution: &'static str,
+    pub zipf_exponent: Option<f64>,
+    pub value_size: String,
+    pub concurrency: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Latencies {
+    pub get: LatencySummary,
+    pub set: LatencySummary,
+    pub all: LatencySummary,
+}
+
+/// Latencies in microseconds
+#[derive(Debug, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub mean_us: f64,
+    pub p50_us: f64,
+    pub p90_us: f64,
+    pub p99_us: f64,
+    pub p999_us: f64,
+    pub max_us: f64,
+}
+
+impl LatencySummary {
+    fn new(histogram: &Histogram) -> Self {
+        let micros = |nanos: u64| nanos as f64 / 1000.0;
+        Self {
+            count: histogram.count(),
+            mean_us: micros(histogram.mean()),
+            p50_us: micros(histogram.percentile(50.0)),
+            p90_us: micros(histogram.percentile(90.0)),
+            p99_us: micros(histogram.percentile(99.0)),
+            p999_us: micros(histogram.percentile(99.9)),
+            max_us: micros(histogram.max()),
+        }
+    }
+}
+
+impl Report {
+    pub fn new(args: &Args, target: String, elapsed: Duration, stats: WorkerStats) -> Self {
+        let mut all = stats.gets.clone();
+        all.merge(&stats.sets);
+        let requests = all.count();
+        let reads = stats.hits + stats.misses;
+        let zipf = args.distribution == Distribution::Zipf;
+        Self {
+            target,
+            workload: WorkloadSummary {
+                read_ratio: args.read_ratio,
+                keys: args.keys,
+                distribution: if zipf { "zipf" } else { "uniform" },
+                zipf_exponent: zipf.then_some(args.zipf_exponent),
+                value_size: args.value_size.to_string(),
+                concurrency: args.concurrency,
+            },
+            elapsed_secs: elapsed.as_secs_f64(),
+            requests,
+            errors: stats.errors,
+            first_error: stats.first_error,
+            throughput: requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            hit_rate: if reads == 0 {
+                0.0
+            } else {
+                stats.hits as f64 / reads as f64
+            },
+            latency: Latencies {
+                get: LatencySummary::new(&stats.gets),
+                set: LatencySummary::new(&stats.sets),
+                all: LatencySummary::new(&all),
+            },
+        }
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Human => self.table(),
+            Format::Json => serde_json::to_string_pretty(self).expect("report serializes"),
+        }
+    }
+
+    fn table(&self) -> String {
+        let workload = &self.workload;
+        let distribution = match workload.zipf_exponent {
+            Some(exponent) => format!("zipf {}", exponent),
+            None => workload.distribution.to_string(),
+        };
+        let mut lines = vec![
+            format!("Target:      {}", self.target),
+            format!(
+                "Workload:    {:.0}% reads, {} keys ({}), values of {}",
+                workload.read_ratio * 100.0,
+                workload.keys,
+                distribution,
+                workload.value_size
+            ),
+            format!("Clients:     {}", workload.concurrency),
+            format!(
+                "Requests:    {} in {:.2}s, {} errors",
+                self.requests, self.elapsed_secs, self.errors
+            ),
+            format!("Throughput:  {:.0} requests/s", self.throughput),
+            format!("Hit rate:    {:.1}%", self.hit_rate * 100.0),
+        ];
+        if let Some(error) = &self.first_error {
+            lines.push(format!("First error: {}", error));
+        }
+
+        lines.push(String::new());
+        lines.push(format!(
+            "{:<5}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}",
+            "", "count", "mean", "p50", "p90", "p99", "p99.9", "max"
+        ));
+        let latency = &self.latency;
+        for (name, summary) in [
+            ("get", &latency.get),
+            ("set", &latency.set),
+            ("all", &latency.all),
+        ] {
+            lines.push(format!(
+                "{:<5}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}",
+                name,
+                summary.count,
+                duration(summary.mean_us),
+                duration(summary.p50_us),
+                duration(summary.p90_us),
+                duration(summary.p99_us),
+                duration(summary.p999_us),
+                duration(summary.max_us)
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Formats microseconds with a unit that keeps 3 to 4 digits.
+fn duration(micros: f64) -> String {
+    if micros < 1.0 {
+        format!("{:.0}ns", micros * 1000.0)
+    } else if micros < 1000.0 {
+        format!("{:.1}µs", micros)
+    } else if micros < 1_000_000.0 {
+        format!("{:.2}ms", micros / 1000.0)
+    } else {
+        format!("{:.2}s", micros / 1_000_000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_units() {
+        assert_eq!(duration(0.25), "250ns");
+        assert_eq!(duration(12.34), "12.3µs");
+        assert_eq!(duration(4_560.0), "4.56ms");
+        assert_eq!(duration(2_500_000.0), "2.50s");
+    }
+}
@@ -0,0 +1,308 @@
+//! Workload Generation
+//!
+//! Produces the operations each worker sends: reads or writes in a given
+//! ratio, keys drawn uniformly or from a Zipfian distribution over a fixed
+//! key space, and values with fixed or uniformly varying sizes. Each worker
+//! has its own seeded generator, so a run can be repeated exactly.
+
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+// == Random Numbers ==
+/// SplitMix64, small and fast; quality is ample for picking keys.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [0, n)
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+// == Key Distribution ==
+/// How keys are picked from the key space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Distribution {
+    /// Every key equally often
+    Uniform,
+    /// A few hot keys and a long tail, as in most caches
+    Zipf,
+}
+
+/// Draws key indexes in [0, keys).
+#[derive(Debug, Clone)]
+pub enum KeyChooser {
+    Uniform { keys: u64 },
+    Zipf(Zipf),
+}
+
+impl KeyChooser {
+    pub fn new(distribution: Distribution, keys: u64, exponent: f64) -> Self {
+        match distribution {
+            Distribution::Uniform => KeyChooser::Uniform { keys },
+            Distribution::Zipf => KeyChooser::Zipf(Zipf::new(keys, exponent)),
+        }
+    }
+
+    pub fn next(&self, rng: &mut Rng) -> u64 {
+        match self {
+            KeyChooser::Uniform { keys } => rng.below(*keys),
+            KeyChooser::Zipf(zipf) => zipf.sample(rng) - 1,
+        }
+    }
+}
+
+/// Zipf distribution over ranks 1..=n, where rank k is drawn with
+/// probability proportional to `1 / k^exponent`.
+///
+/// Uses rejection-inversion sampling (Hörmann and Derflinger, 1996), which
+/// needs constant memory and time whatever the size of the key space.
+#[derive(Debug, Clone)]
+pub struct Zipf {
+    n: f64,
+    exponent: f64,
+    h_integral_x1: f64,
+    h_integral_n: f64,
+    s: f64,
+}
+
+impl Zipf {
+    /// `exponent` must be positive; 1 is classic Zipf, larger is more skewed.
+    pub fn new(n: u64, exponent: f64) -> Self {
+        let mut zipf = Self {
+            n: n as f64,
+            exponent,
+            h_integral_x1: 0.0,
+            h_integral_n: 0.0,
+            s: 0.0,
+        };
+        zipf.h_integral_x1 = zipf.h_integral(1.5) - 1.0;
+        zipf.h_integral_n = zipf.h_integral(zipf.n + 0.5);
+        zipf.s = 2.0 - zipf.h_integral_inverse(zipf.h_integral(2.5) - zipf.h(2.0));
+        zipf
+    }
+
+    /// Returns a rank in 1..=n.
+    pub fn sample(&self, rng: &mut Rng) -> u64 {
+        loop {
+            let u = self.h_integral_n + rng.next_f64() * (self.h_integral_x1 - self.h_integral_n);
+            let x = self.h_integral_inverse(u);
+            let k = (x + 0.5).floor().clamp(1.0, self.n);
+            if k - x <= self.s || u >= self.h_integral(k + 0.5) - self.h(k) {
+                return k as u64;
+            }
+        }
+    }
+
+    fn h(&self, x: f64) -> f64 {
+        (-self.exponent * x.ln()).exp()
+    }
+
+    fn h_integral(&self, x: f64) -> f64 {
+        let log_x = x.ln();
+        helper2((1.0 - self.exponent) * log_x) * log_x
+    }
+
+    fn h_integral_inverse(&self, x: f64) -> f64 {
+        let t = (x * (1.0 - self.exponent)).max(-1.0);
+        (helper1(t) * x).exp()
+    }
+}
+
+/// `ln(1 + x) / x`, continuous at 0
+fn helper1(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.ln_1p() / x
+    } else {
+        1.0 - x / 2.0
+    }
+}
+
+/// `(e^x - 1) / x`, continuous at 0
+fn helper2(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.exp_m1() / x
+    } else {
+        1.0 + x / 2.0
+    }
+}
+
+// == Value Sizes ==
+/// Value sizes in bytes: `100` for a fixed size, `64-4096` for sizes drawn
+/// uniformly from a range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueSize {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl ValueSize {
+    pub fn next(&self, rng: &mut Rng) -> usize {
+        self.min + rng.below((self.max - self.min + 1) as u64) as usize
+    }
+}
+
+impl FromStr for ValueSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid value size '{}'", s))
+        };
+        let (min, max) = match s.split_once('-') {
+            Some((min, max)) => (parse(min)?, parse(max)?),
+            None => (parse(s)?, parse(s)?),
+        };
+        if min > max {
+            return Err(format!("invalid value size '{}': minimum above maximum", s));
+        }
+        Ok(Self { min, max })
+    }
+}
+
+impl fmt::Display for ValueSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            write!(f, "{} bytes", self.min)
+        } else {
+            write!(f, "{}-{} bytes", self.min, self.max)
+        }
+    }
+}
+
+// == Operations ==
+/// One operation of the workload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Get(String),
+    Set(String, usize),
+}
+
+/// Turns random numbers into operations.
+#[derive(Debug, Clone)]
+pub struct Workload {
+    /// Share of reads, from 0 to 1
+    pub read_ratio: f64,
+    pub keys: KeyChooser,
+    pub value_size: ValueSize,
+}
+
+impl Workload {
+    pub fn next(&self, rng: &mut Rng) -> Op {
+        let key = key_name(self.keys.next(rng));
+        if rng.next_f64() < self.read_ratio {
+            Op::Get(key)
+        } else {
+            Op::Set(key, self.value_size.next(rng))
+        }
+    }
+}
+
+/// Name of the key with index `index`
+pub fn key_name(index: u64) -> String {
+    format!("key:{}", index)
+}
+
+/// A value of `size` bytes; slices of one string avoid generating data per
+/// request.
+pub fn value_pool(size: usize) -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = Rng::new(size as u64);
+    (0..size)
+        .map(|_| ALPHABET[rng.below(ALPHABET.len() as u64) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_covers_key_space() {
+        let chooser = KeyChooser::new(Distribution::Uniform, 10, 0.0);
+        let mut rng = Rng::new(1);
+        let mut counts = [0; 10];
+        for _ in 0..100_000 {
+            counts[chooser.next(&mut rng) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| (9_000..11_000).contains(&count)));
+    }
+
+    #[test]
+    fn test_zipf_frequencies() {
+        let zipf = Zipf::new(1000, 1.0);
+        let mut rng = Rng::new(7);
+        let mut counts = vec![0u32; 1001];
+        let samples = 200_000;
+        for _ in 0..samples {
+            let rank = zipf.sample(&mut rng);
+            assert!((1..=1000).contains(&rank));
+            counts[rank as usize] += 1;
+        }
+        // P(k) = 1 / (k * H_1000), with H_1000 ~ 7.485
+        for rank in [1, 2, 10] {
+            let expected = samples as f64 / (rank as f64 * 7.485);
+            let actual = counts[rank] as f64;
+            assert!(
+                (actual - expected).abs() < expected * 0.05,
+                "rank {}: {} vs {}",
+                rank,
+                actual,
+                expected
+            );
+        }
+
+        // Steeper exponents concentrate more on the top key
+        let steep = Zipf::new(1000, 1.5);
+        let top = (0..10_000).filter(|_| steep.sample(&mut rng) == 1).count();
+        assert!(top > 3_000, "{}", top);
+    }
+
+    #[test]
+    fn test_value_size_parsing() {
+        assert_eq!("100".parse(), Ok(ValueSize { min: 100, max: 100 }));
+        assert_eq!("64-4096".parse(), Ok(ValueSize { min: 64, max: 4096 }));
+        assert!("10-5".parse::<ValueSize>().is_err());
+        assert!("big".parse::<ValueSize>().is_err());
+
+        let size = ValueSize { min: 1, max: 3 };
+        let mut rng = Rng::new(3);
+        assert!((0..1000).all(|_| (1..=3).contains(&size.next(&mut rng))));
+    }
+
+    #[test]
+    fn test_read_ratio() {
+        let workload = Workload {
+            read_ratio: 0.9,
+            keys: KeyChooser::new(Distribution::Uniform, 100, 0.0),
+            value_size: ValueSize { min: 10, max: 10 },
+        };
+        let mut rng = Rng::new(5);
+        let reads = (0..10_000)
+            .filter(|_| matches!(workload.next(&mut rng), Op::Get(_)))
+            .count();
+        assert!((8_800..9_200).contains(&reads), "{}", reads);
+    }
+}
@@ -0,0 +1,276 @@
+//! Command Parsing
+//!
+//! Splits an input line into words, with shell-like quoting, and turns the
+//! words into a command. The same syntax is used at the prompt, in one-shot
+//! mode and in pipe mode.
+
+// == Command ==
+/// A command the CLI can send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Get {
+        key: String,
+    },
+    Set {
+        key: String,
+        value: String,
+        /// TTL in seconds, the server's default if None
+        ttl: Option<u64>,
+    },
+    Del {
+        keys: Vec<String>,
+    },
+    Ttl {
+        key: String,
+    },
+    Scan {
+        cursor: String,
+        pattern: Option<String>,
+        count: Option<usize>,
+    },
+    Stats,
+    Ping,
+    Help,
+    Quit,
+}
+
+/// Command names with their syntax, for help and completion
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("get", "get <key>"),
+    ("set", "set <key> <value> [--ttl <seconds>]"),
+    ("del", "del <key> [<key> ...]"),
+    ("ttl", "ttl <key>"),
+    ("scan", "scan [<cursor>] [match <pattern>] [count <n>]"),
+    ("stats", "stats"),
+    ("ping", "ping"),
+    ("help", "help"),
+    ("quit", "quit"),
+];
+
+/// Options each command takes after its arguments, for completion
+pub fn options_of(command: &str) -> &'static [&'static str] {
+    match command {
+        "set" => &["--ttl"],
+        "scan" => &["match", "count"],
+        _ => &[],
+    }
+}
+
+impl Command {
+    /// Parses the words of a line. Command names and options are
+    /// case-insensitive, as in Redis.
+    pub fn parse(words: &[String]) -> Result<Self, String> {
+        let Some((name, args)) = words.split_first() else {
+            return Err("empty command".to_string());
+        };
+        let name = name.to_ascii_lowercase();
+        let command = match name.as_str() {
+            "get" => {
+                let [key] = exact(&name, args)?;
+                Command::Get { key }
+            }
+            "set" => parse_set(args)?,
+            "del" => {
+                if args.is_empty() {
+                    return Err(usage(&name));
+                }
+                Command::Del {
+                    keys: args.to_vec(),
+                }
+            }
+            "ttl" => {
+                let [key] = exact(&name, args)?;
+                Command::Ttl { key }
+            }
+            "scan" => parse_scan(args)?,
+            "stats" => {
+                let [] = exact(&name, args)?;
+                Command::Stats
+            }
+            "ping" => {
+                let [] = exact(&name, args)?;
+                Command::Ping
+            }
+            "help" => Command::Help,
+            "quit" | "exit" => Command::Quit,
+            _ => return Err(format!("unknown command '{}', try 'help'", name)),
+        };
+        Ok(command)
+    }
+}
+
+fn parse_set(args: &[String]) -> Result<Command, String> {
+    let (key, value, options) = match args {
+        [key, value, options @ ..] => (key.clone(), value.clone(), options),
+        _ => return Err(usage("set")),
+    };
+    let ttl = match options {
+        [] => None,
+        [option, seconds] if is_ttl_option(option) => Some(
+            seconds
+                .parse()
+                .map_err(|_| format!("invalid TTL '{}'", seconds))?,
+        ),
+        _ => return Err(usage("set")),
+    };
+    Ok(Command::Set { key, value, ttl })
+}
+
+/// `--ttl`, or Redis' `EX`
+fn is_ttl_option(option: &str) -> bool {
+    option == "--ttl" || option.eq_ignore_ascii_case("ex")
+}
+
+fn parse_scan(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter().peekable();
+    let cursor = match args.peek() {
+        Some(word) if word.chars().all(|c| c.is_ascii_digit()) => args.next().unwrap().clone(),
+        _ => "0".to_string(),
+    };
+    let (mut pattern, mut count) = (None, None);
+    while let Some(option) = args.next() {
+        let value = args.next().ok_or_else(|| usage("scan"))?;
+        match option.to_ascii_lowercase().as_str() {
+            "match" => pattern = Some(value.clone()),
+            "count" => {
+                count = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid count '{}'", value))?,
+                )
+            }
+            _ => return Err(usage("scan")),
+        }
+    }
+    Ok(Command::Scan {
+        cursor,
+        pattern,
+        count,
+    })
+}
+
+/// Checks that a command got exactly `N` arguments.
+fn exact<const N: usize>(name: &str, args: &[String]) -> Result<[String; N], String> {
+    <[String; N]>::try_from(args.to_vec()).map_err(|_| usage(name))
+}
+
+fn usage(name: &str) -> String {
+    let syntax = COMMANDS
+        .iter()
+        .find(|(command, _)| *command == name)
+        .map_or(name, |(_, syntax)| syntax);
+    format!("usage: {}", syntax)
+}
+
+// == Line Splitting ==
+/// Splits a line into words at whitespace. Single quotes keep everything
+/// up to the closing quote; double quotes also allow `\"` and `\\`
+/// escapes.
+pub fn split(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unbalanced quotes".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some('n') => word.push('\n'),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unbalanced quotes".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unbalanced quotes".to_string()),
+                    }
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Command, String> {
+        Command::parse(&split(line).unwrap())
+    }
+
+    #[test]
+    fn test_split_quotes() {
+        assert_eq!(split("  set a  b ").unwrap(), vec!["set", "a", "b"]);
+        assert_eq!(
+            split(r#"set 'user 1' "say \"hi\"\n" x''y"#).unwrap(),
+            vec!["set", "user 1", "say \"hi\"\n", "xy"]
+        );
+        assert_eq!(split(r#"set k """#).unwrap(), vec!["set", "k", ""]);
+        assert!(split("get 'key").is_err());
+        assert!(split("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            parse("SET foo bar --ttl 60"),
+            Ok(Command::Set {
+                key: "foo".to_string(),
+                value: "bar".to_string(),
+                ttl: Some(60),
+            })
+        );
+        assert_eq!(
+            parse("set foo bar ex 5").unwrap(),
+            parse("set foo bar --ttl 5").unwrap()
+        );
+        assert_eq!(
+            parse("del a b"),
+            Ok(Command::Del {
+                keys: vec!["a".to_string(), "b".to_string()],
+            })
+        );
+        assert_eq!(
+            parse("scan 17 MATCH user:* count 100"),
+            Ok(Command::Scan {
+                cursor: "17".to_string(),
+                pattern: Some("user:*".to_string()),
+                count: Some(100),
+            })
+        );
+        assert_eq!(
+            parse("scan match user:*"),
+            Ok(Command::Scan {
+                cursor: "0".to_string(),
+                pattern: Some("user:*".to_string()),
+                count: None,
+            })
+        );
+        assert_eq!(parse("exit"), Ok(Command::Quit));
+
+        assert_eq!(parse("get"), Err("usage: get <key>".to_string()));
+        assert!(parse("set foo bar --ttl soon").is_err());
+        assert!(parse("scan count").is_err());
+        assert!(parse("stats now").is_err());
+        assert!(parse("flushall").unwrap_err().contains("unknown command"));
+    }
+}
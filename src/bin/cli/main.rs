@@ -0,0 +1,166 @@
+//! mini-redis-cli
+//!
+//! Command-line client for the server. Without a command it opens an
+//! interactive prompt with history and tab completion; with one it runs it
+//! and exits, for scripts (`mini-redis-cli set foo bar --ttl 60`); with
+//! `--pipe` it sends the commands read from stdin, one per line, for bulk
+//! loading.
+
+mod command;
+mod output;
+mod pipe;
+mod repl;
+
+use std::process::ExitCode;
+
+use clap::Parser;
+use mini_redis::client::{Client, ClientError, ClientOptions};
+use mini_redis::models::SetRequest;
+
+use command::{split, Command, COMMANDS};
+use output::{render_error, Format, Reply};
+
+/// Command-line options
+#[derive(Debug, Parser)]
+#[command(
+    name = "mini-redis-cli",
+    version,
+    about = "Command-line client for mini_redis"
+)]
+struct Args {
+    /// Server to connect to
+    #[arg(
+        short = 'u',
+        long,
+        env = "MINI_REDIS_URL",
+        default_value = "http://127.0.0.1:3000"
+    )]
+    url: String,
+
+    /// Bearer token to authenticate with
+    #[arg(short = 'a', long, env = "MINI_REDIS_AUTH_TOKEN")]
+    auth_token: Option<String>,
+
+    /// Named database to use instead of the default one
+    #[arg(short = 'n', long)]
+    db: Option<String>,
+
+    /// How replies are printed
+    #[arg(short, long, value_enum, default_value_t = Format::Human)]
+    format: Format,
+
+    /// Send the commands read from stdin, one per line, and report how
+    /// many succeeded
+    #[arg(long, conflicts_with = "command")]
+    pipe: bool,
+
+    /// Commands in flight at once in pipe mode
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u32).range(1..))]
+    pipeline: u32,
+
+    /// Command to run instead of opening the prompt
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let options = ClientOptions {
+        auth_token: args.auth_token.clone(),
+        database: args.db.clone(),
+        ..ClientOptions::default()
+    };
+    let client = match Client::with_options(&args.url, options) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("{}", render_error(&e.to_string(), args.format));
+            return ExitCode::FAILURE;
+        }
+    };
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start the runtime");
+
+    if args.pipe {
+        runtime.block_on(pipe::run(client, args.pipeline as usize, args.format))
+    } else if args.command.is_empty() {
+        repl::run(&runtime, &client, args.db.as_deref(), args.format)
+    } else {
+        let result = Command::parse(&args.command).and_then(|command| {
+            runtime
+                .block_on(execute(&client, command))
+                .map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(reply) => {
+                println!("{}", reply.render(args.format));
+                ExitCode::SUCCESS
+            }
+            Err(message) => {
+                eprintln!("{}", render_error(&message, args.format));
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+// == Execution ==
+/// Sends a command and returns its reply. Quit has none; callers stop
+/// before sending it.
+async fn execute(client: &Client, command: Command) -> Result<Reply, ClientError> {
+    let reply = match command {
+        Command::Get { key } => {
+            let value = client.get(&key).await?.map(|response| response.value);
+            Reply::Value(key, value)
+        }
+        Command::Set { key, value, ttl } => {
+            let mut request = SetRequest::new(key, value);
+            request.ttl = ttl;
+            Reply::Stored(client.set(&request).await?.key)
+        }
+        Command::Del { keys } => {
+            let mut deleted = 0;
+            for key in &keys {
+                if client.delete(key).await? {
+                    deleted += 1;
+                }
+            }
+            Reply::Deleted(deleted)
+        }
+        Command::Ttl { key } => {
+            let ttl = client.ttl(&key).await?;
+            Reply::Ttl(key, ttl)
+        }
+        Command::Scan {
+            cursor,
+            pattern,
+            count,
+        } => Reply::Scan(client.scan(&cursor, pattern.as_deref(), count).await?),
+        Command::Stats => Reply::Stats(client.stats().await?),
+        Command::Ping => {
+            client.health().await?;
+            Reply::Pong
+        }
+        Command::Help | Command::Quit => Reply::Text(help()),
+    };
+    Ok(reply)
+}
+
+/// Syntax of every command.
+fn help() -> String {
+    COMMANDS
+        .iter()
+        .map(|(_, syntax)| *syntax)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses one line of input; None for blank lines.
+fn parse_line(line: &str) -> Option<Result<Command, String>> {
+    match split(line) {
+        Ok(words) if words.is_empty() => None,
+        Ok(words) => Some(Command::parse(&words)),
+        Err(e) => Some(Err(e)),
+    }
+}
@@ -0,0 +1,210 @@
+//! Output Formats
+//!
+//! Prints replies for people (`human`, in the style of redis-cli), for
+//! programs (`json`, the server's response bodies), or bare (`raw`, one
+//! value per line for shell pipelines).
+
+use clap::ValueEnum;
+use mini_redis::models::{ScanResponse, StatsResponse, TtlResponse};
+use serde_json::{json, Value};
+
+/// How replies are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Human,
+    Json,
+    Raw,
+}
+
+// == Reply ==
+/// The outcome of a command.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// Value of a GET, None if the key does not exist
+    Value(String, Option<String>),
+    /// A SET succeeded
+    Stored(String),
+    /// Number of keys a DEL removed
+    Deleted(usize),
+    /// TTL of a key, None if the key does not exist
+    Ttl(String, Option<TtlResponse>),
+    Scan(ScanResponse),
+    Stats(StatsResponse),
+    Pong,
+    /// Free text, such as help
+    Text(String),
+}
+
+impl Reply {
+    /// Renders the reply, without a trailing newline.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Human => self.human(),
+            Format::Json => self.json().to_string(),
+            Format::Raw => self.raw(),
+        }
+    }
+
+    fn human(&self) -> String {
+        match self {
+            Reply::Value(_, Some(value)) => quote(value),
+            Reply::Value(_, None) => "(nil)".to_string(),
+            Reply::Stored(_) | Reply::Pong => self.raw(),
+            Reply::Deleted(_) | Reply::Ttl(..) => format!("(integer) {}", self.raw()),
+            Reply::Scan(scan) => {
+                let mut lines = vec![format!("1) {}", quote(&scan.cursor))];
+                if scan.keys.is_empty() {
+                    lines.push("2) (empty array)".to_string());
+                }
+                let width = scan.keys.len().to_string().len();
+                for (i, key) in scan.keys.iter().enumerate() {
+                    let prefix = if i == 0 { "2) " } else { "   " };
+                    lines.push(format!("{}{:>width$}) {}", prefix, i + 1, quote(key)));
+                }
+                lines.join("\n")
+            }
+            Reply::Stats(_) => self.raw().replace(':', ": "),
+            Reply::Text(text) => text.clone(),
+        }
+    }
+
+    fn raw(&self) -> String {
+        match self {
+            Reply::Value(_, value) => value.clone().unwrap_or_default(),
+            Reply::Stored(_) => "OK".to_string(),
+            Reply::Deleted(count) => count.to_string(),
+            // Seconds as Redis `TTL`: -1 without expiry, -2 if missing
+            Reply::Ttl(
+                _,
+                Some(TtlResponse {
+                    ttl_ms: Some(ttl), ..
+                }),
+            ) => ttl.div_ceil(1000).to_string(),
+            Reply::Ttl(_, Some(_)) => "-1".to_string(),
+            Reply::Ttl(_, None) => "-2".to_string(),
+            Reply::Scan(scan) => std::iter::once(&scan.cursor)
+                .chain(&scan.keys)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Reply::Stats(stats) => [
+                ("hits", stats.hits),
+                ("misses", stats.misses),
+                ("evictions", stats.evictions),
+                ("total_entries", stats.total_entries as u64),
+                ("acl_denials", stats.acl_denials),
+                ("rate_limited", stats.rate_limited),
+            ]
+            .iter()
+            .map(|(name, value)| format!("{}:{}", name, value))
+            .chain(std::iter::once(format!("hit_rate:{:.2}", stats.hit_rate)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+            Reply::Pong => "PONG".to_string(),
+            Reply::Text(text) => text.clone(),
+        }
+    }
+
+    fn json(&self) -> Value {
+        match self {
+            Reply::Value(key, value) => json!({ "key": key, "value": value }),
+            Reply::Stored(key) => json!({ "key": key, "stored": true }),
+            Reply::Deleted(count) => json!({ "deleted": count }),
+            Reply::Ttl(key, ttl) => json!({
+                "key": key,
+                "exists": ttl.is_some(),
+                "ttl_ms": ttl.as_ref().and_then(|ttl| ttl.ttl_ms),
+            }),
+            Reply::Scan(scan) => json!(scan),
+            Reply::Stats(stats) => json!(stats),
+            Reply::Pong => json!({ "status": "healthy" }),
+            Reply::Text(text) => json!({ "text": text }),
+        }
+    }
+}
+
+/// Renders an error message in `format`.
+pub fn render_error(message: &str, format: Format) -> String {
+    match format {
+        Format::Human => format!("(error) {}", message),
+        Format::Json => json!({ "error": message }).to_string(),
+        Format::Raw => message.to_string(),
+    }
+}
+
+/// Quotes a string as redis-cli does, escaping quotes, backslashes and
+/// control characters.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ttl(ms: Option<u64>) -> Reply {
+        Reply::Ttl(
+            "key".to_string(),
+            Some(TtlResponse {
+                key: "key".to_string(),
+                ttl_ms: ms,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_human_output() {
+        let value = Reply::Value("key".to_string(), Some("say \"hi\"\n".to_string()));
+        assert_eq!(value.render(Format::Human), r#""say \"hi\"\n""#);
+        assert_eq!(
+            Reply::Value("key".to_string(), None).render(Format::Human),
+            "(nil)"
+        );
+        assert_eq!(Reply::Deleted(2).render(Format::Human), "(integer) 2");
+        assert_eq!(ttl(Some(59_001)).render(Format::Human), "(integer) 60");
+
+        let scan = Reply::Scan(ScanResponse {
+            cursor: "0".to_string(),
+            keys: (1..=10).map(|i| format!("k{}", i)).collect(),
+        });
+        let lines: Vec<String> = scan
+            .render(Format::Human)
+            .lines()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(lines[0], r#"1) "0""#);
+        assert_eq!(lines[1], r#"2)  1) "k1""#);
+        assert_eq!(lines[10], r#"   10) "k10""#);
+    }
+
+    #[test]
+    fn test_raw_and_json_output() {
+        let value = Reply::Value("key".to_string(), Some("bar".to_string()));
+        assert_eq!(value.render(Format::Raw), "bar");
+        assert_eq!(value.render(Format::Json), r#"{"key":"key","value":"bar"}"#);
+        assert_eq!(ttl(None).render(Format::Raw), "-1");
+        assert_eq!(
+            Reply::Ttl("key".to_string(), None).render(Format::Raw),
+            "-2"
+        );
+        assert_eq!(
+            Reply::Ttl("key".to_string(), None).render(Format::Json),
+            r#"{"exists":false,"key":"key","ttl_ms":null}"#
+        );
+        assert_eq!(render_error("boom", Format::Json), r#"{"error":"boom"}"#);
+    }
+}
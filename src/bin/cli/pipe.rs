@@ -0,0 +1,104 @@
+//! Pipe Mode
+//!
+//! Sends the commands read from stdin, one per line, keeping up to
+//! `pipeline` of them in flight, and prints how many succeeded instead of
+//! every reply. Failed lines are reported on stderr with their line number.
+
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use mini_redis::client::Client;
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::command::Command;
+use crate::output::{render_error, Format};
+use crate::{execute, parse_line};
+
+/// Sends every line of stdin; fails if any command failed.
+pub async fn run(client: Client, pipeline: usize, format: Format) -> ExitCode {
+    let slots = Arc::new(Semaphore::new(pipeline));
+    let mut tasks = JoinSet::new();
+    let (mut replies, mut errors) = (0u64, 0u64);
+    let report = |number: usize, message: &str| {
+        eprintln!(
+            "{}",
+            render_error(&format!("line {}: {}", number, message), format)
+        );
+    };
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut number = 0;
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                report(number + 1, &e.to_string());
+                errors += 1;
+                break;
+            }
+        };
+        number += 1;
+        let command = match parse_line(&line) {
+            None | Some(Ok(Command::Help | Command::Quit)) => continue,
+            Some(Ok(command)) => command,
+            Some(Err(message)) => {
+                report(number, &message);
+                errors += 1;
+                continue;
+            }
+        };
+
+        let permit = slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore closed");
+        let client = client.clone();
+        tasks.spawn(async move {
+            let result = execute(&client, command).await;
+            drop(permit);
+            (number, result)
+        });
+        // Collect finished commands so the set stays small
+        while let Some(done) = tasks.try_join_next() {
+            tally(done, &mut replies, &mut errors, &report);
+        }
+    }
+    while let Some(done) = tasks.join_next().await {
+        tally(done, &mut replies, &mut errors, &report);
+    }
+
+    match format {
+        Format::Json => println!("{}", json!({ "replies": replies, "errors": errors })),
+        Format::Human | Format::Raw => println!("errors: {}, replies: {}", errors, replies),
+    }
+    if errors == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Counts a finished command.
+fn tally<T, E: std::fmt::Display>(
+    done: Result<(usize, Result<T, E>), tokio::task::JoinError>,
+    replies: &mut u64,
+    errors: &mut u64,
+    report: &impl Fn(usize, &str),
+) {
+    match done {
+        Ok((_, Ok(_))) => *replies += 1,
+        Ok((number, Err(e))) => {
+            report(number, &e.to_string());
+            *errors += 1;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            *errors += 1;
+        }
+    }
+}
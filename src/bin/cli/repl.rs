@@ -0,0 +1,158 @@
+//! Interactive Prompt
+//!
+//! Reads commands with line editing, keeps their history across sessions
+//! and completes command names and options with tab.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use mini_redis::client::Client;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use tokio::runtime::Runtime;
+
+use crate::command::{options_of, Command, COMMANDS};
+use crate::output::{render_error, Format};
+use crate::{execute, help, parse_line};
+
+/// File in the home directory holding the prompt's history
+const HISTORY_FILE: &str = ".mini_redis_cli_history";
+
+/// Runs the prompt until `quit` or end of input.
+pub fn run(runtime: &Runtime, client: &Client, db: Option<&str>, format: Format) -> ExitCode {
+    let mut editor: Editor<CommandCompleter, FileHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("{}", render_error(&e.to_string(), format));
+            return ExitCode::FAILURE;
+        }
+    };
+    editor.set_helper(Some(CommandCompleter));
+    let history = history_path();
+    if let Some(path) = &history {
+        // Missing on first use
+        let _ = editor.load_history(path);
+    }
+
+    let address = client.url().trim_start_matches("http://");
+    let prompt = match db {
+        Some(db) => format!("{}[{}]> ", address, db),
+        None => format!("{}> ", address),
+    };
+    loop {
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            // Ctrl-C drops the line, as in a shell
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("{}", render_error(&e.to_string(), format));
+                break;
+            }
+        };
+        let Some(command) = parse_line(&line) else {
+            continue;
+        };
+        let _ = editor.add_history_entry(line.trim());
+        match command {
+            Ok(Command::Quit) => break,
+            Ok(Command::Help) => println!("{}", help()),
+            Ok(command) => match runtime.block_on(execute(client, command)) {
+                Ok(reply) => println!("{}", reply.render(format)),
+                Err(e) => println!("{}", render_error(&e.to_string(), format)),
+            },
+            Err(message) => println!("{}", render_error(&message, format)),
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Err(e) = editor.save_history(path) {
+            eprintln!("Could not save history to {}: {}", path.display(), e);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+// == Completion ==
+/// Completes the command name in the first word, and the command's
+/// options in later ones.
+struct CommandCompleter;
+
+impl Completer for CommandCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..];
+        let candidates = match line[..start].split_whitespace().next() {
+            None => completions(word, COMMANDS.iter().map(|(name, _)| *name)),
+            Some(command) => completions(
+                word,
+                options_of(&command.to_ascii_lowercase()).iter().copied(),
+            ),
+        };
+        Ok((start, candidates))
+    }
+}
+
+fn completions<'a>(prefix: &str, words: impl Iterator<Item = &'a str>) -> Vec<Pair> {
+    let prefix = prefix.to_ascii_lowercase();
+    words
+        .filter(|word| word.starts_with(&prefix))
+        .map(|word| Pair {
+            display: word.to_string(),
+            replacement: format!("{} ", word),
+        })
+        .collect()
+}
+
+impl Hinter for CommandCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for CommandCompleter {}
+
+impl Validator for CommandCompleter {}
+
+impl Helper for CommandCompleter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustyline::history::DefaultHistory;
+
+    fn complete(line: &str) -> (usize, Vec<String>) {
+        let history = DefaultHistory::new();
+        let (start, pairs) = CommandCompleter
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap();
+        (start, pairs.into_iter().map(|pair| pair.display).collect())
+    }
+
+    #[test]
+    fn test_completion() {
+        assert_eq!(
+            complete("s"),
+            (0, vec!["set".to_string(), "scan".into(), "stats".into()])
+        );
+        assert_eq!(complete("GE"), (0, vec!["get".to_string()]));
+        assert_eq!(complete("scan 0 m"), (7, vec!["match".to_string()]));
+        assert_eq!(complete("set k v --"), (8, vec!["--ttl".to_string()]));
+        assert!(complete("get ke").1.is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+//! Logical Databases
+//!
+//! Named, independent cache stores held by one server, each with its own
+//! capacity, default TTL and statistics. Operations spanning two databases
+//! (MOVE, SWAPDB) lock them in a fixed order so they cannot deadlock.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use crate::cache::CacheStore;
+use crate::error::{CacheError, Result};
+
+/// Name of the database used when a request does not select one
+pub const DEFAULT_DATABASE: &str = "0";
+
+// == Database Config ==
+/// Settings of an additional database; unset limits fall back to the
+/// server-wide `max_entries` and `default_ttl`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Name used in `/db/:db/...` paths and the `X-Database` header
+    pub name: String,
+    /// Maximum number of entries
+    pub max_entries: Option<usize>,
+    /// Default TTL in seconds
+    pub default_ttl: Option<u64>,
+}
+
+impl DatabaseConfig {
+    /// Parses a comma-separated list of `name=max_entries/default_ttl`
+    /// entries, where the limits are optional, e.g.
+    /// `staging=10000/60,sessions=5000,scratch`.
+    pub fn parse_list(value: &str) -> std::result::Result<Vec<DatabaseConfig>, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// Returns true if `name` is usable as a path segment.
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+}
+
+impl FromStr for DatabaseConfig {
+    type Err = String;
+
+    fn from_str(spec: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid database '{}', expected name=max_entries/default_ttl",
+                spec
+            )
+        };
+        let (name, limits) = match spec.split_once('=') {
+            Some((name, limits)) => (name.trim(), Some(limits.trim())),
+            None => (spec.trim(), None),
+        };
+
+        let (max_entries, default_ttl) = match limits {
+            None => (None, None),
+            Some(limits) => {
+                let (max_entries, default_ttl) = match limits.split_once('/') {
+                    Some((max_entries, ttl)) => (max_entries, Some(ttl)),
+                    None => (limits, None),
+                };
+                let max_entries = match max_entries.trim() {
+                    "" => None,
+                    value => Some(value.parse().map_err(|_| invalid())?),
+                };
+                let default_ttl = match default_ttl {
+                    Some(ttl) => Some(ttl.trim().parse().map_err(|_| invalid())?),
+                    None => None,
+                };
+                (max_entries, default_ttl)
+            }
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            max_entries,
+            default_ttl,
+        })
+    }
+}
+
+// == Databases ==
+/// The databases of a server, in configuration order with the default
+/// database first.
+#[derive(Debug, Clone)]
+pub struct Databases {
+    stores: Vec<(String, Arc<RwLock<CacheStore>>)>,
+}
+
+impl Databases {
+    // == Constructor ==
+    /// Creates the set with `default` as the default database.
+    pub fn new(default: Arc<RwLock<CacheStore>>) -> Self {
+        Self {
+            stores: vec![(DEFAULT_DATABASE.to_string(), default)],
+        }
+    }
+
+    /// Adds a database; names must be unique.
+    pub fn add(&mut self, name: impl Into<String>, store: CacheStore) -> Result<()> {
+        let name = name.into();
+        if self.get(&name).is_some() {
+            return Err(CacheError::InvalidRequest(format!(
+                "Database '{}' already exists",
+                name
+            )));
+        }
+        self.stores.push((name, Arc::new(RwLock::new(store))));
+        Ok(())
+    }
+
+    // == Lookup ==
+    /// Returns the store of the database called `name`.
+    pub fn get(&self, name: &str) -> Option<&Arc<RwLock<CacheStore>>> {
+        self.position(name).map(|index| &self.stores[index].1)
+    }
+
+    /// Returns the default database's store.
+    pub fn default_store(&self) -> &Arc<RwLock<CacheStore>> {
+        &self.stores[0].1
+    }
+
+    /// Iterates over (name, store) pairs in configuration order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<RwLock<CacheStore>>)> {
+        self.stores
+            .iter()
+            .map(|(name, store)| (name.as_str(), store))
+    }
+
+    /// Returns the number of databases, including the default one.
+    pub fn len(&self) -> usize {
+        self.stores.len()
+    }
+
+    /// Always false: the default database always exists.
+    pub fn is_empty(&self) -> bool {
+        self.stores.is_empty()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.stores.iter().position(|(n, _)| n == name)
+    }
+
+    fn require(&self, name: &str) -> Result<usize> {
+        self.position(name)
+            .ok_or_else(|| CacheError::InvalidRequest(format!("Unknown database '{}'", name)))
+    }
+
+    // == Move ==
+    /// Moves `key` with its TTL from database `from` to database `to`.
+    ///
+    /// Returns false, leaving both databases unchanged, if `to` already
+    /// holds the key.
+    pub async fn move_key(&self, from: &str, to: &str, key: &str) -> Result<bool> {
+        let (from, to) = (self.require(from)?, self.require(to)?);
+        if from == to {
+            return Err(CacheError::InvalidRequest(
+                "Source and destination databases are the same".to_string(),
+            ));
+        }
+
+        // Lock in index order regardless of direction
+        let (mut source, mut target) = if from < to {
+            let source = self.stores[from].1.write().await;
+            (source, self.stores[to].1.write().await)
+        } else {
+            let target = self.stores[to].1.write().await;
+            (self.stores[from].1.write().await, target)
+        };
+
+        if !source.contains_key(key) {
+            return Err(CacheError::NotFound(key.to_string()));
+        }
+        if target.contains_key(key) {
+            return Ok(false);
+        }
+        let entry = source
+            .take(key)
+            .ok_or_else(|| CacheError::NotFound(key.to_string()))?;
+        target.insert_entry(key.to_string(), entry)?;
+        Ok(true)
+    }
+
+    // == Swap ==
+    /// Swaps the contents and statistics of two databases.
+    ///
+    /// Each database keeps its own `max_entries` and default TTL, so the
+    /// smaller one may evict after the swap. Returns the number evicted.
+    pub async fn swap(&self, first: &str, second: &str) -> Result<usize> {
+        let (first, second) = (self.require(first)?, self.require(second)?);
+        if first == second {
+            return Ok(0);
+        }
+
+        let (low, high) = (first.min(second), first.max(second));
+        let mut low = self.stores[low].1.write().await;
+        let mut high = self.stores[high].1.write().await;
+
+        low.swap_contents(&mut high);
+        let (low_max, high_max) = (low.max_entries(), high.max_entries());
+        Ok(low.set_max_entries(low_max) + high.set_max_entries(high_max))
+    }
+
+    /// Swaps the contents of two databases without enforcing their
+    /// capacities, for followers replaying a leader's SWAPDB whose
+    /// evictions arrive as separate commands.
+    pub async fn swap_contents(&self, first: &str, second: &str) -> Result<()> {
+        let (first, second) = (self.require(first)?, self.require(second)?);
+        if first == second {
+            return Ok(());
+        }
+
+        let (low, high) = (first.min(second), first.max(second));
+        let mut low = self.stores[low].1.write().await;
+        let mut high = self.stores[high].1.write().await;
+        low.swap_contents(&mut high);
+        Ok(())
+    }
+
+    // == Flush ==
+    /// Empties one database, returning how many entries were removed.
+    ///
+    /// The old entries are freed on the blocking pool; dropping millions of
+    /// them would otherwise stall the runtime thread.
+    pub async fn flush(&self, name: &str, reset_stats: bool) -> Result<usize> {
+        let index = self.require(name)?;
+        let old = self.stores[index].1.write().await.flush(reset_stats);
+        let removed = old.len();
+        drop_in_background(vec![old]);
+        Ok(removed)
+    }
+
+    /// Empties every database, returning how many entries were removed.
+    pub async fn flush_all(&self, reset_stats: bool) -> usize {
+        let mut old = Vec::with_capacity(self.stores.len());
+        for (_, store) in &self.stores {
+            old.push(store.write().await.flush(reset_stats));
+        }
+        let removed = old.iter().map(CacheStore::len).sum();
+        drop_in_background(old);
+        removed
+    }
+}
+
+/// Frees flushed stores on the blocking pool.
+fn drop_in_background(stores: Vec<CacheStore>) {
+    tokio::task::spawn_blocking(move || drop(stores));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn databases() -> Databases {
+        let mut databases = Databases::new(Arc::new(RwLock::new(CacheStore::new(100, 300))));
+        databases.add("staging", CacheStore::new(2, 60)).unwrap();
+        databases
+    }
+
+    #[test]
+    fn test_parse_database_list() {
+        let specs = DatabaseConfig::parse_list("staging=10000/60, sessions=5000,scratch").unwrap();
+
+        assert_eq!(specs.len(), 3);
+        assert_eq!(specs[0].name, "staging");
+        assert_eq!(specs[0].max_entries, Some(10_000));
+        assert_eq!(specs[0].default_ttl, Some(60));
+        assert_eq!(specs[1].default_ttl, None);
+        assert_eq!(specs[2].max_entries, None);
+        assert!(DatabaseConfig::parse_list("bad=ten").is_err());
+        assert!(DatabaseConfig::is_valid_name("prod-mirror_2"));
+        assert!(!DatabaseConfig::is_valid_name("a/b"));
+    }
+
+    #[test]
+    fn test_duplicate_database_rejected() {
+        let mut databases = databases();
+        assert!(databases.add("staging", CacheStore::new(1, 1)).is_err());
+        assert_eq!(databases.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_move_key() {
+        let databases = databases();
+        databases
+            .default_store()
+            .write()
+            .await
+            .set("key".to_string(), "value".to_string(), None)
+            .unwrap();
+
+        assert!(databases
+            .move_key(DEFAULT_DATABASE, "staging", "key")
+            .await
+            .unwrap());
+        assert!(!databases.default_store().read().await.contains_key("key"));
+        let staging = databases.get("staging").unwrap();
+        assert!(staging.read().await.contains_key("key"));
+
+        // Moving onto an existing key leaves both sides alone
+        databases
+            .default_store()
+            .write()
+            .await
+            .set("key".to_string(), "other".to_string(), None)
+            .unwrap();
+        assert!(!databases
+            .move_key(DEFAULT_DATABASE, "staging", "key")
+            .await
+            .unwrap());
+        assert!(databases.default_store().read().await.contains_key("key"));
+
+        let result = databases.move_key("staging", "staging", "key").await;
+        assert!(matches!(result, Err(CacheError::InvalidRequest(_))));
+        let result = databases.move_key("staging", "missing", "key").await;
+        assert!(matches!(result, Err(CacheError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_swap_keeps_limits() {
+        let databases = databases();
+        {
+            let mut store = databases.default_store().write().await;
+            for i in 0..3 {
+                store
+                    .set(format!("key{}", i), "value".to_string(), None)
+                    .unwrap();
+            }
+        }
+
+        // staging only holds 2 entries, so one of the 3 is evicted
+        let evicted = databases.swap("staging", DEFAULT_DATABASE).await.unwrap();
+        assert_eq!(evicted, 1);
+
+        let default = databases.default_store().read().await;
+        assert!(default.is_empty());
+        assert_eq!(default.max_entries(), 100);
+        let staging = databases.get("staging").unwrap().read().await;
+        assert_eq!(staging.len(), 2);
+        assert_eq!(staging.max_entries(), 2);
+        assert_eq!(staging.default_ttl(), 60);
+    }
+}
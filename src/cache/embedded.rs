@@ -0,0 +1,834 @@
+//! Embedded Cache Module
+//!
+//! `Cache<K, V>` gives programs the store's TTL expiry, LRU eviction and
+//! statistics in-process, without the HTTP layer. It runs on the same
+//! engine as `CacheStore`, but keys and values can be any type and
+//! capacity can be counted in any unit through a weigher.
+//!
+//! ```
+//! use std::time::Duration;
+//! use mini_redis::cache::Cache;
+//!
+//! // Up to 64 MB of values, each kept for 5 minutes
+//! let cache: Cache<u64, Vec<u8>> = Cache::new(64 * 1024 * 1024)
+//!     .with_weigher(|_, value: &Vec<u8>| value.len() as u64)
+//!     .with_default_ttl(Duration::from_secs(300));
+//!
+//! cache.insert(1, vec![0; 1024]).unwrap();
+//! assert_eq!(cache.get(&1), Some(vec![0; 1024]));
+//! assert_eq!(cache.weight(), 1024);
+//! ```
+//!
+//! The handle is cheap to clone and every clone shares the same entries.
+//! Operations lock the cache only while they update it and never across
+//! an `.await`. Threads call `get`, `insert`, `insert_with_ttl` and
+//! `remove`; async tasks call their `_async` versions, which yield to the
+//! runtime instead of blocking its thread while another caller holds the
+//! lock. `spawn_cleanup_task` removes expired entries in the background on
+//! a tokio runtime.
+//!
+//! With a `Loader`, `get_or_load` reads through to the backing store on a
+//! miss. Concurrent misses on one key share a single load, and keys the
+//! loader reports absent are remembered for a short negative TTL, so a
+//! burst of requests for a cold or missing key reaches the backing store
+//! once. `set` and `delete` write through the loader before updating the
+//! cache.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::time::Duration;
+
+use tokio::sync::OnceCell;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::cache::engine::{Engine, Lookup};
+use crate::cache::loader::DynLoader;
+use crate::cache::{current_timestamp_ms, CacheEntry, CacheStats, Loader};
+use crate::error::{CacheError, Result};
+
+/// How long a key the loader reported absent is answered from the cache
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(5);
+
+/// Absent keys remembered at most, least recently used dropped first
+const NEGATIVE_CAPACITY: u64 = 10_000;
+
+/// A load in progress; every caller missing the key waits on the same cell
+type Flight<V> = Arc<OnceCell<std::result::Result<Option<V>, String>>>;
+
+/// Returns when an entry stored now with `ttl` expires (Unix milliseconds),
+/// saturating for TTLs too long to represent.
+fn expires_after(ttl: Duration) -> u64 {
+    let ttl_ms = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+    current_timestamp_ms().saturating_add(ttl_ms)
+}
+
+// == Cache ==
+/// Thread-safe, cloneable handle to an in-process cache.
+pub struct Cache<K, V> {
+    inner: Arc<Mutex<Inner<K, V>>>,
+}
+
+struct Inner<K, V> {
+    engine: Engine<K, V>,
+    /// TTL of entries inserted without one, None = no expiration
+    default_ttl: Option<Duration>,
+    /// Source of missing values and target of written ones
+    loader: Option<Arc<dyn DynLoader<K, V>>>,
+    /// Keys the loader reported absent
+    negative: Engine<K, ()>,
+    negative_ttl: Duration,
+    /// Loads in progress by key
+    flights: HashMap<K, Flight<V>>,
+}
+
+impl<K: Hash + Eq + Clone, V> Inner<K, V> {
+    /// Forgets what is known about `key` besides its entry: a load in
+    /// progress will not store its result, and an absent result is dropped.
+    fn invalidate<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.flights.remove(key);
+        self.negative.remove(key);
+    }
+}
+
+impl<K, V> Clone for Cache<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for Cache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        f.debug_struct("Cache")
+            .field("engine", &inner.engine)
+            .field("default_ttl", &inner.default_ttl)
+            .field("loader", &inner.loader.is_some())
+            .field("negative_ttl", &inner.negative_ttl)
+            .finish()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> {
+    // == Constructor ==
+    /// Creates a cache holding up to `capacity` entries, evicting the least
+    /// recently used beyond that. Entries do not expire unless given a TTL.
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                engine: Engine::new(capacity),
+                default_ttl: None,
+                loader: None,
+                negative: Engine::new(NEGATIVE_CAPACITY),
+                negative_ttl: DEFAULT_NEGATIVE_TTL,
+                flights: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Counts the capacity in the weight `weigher` gives each entry, such
+    /// as its size in bytes, instead of in entries.
+    pub fn with_weigher(self, weigher: impl Fn(&K, &V) -> u64 + Send + Sync + 'static) -> Self {
+        self.lock().engine.set_weigher(Arc::new(weigher));
+        self
+    }
+
+    /// Sets the TTL of entries inserted without one.
+    pub fn with_default_ttl(self, ttl: Duration) -> Self {
+        self.lock().default_ttl = Some(ttl);
+        self
+    }
+
+    /// Reads missing values through `loader` in `get_or_load`, and writes
+    /// through it in `set` and `delete`.
+    pub fn with_loader(self, loader: impl Loader<K, V>) -> Self
+    where
+        K: Sync + 'static,
+        V: Sync + 'static,
+    {
+        self.lock().loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Sets how long keys the loader reported absent are answered without
+    /// asking it again; zero turns negative caching off.
+    pub fn with_negative_ttl(self, ttl: Duration) -> Self {
+        self.lock().negative_ttl = ttl;
+        self
+    }
+
+    // == Read ==
+    /// Returns a clone of the value for `key`, if present and not expired.
+    ///
+    /// Counts as a hit or miss and marks the entry recently used.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        V: Clone,
+    {
+        Self::get_locked(&mut self.lock(), key)
+    }
+
+    /// Async version of `get`.
+    pub async fn get_async<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        V: Clone,
+    {
+        let mut inner = self.lock_async().await;
+        Self::get_locked(&mut inner, key)
+    }
+
+    fn get_locked<Q>(inner: &mut Inner<K, V>, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        V: Clone,
+    {
+        match inner.engine.get(key) {
+            Lookup::Hit(entry) => Some(entry.value.clone()),
+            Lookup::Stale(entry) => Some(entry.value.clone()),
+            Lookup::Miss | Lookup::Expired => None,
+        }
+    }
+
+    /// Returns true if `key` holds an entry that has not expired, without
+    /// counting a read.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.lock().engine.peek(key).is_some()
+    }
+
+    /// Returns the time `key` has left before it expires; `Some(None)` for
+    /// an entry without expiration.
+    pub fn ttl<Q>(&self, key: &Q) -> Option<Option<Duration>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let inner = self.lock();
+        let entry = inner.engine.peek(key)?;
+        Some(entry.ttl_remaining_ms().map(Duration::from_millis))
+    }
+
+    // == Read-Through ==
+    /// Returns the value for `key`, loading it with the loader on a miss
+    /// and caching it with the default TTL.
+    ///
+    /// While a load runs, other callers missing the same key wait for its
+    /// result instead of starting their own. If the loader finds nothing,
+    /// None is cached for the negative TTL. A load failure is returned to
+    /// every waiting caller as `CacheError::Load` and is not cached. Without
+    /// a loader this is `get`.
+    pub async fn get_or_load(&self, key: &K) -> Result<Option<V>>
+    where
+        K: Send + Sync,
+        V: Clone + Send + Sync,
+    {
+        let (flight, loader) = {
+            let mut inner = self.lock_async().await;
+            if let Lookup::Hit(entry) = inner.engine.get(key) {
+                return Ok(Some(entry.value.clone()));
+            }
+            if inner.negative.peek(key).is_some() {
+                return Ok(None);
+            }
+            let Some(loader) = inner.loader.clone() else {
+                return Ok(None);
+            };
+            let flight = inner.flights.entry(key.clone()).or_default().clone();
+            (flight, loader)
+        };
+
+        // If the caller running the load is cancelled, a waiting one
+        // starts it again
+        let result = flight
+            .get_or_init(|| async {
+                let result = loader.load(key).await.map_err(|e| e.to_string());
+                self.finish_load(key, &flight, &result);
+                result
+            })
+            .await;
+        result.clone().map_err(CacheError::Load)
+    }
+
+    /// Caches the result of a load, unless the key was written, removed or
+    /// cleared while it ran.
+    fn finish_load(
+        &self,
+        key: &K,
+        flight: &Flight<V>,
+        result: &std::result::Result<Option<V>, String>,
+    ) where
+        V: Clone,
+    {
+        let mut inner = self.lock();
+        if !inner
+            .flights
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, flight))
+        {
+            return;
+        }
+        inner.flights.remove(key);
+
+        match result {
+            Ok(Some(value)) => {
+                let ttl = inner.default_ttl;
+                if let Err(e) = Self::insert_locked(&mut inner, key.clone(), value.clone(), ttl) {
+                    debug!("Embedded cache did not keep a loaded value: {}", e);
+                }
+            }
+            Ok(None) if !inner.negative_ttl.is_zero() => {
+                let expires_at = expires_after(inner.negative_ttl);
+                let absent = CacheEntry::with_expires_at((), Some(expires_at));
+                // Unit weights always fit
+                let _ = inner.negative.insert(key.clone(), absent);
+            }
+            Ok(None) | Err(_) => {}
+        }
+    }
+
+    // == Write ==
+    /// Stores a value with the default TTL, evicting least recently used
+    /// entries to make room.
+    ///
+    /// Fails with `CacheError::CacheFull` if the value alone weighs more
+    /// than the capacity.
+    pub fn insert(&self, key: K, value: V) -> Result<()> {
+        let mut inner = self.lock();
+        inner.invalidate(&key);
+        let ttl = inner.default_ttl;
+        Self::insert_locked(&mut inner, key, value, ttl)
+    }
+
+    /// Async version of `insert`.
+    pub async fn insert_async(&self, key: K, value: V) -> Result<()> {
+        let mut inner = self.lock_async().await;
+        inner.invalidate(&key);
+        let ttl = inner.default_ttl;
+        Self::insert_locked(&mut inner, key, value, ttl)
+    }
+
+    /// Stores a value that expires after `ttl`.
+    pub fn insert_with_ttl(&self, key: K, value: V, ttl: Duration) -> Result<()> {
+        let mut inner = self.lock();
+        inner.invalidate(&key);
+        Self::insert_locked(&mut inner, key, value, Some(ttl))
+    }
+
+    /// Async version of `insert_with_ttl`.
+    pub async fn insert_with_ttl_async(&self, key: K, value: V, ttl: Duration) -> Result<()> {
+        let mut inner = self.lock_async().await;
+        inner.invalidate(&key);
+        Self::insert_locked(&mut inner, key, value, Some(ttl))
+    }
+
+    fn insert_locked(
+        inner: &mut Inner<K, V>,
+        key: K,
+        value: V,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let expires_at = ttl.map(expires_after);
+        let evicted = inner
+            .engine
+            .insert(key, CacheEntry::with_expires_at(value, expires_at))?;
+        if !evicted.is_empty() {
+            debug!("Embedded cache evicted {} entries", evicted.len());
+        }
+        Ok(())
+    }
+
+    /// Removes `key` and returns its value if it had not expired.
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        Self::remove_locked(&mut self.lock(), key)
+    }
+
+    /// Async version of `remove`.
+    pub async fn remove_async<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let mut inner = self.lock_async().await;
+        Self::remove_locked(&mut inner, key)
+    }
+
+    fn remove_locked<Q>(inner: &mut Inner<K, V>, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        inner.invalidate(key);
+        inner
+            .engine
+            .remove(key)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.value)
+    }
+
+    /// Removes every entry. Statistics are kept.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.engine.clear();
+        inner.negative.clear();
+        inner.flights.clear();
+    }
+
+    // == Write-Through ==
+    /// Writes a value through the loader, then caches it with the default
+    /// TTL. If the write fails the cache is left unchanged. Without a
+    /// loader this is `insert`.
+    pub async fn set(&self, key: K, value: V) -> Result<()> {
+        if let Some(loader) = self.loader() {
+            loader
+                .store(&key, &value)
+                .await
+                .map_err(|e| CacheError::Load(e.to_string()))?;
+        }
+        self.insert_async(key, value).await
+    }
+
+    /// Deletes a key through the loader, then removes it from the cache and
+    /// returns its cached value. If the delete fails the cache is left
+    /// unchanged. Without a loader this is `remove`.
+    pub async fn delete(&self, key: &K) -> Result<Option<V>> {
+        if let Some(loader) = self.loader() {
+            loader
+                .delete(key)
+                .await
+                .map_err(|e| CacheError::Load(e.to_string()))?;
+        }
+        Ok(self.remove_async(key).await)
+    }
+
+    fn loader(&self) -> Option<Arc<dyn DynLoader<K, V>>> {
+        self.lock().loader.clone()
+    }
+
+    // == Expiry ==
+    /// Removes every expired entry and returns how many were removed.
+    ///
+    /// Expired entries are never returned, but without cleanup they hold
+    /// their capacity until read or evicted.
+    pub fn cleanup_expired(&self) -> usize {
+        self.lock().engine.cleanup_expired().len()
+    }
+
+    /// Spawns a tokio task running `cleanup_expired` every `interval`.
+    /// The task stops once every other handle to the cache is dropped.
+    pub fn spawn_cleanup_task(&self, interval: Duration) -> JoinHandle<()>
+    where
+        K: Send + 'static,
+        V: Send + Sync + 'static,
+    {
+        let inner = Arc::downgrade(&self.inner);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+                let removed = Cache { inner }.cleanup_expired();
+                if removed > 0 {
+                    debug!("Embedded cache cleanup removed {} expired entries", removed);
+                }
+            }
+        })
+    }
+
+    // == Capacity ==
+    /// Returns the capacity, in entries or in the weigher's unit.
+    pub fn capacity(&self) -> u64 {
+        self.lock().engine.capacity()
+    }
+
+    /// Changes the capacity, evicting least recently used entries until the
+    /// cache fits. Returns the number of entries evicted.
+    pub fn set_capacity(&self, capacity: u64) -> usize {
+        self.lock().engine.set_capacity(capacity).len()
+    }
+
+    /// Returns the total weight of the stored entries.
+    pub fn weight(&self) -> u64 {
+        self.lock().engine.weight()
+    }
+
+    // == Stats ==
+    pub fn stats(&self) -> CacheStats {
+        self.lock().engine.stats()
+    }
+
+    // == Length ==
+    /// Returns the number of entries, including expired ones not yet
+    /// removed.
+    pub fn len(&self) -> usize {
+        self.lock().engine.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner<K, V>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Takes the lock without blocking the runtime thread, yielding to
+    /// other tasks while another caller holds it.
+    async fn lock_async(&self) -> MutexGuard<'_, Inner<K, V>> {
+        loop {
+            match self.inner.try_lock() {
+                Ok(guard) => return guard,
+                Err(TryLockError::Poisoned(e)) => return e.into_inner(),
+                Err(TryLockError::WouldBlock) => {}
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+// == Unit Tests ==
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::LoadResult;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::thread;
+
+    #[test]
+    fn test_cache_generic_keys_and_values() {
+        let cache: Cache<u32, Vec<u8>> = Cache::new(10);
+        cache.insert(1, vec![1, 2, 3]).unwrap();
+
+        assert_eq!(cache.get(&1), Some(vec![1, 2, 3]));
+        assert_eq!(cache.get(&2), None);
+        assert!(cache.contains_key(&1));
+        assert_eq!(cache.ttl(&1), Some(None));
+        assert_eq!(cache.remove(&1), Some(vec![1, 2, 3]));
+        assert!(cache.is_empty());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+    }
+
+    #[test]
+    fn test_cache_string_keys_borrowed_lookup() {
+        let cache: Cache<String, String> = Cache::new(10);
+        cache
+            .insert("key".to_string(), "value".to_string())
+            .unwrap();
+        assert_eq!(cache.get("key").as_deref(), Some("value"));
+    }
+
+    #[test]
+    fn test_cache_weigher_evicts_lru() {
+        let cache: Cache<&str, String> =
+            Cache::new(10).with_weigher(|_, value: &String| value.len() as u64);
+        cache.insert("a", "x".repeat(4)).unwrap();
+        cache.insert("b", "x".repeat(4)).unwrap();
+        cache.get("a");
+
+        cache.insert("c", "x".repeat(4)).unwrap();
+        assert!(cache.contains_key("a"));
+        assert!(!cache.contains_key("b"));
+        assert_eq!(cache.weight(), 8);
+        assert_eq!(cache.stats().evictions, 1);
+
+        assert!(cache.insert("d", "x".repeat(11)).is_err());
+        assert_eq!(cache.set_capacity(4), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_ttl_expiration() {
+        let cache: Cache<&str, u32> = Cache::new(10).with_default_ttl(Duration::from_millis(50));
+        cache.insert("short", 1).unwrap();
+        cache
+            .insert_with_ttl("long", 2, Duration::from_secs(60))
+            .unwrap();
+        assert!(cache.ttl("short").unwrap().unwrap() <= Duration::from_millis(50));
+
+        thread::sleep(Duration::from_millis(80));
+        assert_eq!(cache.get("short"), None);
+        assert_eq!(cache.get("long"), Some(2));
+        assert_eq!(cache.stats().expired, 1);
+
+        cache.insert("short", 1).unwrap();
+        thread::sleep(Duration::from_millis(80));
+        assert_eq!(cache.cleanup_expired(), 1);
+        assert_eq!(cache.len(), 1);
+
+        // TTLs beyond the clock's range saturate instead of overflowing
+        cache.insert_with_ttl("max", 3, Duration::MAX).unwrap();
+        assert_eq!(cache.get("max"), Some(3));
+        assert!(cache.ttl("max").unwrap().unwrap() > Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_cache_shared_across_threads() {
+        let cache: Cache<u64, u64> = Cache::new(1000);
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        cache.insert(t * 100 + i, i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(cache.len(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_cache_cleanup_task() {
+        let cache: Cache<u32, u32> = Cache::new(10);
+        cache
+            .insert_with_ttl(1, 1, Duration::from_millis(10))
+            .unwrap();
+        let task = cache.spawn_cleanup_task(Duration::from_millis(20));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(cache.len(), 0);
+        assert!(cache.stats().cleanup_runs > 0);
+
+        // The task ends with the last handle
+        drop(cache);
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cache_async_api() {
+        let cache: Cache<String, u32> = Cache::new(10);
+        cache.insert_async("a".to_string(), 1).await.unwrap();
+        cache
+            .insert_with_ttl_async("b".to_string(), 2, Duration::from_millis(30))
+            .await
+            .unwrap();
+
+        assert_eq!(cache.get_async("a").await, Some(1));
+        assert_eq!(cache.get_async("b").await, Some(2));
+        assert_eq!(cache.remove_async("a").await, Some(1));
+        assert_eq!(cache.get_async("a").await, None);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(cache.get_async("b").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_cache_async_api_yields_while_locked() {
+        let cache: Cache<u32, u32> = Cache::new(10);
+        cache.insert(1, 1).unwrap();
+
+        // Another thread holds the lock for a while
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let inner = cache.inner.clone();
+        let holder = thread::spawn(move || {
+            let _guard = inner.lock().unwrap();
+            locked_tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(50));
+        });
+        locked_rx.recv().unwrap();
+
+        // On this single-threaded runtime the other task only runs if the
+        // lookup yields instead of blocking the thread
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = ran.clone();
+        tokio::spawn(async move { flag.store(true, Ordering::SeqCst) });
+
+        assert_eq!(cache.get_async(&1).await, Some(1));
+        assert!(ran.load(Ordering::SeqCst));
+        holder.join().unwrap();
+    }
+
+    // == Loader Tests ==
+
+    /// Backing store counting the calls it gets
+    #[derive(Default)]
+    struct Backend {
+        values: Mutex<HashMap<u32, String>>,
+        loads: AtomicUsize,
+        writes: AtomicUsize,
+        fail: AtomicBool,
+        delay: Duration,
+    }
+
+    impl Backend {
+        fn with(values: &[(u32, &str)], delay: Duration) -> Arc<Self> {
+            let values = values.iter().map(|(k, v)| (*k, v.to_string())).collect();
+            Arc::new(Self {
+                values: Mutex::new(values),
+                delay,
+                ..Self::default()
+            })
+        }
+
+        fn loads(&self) -> usize {
+            self.loads.load(Ordering::SeqCst)
+        }
+
+        fn check(&self) -> LoadResult<()> {
+            if self.fail.load(Ordering::SeqCst) {
+                return Err("backend unavailable".into());
+            }
+            Ok(())
+        }
+    }
+
+    impl Loader<u32, String> for Arc<Backend> {
+        async fn load(&self, key: &u32) -> LoadResult<Option<String>> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.check()?;
+            Ok(self.values.lock().unwrap().get(key).cloned())
+        }
+
+        async fn store(&self, key: &u32, value: &String) -> LoadResult<()> {
+            self.check()?;
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            self.values.lock().unwrap().insert(*key, value.clone());
+            Ok(())
+        }
+
+        async fn delete(&self, key: &u32) -> LoadResult<()> {
+            self.check()?;
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            self.values.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_or_load_coalesces_misses() {
+        let backend = Backend::with(&[(1, "one")], Duration::from_millis(50));
+        let cache: Cache<u32, String> = Cache::new(10).with_loader(backend.clone());
+
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move { cache.get_or_load(&1).await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap().as_deref(), Some("one"));
+        }
+        assert_eq!(backend.loads(), 1);
+
+        // Now cached
+        assert_eq!(cache.get_or_load(&1).await.unwrap().as_deref(), Some("one"));
+        assert_eq!(backend.loads(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_load_caches_absent_keys() {
+        let backend = Backend::with(&[], Duration::ZERO);
+        let cache: Cache<u32, String> = Cache::new(10)
+            .with_loader(backend.clone())
+            .with_negative_ttl(Duration::from_millis(50));
+
+        assert_eq!(cache.get_or_load(&7).await.unwrap(), None);
+        assert_eq!(cache.get_or_load(&7).await.unwrap(), None);
+        assert_eq!(backend.loads(), 1);
+        assert!(!cache.contains_key(&7));
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(cache.get_or_load(&7).await.unwrap(), None);
+        assert_eq!(backend.loads(), 2);
+
+        // Inserting a key forgets that it was absent
+        cache.insert(7, "seven".to_string()).unwrap();
+        assert_eq!(
+            cache.get_or_load(&7).await.unwrap().as_deref(),
+            Some("seven")
+        );
+
+        // Negative TTLs beyond the clock's range saturate
+        let cache: Cache<u32, String> = Cache::new(10)
+            .with_loader(backend.clone())
+            .with_negative_ttl(Duration::MAX);
+        assert_eq!(cache.get_or_load(&8).await.unwrap(), None);
+        assert_eq!(cache.get_or_load(&8).await.unwrap(), None);
+        assert_eq!(backend.loads(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_load_errors_are_not_cached() {
+        let backend = Backend::with(&[(1, "one")], Duration::ZERO);
+        backend.fail.store(true, Ordering::SeqCst);
+        let cache: Cache<u32, String> = Cache::new(10).with_loader(backend.clone());
+
+        let error = cache.get_or_load(&1).await.unwrap_err();
+        assert!(matches!(error, CacheError::Load(ref message) if message == "backend unavailable"));
+
+        backend.fail.store(false, Ordering::SeqCst);
+        assert_eq!(cache.get_or_load(&1).await.unwrap().as_deref(), Some("one"));
+        assert_eq!(backend.loads(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_write_during_load_wins() {
+        let backend = Backend::with(&[(1, "old")], Duration::from_millis(50));
+        let cache: Cache<u32, String> = Cache::new(10).with_loader(backend.clone());
+
+        let loading = {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.get_or_load(&1).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        cache.insert(1, "new".to_string()).unwrap();
+
+        // The load answers its caller but does not replace the newer value
+        assert_eq!(loading.await.unwrap().unwrap().as_deref(), Some("old"));
+        assert_eq!(cache.get(&1).as_deref(), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn test_write_through() {
+        let backend = Backend::with(&[], Duration::ZERO);
+        let cache: Cache<u32, String> = Cache::new(10).with_loader(backend.clone());
+
+        cache.set(1, "one".to_string()).await.unwrap();
+        assert_eq!(cache.get(&1).as_deref(), Some("one"));
+        assert_eq!(
+            backend.values.lock().unwrap().get(&1).map(String::as_str),
+            Some("one")
+        );
+
+        // A failed write leaves the cache as it was
+        backend.fail.store(true, Ordering::SeqCst);
+        assert!(cache.set(1, "uno".to_string()).await.is_err());
+        assert!(cache.delete(&1).await.is_err());
+        assert_eq!(cache.get(&1).as_deref(), Some("one"));
+
+        backend.fail.store(false, Ordering::SeqCst);
+        assert_eq!(cache.delete(&1).await.unwrap().as_deref(), Some("one"));
+        assert!(backend.values.lock().unwrap().is_empty());
+        assert_eq!(backend.writes.load(Ordering::SeqCst), 2);
+    }
+}
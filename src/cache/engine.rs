@@ -0,0 +1,344 @@
+//! Cache Engine Module
+//!
+//! The map, LRU order, weights and statistics shared by `CacheStore` and
+//! the embeddable `Cache`. Callers build the entries and are told which
+//! keys were evicted or expired, so they can keep their own indexes and
+//! replication in step.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::cache::{CacheEntry, CacheStats, LruTracker};
+use crate::error::{CacheError, Result};
+
+/// Returns the weight of an entry, counted against the capacity.
+pub type Weigher<K, V> = Arc<dyn Fn(&K, &V) -> u64 + Send + Sync>;
+
+/// An entry with the weight it was stored at
+#[derive(Debug)]
+struct Weighted<V> {
+    entry: CacheEntry<V>,
+    weight: u64,
+}
+
+/// What a read found
+pub(crate) enum Lookup<'a, V> {
+    /// A live entry, its access already recorded
+    Hit(&'a CacheEntry<V>),
+    /// An expired entry within its grace window, its access already
+    /// recorded
+    Stale(&'a mut CacheEntry<V>),
+    /// No entry
+    Miss,
+    /// An entry past its TTL, now removed
+    Expired,
+}
+
+// == Engine ==
+/// Entries evicted least recently used first once their total weight
+/// exceeds the capacity. Without a weigher every entry weighs 1, so the
+/// capacity is a number of entries.
+pub(crate) struct Engine<K, V> {
+    entries: HashMap<K, Weighted<V>>,
+    lru: LruTracker<K>,
+    stats: CacheStats,
+    weigher: Option<Weigher<K, V>>,
+    /// Total weight of the stored entries
+    weight: u64,
+    capacity: u64,
+}
+
+impl<K, V> fmt::Debug for Engine<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Engine")
+            .field("entries", &self.entries.len())
+            .field("weight", &self.weight)
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Engine<K, V> {
+    // == Constructor ==
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            lru: LruTracker::default(),
+            stats: CacheStats::new(),
+            weigher: None,
+            weight: 0,
+            capacity,
+        }
+    }
+
+    /// Weighs entries with `weigher`, including those already stored.
+    /// Entries are not evicted until the next insert.
+    pub fn set_weigher(&mut self, weigher: Weigher<K, V>) {
+        self.weight = 0;
+        for (key, weighted) in &mut self.entries {
+            weighted.weight = weigher(key, &weighted.entry.value);
+            self.weight += weighted.weight;
+        }
+        self.weigher = Some(weigher);
+    }
+
+    // == Read ==
+    /// Looks up a key as a read: counts a hit or miss, removes the entry if
+    /// it expired past its grace window, and otherwise records the access
+    /// and marks it recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Lookup<'_, V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let Some((stored, weighted)) = self.entries.get_key_value(key) else {
+            self.stats.record_miss();
+            return Lookup::Miss;
+        };
+        if weighted.entry.is_past_grace() {
+            self.remove(key);
+            self.stats.record_miss();
+            self.stats.record_expirations(1);
+            return Lookup::Expired;
+        }
+
+        let stale = weighted.entry.is_expired();
+        if stale {
+            self.stats.record_stale_hit();
+        } else {
+            self.stats.record_hit();
+        }
+        let stored = stored.clone();
+        self.lru.touch::<K>(&stored);
+        let weighted = self.entries.get_mut(key).expect("entry checked above");
+        weighted.entry.record_access();
+        if stale {
+            Lookup::Stale(&mut weighted.entry)
+        } else {
+            Lookup::Hit(&weighted.entry)
+        }
+    }
+
+    /// Returns a live entry without counting a read or touching the LRU
+    /// order.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&CacheEntry<V>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.entries
+            .get(key)
+            .map(|weighted| &weighted.entry)
+            .filter(|entry| !entry.is_expired())
+    }
+
+    /// Returns the entry of `key`, including an expired one not yet
+    /// removed, without counting a read or touching the LRU order.
+    pub fn peek_stored<Q>(&self, key: &Q) -> Option<&CacheEntry<V>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.entries.get(key).map(|weighted| &weighted.entry)
+    }
+
+    /// Iterates over every entry, including expired ones not yet removed.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &CacheEntry<V>)> {
+        self.entries
+            .iter()
+            .map(|(key, weighted)| (key, &weighted.entry))
+    }
+
+    /// Returns how many keys would be evicted before this one.
+    pub fn eviction_rank<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        self.lru.eviction_rank(key)
+    }
+
+    // == Write ==
+    /// Stores an entry, replacing any entry for the same key, and returns
+    /// the keys evicted to make room for it, oldest first.
+    ///
+    /// Fails without changing anything if the entry alone weighs more than
+    /// the capacity.
+    pub fn insert(&mut self, key: K, entry: CacheEntry<V>) -> Result<Vec<K>> {
+        let weight = self.weigh(&key, &entry.value);
+        if weight > self.capacity {
+            return Err(CacheError::CacheFull(format!(
+                "Entry of weight {} exceeds the capacity of {}",
+                weight, self.capacity
+            )));
+        }
+
+        self.remove(&key);
+        let evicted = self.evict_to(self.capacity - weight);
+        self.lru.touch(&key);
+        self.entries.insert(key, Weighted { entry, weight });
+        self.weight += weight;
+        self.stats.set_total_entries(self.entries.len());
+        Ok(evicted)
+    }
+
+    /// Removes an entry, live or expired, and returns it.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<CacheEntry<V>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let weighted = self.entries.remove(key)?;
+        self.lru.remove(key);
+        self.weight -= weighted.weight;
+        self.stats.set_total_entries(self.entries.len());
+        Some(weighted.entry)
+    }
+
+    /// Removes every entry expired past its grace window and returns their
+    /// keys.
+    pub fn cleanup_expired(&mut self) -> Vec<K> {
+        let expired: Vec<K> = self
+            .entries
+            .iter()
+            .filter(|(_, weighted)| weighted.entry.is_past_grace())
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        self.stats.record_cleanup(expired.len());
+        expired
+    }
+
+    /// Removes every entry. Statistics other than the entry count are kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.lru = LruTracker::default();
+        self.weight = 0;
+        self.stats.set_total_entries(0);
+    }
+
+    /// Exchanges entries and statistics with `other`, keeping the
+    /// capacities and weighers of both.
+    pub fn swap_contents(&mut self, other: &mut Engine<K, V>) {
+        std::mem::swap(&mut self.entries, &mut other.entries);
+        std::mem::swap(&mut self.lru, &mut other.lru);
+        std::mem::swap(&mut self.stats, &mut other.stats);
+        std::mem::swap(&mut self.weight, &mut other.weight);
+    }
+
+    // == Capacity ==
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Changes the capacity and returns the keys evicted to fit in it.
+    pub fn set_capacity(&mut self, capacity: u64) -> Vec<K> {
+        self.capacity = capacity;
+        self.evict_to(capacity)
+    }
+
+    /// Evicts least recently used entries until the total weight is at
+    /// most `weight`.
+    fn evict_to(&mut self, weight: u64) -> Vec<K> {
+        let mut evicted = Vec::new();
+        while self.weight > weight {
+            let Some(key) = self.lru.evict_oldest() else {
+                break;
+            };
+            if let Some(weighted) = self.entries.remove(&key) {
+                self.weight -= weighted.weight;
+            }
+            self.stats.record_eviction();
+            evicted.push(key);
+        }
+        self.stats.set_total_entries(self.entries.len());
+        evicted
+    }
+
+    fn weigh(&self, key: &K, value: &V) -> u64 {
+        self.weigher
+            .as_ref()
+            .map_or(1, |weigher| weigher(key, value))
+    }
+
+    // == Stats ==
+    /// Returns the statistics with an up-to-date entry count.
+    pub fn stats(&self) -> CacheStats {
+        let mut stats = self.stats.clone();
+        stats.set_total_entries(self.entries.len());
+        stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut CacheStats {
+        &mut self.stats
+    }
+
+    // == Length ==
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the total weight of the stored entries.
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+}
+
+// == Unit Tests ==
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_evicts_by_weight() {
+        let mut engine: Engine<String, String> = Engine::new(10);
+        engine.set_weigher(Arc::new(|_, value: &String| value.len() as u64));
+
+        engine
+            .insert("a".to_string(), CacheEntry::new("xxxx".to_string(), None))
+            .unwrap();
+        engine
+            .insert("b".to_string(), CacheEntry::new("xxxx".to_string(), None))
+            .unwrap();
+        assert_eq!(engine.weight(), 8);
+
+        // Reading "a" makes "b" the oldest
+        assert!(matches!(engine.get("a"), Lookup::Hit(_)));
+        let evicted = engine
+            .insert("c".to_string(), CacheEntry::new("xxxxxx".to_string(), None))
+            .unwrap();
+        assert_eq!(evicted, vec!["b".to_string()]);
+        assert_eq!(engine.weight(), 10);
+        assert_eq!(engine.stats().evictions, 1);
+
+        // Replacing an entry frees its old weight first
+        let evicted = engine
+            .insert("c".to_string(), CacheEntry::new("x".to_string(), None))
+            .unwrap();
+        assert!(evicted.is_empty());
+        assert_eq!(engine.weight(), 5);
+
+        assert!(engine
+            .insert("d".to_string(), CacheEntry::new("x".repeat(11), None))
+            .is_err());
+        assert_eq!(engine.len(), 2);
+    }
+
+    #[test]
+    fn test_engine_shrink_and_remove() {
+        let mut engine: Engine<u32, u32> = Engine::new(3);
+        for i in 0..3 {
+            engine.insert(i, CacheEntry::new(i, None)).unwrap();
+        }
+        assert_eq!(engine.set_capacity(1), vec![0, 1]);
+        assert_eq!(engine.remove(&2).unwrap().value, 2);
+        assert_eq!(engine.weight(), 0);
+        assert!(matches!(engine.get(&2), Lookup::Miss));
+        assert_eq!(engine.stats().misses, 1);
+    }
+}
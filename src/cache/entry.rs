@@ -4,36 +4,95 @@
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::random::random_unit;
+
+/// Weight of the compute time in early refresh decisions; above 1 favors
+/// refreshing earlier, below 1 later
+pub const XFETCH_BETA: f64 = 1.0;
+
 // == Cache Entry ==
 /// Represents a single cache entry with value and metadata.
 #[derive(Debug, Clone)]
-pub struct CacheEntry {
+pub struct CacheEntry<V = String> {
     /// The stored value
-    pub value: String,
+    pub value: V,
     /// Creation timestamp (Unix milliseconds)
     pub created_at: u64,
     /// Expiration timestamp (Unix milliseconds), None = no expiration
     pub expires_at: Option<u64>,
+    /// Last read timestamp (Unix milliseconds), equal to `created_at` until first read
+    pub last_accessed_at: u64,
+    /// Number of successful reads of this entry
+    pub access_count: u64,
+    /// Milliseconds the entry is still served, flagged as stale, after it
+    /// expires; None = removed on expiry
+    pub grace_ms: Option<u64>,
+    /// Whether a stale read has already been asked to refresh the value
+    pub refresh_claimed: bool,
+    /// Milliseconds the value took to compute, used to refresh it early;
+    /// None = only refreshed once stale
+    pub compute_ms: Option<u64>,
 }
 
-impl CacheEntry {
+impl<V> CacheEntry<V> {
     // == Constructor ==
     /// Creates a new cache entry with optional TTL.
     ///
     /// # Arguments
     /// * `value` - The value to store
     /// * `ttl_seconds` - Optional TTL in seconds
-    pub fn new(value: String, ttl_seconds: Option<u64>) -> Self {
+    pub fn new(value: V, ttl_seconds: Option<u64>) -> Self {
         let now = current_timestamp_ms();
-        let expires_at = ttl_seconds.map(|ttl| now + (ttl * 1000));
+        let expires_at = ttl_seconds.map(|ttl| now.saturating_add(ttl.saturating_mul(1000)));
 
         Self {
             value,
             created_at: now,
             expires_at,
+            last_accessed_at: now,
+            access_count: 0,
+            grace_ms: None,
+            refresh_claimed: false,
+            compute_ms: None,
+        }
+    }
+
+    /// Creates an entry expiring at an absolute time, as received from a
+    /// replication leader.
+    pub fn with_expires_at(value: V, expires_at: Option<u64>) -> Self {
+        Self {
+            expires_at,
+            ..Self::new(value, None)
         }
     }
 
+    /// Keeps the entry for `grace_ms` after it expires, served as stale.
+    /// Has no effect on entries without a TTL.
+    pub fn with_grace_ms(mut self, grace_ms: u64) -> Self {
+        self.grace_ms = Some(grace_ms);
+        self
+    }
+
+    /// Records how long the value took to compute, so reads may be asked
+    /// to refresh it shortly before it expires.
+    pub fn with_compute_ms(mut self, compute_ms: u64) -> Self {
+        self.compute_ms = Some(compute_ms);
+        self
+    }
+
+    // == Record Access ==
+    /// Records a successful read of this entry.
+    pub fn record_access(&mut self) {
+        self.last_accessed_at = current_timestamp_ms();
+        self.access_count += 1;
+    }
+
+    // == Idle Time ==
+    /// Returns milliseconds since the entry was last read (or created).
+    pub fn idle_ms(&self) -> u64 {
+        current_timestamp_ms().saturating_sub(self.last_accessed_at)
+    }
+
     // == Is Expired ==
     /// Checks if the entry has expired.
     ///
@@ -51,6 +110,50 @@ impl CacheEntry {
         }
     }
 
+    // == Grace ==
+    /// Returns when the grace window ends (Unix milliseconds), or None if
+    /// the entry has no TTL or no grace window.
+    pub fn stale_until(&self) -> Option<u64> {
+        Some(self.expires_at?.saturating_add(self.grace_ms?))
+    }
+
+    /// Checks if the entry has expired but is still within its grace
+    /// window, so it may be served as stale.
+    pub fn is_stale(&self) -> bool {
+        self.is_expired()
+            && self
+                .stale_until()
+                .is_some_and(|until| current_timestamp_ms() < until)
+    }
+
+    /// Checks if the entry has expired and is past its grace window, if
+    /// any, so it can no longer be served.
+    pub fn is_past_grace(&self) -> bool {
+        self.is_expired() && !self.is_stale()
+    }
+
+    // == Early Refresh ==
+    /// Decides at random whether a read should recompute the value before
+    /// it expires (XFetch), so one reader refreshes it while the others
+    /// still hit.
+    ///
+    /// The chance grows as the expiry approaches and with the time the value
+    /// took to compute. Entries without a TTL or compute time, and expired
+    /// entries, are never refreshed early.
+    pub fn should_refresh_early(&self) -> bool {
+        self.should_refresh_early_with(random_unit())
+    }
+
+    /// `should_refresh_early` with the random draw in (0, 1] given.
+    fn should_refresh_early_with(&self, random: f64) -> bool {
+        match (self.expires_at, self.compute_ms) {
+            (Some(expires_at), Some(compute_ms)) if !self.is_expired() => {
+                xfetch(current_timestamp_ms(), expires_at, compute_ms, random)
+            }
+            _ => false,
+        }
+    }
+
     // == Time To Live ==
     /// Returns remaining TTL in milliseconds, or None if no expiration is set.
     ///
@@ -61,14 +164,8 @@ impl CacheEntry {
     /// - `Some(remaining_ms)` if the entry has TTL and hasn't expired
     /// - `None` if the entry has no TTL (never expires)
     pub fn ttl_remaining_ms(&self) -> Option<u64> {
-        self.expires_at.map(|expires| {
-            let now = current_timestamp_ms();
-            if expires > now {
-                expires - now
-            } else {
-                0
-            }
-        })
+        self.expires_at
+            .map(|expires| expires.saturating_sub(current_timestamp_ms()))
     }
 
     /// Returns remaining TTL in seconds, or None if no expiration is set.
@@ -84,7 +181,35 @@ impl CacheEntry {
     }
 }
 
+impl CacheEntry {
+    // == Encoding ==
+    /// Returns the Redis-style internal encoding name for the value.
+    ///
+    /// - `int` for values that parse as a 64-bit integer
+    /// - `embstr` for short strings (up to 44 bytes)
+    /// - `raw` for everything else
+    pub fn encoding(&self) -> &'static str {
+        if self.value.parse::<i64>().is_ok() {
+            "int"
+        } else if self.value.len() <= EMBSTR_MAX_LEN {
+            "embstr"
+        } else {
+            "raw"
+        }
+    }
+}
+
+/// Longest string Redis stores with the `embstr` encoding
+const EMBSTR_MAX_LEN: usize = 44;
+
 // == Utility Functions ==
+/// XFetch: refresh once `now - compute_ms * beta * ln(random)` reaches the
+/// expiry, for `random` in (0, 1].
+fn xfetch(now: u64, expires_at: u64, compute_ms: u64, random: f64) -> bool {
+    let gap = compute_ms as f64 * XFETCH_BETA * -random.ln();
+    now as f64 + gap >= expires_at as f64
+}
+
 /// Returns current Unix timestamp in milliseconds.
 pub fn current_timestamp_ms() -> u64 {
     SystemTime::now()
@@ -170,6 +295,29 @@ mod tests {
         assert_eq!(entry.ttl_remaining_ms().unwrap(), 0);
     }
 
+    #[test]
+    fn test_record_access() {
+        let mut entry = CacheEntry::new("test_value".to_string(), None);
+        assert_eq!(entry.access_count, 0);
+        assert_eq!(entry.last_accessed_at, entry.created_at);
+
+        sleep(Duration::from_millis(10));
+        entry.record_access();
+        entry.record_access();
+
+        assert_eq!(entry.access_count, 2);
+        assert!(entry.last_accessed_at > entry.created_at);
+        assert!(entry.idle_ms() < 1000);
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(CacheEntry::new("12345".to_string(), None).encoding(), "int");
+        assert_eq!(CacheEntry::new("-7".to_string(), None).encoding(), "int");
+        assert_eq!(CacheEntry::new("hello".to_string(), None).encoding(), "embstr");
+        assert_eq!(CacheEntry::new("x".repeat(45), None).encoding(), "raw");
+    }
+
     #[test]
     fn test_expiration_boundary_condition() {
         // Create an entry with a known expiration time
@@ -178,9 +326,70 @@ mod tests {
             value: "test".to_string(),
             created_at: now,
             expires_at: Some(now), // Expires exactly at creation time
+            last_accessed_at: now,
+            access_count: 0,
+            grace_ms: None,
+            refresh_claimed: false,
+            compute_ms: None,
         };
 
         // Entry should be expired when current time >= expires_at
         assert!(entry.is_expired(), "Entry should be expired at boundary");
     }
+
+    #[test]
+    fn test_grace_window() {
+        let now = current_timestamp_ms();
+        let entry = CacheEntry::with_expires_at("v".to_string(), Some(now - 10));
+        assert!(entry.is_past_grace());
+        assert!(!entry.is_stale());
+
+        let entry = entry.with_grace_ms(60_000);
+        assert_eq!(entry.stale_until(), Some(now - 10 + 60_000));
+        assert!(entry.is_expired());
+        assert!(entry.is_stale());
+        assert!(!entry.is_past_grace());
+
+        let entry = CacheEntry::with_expires_at("v".to_string(), Some(now - 20)).with_grace_ms(10);
+        assert!(entry.is_past_grace());
+
+        // Entries without a TTL never go stale
+        let entry = CacheEntry::new("v".to_string(), None).with_grace_ms(10);
+        assert_eq!(entry.stale_until(), None);
+        assert!(!entry.is_stale() && !entry.is_past_grace());
+    }
+
+    #[test]
+    fn test_xfetch() {
+        // A draw of 1 never refreshes before the expiry
+        assert!(!xfetch(1_000, 2_000, 500, 1.0));
+        assert!(xfetch(2_000, 2_000, 500, 1.0));
+        // The gap is compute time times -ln(random)
+        assert!(xfetch(1_600, 2_000, 500, 0.4));
+        assert!(!xfetch(1_600, 2_000, 500, 0.5));
+        assert!(!xfetch(1_000, 2_000, 0, f64::MIN_POSITIVE));
+    }
+
+    #[test]
+    fn test_should_refresh_early() {
+        let now = current_timestamp_ms();
+        let entry =
+            |expires_in: u64| CacheEntry::with_expires_at("v".to_string(), Some(now + expires_in));
+
+        assert!(!entry(1_000).should_refresh_early());
+        assert!(!entry(60_000).with_compute_ms(10).should_refresh_early());
+        assert!(!CacheEntry::new("v".to_string(), None)
+            .with_compute_ms(u64::MAX)
+            .should_refresh_early());
+
+        // With the expiry one compute time away, draws up to 1 / e refresh
+        let close = entry(10_000).with_compute_ms(10_000);
+        assert!(close.should_refresh_early_with(f64::MIN_POSITIVE));
+        assert!(close.should_refresh_early_with(0.3));
+        assert!(!close.should_refresh_early_with(0.5));
+        assert!(!close.should_refresh_early_with(1.0));
+        assert!(!entry(60_000)
+            .with_compute_ms(10)
+            .should_refresh_early_with(f64::MIN_POSITIVE));
+    }
 }
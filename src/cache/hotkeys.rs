@@ -0,0 +1,237 @@
+//! Hot Key Tracker Module
+//!
+//! Approximate top-K of the most accessed keys using the Space-Saving algorithm.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use serde::Serialize;
+
+/// Default number of counters kept by the tracker
+pub const DEFAULT_HOTKEY_CAPACITY: usize = 64;
+
+// == Hot Key ==
+/// An access frequency estimate for a single key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HotKey {
+    /// The tracked key
+    pub key: String,
+    /// Estimated access count (never underestimates)
+    pub count: u64,
+    /// Maximum overestimation of `count`
+    pub error: u64,
+}
+
+// == Hot Key Tracker ==
+/// Tracks the most frequently accessed keys in bounded memory.
+///
+/// Keeps at most `capacity` counters. When an untracked key arrives and all
+/// counters are in use, the key with the lowest count is replaced and the new
+/// key inherits that count as its error bound. Any key accessed more than
+/// `total / capacity` times is guaranteed to be tracked.
+///
+/// Counters are also ordered by count, so each access takes O(log capacity).
+#[derive(Debug)]
+pub struct HotKeyTracker {
+    /// Counters by key: (count, error)
+    counters: HashMap<Arc<str>, (u64, u64)>,
+    /// The same counters ordered by (count, key), lowest first
+    by_count: BTreeSet<(u64, Arc<str>)>,
+    /// Maximum number of counters
+    capacity: usize,
+}
+
+impl HotKeyTracker {
+    // == Constructor ==
+    /// Creates a new tracker keeping at most `capacity` counters.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            counters: HashMap::with_capacity(capacity),
+            by_count: BTreeSet::new(),
+            capacity,
+        }
+    }
+
+    // == Record ==
+    /// Records one access to `key`.
+    pub fn record(&mut self, key: &str) {
+        if let Some((tracked, (count, _))) = self.counters.get_key_value(key) {
+            let (tracked, count) = (tracked.clone(), *count);
+            self.set_count(tracked, count, count + 1);
+            return;
+        }
+
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.counters.len() < self.capacity {
+            let key: Arc<str> = Arc::from(key);
+            self.by_count.insert((1, key.clone()));
+            self.counters.insert(key, (1, 0));
+            return;
+        }
+
+        // Replace the counter with the smallest count
+        if let Some((min_count, min_key)) = self.by_count.pop_first() {
+            self.counters.remove(&min_key);
+            let key: Arc<str> = Arc::from(key);
+            self.by_count.insert((min_count + 1, key.clone()));
+            self.counters.insert(key, (min_count + 1, min_count));
+        }
+    }
+
+    /// Moves the counter of a tracked key from `old` to `new`.
+    fn set_count(&mut self, key: Arc<str>, old: u64, new: u64) {
+        let mut entry = (old, key);
+        self.by_count.remove(&entry);
+        entry.0 = new;
+        if let Some((count, _)) = self.counters.get_mut(&entry.1) {
+            *count = new;
+        }
+        self.by_count.insert(entry);
+    }
+
+    // == Top ==
+    /// Returns up to `n` keys ordered by estimated count, highest first.
+    pub fn top(&self, n: usize) -> Vec<HotKey> {
+        let mut keys: Vec<HotKey> = self
+            .counters
+            .iter()
+            .map(|(key, (count, error))| HotKey {
+                key: key.to_string(),
+                count: *count,
+                error: *error,
+            })
+            .collect();
+
+        keys.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+        keys.truncate(n);
+        keys
+    }
+}
+
+impl Default for HotKeyTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_HOTKEY_CAPACITY)
+    }
+}
+
+// == Unit Tests ==
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hotkeys_counts_exactly_below_capacity() {
+        let mut tracker = HotKeyTracker::new(10);
+
+        for _ in 0..5 {
+            tracker.record("a");
+        }
+        tracker.record("b");
+        tracker.record("b");
+
+        let top = tracker.top(10);
+        assert_eq!(top.len(), 2);
+        assert_eq!(
+            top[0],
+            HotKey {
+                key: "a".to_string(),
+                count: 5,
+                error: 0
+            }
+        );
+        assert_eq!(
+            top[1],
+            HotKey {
+                key: "b".to_string(),
+                count: 2,
+                error: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_hotkeys_bounded_by_capacity() {
+        let mut tracker = HotKeyTracker::new(3);
+
+        for i in 0..100 {
+            tracker.record(&format!("key{}", i));
+        }
+
+        assert_eq!(tracker.top(usize::MAX).len(), 3);
+    }
+
+    #[test]
+    fn test_hotkeys_finds_dominant_key_among_noise() {
+        let mut tracker = HotKeyTracker::new(8);
+
+        // One key receives 90% of the traffic
+        for i in 0..1000 {
+            if i % 10 == 0 {
+                tracker.record(&format!("noise{}", i));
+            } else {
+                tracker.record("hot");
+            }
+        }
+
+        let top = tracker.top(1);
+        assert_eq!(top[0].key, "hot");
+        assert!(top[0].count >= 900);
+    }
+
+    #[test]
+    fn test_hotkeys_replacement_inherits_error() {
+        let mut tracker = HotKeyTracker::new(1);
+
+        tracker.record("a");
+        tracker.record("a");
+        tracker.record("b");
+
+        let top = tracker.top(1);
+        assert_eq!(
+            top[0],
+            HotKey {
+                key: "b".to_string(),
+                count: 3,
+                error: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_hotkeys_replaces_lowest_count() {
+        let mut tracker = HotKeyTracker::new(3);
+
+        for (key, times) in [("a", 5), ("b", 2), ("c", 4)] {
+            for _ in 0..times {
+                tracker.record(key);
+            }
+        }
+        tracker.record("d");
+        tracker.record("e");
+
+        // "d" replaced "b" (2), then "e" replaced "d" (3)
+        let top: Vec<(String, u64, u64)> = tracker
+            .top(usize::MAX)
+            .into_iter()
+            .map(|hot| (hot.key, hot.count, hot.error))
+            .collect();
+        assert_eq!(
+            top,
+            vec![
+                ("a".to_string(), 5, 0),
+                ("c".to_string(), 4, 0),
+                ("e".to_string(), 4, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hotkeys_zero_capacity_tracks_nothing() {
+        let mut tracker = HotKeyTracker::new(0);
+        tracker.record("a");
+        assert!(tracker.top(usize::MAX).is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+//! Cache Loader Module
+//!
+//! The `Loader` trait connects an embedded `Cache` to the backing store it
+//! caches, such as a database: `Cache::get_or_load` reads through it on a
+//! miss, and `Cache::set` and `Cache::delete` write through it.
+
+use std::error::Error as StdError;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Error returned by a loader
+pub type LoadError = Box<dyn StdError + Send + Sync>;
+
+/// Result of a loader
+pub type LoadResult<T> = std::result::Result<T, LoadError>;
+
+// == Loader ==
+/// Fetches values missing from the cache and, optionally, writes changes
+/// back to their source.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use mini_redis::cache::{LoadResult, Loader};
+///
+/// struct Users(HashMap<u64, String>);
+///
+/// impl Loader<u64, String> for Users {
+///     async fn load(&self, id: &u64) -> LoadResult<Option<String>> {
+///         Ok(self.0.get(id).cloned())
+///     }
+/// }
+/// ```
+pub trait Loader<K, V>: Send + Sync + 'static {
+    /// Returns the value of `key` from the backing store, or None if it has
+    /// none. Absent keys are cached as such for the cache's negative TTL.
+    fn load(&self, key: &K) -> impl Future<Output = LoadResult<Option<V>>> + Send;
+
+    /// Writes a value set through the cache. Does nothing by default, so
+    /// writes only reach the cache.
+    fn store(&self, _key: &K, _value: &V) -> impl Future<Output = LoadResult<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Removes a key deleted through the cache. Does nothing by default.
+    fn delete(&self, _key: &K) -> impl Future<Output = LoadResult<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// `Loader` with boxed futures, so a cache can hold any loader behind one
+/// pointer type.
+pub(crate) trait DynLoader<K, V>: Send + Sync {
+    fn load<'a>(&'a self, key: &'a K) -> BoxFuture<'a, LoadResult<Option<V>>>;
+    fn store<'a>(&'a self, key: &'a K, value: &'a V) -> BoxFuture<'a, LoadResult<()>>;
+    fn delete<'a>(&'a self, key: &'a K) -> BoxFuture<'a, LoadResult<()>>;
+}
+
+impl<K: Sync + 'static, V: Sync + 'static, L: Loader<K, V>> DynLoader<K, V> for L {
+    fn load<'a>(&'a self, key: &'a K) -> BoxFuture<'a, LoadResult<Option<V>>> {
+        Box::pin(Loader::load(self, key))
+    }
+
+    fn store<'a>(&'a self, key: &'a K, value: &'a V) -> BoxFuture<'a, LoadResult<()>> {
+        Box::pin(Loader::store(self, key, value))
+    }
+
+    fn delete<'a>(&'a self, key: &'a K) -> BoxFuture<'a, LoadResult<()>> {
+        Box::pin(Loader::delete(self, key))
+    }
+}
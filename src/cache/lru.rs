@@ -2,6 +2,7 @@
 //!
 //! Implements Least Recently Used tracking for cache eviction.
 
+use std::borrow::Borrow;
 use std::collections::VecDeque;
 
 // == LRU Tracker ==
@@ -10,54 +11,82 @@ use std::collections::VecDeque;
 /// Keys are stored in a VecDeque where:
 /// - Front = Most recently used
 /// - Back = Least recently used
-#[derive(Debug, Default)]
-pub struct LruTracker {
+#[derive(Debug)]
+pub struct LruTracker<K = String> {
     /// Order of keys by access time
-    order: VecDeque<String>,
+    order: VecDeque<K>,
+}
+
+impl<K> Default for LruTracker<K> {
+    fn default() -> Self {
+        Self {
+            order: VecDeque::new(),
+        }
+    }
 }
 
 impl LruTracker {
     // == Constructor ==
     /// Creates a new empty LRU tracker.
     pub fn new() -> Self {
-        Self {
-            order: VecDeque::new(),
-        }
+        Self::default()
     }
+}
 
+impl<K: Eq> LruTracker<K> {
     // == Touch ==
     /// Marks a key as recently used (moves to front).
     ///
     /// If key exists, removes it first then adds to front.
     /// If key is new, just adds to front.
-    pub fn touch(&mut self, key: &str) {
+    pub fn touch<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq + ToOwned<Owned = K>,
+    {
         // Remove existing occurrence
         self.remove(key);
         // Add to front (most recent)
-        self.order.push_front(key.to_string());
+        self.order.push_front(key.to_owned());
     }
 
     // == Remove ==
     /// Removes a key from the tracker.
-    pub fn remove(&mut self, key: &str) {
-        self.order.retain(|k| k != key);
+    pub fn remove<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        self.order.retain(|k| k.borrow() != key);
     }
 
     // == Evict Oldest ==
     /// Returns and removes the least recently used key.
     ///
     /// Returns None if tracker is empty.
-    pub fn evict_oldest(&mut self) -> Option<String> {
+    pub fn evict_oldest(&mut self) -> Option<K> {
         self.order.pop_back()
     }
 
     // == Peek Oldest ==
     /// Returns the least recently used key without removing it.
     #[allow(dead_code)]
-    pub fn peek_oldest(&self) -> Option<&String> {
+    pub fn peek_oldest(&self) -> Option<&K> {
         self.order.back()
     }
 
+    // == Eviction Rank ==
+    /// Returns how many keys would be evicted before this one (0 = next to go).
+    ///
+    /// Returns None if the key is not tracked.
+    pub fn eviction_rank<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        self.order.iter().rev().position(|k| k.borrow() == key)
+    }
+
     // == Length ==
     /// Returns the number of tracked keys.
     pub fn len(&self) -> usize {
@@ -73,8 +102,12 @@ impl LruTracker {
     // == Contains ==
     /// Checks if a key is being tracked.
     #[allow(dead_code)]
-    pub fn contains(&self, key: &str) -> bool {
-        self.order.iter().any(|k| k == key)
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        self.order.iter().any(|k| k.borrow() == key)
     }
 }
 
@@ -239,6 +272,23 @@ mod tests {
         assert_eq!(lru.peek_oldest(), Some(&"b".to_string()));
     }
 
+    #[test]
+    fn test_lru_eviction_rank() {
+        let mut lru = LruTracker::new();
+
+        lru.touch("a");
+        lru.touch("b");
+        lru.touch("c");
+
+        assert_eq!(lru.eviction_rank("a"), Some(0));
+        assert_eq!(lru.eviction_rank("c"), Some(2));
+        assert_eq!(lru.eviction_rank("missing"), None);
+
+        lru.touch("a");
+        assert_eq!(lru.eviction_rank("a"), Some(2));
+        assert_eq!(lru.eviction_rank("b"), Some(0));
+    }
+
     #[test]
     fn test_lru_touch_moves_to_front() {
         let mut lru = LruTracker::new();
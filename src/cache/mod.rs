@@ -1,8 +1,14 @@
 //! Cache Module
 //!
-//! Provides in-memory caching with TTL expiration and LRU eviction.
+//! Provides in-memory caching with TTL expiration and LRU eviction, served
+//! over HTTP by `CacheStore` or embedded in other programs with `Cache`.
 
+mod databases;
+mod embedded;
+mod engine;
 mod entry;
+mod hotkeys;
+mod loader;
 mod lru;
 mod stats;
 mod store;
@@ -11,10 +17,15 @@ mod store;
 mod property_tests;
 
 // Re-export public types
-pub use entry::CacheEntry;
+pub use databases::{DatabaseConfig, Databases, DEFAULT_DATABASE};
+pub use embedded::{Cache, DEFAULT_NEGATIVE_TTL};
+pub use engine::Weigher;
+pub use entry::{current_timestamp_ms, CacheEntry};
+pub use hotkeys::{HotKey, HotKeyTracker, DEFAULT_HOTKEY_CAPACITY};
+pub use loader::{LoadError, LoadResult, Loader};
 pub use lru::LruTracker;
 pub use stats::CacheStats;
-pub use store::CacheStore;
+pub use store::{BigKey, CacheStore, CachedValue, KeyInfo, KeyspaceInfo, SetOptions};
 
 // == Public Constants ==
 /// Maximum allowed key length in bytes
@@ -22,3 +33,6 @@ pub const MAX_KEY_LENGTH: usize = 256;
 
 /// Maximum allowed value size in bytes
 pub const MAX_VALUE_SIZE: usize = 1024 * 1024; // 1 MB
+
+/// Maximum stale-grace window in seconds
+pub const MAX_GRACE: u64 = 30 * 24 * 60 * 60; // 30 days
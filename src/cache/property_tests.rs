@@ -3,7 +3,6 @@
 //! Uses proptest to verify correctness properties defined in the design document.
 
 use proptest::prelude::*;
-use std::collections::HashSet;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -278,7 +277,7 @@ proptest! {
     fn prop_lru_access_tracking(
         // Generate unique keys
         keys in prop::collection::vec(valid_key_strategy(), 3..8),
-        access_index in 0usize..100,
+        _access_index in 0usize..100,
         new_key in valid_key_strategy(),
         new_value in valid_value_strategy()
     ) {
@@ -359,6 +358,21 @@ proptest! {
             CacheError::NotFound(error_msg.clone()),
             CacheError::Expired(error_msg.clone()),
             CacheError::InvalidRequest(error_msg.clone()),
+            CacheError::Unauthorized(error_msg.clone()),
+            CacheError::Forbidden(error_msg.clone()),
+            CacheError::ReadOnly(error_msg.clone()),
+            CacheError::Moved {
+                slot: error_msg.len() as u16,
+                address: "127.0.0.1:7001".to_string(),
+                location: "http://127.0.0.1:7001/get/key".to_string(),
+            },
+            CacheError::Ask {
+                slot: error_msg.len() as u16,
+                address: "127.0.0.1:7001".to_string(),
+                location: "http://127.0.0.1:7001/get/key?asking=1".to_string(),
+            },
+            CacheError::ClusterDown(error_msg.clone()),
+            CacheError::RateLimited { retry_after_secs: error_msg.len() as u64 },
             CacheError::CacheFull(error_msg.clone()),
             CacheError::Internal(error_msg.clone()),
         ];
@@ -512,7 +526,7 @@ proptest! {
             // Hit rate should be valid (0.0 to 1.0 or NaN if no requests)
             let hit_rate = stats.hit_rate();
             prop_assert!(
-                hit_rate.is_nan() || (hit_rate >= 0.0 && hit_rate <= 1.0),
+                hit_rate.is_nan() || (0.0..=1.0).contains(&hit_rate),
                 "Hit rate should be between 0 and 1, got {}",
                 hit_rate
             );
@@ -558,7 +572,29 @@ mod tests {
             (CacheError::NotFound("key".to_string()), StatusCode::NOT_FOUND),
             (CacheError::Expired("key".to_string()), StatusCode::NOT_FOUND),
             (CacheError::InvalidRequest("bad".to_string()), StatusCode::BAD_REQUEST),
+            (CacheError::Unauthorized("token".to_string()), StatusCode::UNAUTHORIZED),
+            (CacheError::Forbidden("denied".to_string()), StatusCode::FORBIDDEN),
+            (CacheError::ReadOnly("follower".to_string()), StatusCode::FORBIDDEN),
+            (
+                CacheError::Moved {
+                    slot: 1,
+                    address: "127.0.0.1:7001".to_string(),
+                    location: "http://127.0.0.1:7001/get/key".to_string(),
+                },
+                StatusCode::TEMPORARY_REDIRECT,
+            ),
+            (
+                CacheError::Ask {
+                    slot: 1,
+                    address: "127.0.0.1:7001".to_string(),
+                    location: "http://127.0.0.1:7001/get/key?asking=1".to_string(),
+                },
+                StatusCode::TEMPORARY_REDIRECT,
+            ),
+            (CacheError::ClusterDown("slot 1".to_string()), StatusCode::SERVICE_UNAVAILABLE),
+            (CacheError::RateLimited { retry_after_secs: 1 }, StatusCode::TOO_MANY_REQUESTS),
             (CacheError::CacheFull("full".to_string()), StatusCode::SERVICE_UNAVAILABLE),
+            (CacheError::Load("timeout".to_string()), StatusCode::BAD_GATEWAY),
             (CacheError::Internal("error".to_string()), StatusCode::INTERNAL_SERVER_ERROR),
         ];
 
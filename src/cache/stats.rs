@@ -4,18 +4,30 @@
 
 use serde::Serialize;
 
+use crate::cache::current_timestamp_ms;
+
 // == Cache Stats ==
 /// Tracks cache performance metrics.
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct CacheStats {
     /// Number of successful cache retrievals
     pub hits: u64,
+    /// Hits on expired entries still within their grace window
+    pub stale_hits: u64,
     /// Number of failed cache retrievals (key not found or expired)
     pub misses: u64,
     /// Number of entries evicted due to LRU policy
     pub evictions: u64,
+    /// Number of entries removed because their TTL elapsed
+    pub expired: u64,
     /// Current number of entries in the cache
     pub total_entries: usize,
+    /// Number of expired-entry cleanup passes run
+    pub cleanup_runs: u64,
+    /// Time of the last cleanup pass (Unix milliseconds)
+    pub last_cleanup_at: Option<u64>,
+    /// Entries removed by the last cleanup pass
+    pub last_cleanup_removed: usize,
 }
 
 impl CacheStats {
@@ -44,6 +56,13 @@ impl CacheStats {
         self.hits += 1;
     }
 
+    // == Record Stale Hit ==
+    /// Increments the hit and stale hit counters.
+    pub fn record_stale_hit(&mut self) {
+        self.hits += 1;
+        self.stale_hits += 1;
+    }
+
     // == Record Miss ==
     /// Increments the miss counter.
     pub fn record_miss(&mut self) {
@@ -56,6 +75,21 @@ impl CacheStats {
         self.evictions += 1;
     }
 
+    // == Record Expirations ==
+    /// Adds `count` to the expired entries counter.
+    pub fn record_expirations(&mut self, count: usize) {
+        self.expired += count as u64;
+    }
+
+    // == Record Cleanup ==
+    /// Records a completed cleanup pass that removed `removed` entries.
+    pub fn record_cleanup(&mut self, removed: usize) {
+        self.cleanup_runs += 1;
+        self.last_cleanup_at = Some(current_timestamp_ms());
+        self.last_cleanup_removed = removed;
+        self.record_expirations(removed);
+    }
+
     // == Update Entry Count ==
     /// Updates the total entries count.
     pub fn set_total_entries(&mut self, count: usize) {
@@ -74,7 +108,10 @@ mod tests {
         assert_eq!(stats.hits, 0);
         assert_eq!(stats.misses, 0);
         assert_eq!(stats.evictions, 0);
+        assert_eq!(stats.expired, 0);
         assert_eq!(stats.total_entries, 0);
+        assert_eq!(stats.cleanup_runs, 0);
+        assert!(stats.last_cleanup_at.is_none());
     }
 
     #[test]
@@ -116,6 +153,18 @@ mod tests {
         assert_eq!(stats.evictions, 2);
     }
 
+    #[test]
+    fn test_record_cleanup() {
+        let mut stats = CacheStats::new();
+        stats.record_expirations(1);
+        stats.record_cleanup(3);
+
+        assert_eq!(stats.cleanup_runs, 1);
+        assert_eq!(stats.last_cleanup_removed, 3);
+        assert!(stats.last_cleanup_at.is_some());
+        assert_eq!(stats.expired, 4);
+    }
+
     #[test]
     fn test_set_total_entries() {
         let mut stats = CacheStats::new();
@@ -2,25 +2,119 @@
 //!
 //! Main cache engine combining HashMap storage with LRU tracking and TTL expiration.
 
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 
-use crate::cache::{CacheEntry, CacheStats, LruTracker, MAX_KEY_LENGTH, MAX_VALUE_SIZE};
+use serde::Serialize;
+
+use crate::acl::glob_match;
+use crate::cache::engine::{Engine, Lookup};
+use crate::cache::{CacheEntry, CacheStats, HotKey, HotKeyTracker, MAX_KEY_LENGTH, MAX_VALUE_SIZE};
+use crate::cluster::key_slot;
 use crate::error::{CacheError, Result};
+use crate::replication::{Command, ReplicationSink};
+
+// == Big Key ==
+/// A key and the size of its stored value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BigKey {
+    /// The stored key
+    pub key: String,
+    /// Value size in bytes
+    pub size: usize,
+}
+
+// == Key Info ==
+/// Internal metadata for a single key, as returned by `CacheStore::inspect`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyInfo {
+    /// The inspected key
+    pub key: String,
+    /// Value type (all values are currently strings)
+    pub value_type: &'static str,
+    /// Redis-style encoding of the value (`int`, `embstr` or `raw`)
+    pub encoding: &'static str,
+    /// Encoded size in bytes (key plus value)
+    pub size: usize,
+    /// Creation timestamp (Unix milliseconds)
+    pub created_at: u64,
+    /// Last read timestamp (Unix milliseconds)
+    pub last_accessed_at: u64,
+    /// Milliseconds since the last read
+    pub idle_ms: u64,
+    /// Number of successful reads
+    pub access_count: u64,
+    /// Remaining TTL in milliseconds, None if the key never expires
+    pub ttl_ms: Option<u64>,
+    /// Number of keys that would be evicted before this one (0 = next)
+    pub eviction_rank: Option<usize>,
+}
+
+// == Keyspace Info ==
+/// Summary of the keys currently held by the store.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct KeyspaceInfo {
+    /// Total number of keys
+    pub keys: usize,
+    /// Keys with a TTL
+    pub expires: usize,
+    /// Keys without a TTL
+    pub persistent: usize,
+    /// Average remaining TTL in milliseconds across keys with a TTL
+    pub avg_ttl_ms: u64,
+}
+
+// == Set Options ==
+/// Settings of a write other than its value and TTL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SetOptions {
+    /// Seconds the entry is still served, flagged as stale, after it
+    /// expires; None = removed on expiry
+    pub grace: Option<u64>,
+    /// Milliseconds the value took to compute; reads are then asked at
+    /// random to refresh it shortly before it expires
+    pub compute_ms: Option<u64>,
+}
+
+// == Cached Value ==
+/// A value read by `CacheStore::read`, with how fresh it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedValue {
+    /// The stored value
+    pub value: String,
+    /// The entry expired and is being served within its grace window
+    pub stale: bool,
+    /// The reader should recompute the value and set it again. Only the
+    /// first stale read of an entry is asked to, so the others keep being
+    /// served without refreshing it too. Values set with a compute time are
+    /// also refreshed early by a few reads before they expire.
+    pub refresh: bool,
+}
+
+/// Approximate bookkeeping bytes per entry beyond the key and value data
+/// (entry struct, hash table slot and LRU slot)
+const ENTRY_OVERHEAD_BYTES: usize = std::mem::size_of::<CacheEntry>()
+    + std::mem::size_of::<String>() * 2
+    + std::mem::size_of::<u64>();
 
 // == Cache Store ==
 /// Main cache storage with LRU eviction and TTL support.
 #[derive(Debug)]
 pub struct CacheStore {
-    /// Key-value storage
-    entries: HashMap<String, CacheEntry>,
-    /// LRU access tracker
-    lru: LruTracker,
-    /// Performance statistics
-    stats: CacheStats,
-    /// Maximum number of entries allowed
-    max_entries: usize,
+    /// Entries, LRU order and statistics, with one unit of weight per
+    /// entry so the capacity is `max_entries`
+    engine: Engine<String, String>,
+    /// Approximate access frequency of the most accessed keys
+    hotkeys: HotKeyTracker,
     /// Default TTL in seconds for entries without explicit TTL
     default_ttl: u64,
+    /// Where changes are recorded for followers, once one has synced
+    replication: Option<ReplicationSink>,
+    /// Keys of each hash slot, kept in cluster mode so a slot can be
+    /// migrated without scanning the whole map
+    slots: Option<HashMap<u16, HashSet<String>>>,
 }
 
 impl CacheStore {
@@ -32,12 +126,22 @@ impl CacheStore {
     /// * `default_ttl` - Default TTL in seconds for entries without explicit TTL
     pub fn new(max_entries: usize, default_ttl: u64) -> Self {
         Self {
-            entries: HashMap::new(),
-            lru: LruTracker::new(),
-            stats: CacheStats::new(),
-            max_entries,
+            engine: Engine::new(max_entries as u64),
+            hotkeys: HotKeyTracker::default(),
             default_ttl,
+            replication: None,
+            slots: None,
+        }
+    }
+
+    /// Starts indexing keys by hash slot, for `keys_in_slot` and
+    /// `count_keys_in_slot`.
+    pub fn enable_slot_index(&mut self) {
+        let mut slots: HashMap<u16, HashSet<String>> = HashMap::new();
+        for (key, _) in self.engine.iter() {
+            slots.entry(key_slot(key)).or_default().insert(key.clone());
         }
+        self.slots = Some(slots);
     }
 
     // == Set ==
@@ -51,6 +155,17 @@ impl CacheStore {
     /// * `value` - The value to store
     /// * `ttl` - Optional TTL in seconds (uses default_ttl if None)
     pub fn set(&mut self, key: String, value: String, ttl: Option<u64>) -> Result<()> {
+        self.set_with(key, value, ttl, SetOptions::default())
+    }
+
+    /// Stores a key-value pair like `set`, with the given options.
+    pub fn set_with(
+        &mut self,
+        key: String,
+        value: String,
+        ttl: Option<u64>,
+        options: SetOptions,
+    ) -> Result<()> {
         // Validate key length
         if key.len() > MAX_KEY_LENGTH {
             return Err(CacheError::InvalidRequest(format!(
@@ -67,67 +182,61 @@ impl CacheStore {
             )));
         }
 
-        // Check if key already exists (overwrite case)
-        let is_overwrite = self.entries.contains_key(&key);
-
-        // If not overwriting and at capacity, evict oldest entry
-        if !is_overwrite && self.entries.len() >= self.max_entries {
-            if let Some(evicted_key) = self.lru.evict_oldest() {
-                self.entries.remove(&evicted_key);
-                self.stats.record_eviction();
-            } else {
-                return Err(CacheError::CacheFull(
-                    "Cache is full and eviction failed".to_string(),
-                ));
-            }
-        }
-
         // Use provided TTL or default
         let effective_ttl = Some(ttl.unwrap_or(self.default_ttl));
 
-        // Create and store entry
-        let entry = CacheEntry::new(value, effective_ttl);
-        self.entries.insert(key.clone(), entry);
-
-        // Update LRU tracker (touch moves to front)
-        self.lru.touch(&key);
-
-        // Update stats
-        self.stats.set_total_entries(self.entries.len());
-
-        Ok(())
+        // Create and store entry, evicting the oldest if at capacity
+        let mut entry = CacheEntry::new(value, effective_ttl);
+        if let Some(grace) = options.grace {
+            entry = entry.with_grace_ms(grace.saturating_mul(1000));
+        }
+        if let Some(compute_ms) = options.compute_ms {
+            entry = entry.with_compute_ms(compute_ms);
+        }
+        self.hotkeys.record(&key);
+        self.insert_entry(key, entry)
     }
 
     // == Get ==
     /// Retrieves a value by key.
     ///
-    /// Returns the value if found and not expired.
-    /// Expired entries are removed and counted as misses.
+    /// Returns the value if found and not expired, or expired but within
+    /// its grace window. Expired entries are removed and counted as misses.
     ///
     /// # Arguments
     /// * `key` - The key to retrieve
     pub fn get(&mut self, key: &str) -> Result<String> {
-        // Check if entry exists
-        if let Some(entry) = self.entries.get(key) {
-            // Check if expired
-            if entry.is_expired() {
-                // Remove expired entry
-                self.entries.remove(key);
-                self.lru.remove(key);
-                self.stats.set_total_entries(self.entries.len());
-                self.stats.record_miss();
-                return Err(CacheError::Expired(key.to_string()));
-            }
+        self.read(key).map(|cached| cached.value)
+    }
 
-            // Entry exists and is valid - record hit and update LRU
-            let value = entry.value.clone();
-            self.stats.record_hit();
-            self.lru.touch(key);
-            Ok(value)
-        } else {
-            // Entry doesn't exist
-            self.stats.record_miss();
-            Err(CacheError::NotFound(key.to_string()))
+    /// Retrieves a value by key like `get`, telling whether it is stale and
+    /// whether the caller should refresh it.
+    pub fn read(&mut self, key: &str) -> Result<CachedValue> {
+        self.hotkeys.record(key);
+
+        match self.engine.get(key) {
+            // Entry exists and is valid - hit recorded and LRU updated
+            Lookup::Hit(entry) => Ok(CachedValue {
+                value: entry.value.clone(),
+                stale: false,
+                refresh: entry.should_refresh_early(),
+            }),
+            // Expired within its grace window - served as a stale hit
+            Lookup::Stale(entry) => Ok(CachedValue {
+                value: entry.value.clone(),
+                stale: true,
+                refresh: !std::mem::replace(&mut entry.refresh_claimed, true),
+            }),
+            // Expired entry was removed and counted as a miss
+            Lookup::Expired => {
+                self.unindex(key);
+                self.replicate(|db| Command::Expire {
+                    db,
+                    key: key.to_string(),
+                });
+                Err(CacheError::Expired(key.to_string()))
+            }
+            Lookup::Miss => Err(CacheError::NotFound(key.to_string())),
         }
     }
 
@@ -137,9 +246,11 @@ impl CacheStore {
     /// # Arguments
     /// * `key` - The key to delete
     pub fn delete(&mut self, key: &str) -> Result<()> {
-        if self.entries.remove(key).is_some() {
-            self.lru.remove(key);
-            self.stats.set_total_entries(self.entries.len());
+        if self.remove_raw(key).is_some() {
+            self.replicate(|db| Command::Del {
+                db,
+                key: key.to_string(),
+            });
             Ok(())
         } else {
             Err(CacheError::NotFound(key.to_string()))
@@ -149,9 +260,7 @@ impl CacheStore {
     // == Stats ==
     /// Returns current cache statistics.
     pub fn stats(&self) -> CacheStats {
-        let mut stats = self.stats.clone();
-        stats.set_total_entries(self.entries.len());
-        stats
+        self.engine.stats()
     }
 
     // == Cleanup Expired ==
@@ -159,43 +268,406 @@ impl CacheStore {
     ///
     /// Returns the number of entries removed.
     pub fn cleanup_expired(&mut self) -> usize {
-        let expired_keys: Vec<String> = self
-            .entries
-            .iter()
-            .filter(|(_, entry)| entry.is_expired())
-            .map(|(key, _)| key.clone())
-            .collect();
-
+        let expired_keys = self.engine.cleanup_expired();
         let count = expired_keys.len();
 
         for key in expired_keys {
-            self.entries.remove(&key);
-            self.lru.remove(&key);
+            self.unindex(&key);
+            self.replicate(|db| Command::Expire { db, key });
+        }
+        count
+    }
+
+    // == Inspect ==
+    /// Returns internal metadata for a live key.
+    ///
+    /// Unlike `get`, this does not count as a hit or miss, does not touch the
+    /// LRU order and does not update the entry's access time.
+    pub fn inspect(&self, key: &str) -> Option<KeyInfo> {
+        let entry = self.engine.peek(key)?;
+
+        Some(KeyInfo {
+            key: key.to_string(),
+            value_type: "string",
+            encoding: entry.encoding(),
+            size: key.len() + entry.value.len(),
+            created_at: entry.created_at,
+            last_accessed_at: entry.last_accessed_at,
+            idle_ms: entry.idle_ms(),
+            access_count: entry.access_count,
+            ttl_ms: entry.ttl_remaining_ms(),
+            eviction_rank: self.engine.eviction_rank(key),
+        })
+    }
+
+    // == Hot Keys ==
+    /// Returns up to `n` of the most frequently accessed keys, highest first.
+    ///
+    /// Counts are approximate (Space-Saving) and include reads of missing keys.
+    pub fn hot_keys(&self, n: usize) -> Vec<HotKey> {
+        self.hotkeys.top(n)
+    }
+
+    // == Big Keys ==
+    /// Returns up to `n` live entries with the largest values, largest first.
+    ///
+    /// Scans the whole map, so it only runs on demand rather than per write.
+    pub fn big_keys(&self, n: usize) -> Vec<BigKey> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        // Min-heap of the n largest seen so far
+        let mut heap: BinaryHeap<Reverse<(usize, &String)>> =
+            BinaryHeap::with_capacity(n.min(self.len()) + 1);
+        for (key, entry) in self.engine.iter().filter(|(_, e)| !e.is_expired()) {
+            heap.push(Reverse((entry.value.len(), key)));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, key))| BigKey {
+                key: key.clone(),
+                size,
+            })
+            .collect()
+    }
+
+    // == Scan ==
+    /// Returns up to `count` live keys matching the glob `pattern`, starting
+    /// at `cursor`, and the cursor to continue from (0 once done).
+    ///
+    /// Keys are visited in the order of a hash of their name and the cursor
+    /// is the hash to resume at, so a scan from 0 to the end returns every
+    /// key present throughout exactly once, however the store changes in
+    /// between. As in Redis, `count` bounds the keys visited rather than
+    /// returned, so a page can hold fewer matches or none. Each page looks
+    /// at the whole map.
+    pub fn scan(&self, cursor: u64, pattern: Option<&str>, count: usize) -> (u64, Vec<String>) {
+        let count = count.max(1);
+        let mut candidates: Vec<(u64, &str)> = self
+            .live_entries()
+            .map(|(key, _)| (scan_hash(key), key))
+            .filter(|(hash, _)| *hash >= cursor)
+            .collect();
+
+        let mut next = 0;
+        if candidates.len() > count {
+            candidates.select_nth_unstable(count - 1);
+            // Keys sharing the last hash go in the same page, since the
+            // cursor cannot point between them
+            let last = candidates[count - 1].0;
+            candidates.retain(|(hash, _)| *hash <= last);
+            next = last.checked_add(1).unwrap_or(0);
+        }
+        candidates.sort_unstable();
+
+        let keys = candidates
+            .into_iter()
+            .filter(|(_, key)| pattern.is_none_or(|pattern| glob_match(pattern, key)))
+            .map(|(_, key)| key.to_string())
+            .collect();
+        (next, keys)
+    }
+
+    // == Keyspace Info ==
+    /// Summarizes the keys in the store, split by whether they carry a TTL.
+    pub fn keyspace_info(&self) -> KeyspaceInfo {
+        let mut info = KeyspaceInfo {
+            keys: self.engine.len(),
+            ..KeyspaceInfo::default()
+        };
+
+        let mut ttl_total: u64 = 0;
+        for (_, entry) in self.engine.iter() {
+            match entry.ttl_remaining_ms() {
+                Some(remaining) => {
+                    info.expires += 1;
+                    ttl_total += remaining;
+                }
+                None => info.persistent += 1,
+            }
         }
 
-        self.stats.set_total_entries(self.entries.len());
+        if info.expires > 0 {
+            info.avg_ttl_ms = ttl_total / info.expires as u64;
+        }
+        info
+    }
+
+    // == Memory Usage ==
+    /// Returns an estimate in bytes of the memory held by stored entries.
+    ///
+    /// Counts key and value bytes (the key is stored twice, in the map and the
+    /// LRU tracker) plus a fixed per-entry overhead; allocator slack is ignored.
+    pub fn memory_usage(&self) -> usize {
+        self.engine
+            .iter()
+            .map(|(key, entry)| key.len() * 2 + entry.value.len() + ENTRY_OVERHEAD_BYTES)
+            .sum()
+    }
+
+    // == Capacity ==
+    /// Returns the maximum number of entries the store holds before evicting.
+    pub fn max_entries(&self) -> usize {
+        self.engine.capacity() as usize
+    }
+
+    /// Changes the capacity, evicting least recently used entries until the
+    /// store fits. Returns the number of entries evicted.
+    pub fn set_max_entries(&mut self, max_entries: usize) -> usize {
+        let evicted = self.engine.set_capacity(max_entries as u64);
+        let count = evicted.len();
+        self.evicted(evicted);
+        self.replicate_limits();
         count
     }
 
+    // == Default TTL ==
+    /// Returns the TTL in seconds applied to entries set without one.
+    pub fn default_ttl(&self) -> u64 {
+        self.default_ttl
+    }
+
+    /// Changes the TTL applied to entries set from now on without one.
+    pub fn set_default_ttl(&mut self, default_ttl: u64) {
+        self.default_ttl = default_ttl;
+        self.replicate_limits();
+    }
+
+    // == Flush ==
+    /// Swaps in an empty store with the same limits and returns the old one.
+    ///
+    /// Dropping millions of entries takes a while, so callers can release
+    /// the lock first and drop the returned store elsewhere. Statistics
+    /// carry over unless `reset_stats` is set; hot key tracking starts over.
+    pub fn flush(&mut self, reset_stats: bool) -> CacheStore {
+        let empty = CacheStore::new(self.max_entries(), self.default_ttl);
+        let mut old = std::mem::replace(self, empty);
+        if !reset_stats {
+            let stats = self.engine.stats_mut();
+            *stats = std::mem::take(old.engine.stats_mut());
+            stats.set_total_entries(0);
+        }
+        self.replication = old.replication.take();
+        if old.slots.is_some() {
+            self.slots = Some(HashMap::new());
+        }
+        self.replicate(|db| Command::Flush { db });
+        old
+    }
+
+    // == Swap ==
+    /// Exchanges entries and statistics with `other`. Both stores keep
+    /// their own limits, so either may hold more entries than its
+    /// `max_entries` until `set_max_entries` is applied again.
+    pub fn swap_contents(&mut self, other: &mut CacheStore) {
+        self.engine.swap_contents(&mut other.engine);
+        std::mem::swap(&mut self.hotkeys, &mut other.hotkeys);
+        std::mem::swap(&mut self.slots, &mut other.slots);
+
+        if let Some(other_db) = other.replication.as_ref().map(|sink| sink.db().to_string()) {
+            self.replicate(|db1| Command::SwapDb { db1, db2: other_db });
+        }
+    }
+
+    // == Move ==
+    /// Returns true if `key` holds an entry that has not expired.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.engine.peek(key).is_some()
+    }
+
+    /// Removes a live entry and returns it with its TTL and access metadata,
+    /// so it can be inserted into another store.
+    pub fn take(&mut self, key: &str) -> Option<CacheEntry> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let entry = self.remove_raw(key)?;
+        self.replicate(|db| Command::Del {
+            db,
+            key: key.to_string(),
+        });
+        Some(entry)
+    }
+
+    /// Like `take`, but only removes the entry if `matches` accepts it, so
+    /// an entry changed since it was read is left in place.
+    pub fn take_if(
+        &mut self,
+        key: &str,
+        matches: impl FnOnce(&CacheEntry) -> bool,
+    ) -> Option<CacheEntry> {
+        if !self.entry(key).is_some_and(matches) {
+            return None;
+        }
+        self.take(key)
+    }
+
+    /// Inserts an entry taken from another store, keeping its expiry and
+    /// access metadata. Evicts the least recently used entry when full.
+    pub fn insert_entry(&mut self, key: String, entry: CacheEntry) -> Result<()> {
+        let (value, expires_at) = (entry.value.clone(), entry.expires_at);
+        let (grace_ms, compute_ms) = (entry.grace_ms, entry.compute_ms);
+        let evicted = self.engine.insert(key.clone(), entry)?;
+        self.evicted(evicted);
+        if let Some(slots) = &mut self.slots {
+            slots.entry(key_slot(&key)).or_default().insert(key.clone());
+        }
+        self.replicate(|db| Command::Set {
+            db,
+            key,
+            value,
+            expires_at,
+            grace_ms,
+            compute_ms,
+        });
+        Ok(())
+    }
+
+    /// Drops keys the engine evicted from the slot index and records them
+    /// for followers.
+    fn evicted(&mut self, keys: Vec<String>) {
+        for key in keys {
+            self.unindex(&key);
+            self.replicate(|db| Command::Evict { db, key });
+        }
+    }
+
+    // == Hash Slots ==
+    /// Returns a live entry without touching its access metadata.
+    pub fn entry(&self, key: &str) -> Option<&CacheEntry> {
+        self.engine.peek(key)
+    }
+
+    /// Like `entry`, but also returns an expired entry not yet removed.
+    pub fn stored_entry(&self, key: &str) -> Option<&CacheEntry> {
+        self.engine.peek_stored(key)
+    }
+
+    /// Returns up to `count` live keys hashing to `slot`.
+    ///
+    /// Uses the slot index when enabled, otherwise scans the whole map.
+    pub fn keys_in_slot(&self, slot: u16, count: usize) -> Vec<String> {
+        let live = |key: &&String| self.contains_key(key);
+        match &self.slots {
+            Some(slots) => slots
+                .get(&slot)
+                .into_iter()
+                .flatten()
+                .filter(live)
+                .take(count)
+                .cloned()
+                .collect(),
+            None => self
+                .engine
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| key_slot(key) == slot)
+                .filter(live)
+                .take(count)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Returns the number of live keys hashing to `slot`.
+    pub fn count_keys_in_slot(&self, slot: u16) -> usize {
+        self.keys_in_slot(slot, usize::MAX).len()
+    }
+
+    /// Removes from the engine, keeping the slot index in step.
+    fn remove_raw(&mut self, key: &str) -> Option<CacheEntry> {
+        let entry = self.engine.remove(key)?;
+        self.unindex(key);
+        Some(entry)
+    }
+
+    /// Drops a removed key from the slot index.
+    fn unindex(&mut self, key: &str) {
+        if let Some(slots) = &mut self.slots {
+            let slot = key_slot(key);
+            if let Some(keys) = slots.get_mut(&slot) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    slots.remove(&slot);
+                }
+            }
+        }
+    }
+
+    // == Replication ==
+    /// Starts recording every change to `sink`.
+    pub fn set_replication(&mut self, sink: ReplicationSink) {
+        self.replication = Some(sink);
+    }
+
+    /// Returns true if changes are being recorded for followers.
+    pub fn is_replicated(&self) -> bool {
+        self.replication.is_some()
+    }
+
+    /// Iterates over the entries that have not expired.
+    pub fn live_entries(&self) -> impl Iterator<Item = (&str, &CacheEntry)> {
+        self.engine
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired())
+            .map(|(key, entry)| (key.as_str(), entry))
+    }
+
+    /// Iterates over the entries that can still be served, including
+    /// expired ones within their grace window.
+    pub fn servable_entries(&self) -> impl Iterator<Item = (&str, &CacheEntry)> {
+        self.engine
+            .iter()
+            .filter(|(_, entry)| !entry.is_past_grace())
+            .map(|(key, entry)| (key.as_str(), entry))
+    }
+
+    fn replicate(&self, command: impl FnOnce(String) -> Command) {
+        if let Some(sink) = &self.replication {
+            sink.record(command);
+        }
+    }
+
+    fn replicate_limits(&self) {
+        self.replicate(|db| Command::Limits {
+            db,
+            max_entries: self.max_entries(),
+            default_ttl: self.default_ttl,
+        });
+    }
+
     // == Length ==
     /// Returns the current number of entries in the cache.
     pub fn len(&self) -> usize {
-        self.entries.len()
+        self.engine.len()
     }
 
     // == Is Empty ==
     /// Returns true if the cache is empty.
     #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+        self.engine.len() == 0
     }
 }
 
+/// Position of a key in scan order. Fixed for the life of the process,
+/// unlike the map's own randomly seeded hasher.
+fn scan_hash(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
 
 // == Unit Tests ==
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cache::current_timestamp_ms;
     use std::thread::sleep;
     use std::time::Duration;
 
@@ -274,6 +746,92 @@ mod tests {
         assert!(matches!(result, Err(CacheError::Expired(_))));
     }
 
+    #[test]
+    fn test_store_serves_stale_within_grace() {
+        let mut store = CacheStore::new(100, 300);
+        let grace = SetOptions {
+            grace: Some(60),
+            ..SetOptions::default()
+        };
+        store
+            .set_with("key".to_string(), "value".to_string(), Some(0), grace)
+            .unwrap();
+
+        // Only the first stale read is asked to refresh
+        let first = store.read("key").unwrap();
+        assert_eq!(first.value, "value");
+        assert!(first.stale && first.refresh);
+        let second = store.read("key").unwrap();
+        assert!(second.stale && !second.refresh);
+        assert_eq!(store.get("key").unwrap(), "value");
+
+        // Cleanup and TTL lookups treat the entry as expired but keep it
+        assert_eq!(store.cleanup_expired(), 0);
+        assert!(store.entry("key").is_none());
+        assert_eq!(store.stats().stale_hits, 3);
+        assert_eq!(store.stats().hits, 3);
+
+        // Setting the key again makes it fresh
+        store.set("key".to_string(), "new".to_string(), None).unwrap();
+        let fresh = store.read("key").unwrap();
+        assert!(!fresh.stale && !fresh.refresh);
+
+        // Out-of-range windows saturate instead of overflowing
+        let endless = SetOptions {
+            grace: Some(u64::MAX),
+            ..SetOptions::default()
+        };
+        store
+            .set_with("endless".to_string(), "value".to_string(), Some(0), endless)
+            .unwrap();
+        assert_eq!(store.read("endless").unwrap().value, "value");
+        assert!(store.read("endless").unwrap().stale);
+    }
+
+    #[test]
+    fn test_store_refreshes_early_by_compute_time() {
+        let mut store = CacheStore::new(100, 300);
+        let slow = SetOptions {
+            compute_ms: Some(u64::MAX / 2),
+            ..SetOptions::default()
+        };
+        store
+            .set_with("slow".to_string(), "value".to_string(), Some(60), slow)
+            .unwrap();
+        store.set("plain".to_string(), "value".to_string(), Some(60)).unwrap();
+
+        // A compute time far beyond the TTL makes every read refresh
+        let read = store.read("slow").unwrap();
+        assert!(read.refresh && !read.stale);
+        assert!(store.read("slow").unwrap().refresh);
+        assert_eq!(store.entry("slow").unwrap().compute_ms, Some(u64::MAX / 2));
+
+        // Without a compute time reads are never asked before expiry
+        assert!(!store.read("plain").unwrap().refresh);
+    }
+
+    #[test]
+    fn test_store_removes_entries_past_grace() {
+        let mut store = CacheStore::new(100, 300);
+        let expired = current_timestamp_ms() - 20;
+        let entry = CacheEntry::with_expires_at("value".to_string(), Some(expired));
+        store
+            .insert_entry("gone".to_string(), entry.clone().with_grace_ms(10))
+            .unwrap();
+        store.insert_entry("kept".to_string(), entry.with_grace_ms(60_000)).unwrap();
+
+        assert!(matches!(store.read("gone"), Err(CacheError::Expired(_))));
+        store
+            .insert_entry(
+                "cleaned".to_string(),
+                CacheEntry::with_expires_at("value".to_string(), Some(expired)).with_grace_ms(10),
+            )
+            .unwrap();
+        assert_eq!(store.cleanup_expired(), 1);
+        assert_eq!(store.len(), 1);
+        assert!(store.read("kept").unwrap().stale);
+    }
+
     #[test]
     fn test_store_lru_eviction() {
         let mut store = CacheStore::new(3, 300);
@@ -340,6 +898,264 @@ mod tests {
         assert!(store.get("key2").is_ok());
     }
 
+    #[test]
+    fn test_store_hot_keys() {
+        let mut store = CacheStore::new(100, 300);
+
+        store.set("hot".to_string(), "value".to_string(), None).unwrap();
+        store.set("cold".to_string(), "value".to_string(), None).unwrap();
+        for _ in 0..5 {
+            store.get("hot").unwrap();
+        }
+
+        let hot = store.hot_keys(1);
+        assert_eq!(hot.len(), 1);
+        assert_eq!(hot[0].key, "hot");
+        assert_eq!(hot[0].count, 6);
+    }
+
+    #[test]
+    fn test_store_big_keys() {
+        let mut store = CacheStore::new(100, 300);
+
+        store.set("small".to_string(), "x".to_string(), None).unwrap();
+        store.set("large".to_string(), "x".repeat(1000), None).unwrap();
+        store.set("medium".to_string(), "x".repeat(100), None).unwrap();
+
+        let big = store.big_keys(2);
+        assert_eq!(
+            big,
+            vec![
+                BigKey { key: "large".to_string(), size: 1000 },
+                BigKey { key: "medium".to_string(), size: 100 },
+            ]
+        );
+        assert!(store.big_keys(0).is_empty());
+    }
+
+    #[test]
+    fn test_store_scan() {
+        let mut store = CacheStore::new(1000, 300);
+        for i in 0..100 {
+            store.set(format!("user:{}", i), "v".to_string(), None).unwrap();
+            store.set(format!("item:{}", i), "v".to_string(), None).unwrap();
+        }
+
+        // Paging returns each key once, with keys added meanwhile or not
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = store.scan(cursor, None, 7);
+            assert!(keys.len() <= 7);
+            seen.extend(keys);
+            store.set(format!("new:{}", cursor), "v".to_string(), None).unwrap();
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        let original = seen.iter().filter(|key| !key.starts_with("new:")).count();
+        assert_eq!(original, 200);
+        let unique: HashSet<&String> = seen.iter().collect();
+        assert_eq!(unique.len(), seen.len());
+
+        // The pattern filters each page
+        let (next, keys) = store.scan(0, Some("user:*"), 10_000);
+        assert_eq!(next, 0);
+        assert_eq!(keys.len(), 100);
+        assert!(keys.iter().all(|key| key.starts_with("user:")));
+        assert_eq!(store.scan(0, Some("none:*"), 10_000), (0, Vec::new()));
+    }
+
+    #[test]
+    fn test_store_inspect_is_side_effect_free() {
+        let mut store = CacheStore::new(100, 300);
+
+        store.set("key1".to_string(), "value1".to_string(), Some(60)).unwrap();
+        store.set("key2".to_string(), "42".to_string(), None).unwrap();
+        store.get("key1").unwrap();
+
+        let info = store.inspect("key1").unwrap();
+        assert_eq!(info.value_type, "string");
+        assert_eq!(info.encoding, "embstr");
+        assert_eq!(info.size, 10);
+        assert_eq!(info.access_count, 1);
+        assert_eq!(info.eviction_rank, Some(1));
+        assert!(info.ttl_ms.unwrap() <= 60_000);
+        assert_eq!(store.inspect("key2").unwrap().encoding, "int");
+
+        // Inspecting changed neither stats nor LRU order nor access counts
+        let stats = store.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 0));
+        assert_eq!(store.inspect("key1").unwrap().access_count, 1);
+        assert_eq!(store.inspect("key2").unwrap().eviction_rank, Some(0));
+        assert!(store.inspect("missing").is_none());
+    }
+
+    #[test]
+    fn test_store_keyspace_info() {
+        let mut store = CacheStore::new(100, 300);
+
+        store.set("key1".to_string(), "value1".to_string(), Some(100)).unwrap();
+        store.set("key2".to_string(), "value2".to_string(), Some(200)).unwrap();
+
+        let info = store.keyspace_info();
+        assert_eq!(info.keys, 2);
+        assert_eq!(info.expires, 2);
+        assert_eq!(info.persistent, 0);
+        assert!(info.avg_ttl_ms > 140_000 && info.avg_ttl_ms <= 150_000);
+    }
+
+    #[test]
+    fn test_store_memory_usage_tracks_entries() {
+        let mut store = CacheStore::new(100, 300);
+        assert_eq!(store.memory_usage(), 0);
+
+        store.set("key".to_string(), "x".repeat(1000), None).unwrap();
+        let used = store.memory_usage();
+        assert!(used >= 1000 + 3);
+
+        store.delete("key").unwrap();
+        assert_eq!(store.memory_usage(), 0);
+    }
+
+    #[test]
+    fn test_store_expirations_counted() {
+        let mut store = CacheStore::new(100, 300);
+
+        store.set("key1".to_string(), "value1".to_string(), Some(1)).unwrap();
+        store.set("key2".to_string(), "value2".to_string(), Some(1)).unwrap();
+        sleep(Duration::from_millis(1100));
+
+        let _ = store.get("key1");
+        store.cleanup_expired();
+
+        let stats = store.stats();
+        assert_eq!(stats.expired, 2);
+        assert_eq!(stats.cleanup_runs, 1);
+        assert_eq!(stats.last_cleanup_removed, 1);
+    }
+
+    #[test]
+    fn test_store_shrink_evicts_lru() {
+        let mut store = CacheStore::new(5, 300);
+        for i in 0..5 {
+            store.set(format!("key{}", i), "value".to_string(), None).unwrap();
+        }
+        store.get("key0").unwrap();
+
+        assert_eq!(store.set_max_entries(2), 3);
+        assert_eq!(store.len(), 2);
+        assert!(store.get("key0").is_ok());
+        assert!(store.get("key4").is_ok());
+        assert_eq!(store.stats().evictions, 3);
+        assert_eq!(store.stats().total_entries, 2);
+
+        // Growing again evicts nothing
+        assert_eq!(store.set_max_entries(10), 0);
+    }
+
+    #[test]
+    fn test_store_take_and_insert_entry() {
+        let mut source = CacheStore::new(10, 300);
+        let mut target = CacheStore::new(1, 300);
+        source.set("moved".to_string(), "value".to_string(), Some(60)).unwrap();
+        target.set("old".to_string(), "value".to_string(), None).unwrap();
+
+        let entry = source.take("moved").unwrap();
+        assert!(!source.contains_key("moved"));
+        assert_eq!(source.stats().total_entries, 0);
+
+        target.insert_entry("moved".to_string(), entry).unwrap();
+        assert!(target.contains_key("moved"));
+        assert!(!target.contains_key("old"));
+        assert!(target.inspect("moved").unwrap().ttl_ms.unwrap() <= 60_000);
+        assert!(source.take("missing").is_none());
+    }
+
+    #[test]
+    fn test_store_keys_in_slot() {
+        let mut indexed = CacheStore::new(10, 300);
+        indexed.enable_slot_index();
+        let mut scanned = CacheStore::new(10, 300);
+
+        // "foo" and "{foo}.bar" share slot 12182, "bar" is in 5061
+        for store in [&mut indexed, &mut scanned] {
+            for key in ["foo", "{foo}.bar", "bar"] {
+                store.set(key.to_string(), "value".to_string(), None).unwrap();
+            }
+            store.delete("foo").unwrap();
+
+            assert_eq!(store.keys_in_slot(12182, 10), vec!["{foo}.bar"]);
+            assert_eq!(store.count_keys_in_slot(5061), 1);
+            assert_eq!(store.count_keys_in_slot(0), 0);
+
+            // Only an unchanged entry is taken
+            assert!(store.take_if("bar", |e| e.value == "other").is_none());
+            assert!(store.take_if("bar", |e| e.value == "value").is_some());
+            assert_eq!(store.count_keys_in_slot(5061), 0);
+        }
+
+        // The index survives a flush
+        indexed.set("foo".to_string(), "value".to_string(), None).unwrap();
+        drop(indexed.flush(false));
+        indexed.set("foo".to_string(), "value".to_string(), None).unwrap();
+        assert_eq!(indexed.keys_in_slot(12182, 10), vec!["foo"]);
+    }
+
+    #[test]
+    fn test_store_flush() {
+        let mut store = CacheStore::new(10, 300);
+        for i in 0..3 {
+            store.set(format!("key{}", i), "value".to_string(), None).unwrap();
+        }
+        store.get("key0").unwrap();
+
+        let old = store.flush(false);
+        assert_eq!(old.len(), 3);
+        assert!(store.is_empty());
+        assert_eq!(store.max_entries(), 10);
+        assert_eq!(store.default_ttl(), 300);
+        assert_eq!(store.stats().total_entries, 0);
+        assert_eq!(store.stats().hits, 1);
+        assert!(store.hot_keys(10).is_empty());
+
+        store.set("key".to_string(), "value".to_string(), None).unwrap();
+        assert_eq!(store.flush(true).len(), 1);
+        assert_eq!(store.stats().hits, 0);
+    }
+
+    #[test]
+    fn test_store_records_replicated_commands() {
+        use crate::replication::{Message, ReplicationLog};
+        use std::sync::Arc;
+
+        let log = Arc::new(ReplicationLog::new(100));
+        let mut store = CacheStore::new(1, 300);
+        store.set("unrecorded".to_string(), "value".to_string(), None).unwrap();
+        store.set_replication(ReplicationSink::new("0", log.clone()));
+
+        store.set("key".to_string(), "value".to_string(), None).unwrap();
+        store.delete("key").unwrap();
+        store.flush(false);
+
+        let (lines, _) = log.resume(log.replid(), 0).unwrap();
+        let commands: Vec<Command> = lines
+            .iter()
+            .map(|line| match serde_json::from_slice(line).unwrap() {
+                Message::Command { command, .. } => command,
+                other => panic!("unexpected message {:?}", other),
+            })
+            .collect();
+        let db = || "0".to_string();
+        assert!(matches!(&commands[0], Command::Evict { key, .. } if key == "unrecorded"));
+        assert!(matches!(&commands[1], Command::Set { key, .. } if key == "key"));
+        assert_eq!(commands[2], Command::Del { db: db(), key: "key".to_string() });
+        assert_eq!(commands[3], Command::Flush { db: db() });
+        assert_eq!(commands.len(), 4);
+        assert!(store.is_replicated());
+    }
+
     #[test]
     fn test_store_key_too_long() {
         let mut store = CacheStore::new(100, 300);
@@ -0,0 +1,79 @@
+//! Client Errors
+//!
+//! Failures of requests made with `Client` and `ShardedClient`.
+
+use thiserror::Error;
+
+/// Result type of client operations
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+// == Client Error ==
+/// Why a request to a server failed.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The server URL cannot be used
+    #[error("Invalid server URL '{url}': {reason}")]
+    InvalidUrl { url: String, reason: String },
+
+    /// The server could not be reached or the connection broke
+    #[error("Request to {url} failed: {reason}")]
+    Connection { url: String, reason: String },
+
+    /// The server did not answer in time
+    #[error("Request to {url} timed out")]
+    Timeout { url: String },
+
+    /// The server answered with an error status
+    #[error("Server answered {status}: {message}")]
+    Server { status: u16, message: String },
+
+    /// The response body is not what the API returns
+    #[error("Invalid response from {url}: {reason}")]
+    Decode { url: String, reason: String },
+
+    /// Cluster redirects did not lead to a node serving the key
+    #[error("Too many redirects, last to {location}")]
+    TooManyRedirects { location: String },
+
+    /// A redirect pointed somewhere the client does not follow
+    #[error("Refusing redirect to {location}: {reason}")]
+    RefusedRedirect { location: String, reason: String },
+
+    /// A sharded client has no server to send the key to
+    #[error("No servers configured")]
+    NoServers,
+}
+
+impl ClientError {
+    /// Returns true if sending the request again may succeed: the server
+    /// was unreachable, slow, overloaded or temporarily unavailable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Connection { .. } | ClientError::Timeout { .. } => true,
+            ClientError::Server { status, .. } => matches!(status, 429 | 502 | 503 | 504),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_errors() {
+        let server = |status| ClientError::Server {
+            status,
+            message: String::new(),
+        };
+        assert!(server(503).is_retryable());
+        assert!(server(429).is_retryable());
+        assert!(!server(400).is_retryable());
+        assert!(!server(403).is_retryable());
+        assert!(ClientError::Timeout {
+            url: "http://127.0.0.1:3000".to_string()
+        }
+        .is_retryable());
+        assert!(!ClientError::NoServers.is_retryable());
+    }
+}
@@ -0,0 +1,523 @@
+//! Client Module
+//!
+//! A typed async client for the HTTP API. `Client` talks to one server over
+//! a pool of keep-alive connections, resends requests that failed for
+//! transient reasons with exponential backoff, and follows cluster `MOVED`
+//! and `ASK` redirects. `ShardedClient` spreads keys over several
+//! independent servers with a consistent hash ring, for sharding without
+//! cluster mode.
+//!
+//! ```no_run
+//! # async fn example() -> mini_redis::client::Result<()> {
+//! use mini_redis::client::ShardedClient;
+//! use mini_redis::models::SetRequest;
+//!
+//! let client = ShardedClient::new(["http://10.0.0.1:3000", "http://10.0.0.2:3000"])?;
+//! client.set(&SetRequest::new("user:1", "alice").with_ttl(60)).await?;
+//! let value = client.get("user:1").await?.map(|response| response.value);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only plain HTTP is supported; put a TLS-terminating proxy in front of
+//! servers that require HTTPS.
+
+mod error;
+mod retry;
+mod ring;
+mod sharded;
+
+use std::error::Error as StdError;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Bytes;
+use axum::http::{header, Method, Request, StatusCode, Uri};
+use http_body_util::{BodyExt, Full};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::models::{
+    DeleteResponse, GetResponse, HealthResponse, ScanResponse, SetRequest, SetResponse,
+    StatsResponse, TtlResponse,
+};
+
+pub use error::{ClientError, Result};
+pub use retry::{RetryPolicy, DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF, DEFAULT_MAX_RETRIES};
+pub use ring::{HashRing, DEFAULT_VIRTUAL_NODES};
+pub use sharded::ShardedClient;
+
+/// Cluster redirects followed for one request
+const MAX_REDIRECTS: usize = 5;
+
+type HttpClient = hyper_util::client::legacy::Client<HttpConnector, Full<Bytes>>;
+
+// == Client Options ==
+/// Settings shared by every server a client talks to.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// Bearer token sent with every request to the server and to
+    /// `trusted_hosts`
+    pub auth_token: Option<String>,
+    /// Other servers, as `host:port`, that redirects may send the bearer
+    /// token to, e.g. the other nodes of a cluster that requires it
+    pub trusted_hosts: Vec<String>,
+    /// Named database to use instead of the default one
+    pub database: Option<String>,
+    /// Longest wait for one attempt, from sending to the full response
+    pub timeout: Duration,
+    /// Longest wait for a new connection
+    pub connect_timeout: Duration,
+    pub retry: RetryPolicy,
+    /// Idle connections kept open per server
+    pub max_idle_connections: usize,
+    /// How long an idle connection is kept open
+    pub idle_timeout: Duration,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            auth_token: None,
+            trusted_hosts: Vec::new(),
+            database: None,
+            timeout: Duration::from_secs(5),
+            connect_timeout: Duration::from_secs(1),
+            retry: RetryPolicy::default(),
+            max_idle_connections: 32,
+            idle_timeout: Duration::from_secs(90),
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Builds the connection pool these options describe.
+    fn http_client(&self) -> HttpClient {
+        let mut connector = HttpConnector::new();
+        connector.set_nodelay(true);
+        connector.set_connect_timeout(Some(self.connect_timeout));
+        hyper_util::client::legacy::Client::builder(TokioExecutor::new())
+            .pool_max_idle_per_host(self.max_idle_connections)
+            .pool_idle_timeout(self.idle_timeout)
+            .build(connector)
+    }
+}
+
+// == Client ==
+/// A client of one server. Clones share the connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    /// `http://host:port`, with any path prefix and no trailing slash
+    base: String,
+    http: HttpClient,
+    options: Arc<ClientOptions>,
+}
+
+impl Client {
+    /// Creates a client of the server at `url` (`http://host:port` or
+    /// `host:port`) with the default options.
+    pub fn new(url: &str) -> Result<Self> {
+        Self::with_options(url, ClientOptions::default())
+    }
+
+    /// Creates a client of the server at `url` with `options`.
+    pub fn with_options(url: &str, options: ClientOptions) -> Result<Self> {
+        let http = options.http_client();
+        Self::with_pool(url, Arc::new(options), http)
+    }
+
+    /// Creates a client sharing `http` with other clients.
+    fn with_pool(url: &str, options: Arc<ClientOptions>, http: HttpClient) -> Result<Self> {
+        Ok(Self {
+            base: base_url(url)?,
+            http,
+            options,
+        })
+    }
+
+    /// The server's URL.
+    pub fn url(&self) -> &str {
+        &self.base
+    }
+
+    // == Commands ==
+    /// Stores a value (`PUT /set`).
+    pub async fn set(&self, request: &SetRequest) -> Result<SetResponse> {
+        let body = serde_json::to_vec(request).map_err(|e| ClientError::Decode {
+            url: self.base.clone(),
+            reason: e.to_string(),
+        })?;
+        self.request(Method::PUT, &self.database_path("set"), Some(body.into()))
+            .await
+    }
+
+    /// Returns the value of `key` (`GET /get/:key`), or None if the key
+    /// does not exist or has expired.
+    pub async fn get(&self, key: &str) -> Result<Option<GetResponse>> {
+        let path = format!("{}/{}", self.database_path("get"), encode_segment(key));
+        not_found_as_none(self.request(Method::GET, &path, None).await)
+    }
+
+    /// Deletes `key` (`DELETE /del/:key`); returns false if it did not
+    /// exist.
+    pub async fn delete(&self, key: &str) -> Result<bool> {
+        let path = format!("{}/{}", self.database_path("del"), encode_segment(key));
+        let deleted: Option<DeleteResponse> =
+            not_found_as_none(self.request(Method::DELETE, &path, None).await)?;
+        Ok(deleted.is_some())
+    }
+
+    /// Returns the remaining time to live of `key` (`GET /ttl/:key`), or
+    /// None if the key does not exist.
+    pub async fn ttl(&self, key: &str) -> Result<Option<TtlResponse>> {
+        let path = format!("{}/{}", self.database_path("ttl"), encode_segment(key));
+        not_found_as_none(self.request(Method::GET, &path, None).await)
+    }
+
+    /// Returns one page of keys matching the glob `pattern` (`GET /scan`).
+    /// Start with cursor "0" and pass the returned cursor until it is "0"
+    /// again; `count` is how many keys the server looks at per page.
+    pub async fn scan(
+        &self,
+        cursor: &str,
+        pattern: Option<&str>,
+        count: Option<usize>,
+    ) -> Result<ScanResponse> {
+        let mut path = format!(
+            "{}?cursor={}",
+            self.database_path("scan"),
+            encode_segment(cursor)
+        );
+        if let Some(pattern) = pattern {
+            path.push_str(&format!("&match={}", encode_segment(pattern)));
+        }
+        if let Some(count) = count {
+            path.push_str(&format!("&count={}", count));
+        }
+        self.request(Method::GET, &path, None).await
+    }
+
+    /// Returns the statistics of the database (`GET /stats`).
+    pub async fn stats(&self) -> Result<StatsResponse> {
+        self.request(Method::GET, &self.database_path("stats"), None)
+            .await
+    }
+
+    /// Checks that the server is up (`GET /health`).
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.request(Method::GET, "/health", None).await
+    }
+
+    /// Sends any API request and returns the JSON response, for endpoints
+    /// without a typed method. `path` starts with `/`.
+    pub async fn request_json(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<Value> {
+        let body = body.map(|body| Bytes::from(body.to_string()));
+        self.request(method, path, body).await
+    }
+
+    /// Path of a command, in the configured database if any.
+    fn database_path(&self, command: &str) -> String {
+        match &self.options.database {
+            Some(db) => format!("/db/{}/{}", encode_segment(db), command),
+            None => format!("/{}", command),
+        }
+    }
+
+    // == Transport ==
+    /// Sends a request, retrying and following redirects, and decodes the
+    /// successful response. Only plain HTTP redirects are followed, and the
+    /// bearer token goes only to this server and the trusted hosts.
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Bytes>,
+    ) -> Result<T> {
+        let mut url = format!("{}{}", self.base, path);
+        let mut retries = 0;
+        let mut redirects = 0;
+        let mut authorize = true;
+        loop {
+            match self.attempt(&method, &url, body.clone(), authorize).await {
+                Ok(Response::Body(bytes)) => {
+                    return serde_json::from_slice(&bytes).map_err(|e| ClientError::Decode {
+                        url,
+                        reason: e.to_string(),
+                    })
+                }
+                Ok(Response::Redirect(location)) => {
+                    redirects += 1;
+                    if redirects > MAX_REDIRECTS {
+                        return Err(ClientError::TooManyRedirects { location });
+                    }
+                    authorize = self.trusts(&redirect_authority(&location)?);
+                    url = location;
+                }
+                Err(e) if e.is_retryable() && retries < self.options.retry.max_retries => {
+                    retries += 1;
+                    tokio::time::sleep(self.options.retry.delay(retries)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Returns true if the bearer token may be sent to `authority`.
+    fn trusts(&self, authority: &str) -> bool {
+        let own = self.base.parse::<Uri>().ok();
+        own.as_ref()
+            .and_then(Uri::authority)
+            .is_some_and(|own| own.as_str().eq_ignore_ascii_case(authority))
+            || self
+                .options
+                .trusted_hosts
+                .iter()
+                .any(|host| host.eq_ignore_ascii_case(authority))
+    }
+
+    /// Sends one request, with the bearer token if `authorize`, and reads
+    /// the whole response.
+    async fn attempt(
+        &self,
+        method: &Method,
+        url: &str,
+        body: Option<Bytes>,
+        authorize: bool,
+    ) -> Result<Response> {
+        let connection = |e: &dyn StdError| ClientError::Connection {
+            url: url.to_string(),
+            reason: describe(e),
+        };
+
+        let mut request = Request::builder().method(method.clone()).uri(url);
+        if let Some(token) = self.options.auth_token.as_ref().filter(|_| authorize) {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        if body.is_some() {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+        }
+        let request = request
+            .body(Full::new(body.unwrap_or_default()))
+            .map_err(|e| connection(&e))?;
+
+        let exchange = async {
+            let response = self
+                .http
+                .request(request)
+                .await
+                .map_err(|e| connection(&e))?;
+            let status = response.status();
+            let location = response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let bytes = response
+                .into_body()
+                .collect()
+                .await
+                .map_err(|e| connection(&e))?
+                .to_bytes();
+            Ok((status, location, bytes))
+        };
+        let (status, location, bytes) = tokio::time::timeout(self.options.timeout, exchange)
+            .await
+            .map_err(|_| ClientError::Timeout {
+                url: url.to_string(),
+            })??;
+
+        if status.is_success() {
+            return Ok(Response::Body(bytes));
+        }
+        if matches!(
+            status,
+            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+        ) {
+            if let Some(location) = location {
+                return Ok(Response::Redirect(location));
+            }
+        }
+        let message = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|value| value["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned());
+        Err(ClientError::Server {
+            status: status.as_u16(),
+            message,
+        })
+    }
+}
+
+/// What one attempt got back
+enum Response {
+    Body(Bytes),
+    /// A cluster node pointing at the node serving the key
+    Redirect(String),
+}
+
+/// Maps a 404 answer to None.
+fn not_found_as_none<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ClientError::Server { status: 404, .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks a server URL and returns it as `http://authority[/prefix]`.
+fn base_url(url: &str) -> Result<String> {
+    let invalid = |reason: &str| ClientError::InvalidUrl {
+        url: url.to_string(),
+        reason: reason.to_string(),
+    };
+    let with_scheme = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    };
+    let uri: Uri = with_scheme
+        .parse()
+        .map_err(|e: axum::http::uri::InvalidUri| invalid(&e.to_string()))?;
+    match uri.scheme_str() {
+        Some("http") => {}
+        Some("https") => return Err(invalid("HTTPS is not supported")),
+        _ => return Err(invalid("expected an http:// URL")),
+    }
+    let authority = uri.authority().ok_or_else(|| invalid("no host"))?;
+    if uri.query().is_some() {
+        return Err(invalid("query strings are not supported"));
+    }
+    Ok(format!(
+        "http://{}{}",
+        authority,
+        uri.path().trim_end_matches('/')
+    ))
+}
+
+/// Returns the `host:port` a redirect points at, refusing anything but a
+/// plain HTTP URL.
+fn redirect_authority(location: &str) -> Result<String> {
+    let refused = |reason: &str| ClientError::RefusedRedirect {
+        location: location.to_string(),
+        reason: reason.to_string(),
+    };
+    let uri: Uri = location.parse().map_err(|_| refused("not a URL"))?;
+    if uri.scheme_str() != Some("http") {
+        return Err(refused("not an http:// URL"));
+    }
+    uri.authority()
+        .map(|authority| authority.as_str().to_string())
+        .ok_or_else(|| refused("no host"))
+}
+
+/// Percent-encodes `segment` for use as one path segment.
+pub(crate) fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// An error and its causes, e.g. `client error (Connect): connection refused`.
+pub(crate) fn describe(error: &dyn StdError) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_urls() {
+        assert_eq!(base_url("127.0.0.1:3000").unwrap(), "http://127.0.0.1:3000");
+        assert_eq!(
+            base_url("http://cache.local:3000/").unwrap(),
+            "http://cache.local:3000"
+        );
+        assert_eq!(
+            base_url("http://proxy:8080/cache/").unwrap(),
+            "http://proxy:8080/cache"
+        );
+        for invalid in [
+            "https://cache:3000",
+            "ftp://cache",
+            "http://cache:3000/?x=1",
+            "",
+        ] {
+            assert!(
+                matches!(base_url(invalid), Err(ClientError::InvalidUrl { .. })),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_path_segments_are_encoded() {
+        assert_eq!(encode_segment("user:1"), "user%3A1");
+        assert_eq!(encode_segment("a b/c?d"), "a%20b%2Fc%3Fd");
+        assert_eq!(encode_segment("plain-key_1.~"), "plain-key_1.~");
+        assert_eq!(encode_segment("é"), "%C3%A9");
+    }
+
+    #[test]
+    fn test_redirect_targets() {
+        assert_eq!(
+            redirect_authority("http://10.0.0.2:3000/get/foo").unwrap(),
+            "10.0.0.2:3000"
+        );
+        for refused in ["https://10.0.0.2:3000/get/foo", "/get/foo", "file:///etc"] {
+            assert!(
+                matches!(
+                    redirect_authority(refused),
+                    Err(ClientError::RefusedRedirect { .. })
+                ),
+                "{}",
+                refused
+            );
+        }
+
+        let options = ClientOptions {
+            trusted_hosts: vec!["10.0.0.2:3000".to_string()],
+            ..ClientOptions::default()
+        };
+        let client = Client::with_options("http://Cache.local:3000/", options).unwrap();
+        assert!(client.trusts("cache.local:3000"));
+        assert!(client.trusts("10.0.0.2:3000"));
+        assert!(!client.trusts("10.0.0.3:3000"));
+        assert!(!client.trusts("cache.local:3001"));
+    }
+
+    #[test]
+    fn test_database_paths() {
+        let options = ClientOptions {
+            database: Some("sessions".to_string()),
+            ..ClientOptions::default()
+        };
+        let client = Client::with_options("127.0.0.1:3000", options).unwrap();
+        assert_eq!(client.database_path("set"), "/db/sessions/set");
+        assert_eq!(
+            Client::new("127.0.0.1:3000").unwrap().database_path("set"),
+            "/set"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Retry Policy
+//!
+//! Failed requests that may succeed later are sent again after an
+//! exponentially growing, jittered delay.
+
+use std::time::Duration;
+
+use crate::random::random_u64;
+
+/// Retries after the first attempt unless the policy sets its own
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Delay before the first retry unless the policy sets its own
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Longest delay between retries unless the policy sets its own
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+// == Retry Policy ==
+/// How often and how long to wait before resending a failed request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retries
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Upper bound of the delay before retry number `retry` (from 1):
+    /// `initial_backoff * 2^(retry - 1)`, capped at `max_backoff`.
+    pub fn max_delay(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Delay before retry number `retry`: between half and all of
+    /// `max_delay`, so clients failing together do not retry together.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let max = self.max_delay(retry);
+        let jitter = random_u64() % 1000;
+        max.mul_f64(0.5 + jitter as f64 / 2000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(policy.max_delay(1), Duration::from_millis(100));
+        assert_eq!(policy.max_delay(2), Duration::from_millis(200));
+        assert_eq!(policy.max_delay(4), Duration::from_millis(800));
+        assert_eq!(policy.max_delay(5), Duration::from_secs(1));
+        assert_eq!(policy.max_delay(40), Duration::from_secs(1));
+
+        for retry in 1..=6 {
+            let delay = policy.delay(retry);
+            assert!(delay >= policy.max_delay(retry) / 2);
+            assert!(delay <= policy.max_delay(retry));
+        }
+    }
+
+    #[test]
+    fn test_delay_with_unbounded_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 100,
+            initial_backoff: Duration::MAX,
+            max_backoff: Duration::MAX,
+        };
+        for retry in [1, 2, 100] {
+            let delay = policy.delay(retry);
+            assert!(delay >= policy.max_delay(retry) / 2);
+            assert!(delay <= policy.max_delay(retry));
+        }
+        assert_eq!(policy.max_delay(1), Duration::MAX);
+    }
+}
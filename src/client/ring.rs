@@ -0,0 +1,185 @@
+//! Consistent Hash Ring
+//!
+//! Places every server at many points ("virtual nodes") on a 64-bit ring,
+//! and maps a key to the first server point at or after the key's hash.
+//! Adding a server only takes over the keys just before its points, about
+//! `1 / servers` of them, and removing one only moves its own keys. The
+//! layout depends only on the set of servers, not the order they were
+//! added in, so every client with the same servers agrees.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Points per server unless the ring sets its own
+pub const DEFAULT_VIRTUAL_NODES: usize = 160;
+
+// == Hash Ring ==
+/// Servers placed on a consistent hash ring.
+#[derive(Debug, Clone)]
+pub struct HashRing {
+    virtual_nodes: usize,
+    nodes: BTreeSet<String>,
+    /// Hash of every point and the server it belongs to
+    points: BTreeMap<u64, String>,
+}
+
+impl Default for HashRing {
+    fn default() -> Self {
+        Self::new(DEFAULT_VIRTUAL_NODES)
+    }
+}
+
+impl HashRing {
+    /// Creates an empty ring placing each server at `virtual_nodes` points
+    /// (at least one).
+    pub fn new(virtual_nodes: usize) -> Self {
+        Self {
+            virtual_nodes: virtual_nodes.max(1),
+            nodes: BTreeSet::new(),
+            points: BTreeMap::new(),
+        }
+    }
+
+    /// Adds `node`; returns false if it was already on the ring.
+    pub fn add(&mut self, node: impl Into<String>) -> bool {
+        let node = node.into();
+        if self.nodes.contains(&node) {
+            return false;
+        }
+        for point in self.points_of(&node) {
+            // Ties go to the smaller name, whatever the order of adding
+            match self.points.get(&point) {
+                Some(owner) if *owner <= node => {}
+                _ => {
+                    self.points.insert(point, node.clone());
+                }
+            }
+        }
+        self.nodes.insert(node);
+        true
+    }
+
+    /// Removes `node`; returns false if it was not on the ring.
+    pub fn remove(&mut self, node: &str) -> bool {
+        if !self.nodes.remove(node) {
+            return false;
+        }
+        self.points.retain(|_, owner| owner != node);
+        // Restore points the removed node had won in a tie
+        let nodes: Vec<String> = self.nodes.iter().cloned().collect();
+        for other in nodes {
+            for point in self.points_of(&other) {
+                self.points.entry(point).or_insert_with(|| other.clone());
+            }
+        }
+        true
+    }
+
+    /// Returns the server `key` maps to, or None if the ring is empty.
+    pub fn node_for(&self, key: &str) -> Option<&str> {
+        let hash = hash(key.as_bytes());
+        self.points
+            .range(hash..)
+            .next()
+            .or_else(|| self.points.iter().next())
+            .map(|(_, node)| node.as_str())
+    }
+
+    /// The servers on the ring, in name order.
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(String::as_str)
+    }
+
+    /// Returns true if `node` is on the ring.
+    pub fn contains(&self, node: &str) -> bool {
+        self.nodes.contains(node)
+    }
+
+    /// Number of servers on the ring.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the ring has no servers.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn points_of<'a>(&self, node: &'a str) -> impl Iterator<Item = u64> + 'a {
+        (0..self.virtual_nodes).map(move |i| hash(format!("{}#{}", node, i).as_bytes()))
+    }
+}
+
+/// FNV-1a followed by the MurmurHash3 finalizer, which spreads the close
+/// inputs of one server's points evenly over the ring.
+fn hash(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: usize = 10_000;
+
+    fn ring(nodes: &[&str]) -> HashRing {
+        let mut ring = HashRing::default();
+        for node in nodes {
+            ring.add(*node);
+        }
+        ring
+    }
+
+    fn owners(ring: &HashRing) -> Vec<String> {
+        (0..KEYS)
+            .map(|i| ring.node_for(&format!("key:{}", i)).unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_keys_spread_evenly() {
+        let ring = ring(&["a", "b", "c", "d"]);
+        let owners = owners(&ring);
+        for node in ring.nodes() {
+            let share = owners.iter().filter(|owner| *owner == node).count();
+            // A quarter each, give or take
+            assert!((1500..3500).contains(&share), "{} has {}", node, share);
+        }
+        assert!(HashRing::default().node_for("key").is_none());
+    }
+
+    #[test]
+    fn test_adding_a_node_moves_only_its_share() {
+        let mut ring = ring(&["a", "b", "c", "d"]);
+        let before = owners(&ring);
+        assert!(ring.add("e"));
+        assert!(!ring.add("e"));
+        let after = owners(&ring);
+
+        let moved: Vec<usize> = (0..KEYS).filter(|&i| before[i] != after[i]).collect();
+        // About a fifth of the keys, all of them to the new node
+        assert!(moved.len() < KEYS * 3 / 10, "{} keys moved", moved.len());
+        assert!(moved.len() > KEYS / 10, "{} keys moved", moved.len());
+        assert!(moved.iter().all(|&i| after[i] == "e"));
+
+        assert!(ring.remove("e"));
+        assert_eq!(owners(&ring), before);
+        assert!(!ring.remove("e"));
+    }
+
+    #[test]
+    fn test_layout_ignores_insertion_order() {
+        let forward = ring(&["http://10.0.0.1:3000", "http://10.0.0.2:3000"]);
+        let backward = ring(&["http://10.0.0.2:3000", "http://10.0.0.1:3000"]);
+        assert_eq!(owners(&forward), owners(&backward));
+        assert_eq!(forward.len(), 2);
+    }
+}
@@ -0,0 +1,151 @@
+//! Sharded Client
+//!
+//! Sends each key to one of several independent servers, chosen with a
+//! consistent hash ring over their URLs. All servers share one connection
+//! pool and the same options.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{
+    base_url, Client, ClientError, ClientOptions, HashRing, HttpClient, Result,
+    DEFAULT_VIRTUAL_NODES,
+};
+use crate::models::{GetResponse, SetRequest, SetResponse, StatsResponse, TtlResponse};
+
+// == Sharded Client ==
+/// A client spreading keys over several servers.
+#[derive(Debug, Clone)]
+pub struct ShardedClient {
+    ring: HashRing,
+    /// Client of each server, by URL
+    clients: HashMap<String, Client>,
+    options: Arc<ClientOptions>,
+    http: HttpClient,
+}
+
+impl ShardedClient {
+    /// Creates a client of the servers at `urls` with the default options.
+    pub fn new<I, S>(urls: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::with_options(urls, ClientOptions::default(), DEFAULT_VIRTUAL_NODES)
+    }
+
+    /// Creates a client of the servers at `urls` with `options`, placing
+    /// each server at `virtual_nodes` points on the ring.
+    pub fn with_options<I, S>(urls: I, options: ClientOptions, virtual_nodes: usize) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let http = options.http_client();
+        let mut client = Self {
+            ring: HashRing::new(virtual_nodes),
+            clients: HashMap::new(),
+            options: Arc::new(options),
+            http,
+        };
+        for url in urls {
+            client.add_server(url.as_ref())?;
+        }
+        Ok(client)
+    }
+
+    /// Adds the server at `url`; it takes over about `1 / servers` of the
+    /// keys. Returns false if it was already added.
+    pub fn add_server(&mut self, url: &str) -> Result<bool> {
+        let client = Client::with_pool(url, self.options.clone(), self.http.clone())?;
+        if !self.ring.add(client.url()) {
+            return Ok(false);
+        }
+        self.clients.insert(client.url().to_string(), client);
+        Ok(true)
+    }
+
+    /// Removes the server at `url`; its keys move to the other servers.
+    /// Returns false if it was not added.
+    pub fn remove_server(&mut self, url: &str) -> bool {
+        let Ok(url) = base_url(url) else {
+            return false;
+        };
+        self.clients.remove(&url);
+        self.ring.remove(&url)
+    }
+
+    /// URLs of the servers, in name order.
+    pub fn servers(&self) -> impl Iterator<Item = &str> {
+        self.ring.nodes()
+    }
+
+    /// Returns the URL of the server `key` maps to.
+    pub fn server_for(&self, key: &str) -> Option<&str> {
+        self.ring.node_for(key)
+    }
+
+    /// Returns the client of the server `key` maps to.
+    pub fn client_for(&self, key: &str) -> Result<&Client> {
+        self.ring
+            .node_for(key)
+            .and_then(|url| self.clients.get(url))
+            .ok_or(ClientError::NoServers)
+    }
+
+    // == Commands ==
+    /// Stores a value on the server of its key.
+    pub async fn set(&self, request: &SetRequest) -> Result<SetResponse> {
+        self.client_for(&request.key)?.set(request).await
+    }
+
+    /// Returns the value of `key` from its server, or None if missing.
+    pub async fn get(&self, key: &str) -> Result<Option<GetResponse>> {
+        self.client_for(key)?.get(key).await
+    }
+
+    /// Deletes `key` from its server; returns false if it did not exist.
+    pub async fn delete(&self, key: &str) -> Result<bool> {
+        self.client_for(key)?.delete(key).await
+    }
+
+    /// Returns the remaining time to live of `key` from its server, or None
+    /// if missing.
+    pub async fn ttl(&self, key: &str) -> Result<Option<TtlResponse>> {
+        self.client_for(key)?.ttl(key).await
+    }
+
+    /// Returns the statistics of every server, by URL.
+    pub async fn stats(&self) -> Vec<(String, Result<StatsResponse>)> {
+        let mut stats = Vec::new();
+        for url in self.ring.nodes() {
+            stats.push((url.to_string(), self.clients[url].stats().await));
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_servers_are_normalized() {
+        let mut client = ShardedClient::new(["127.0.0.1:3000", "http://127.0.0.1:3001/"]).unwrap();
+        assert_eq!(
+            client.servers().collect::<Vec<_>>(),
+            vec!["http://127.0.0.1:3000", "http://127.0.0.1:3001"]
+        );
+        assert!(!client.add_server("http://127.0.0.1:3000").unwrap());
+        assert!(client.add_server("https://127.0.0.1:3002").is_err());
+
+        let server = client.server_for("user:1").unwrap().to_string();
+        assert_eq!(client.client_for("user:1").unwrap().url(), server);
+        assert!(client.remove_server("127.0.0.1:3000"));
+        assert!(client.remove_server("127.0.0.1:3001"));
+        assert!(matches!(
+            client.client_for("user:1"),
+            Err(ClientError::NoServers)
+        ));
+    }
+}
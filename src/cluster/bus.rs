@@ -0,0 +1,86 @@
+//! Cluster Bus
+//!
+//! The service nodes gossip and vote on. It listens on its own port, by
+//! default the API port plus 10000, so cluster traffic stays apart from
+//! client requests and their authentication, rate limits and redirects.
+
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::post;
+use axum::{Json, Router};
+
+use super::gossip::{GOSSIP_PATH, VOTE_PATH};
+use super::{Cluster, GossipMessage, VoteRequest, VoteResponse};
+use crate::api::auth::bearer_token;
+use crate::error::{CacheError, Result};
+
+#[derive(Clone)]
+struct BusState {
+    cluster: Arc<Cluster>,
+    /// Token other nodes must present, when set
+    auth_token: Option<Arc<str>>,
+}
+
+/// Creates the router of the cluster bus. When `auth_token` is set, every
+/// message must carry it as a bearer token.
+pub fn bus_router(cluster: Arc<Cluster>, auth_token: Option<String>) -> Router {
+    let state = BusState {
+        cluster,
+        auth_token: auth_token.map(Arc::from),
+    };
+    Router::new()
+        .route(GOSSIP_PATH, post(gossip_handler))
+        .route(VOTE_PATH, post(vote_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+async fn require_token(
+    State(state): State<BusState>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    if let Some(expected) = &state.auth_token {
+        if bearer_token(req.headers()) != Some(&**expected) {
+            return Err(CacheError::Unauthorized(
+                "Invalid cluster bus token".to_string(),
+            ));
+        }
+    }
+    Ok(next.run(req).await)
+}
+
+/// Merges the sender's view and answers with this node's.
+async fn gossip_handler(
+    State(state): State<BusState>,
+    Json(message): Json<GossipMessage>,
+) -> Result<Json<GossipMessage>> {
+    reachable(&state.cluster, &message.sender.id)?;
+    state.cluster.receive_gossip(&message);
+    Ok(Json(state.cluster.gossip_message()))
+}
+
+/// Decides on a follower's request to replace its failed leader.
+async fn vote_handler(
+    State(state): State<BusState>,
+    Json(request): Json<VoteRequest>,
+) -> Result<Json<VoteResponse>> {
+    reachable(&state.cluster, &request.candidate)?;
+    Ok(Json(VoteResponse {
+        granted: state.cluster.grant_vote(&request),
+    }))
+}
+
+/// Drops messages from nodes cut off by `Cluster::set_unreachable`.
+fn reachable(cluster: &Cluster, id: &str) -> Result<()> {
+    if cluster.is_unreachable(id) {
+        return Err(CacheError::ClusterDown(format!(
+            "node '{}' is unreachable",
+            id
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! Node-to-Node Requests
+//!
+//! JSON requests to the admin endpoints and cluster bus of other nodes,
+//! used to coordinate slot migrations and to gossip. Like replication,
+//! these use HTTP/1.1; admin endpoints are reached over TLS when the nodes
+//! serve it, and the bus always over plain HTTP.
+
+use std::time::Duration;
+
+use axum::body::Bytes;
+use axum::http::{header, Request};
+use http_body_util::{BodyExt, Full};
+use hyper::client::conn::http1::SendRequest;
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tracing::debug;
+
+/// Longest wait for another node to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts `body` as JSON to `path` on the node at `address` and returns the
+/// decoded response, or an error for any status other than 2xx.
+///
+/// The node is reached over TLS when `tls` is given.
+pub(crate) async fn post_json(
+    address: &str,
+    path: &str,
+    body: &impl Serialize,
+    auth_token: Option<&str>,
+    tls: Option<&TlsConnector>,
+) -> Result<Value, String> {
+    tokio::time::timeout(REQUEST_TIMEOUT, send(address, path, body, auth_token, tls))
+        .await
+        .map_err(|_| format!("{} did not answer within {:?}", address, REQUEST_TIMEOUT))?
+}
+
+/// Like `post_json` over plain HTTP, giving up after `timeout`.
+pub(crate) async fn post_json_within(
+    address: &str,
+    path: &str,
+    body: &impl Serialize,
+    auth_token: Option<&str>,
+    timeout: Duration,
+) -> Result<Value, String> {
+    tokio::time::timeout(timeout, send(address, path, body, auth_token, None))
+        .await
+        .map_err(|_| format!("{} did not answer within {:?}", address, timeout))?
+}
+
+async fn send(
+    address: &str,
+    path: &str,
+    body: &impl Serialize,
+    auth_token: Option<&str>,
+    tls: Option<&TlsConnector>,
+) -> Result<Value, String> {
+    let stream = TcpStream::connect(address)
+        .await
+        .map_err(|e| format!("connect to {}: {}", address, e))?;
+    let mut sender = match tls {
+        Some(tls) => {
+            let name = crate::tls::server_name(address).map_err(|e| e.to_string())?;
+            let stream = tls
+                .connect(name, stream)
+                .await
+                .map_err(|e| format!("TLS handshake with {}: {}", address, e))?;
+            handshake(address, stream).await?
+        }
+        None => handshake(address, stream).await?,
+    };
+
+    let body = serde_json::to_vec(body).map_err(|e| e.to_string())?;
+    let mut request = Request::post(path)
+        .header(header::HOST, address)
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = auth_token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let request = request
+        .body(Full::new(Bytes::from(body)))
+        .map_err(|e| e.to_string())?;
+
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| format!("request to {}: {}", address, e))?;
+    let status = response.status();
+    let bytes = response
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| format!("response from {}: {}", address, e))?
+        .to_bytes();
+    let value: Value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+
+    if !status.is_success() {
+        let reason = value["error"].as_str().unwrap_or_default();
+        return Err(format!("{} answered {} {}", address, status, reason)
+            .trim_end()
+            .to_string());
+    }
+    Ok(value)
+}
+
+async fn handshake<T>(address: &str, io: T) -> Result<SendRequest<Full<Bytes>>, String>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(io))
+        .await
+        .map_err(|e| format!("handshake with {}: {}", address, e))?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!("Cluster connection closed: {}", e);
+        }
+    });
+    Ok(sender)
+}
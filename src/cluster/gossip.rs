@@ -0,0 +1,686 @@
+//! Cluster Gossip
+//!
+//! Every node regularly sends its view of the cluster to each node it knows
+//! on the cluster bus and merges the view sent back. Views carry the sender's
+//! slots with the epoch it gained them at, so the newest claim on a slot
+//! wins everywhere, and the other nodes the sender knows, so nodes joined
+//! through `cluster_meet` are discovered by all.
+//!
+//! A node that does not answer within the node timeout is suspected
+//! (`pfail`) by each node on its own. Leaders report their suspicions in
+//! gossip, and once a majority of the leaders suspect a node it is marked
+//! failed (`fail`). A follower of a failed leader then asks the leaders for
+//! votes at a new epoch, and takes over the leader's slots with a majority.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info};
+
+use super::client::post_json_within;
+use super::{Cluster, ClusterEventKind, NodeEntry, NodeHealth, SlotRange, Topology};
+use crate::replication::FollowerStatus;
+
+/// Bus route exchanging views of the cluster
+pub(crate) const GOSSIP_PATH: &str = "/gossip";
+
+/// Bus route asking for a failover vote
+pub(crate) const VOTE_PATH: &str = "/failover/vote";
+
+// == Messages ==
+/// A node as seen by the sender of a gossip message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeView {
+    pub id: String,
+    /// `host:port` of the node's API
+    pub address: String,
+    /// `host:port` of the node's cluster bus
+    pub bus_address: String,
+    #[serde(default)]
+    pub config_epoch: u64,
+    /// Slots served by the node; only sent for the sender itself
+    #[serde(default)]
+    pub slots: Vec<SlotRange>,
+    /// API address of the node's leader, for followers
+    #[serde(default)]
+    pub leader: Option<String>,
+    pub health: NodeHealth,
+}
+
+/// One node's view of the cluster, sent on every gossip round and answered
+/// with the receiver's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GossipMessage {
+    pub sender: NodeView,
+    /// Highest epoch the sender has seen
+    pub current_epoch: u64,
+    /// Every other node the sender knows
+    #[serde(default)]
+    pub nodes: Vec<NodeView>,
+}
+
+/// A follower asking to replace its failed leader at `epoch`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteRequest {
+    /// Id of the follower
+    pub candidate: String,
+    /// Id of the failed leader
+    pub leader: String,
+    pub epoch: u64,
+}
+
+/// Answer to a `VoteRequest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteResponse {
+    pub granted: bool,
+}
+
+// == Gossip ==
+impl Cluster {
+    /// This node's view of the cluster, to send to another node.
+    pub fn gossip_message(&self) -> GossipMessage {
+        let topology = self.read();
+        let view = |index: usize| {
+            let entry = &topology.nodes[index];
+            NodeView {
+                id: entry.id.clone(),
+                address: entry.address.clone(),
+                bus_address: entry.bus_address.clone(),
+                config_epoch: entry.config_epoch,
+                slots: if index == topology.myself {
+                    topology.node(index).slots
+                } else {
+                    Vec::new()
+                },
+                leader: entry.leader.clone(),
+                health: entry.health,
+            }
+        };
+        GossipMessage {
+            sender: view(topology.myself),
+            current_epoch: topology.current_epoch,
+            nodes: (0..topology.nodes.len())
+                .filter(|&index| index != topology.myself)
+                .map(view)
+                .collect(),
+        }
+    }
+
+    /// Merges another node's view of the cluster into this one.
+    ///
+    /// The sender is known to be up. Its slot claims replace those made at
+    /// older epochs, and its opinion of every other node counts towards
+    /// failing it when the sender is a leader.
+    pub fn receive_gossip(&self, message: &GossipMessage) {
+        let sender = &message.sender;
+        if sender.id == self.myself {
+            return;
+        }
+        let mut topology = self.write();
+        let now = Instant::now();
+        topology.current_epoch = topology.current_epoch.max(message.current_epoch);
+
+        let index = topology.upsert(sender, None);
+        let entry = &mut topology.nodes[index];
+        entry.address = sender.address.clone();
+        entry.bus_address = sender.bus_address.clone();
+        entry.leader = sender.leader.clone();
+        entry.config_epoch = entry.config_epoch.max(sender.config_epoch);
+        entry.last_seen = now;
+        topology.mark_reachable(index, self.node_timeout);
+        topology.claim_slots(index, &sender.slots);
+
+        let reporter = topology.slot_counts()[index] > 0;
+        for view in &message.nodes {
+            if view.id == self.myself {
+                continue;
+            }
+            let known = topology.upsert(view, Some(index));
+            if reporter {
+                let reports = &mut topology.nodes[known].fail_reports;
+                match view.health {
+                    NodeHealth::Online => reports.remove(&index),
+                    NodeHealth::Pfail | NodeHealth::Fail => reports.insert(index, now),
+                };
+            }
+            if view.health == NodeHealth::Fail && topology.nodes[known].health != NodeHealth::Fail {
+                topology.mark_failed(known, format!("was reported failed by '{}'", sender.id));
+            }
+        }
+    }
+
+    /// Suspects the nodes that have not answered within the node timeout,
+    /// and fails those a majority of the leaders suspect.
+    pub fn check_failures(&self) {
+        let mut topology = self.write();
+        let now = Instant::now();
+        let counts = topology.slot_counts();
+        let leaders = counts.iter().filter(|&&count| count > 0).count();
+        let myself = topology.myself;
+
+        for index in 0..topology.nodes.len() {
+            if index == myself {
+                continue;
+            }
+            let entry = &mut topology.nodes[index];
+            entry
+                .fail_reports
+                .retain(|_, at| now.duration_since(*at) < self.node_timeout * 2);
+            let silent = now.duration_since(entry.last_seen);
+            if entry.health == NodeHealth::Online && silent > self.node_timeout {
+                entry.health = NodeHealth::Pfail;
+                let detail = format!("has not answered for {}ms", silent.as_millis());
+                topology.record(index, ClusterEventKind::Pfail, detail);
+            }
+
+            let entry = &topology.nodes[index];
+            if entry.health == NodeHealth::Pfail {
+                let reports = entry
+                    .fail_reports
+                    .keys()
+                    .filter(|&&reporter| counts[reporter] > 0)
+                    .count()
+                    + usize::from(counts[myself] > 0);
+                if reports >= quorum(leaders) {
+                    let detail =
+                        format!("is failing according to {} of {} leaders", reports, leaders);
+                    topology.mark_failed(index, detail);
+                }
+            }
+        }
+    }
+
+    /// Bus addresses to gossip with: every reachable node, and the meet
+    /// addresses no known node listens on.
+    pub fn gossip_targets(&self) -> Vec<String> {
+        let topology = self.read();
+        let mut targets: Vec<String> = topology
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != topology.myself && !topology.unreachable.contains(index))
+            .map(|(_, node)| node.bus_address.clone())
+            .collect();
+        for seed in &self.meet {
+            if !topology.nodes.iter().any(|node| &node.bus_address == seed) {
+                targets.push(seed.clone());
+            }
+        }
+        targets
+    }
+
+    // == Failover ==
+    /// Starts an election when this node follows a failed leader that still
+    /// serves slots, returning the vote request to send to the leaders.
+    ///
+    /// Followers wait half a node timeout per follower ranked before them
+    /// by id, so they rarely compete, and retry after two node timeouts.
+    pub fn election(&self) -> Option<VoteRequest> {
+        let mut topology = self.write();
+        let myself = topology.myself;
+        let leader = topology.leader_of(myself)?;
+        if topology.nodes[leader].health != NodeHealth::Fail || topology.slot_counts()[leader] == 0
+        {
+            topology.next_election = None;
+            return None;
+        }
+
+        let now = Instant::now();
+        let mut followers: Vec<&str> = (0..topology.nodes.len())
+            .filter(|&index| topology.leader_of(index) == Some(leader))
+            .map(|index| topology.nodes[index].id.as_str())
+            .collect();
+        followers.sort_unstable();
+        let rank = followers
+            .iter()
+            .position(|&id| id == self.myself)
+            .unwrap_or_default();
+        let due = *topology
+            .next_election
+            .get_or_insert(now + self.node_timeout / 2 * rank as u32);
+        if now < due {
+            return None;
+        }
+
+        topology.current_epoch += 1;
+        topology.next_election = Some(now + self.node_timeout * 2);
+        let request = VoteRequest {
+            candidate: self.myself.clone(),
+            leader: topology.nodes[leader].id.clone(),
+            epoch: topology.current_epoch,
+        };
+        info!(
+            "Leader '{}' failed, asking for votes to replace it at epoch {}",
+            request.leader, request.epoch
+        );
+        Some(request)
+    }
+
+    /// Bus addresses of the leaders to ask for votes: every reachable
+    /// leader that has not failed.
+    pub fn voters(&self) -> Vec<String> {
+        let topology = self.read();
+        let counts = topology.slot_counts();
+        topology
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|&(index, node)| {
+                index != topology.myself
+                    && counts[index] > 0
+                    && node.health != NodeHealth::Fail
+                    && !topology.unreachable.contains(&index)
+            })
+            .map(|(_, node)| node.bus_address.clone())
+            .collect()
+    }
+
+    /// Decides on a vote request. A leader votes once per epoch, only for a
+    /// follower of a leader it also considers failed, and only once per
+    /// failed leader within two node timeouts.
+    pub fn grant_vote(&self, request: &VoteRequest) -> bool {
+        let mut topology = self.write();
+        let now = Instant::now();
+        topology.current_epoch = topology.current_epoch.max(request.epoch);
+        let myself = topology.myself;
+        if topology.slot_counts()[myself] == 0 || request.epoch <= topology.last_vote_epoch {
+            return false;
+        }
+        let (Some(candidate), Some(leader)) = (
+            topology.index_of(&request.candidate),
+            topology.index_of(&request.leader),
+        ) else {
+            return false;
+        };
+        if topology.leader_of(candidate) != Some(leader)
+            || topology.nodes[leader].health != NodeHealth::Fail
+            || topology
+                .votes
+                .get(&leader)
+                .is_some_and(|at| now.duration_since(*at) < self.node_timeout * 2)
+        {
+            return false;
+        }
+
+        topology.last_vote_epoch = request.epoch;
+        topology.votes.insert(leader, now);
+        info!(
+            "Voted for '{}' to replace '{}' at epoch {}",
+            request.candidate, request.leader, request.epoch
+        );
+        true
+    }
+
+    /// Takes over the slots of the failed leader if `votes` is a majority of
+    /// the leaders and the leader is still failed. Returns true if this
+    /// node is now a leader.
+    pub fn promote(&self, request: &VoteRequest, votes: usize) -> bool {
+        let mut topology = self.write();
+        let counts = topology.slot_counts();
+        let leaders = counts.iter().filter(|&&count| count > 0).count();
+        if votes < quorum(leaders) {
+            info!(
+                "Failover of '{}' at epoch {} got {} of {} votes",
+                request.leader, request.epoch, votes, leaders
+            );
+            return false;
+        }
+        let myself = topology.myself;
+        let Some(leader) = topology.index_of(&request.leader) else {
+            return false;
+        };
+        if topology.leader_of(myself) != Some(leader)
+            || topology.nodes[leader].health != NodeHealth::Fail
+        {
+            return false;
+        }
+
+        for owner in topology.owners.iter_mut() {
+            if *owner == Some(leader) {
+                *owner = Some(myself);
+            }
+        }
+        topology.importing.retain(|_, source| *source != leader);
+        topology.current_epoch = topology.current_epoch.max(request.epoch);
+        topology.nodes[myself].config_epoch = request.epoch;
+        topology.nodes[myself].leader = None;
+        topology.next_election = None;
+        let detail = format!(
+            "took over {} slots from '{}' at epoch {} with {} of {} votes",
+            counts[leader], request.leader, request.epoch, votes, leaders
+        );
+        topology.record(myself, ClusterEventKind::Promoted, detail);
+        true
+    }
+}
+
+/// Votes or reports needed out of `leaders`: a strict majority.
+fn quorum(leaders: usize) -> usize {
+    leaders / 2 + 1
+}
+
+impl Topology {
+    /// Returns the index of the node in `view`, adding it if it is new.
+    /// `via` is the node that told us about it.
+    fn upsert(&mut self, view: &NodeView, via: Option<usize>) -> usize {
+        if let Some(index) = self.index_of(&view.id) {
+            return index;
+        }
+        let mut entry = NodeEntry::new(
+            view.id.clone(),
+            view.address.clone(),
+            view.bus_address.clone(),
+        );
+        entry.leader = view.leader.clone();
+        self.nodes.push(entry);
+        let index = self.nodes.len() - 1;
+        let detail = match via {
+            Some(via) => format!(
+                "joined at {}, introduced by '{}'",
+                view.address, self.nodes[via].id
+            ),
+            None => format!("joined at {}", view.address),
+        };
+        self.record(index, ClusterEventKind::Joined, detail);
+        index
+    }
+
+    /// Clears the suspicion of a node that answered. A failed node stays
+    /// failed while it still serves slots, so its followers can take over,
+    /// unless the failure is older than two node timeouts.
+    fn mark_reachable(&mut self, index: usize, node_timeout: Duration) {
+        let serves_slots = self.slot_counts()[index] > 0;
+        let entry = &mut self.nodes[index];
+        let recovered = match entry.health {
+            NodeHealth::Online => false,
+            NodeHealth::Pfail => true,
+            NodeHealth::Fail => {
+                !serves_slots
+                    || entry
+                        .failed_at
+                        .is_none_or(|at| at.elapsed() > node_timeout * 2)
+            }
+        };
+        if recovered {
+            entry.health = NodeHealth::Online;
+            entry.failed_at = None;
+            entry.fail_reports.clear();
+            self.record(
+                index,
+                ClusterEventKind::Online,
+                "is reachable again".to_string(),
+            );
+        }
+    }
+
+    fn mark_failed(&mut self, index: usize, detail: String) {
+        let entry = &mut self.nodes[index];
+        entry.health = NodeHealth::Fail;
+        entry.failed_at = Some(Instant::now());
+        self.record(index, ClusterEventKind::Fail, detail);
+    }
+
+    /// Gives the node at `index` the slots it claims that are unassigned or
+    /// owned at an older epoch. Slots being imported here are left alone.
+    fn claim_slots(&mut self, index: usize, ranges: &[SlotRange]) {
+        let epoch = self.nodes[index].config_epoch;
+        let mut claimed = 0;
+        let mut previous: Vec<usize> = Vec::new();
+        for slot in ranges.iter().flat_map(|range| range.start..=range.end) {
+            if self.importing.contains_key(&slot) {
+                continue;
+            }
+            let owner = self.owners[slot as usize];
+            if owner == Some(index)
+                || owner.is_some_and(|owner| self.nodes[owner].config_epoch >= epoch)
+            {
+                continue;
+            }
+            self.owners[slot as usize] = Some(index);
+            claimed += 1;
+            if let Some(owner) = owner {
+                if owner == self.myself {
+                    self.migrating.remove(&slot);
+                }
+                if !previous.contains(&owner) {
+                    previous.push(owner);
+                }
+            }
+        }
+
+        if claimed > 0 {
+            let mut detail = format!("claimed {} slots at epoch {}", claimed, epoch);
+            if !previous.is_empty() {
+                let owners: Vec<String> = previous
+                    .iter()
+                    .map(|&owner| format!("'{}'", self.nodes[owner].id))
+                    .collect();
+                detail.push_str(&format!(" from {}", owners.join(", ")));
+            }
+            self.record(index, ClusterEventKind::SlotsMoved, detail);
+        }
+    }
+}
+
+// == Gossip Task ==
+/// Spawns the task gossiping with the other nodes, detecting failures and
+/// running failovers. `follower` is this server's replication link, which
+/// is stopped when it takes over from its leader.
+///
+/// The task runs until aborted.
+pub fn spawn_gossip_task(
+    cluster: Arc<Cluster>,
+    follower: Option<Arc<FollowerStatus>>,
+    auth_token: Option<String>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let node_timeout = cluster.node_timeout();
+        let period = (node_timeout / 5).clamp(Duration::from_millis(10), Duration::from_secs(1));
+        let request_timeout = (node_timeout / 2).max(Duration::from_millis(100));
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            // Answers arrive in the background, so a slow node never
+            // delays the next round
+            let message = Arc::new(cluster.gossip_message());
+            for target in cluster.gossip_targets() {
+                let cluster = cluster.clone();
+                let message = message.clone();
+                let auth_token = auth_token.clone();
+                tokio::spawn(async move {
+                    let reply = post_json_within(
+                        &target,
+                        GOSSIP_PATH,
+                        &*message,
+                        auth_token.as_deref(),
+                        request_timeout,
+                    )
+                    .await
+                    .and_then(|value| serde_json::from_value(value).map_err(|e| e.to_string()));
+                    match reply {
+                        Ok(reply) => cluster.receive_gossip(&reply),
+                        Err(e) => debug!("Gossip with {} failed: {}", target, e),
+                    }
+                });
+            }
+
+            cluster.check_failures();
+            if let Some(request) = cluster.election() {
+                let votes =
+                    request_votes(&cluster, &request, auth_token.as_deref(), request_timeout).await;
+                if cluster.promote(&request, votes) {
+                    if let Some(follower) = &follower {
+                        follower.promote();
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Sends `request` to every voter and counts the votes granted.
+async fn request_votes(
+    cluster: &Cluster,
+    request: &VoteRequest,
+    auth_token: Option<&str>,
+    timeout: Duration,
+) -> usize {
+    let mut ballots = JoinSet::new();
+    for voter in cluster.voters() {
+        let request = request.clone();
+        let auth_token = auth_token.map(str::to_string);
+        ballots.spawn(async move {
+            post_json_within(&voter, VOTE_PATH, &request, auth_token.as_deref(), timeout)
+                .await
+                .and_then(|value| {
+                    serde_json::from_value::<VoteResponse>(value).map_err(|e| e.to_string())
+                })
+                .map_err(|e| debug!("Vote request to {} failed: {}", voter, e))
+        });
+    }
+
+    let mut votes = 0;
+    while let Some(ballot) = ballots.join_next().await {
+        if let Ok(Ok(VoteResponse { granted: true })) = ballot {
+            votes += 1;
+        }
+    }
+    votes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster::{ClusterNode, SlotState};
+
+    const NODES: &str = "a|127.0.0.1:7000|0-5460; b|127.0.0.1:7001|5461-10922; \
+                         c|127.0.0.1:7002|10923-16383; d|127.0.0.1:7003|";
+
+    fn node(myself: &str, table: &str) -> Cluster {
+        let nodes = ClusterNode::parse_list(table).unwrap();
+        Cluster::new(myself, nodes)
+            .unwrap()
+            .with_node_timeout(Duration::from_millis(100))
+    }
+
+    /// One gossip round trip from `from` to `to`.
+    fn exchange(from: &Cluster, to: &Cluster) {
+        to.receive_gossip(&from.gossip_message());
+        from.receive_gossip(&to.gossip_message());
+    }
+
+    fn health(cluster: &Cluster, id: &str) -> NodeHealth {
+        cluster
+            .node_statuses()
+            .into_iter()
+            .find(|status| status.node.id == id)
+            .map(|status| status.health)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_gossip_discovers_nodes_and_slots() {
+        let a = node("a", "a|127.0.0.1:7000|0-8191");
+        let b = node("b", "b|127.0.0.1:7001|8192-16383");
+        let c = node("c", "c|127.0.0.1:7002|");
+
+        exchange(&b, &a);
+        assert!(a.is_complete());
+        // c only meets a, and learns about b from it
+        exchange(&c, &a);
+        assert_eq!(c.nodes().len(), 3);
+        assert_eq!(c.slots_assigned(), 8192);
+        assert_eq!(
+            c.gossip_targets(),
+            vec!["127.0.0.1:17000", "127.0.0.1:17001"]
+        );
+        exchange(&c, &b);
+        assert!(c.is_complete());
+        assert_eq!(c.owner(10000).unwrap().id, "b");
+
+        let events: Vec<ClusterEventKind> = c.events().iter().map(|e| e.event).collect();
+        assert!(events.contains(&ClusterEventKind::Joined));
+        assert!(events.contains(&ClusterEventKind::SlotsMoved));
+    }
+
+    #[test]
+    fn test_newer_epoch_wins_slot_claims() {
+        let a = node("a", NODES);
+        let b = node("b", NODES);
+        let slot = [SlotRange { start: 0, end: 0 }];
+
+        // a's claim on slot 0 is as old as b's view of it
+        b.set_slots(&slot, &SlotState::Node("b".to_string()))
+            .unwrap();
+        assert_eq!(b.current_epoch(), 1);
+        exchange(&a, &b);
+        assert_eq!(a.owner(0).unwrap().id, "b");
+        assert_eq!(b.owner(0).unwrap().id, "b");
+        assert_eq!(a.current_epoch(), 1);
+    }
+
+    #[test]
+    fn test_failover_needs_a_majority_of_leaders() {
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|id| node(id, NODES));
+        let d = d.with_leader(Some("127.0.0.1:7000".to_string()));
+        let all = [&a, &b, &c, &d];
+        for (i, x) in all.iter().enumerate() {
+            for y in &all[i + 1..] {
+                exchange(x, y);
+            }
+        }
+        let request = VoteRequest {
+            candidate: "d".to_string(),
+            leader: "a".to_string(),
+            epoch: 1,
+        };
+        assert!(!b.grant_vote(&request), "a has not failed");
+
+        // a stops answering; the others keep talking
+        std::thread::sleep(Duration::from_millis(150));
+        exchange(&b, &c);
+        exchange(&b, &d);
+        exchange(&c, &d);
+        b.check_failures();
+        assert_eq!(health(&b, "a"), NodeHealth::Pfail);
+        assert_eq!(health(&b, "c"), NodeHealth::Online);
+
+        // c's own suspicion and b's report make two of three leaders
+        c.receive_gossip(&b.gossip_message());
+        c.check_failures();
+        assert_eq!(health(&c, "a"), NodeHealth::Fail);
+        b.receive_gossip(&c.gossip_message());
+        d.receive_gossip(&c.gossip_message());
+        assert_eq!(health(&d, "a"), NodeHealth::Fail);
+
+        let request = d.election().unwrap();
+        assert_eq!(request.leader, "a");
+        assert!(d.election().is_none(), "one election at a time");
+        assert!(b.grant_vote(&request));
+        assert!(!b.grant_vote(&request), "one vote per epoch");
+        assert!(c.grant_vote(&request));
+        assert!(!d.promote(&request, 1));
+        assert!(d.promote(&request, 2));
+
+        assert_eq!(d.slots_served(), 5461);
+        assert_eq!(d.owner(0).unwrap().id, "d");
+        let events: Vec<ClusterEventKind> = d.events().iter().map(|e| e.event).collect();
+        assert_eq!(events[0], ClusterEventKind::Promoted);
+
+        // The newer epoch wins on every node, including the old leader
+        for other in [&b, &a] {
+            other.receive_gossip(&d.gossip_message());
+            assert_eq!(other.owner(0).unwrap().id, "d");
+        }
+        assert_eq!(a.slots_served(), 0);
+        b.receive_gossip(&a.gossip_message());
+        assert_eq!(health(&b, "a"), NodeHealth::Online);
+        assert_eq!(b.owner(0).unwrap().id, "d");
+    }
+}
@@ -0,0 +1,409 @@
+//! Slot Migration
+//!
+//! Moves the keys of hash slots to another node while both keep serving.
+//! The target is told to import the slots and they are marked migrating
+//! here, so keys not yet moved are still served locally and requests for
+//! the others are redirected with ASK. Keys are copied in batches with
+//! their remaining TTL, and removed here once the target has stored them
+//! unless they changed in the meantime. Once a slot is empty it is
+//! assigned to the target on both nodes and announced to the others.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use rustls::ClientConfig;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio_rustls::TlsConnector;
+use tracing::{info, warn};
+
+use super::client::post_json;
+use super::{Cluster, ClusterNode, SlotRange, SlotState};
+use crate::cache::{current_timestamp_ms, Databases};
+
+/// Keys sent to the target per request unless the migration sets its own
+pub const DEFAULT_MIGRATION_BATCH_SIZE: usize = 100;
+
+/// Finished migrations kept for `/admin/cluster/migrations`
+const MIGRATION_HISTORY: usize = 16;
+
+/// Route changing slot assignments on a node
+const SETSLOT_PATH: &str = "/admin/cluster/setslot";
+
+/// Route storing migrated keys on a node
+const RESTORE_PATH: &str = "/admin/cluster/restore";
+
+// == Migration Status ==
+/// Progress of a migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationState {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// A migration of slots to another node, as reported by
+/// `/admin/cluster/migrations`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationStatus {
+    /// Sequence number of the migration on this node
+    pub id: u64,
+    /// Id of the node receiving the slots
+    pub target: String,
+    /// Slot ranges being migrated
+    pub slots: Vec<String>,
+    pub state: MigrationState,
+    /// Number of slots to migrate
+    pub slots_total: usize,
+    /// Slots already assigned to the target
+    pub slots_done: usize,
+    /// Slot whose keys are being moved
+    pub current_slot: Option<u16>,
+    /// Keys removed here after the target stored them
+    pub keys_migrated: u64,
+    /// Start timestamp (Unix milliseconds)
+    pub started_at: u64,
+    /// End timestamp (Unix milliseconds), None while running
+    pub finished_at: Option<u64>,
+    /// Why the migration failed
+    pub error: Option<String>,
+}
+
+// == Migrations ==
+/// The running migration and the most recent finished ones. Only one
+/// migration runs at a time.
+#[derive(Debug, Default)]
+pub struct Migrations {
+    jobs: Mutex<Jobs>,
+}
+
+#[derive(Debug, Default)]
+struct Jobs {
+    next_id: u64,
+    /// Oldest first
+    history: VecDeque<MigrationStatus>,
+}
+
+impl Migrations {
+    /// Every known migration, newest first.
+    pub fn list(&self) -> Vec<MigrationStatus> {
+        self.lock().history.iter().rev().cloned().collect()
+    }
+
+    /// Returns true if a migration is in progress.
+    pub fn is_running(&self) -> bool {
+        self.lock()
+            .history
+            .iter()
+            .any(|job| job.state == MigrationState::Running)
+    }
+
+    /// Records a new running migration, unless one is already running.
+    fn begin(&self, target: &str, slots: &[SlotRange]) -> Result<MigrationStatus, String> {
+        let mut jobs = self.lock();
+        if let Some(running) = jobs
+            .history
+            .iter()
+            .find(|job| job.state == MigrationState::Running)
+        {
+            return Err(format!("migration {} is still running", running.id));
+        }
+
+        jobs.next_id += 1;
+        let status = MigrationStatus {
+            id: jobs.next_id,
+            target: target.to_string(),
+            slots: slots.iter().map(ToString::to_string).collect(),
+            state: MigrationState::Running,
+            slots_total: slots.iter().map(SlotRange::count).sum(),
+            slots_done: 0,
+            current_slot: None,
+            keys_migrated: 0,
+            started_at: current_timestamp_ms(),
+            finished_at: None,
+            error: None,
+        };
+        jobs.history.push_back(status.clone());
+        if jobs.history.len() > MIGRATION_HISTORY {
+            jobs.history.pop_front();
+        }
+        Ok(status)
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut MigrationStatus)) {
+        if let Some(job) = self.lock().history.iter_mut().find(|job| job.id == id) {
+            change(job);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Jobs> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// == Start Migration ==
+/// Starts moving `slots` to the node `target` in the background and
+/// returns the new migration's status.
+///
+/// Every slot must be served by this node, and no other migration may be
+/// running. `auth_token` is presented to the other nodes, which are
+/// reached over TLS when `tls` is given.
+pub fn start_migration(
+    cluster: Arc<Cluster>,
+    databases: Arc<Databases>,
+    target: &str,
+    slots: Vec<SlotRange>,
+    batch_size: usize,
+    auth_token: Option<String>,
+    tls: Option<Arc<ClientConfig>>,
+) -> Result<MigrationStatus, String> {
+    let target = match cluster.node(target) {
+        Some(node) if node.id == cluster.myself_id() => {
+            return Err("cannot migrate slots to this node".to_string())
+        }
+        Some(node) => node,
+        None => return Err(format!("unknown node '{}'", target)),
+    };
+    if slots.is_empty() {
+        return Err("no slots to migrate".to_string());
+    }
+    if batch_size == 0 {
+        return Err("batch_size must be at least 1".to_string());
+    }
+    for slot in slots.iter().flat_map(|range| range.start..=range.end) {
+        if cluster
+            .owner(slot)
+            .is_none_or(|owner| owner.id != cluster.myself_id())
+        {
+            return Err(format!("slot {} is not served by this node", slot));
+        }
+    }
+
+    let status = cluster.migrations().begin(&target.id, &slots)?;
+    info!(
+        "Migration {} started: {} slots to node '{}'",
+        status.id, status.slots_total, target.id
+    );
+
+    let migrator = Migrator {
+        id: status.id,
+        cluster,
+        databases,
+        target,
+        batch_size,
+        auth_token,
+        tls: tls.map(TlsConnector::from),
+    };
+    tokio::spawn(async move {
+        let result = migrator.run(&slots).await;
+        let migrations = migrator.cluster.migrations();
+        migrations.update(migrator.id, |status| {
+            status.current_slot = None;
+            status.finished_at = Some(current_timestamp_ms());
+            match result {
+                Ok(()) => {
+                    info!("Migration {} completed", status.id);
+                    status.state = MigrationState::Completed;
+                }
+                Err(e) => {
+                    warn!("Migration {} failed: {}", status.id, e);
+                    status.state = MigrationState::Failed;
+                    status.error = Some(e);
+                }
+            }
+        });
+    });
+
+    Ok(status)
+}
+
+// == Migrator ==
+/// A key copied to the target, with what it held when it was read
+struct Copied {
+    db: String,
+    key: String,
+    value: String,
+    expires_at: Option<u64>,
+    ttl_ms: Option<u64>,
+    grace_ms: Option<u64>,
+    compute_ms: Option<u64>,
+}
+
+struct Migrator {
+    id: u64,
+    cluster: Arc<Cluster>,
+    databases: Arc<Databases>,
+    target: ClusterNode,
+    batch_size: usize,
+    auth_token: Option<String>,
+    tls: Option<TlsConnector>,
+}
+
+impl Migrator {
+    /// Migrates every slot in `slots`. A failure leaves the current slot
+    /// migrating; keys already moved stay on the target.
+    async fn run(&self, slots: &[SlotRange]) -> Result<(), String> {
+        let ranges: Vec<String> = slots.iter().map(ToString::to_string).collect();
+        self.post(
+            &self.target,
+            SETSLOT_PATH,
+            json!({"slots": ranges, "state": "importing", "node": self.cluster.myself_id()}),
+        )
+        .await?;
+        self.cluster
+            .set_slots(slots, &SlotState::Migrating(self.target.id.clone()))?;
+
+        for slot in slots.iter().flat_map(|range| range.start..=range.end) {
+            self.update(|status| status.current_slot = Some(slot));
+            self.move_keys(slot, false).await?;
+
+            // The target first, so no request bounces between the two
+            let assign =
+                json!({"slots": [slot.to_string()], "state": "node", "node": self.target.id});
+            self.post(&self.target, SETSLOT_PATH, assign.clone())
+                .await?;
+            self.cluster.set_slots(
+                &[SlotRange {
+                    start: slot,
+                    end: slot,
+                }],
+                &SlotState::Node(self.target.id.clone()),
+            )?;
+            // Writes that raced with the hand-over, unless the target has
+            // taken newer ones since
+            self.move_keys(slot, true).await?;
+
+            for node in self.cluster.nodes() {
+                if node.id == self.target.id || node.id == self.cluster.myself_id() {
+                    continue;
+                }
+                if let Err(e) = self.post(&node, SETSLOT_PATH, assign.clone()).await {
+                    warn!("Could not announce slot {} to '{}': {}", slot, node.id, e);
+                }
+            }
+            self.update(|status| status.slots_done += 1);
+        }
+        Ok(())
+    }
+
+    /// Copies the keys of `slot` to the target until none are left here.
+    /// With `keep_existing`, keys the target already holds keep its value.
+    async fn move_keys(&self, slot: u16, keep_existing: bool) -> Result<(), String> {
+        loop {
+            let batch = self.next_batch(slot).await;
+            if batch.is_empty() {
+                return Ok(());
+            }
+
+            let entries: Vec<Value> = batch
+                .iter()
+                .map(|copy| {
+                    json!({
+                        "db": copy.db,
+                        "key": copy.key,
+                        "value": copy.value,
+                        "ttl_ms": copy.ttl_ms,
+                        "grace_ms": copy.grace_ms,
+                        "compute_ms": copy.compute_ms,
+                    })
+                })
+                .collect();
+            self.post(
+                &self.target,
+                RESTORE_PATH,
+                json!({ "entries": entries, "keep_existing": keep_existing }),
+            )
+            .await?;
+
+            // Keys changed since they were read stay for the next batch;
+            // keys deleted meanwhile are deleted on the target too, if it
+            // still holds the copy
+            let mut moved = 0;
+            let mut deleted = Vec::new();
+            for copy in &batch {
+                let Some(store) = self.databases.get(&copy.db) else {
+                    continue;
+                };
+                let mut store = store.write().await;
+                let unchanged = |entry: &crate::cache::CacheEntry| {
+                    entry.value == copy.value && entry.expires_at == copy.expires_at
+                };
+                if store.take_if(&copy.key, unchanged).is_some() {
+                    moved += 1;
+                } else if !store.contains_key(&copy.key) {
+                    deleted.push(json!({"db": copy.db, "key": copy.key, "value": copy.value}));
+                }
+            }
+            if !deleted.is_empty() {
+                self.post(&self.target, RESTORE_PATH, json!({ "deleted": deleted }))
+                    .await?;
+            }
+            self.update(|status| status.keys_migrated += moved);
+        }
+    }
+
+    /// Reads up to `batch_size` keys of `slot` across all databases.
+    async fn next_batch(&self, slot: u16) -> Vec<Copied> {
+        let mut batch = Vec::new();
+        for (db, store) in self.databases.iter() {
+            let remaining = self.batch_size - batch.len();
+            if remaining == 0 {
+                break;
+            }
+            let store = store.read().await;
+            for key in store.keys_in_slot(slot, remaining) {
+                if let Some(entry) = store.entry(&key) {
+                    batch.push(Copied {
+                        db: db.to_string(),
+                        value: entry.value.clone(),
+                        expires_at: entry.expires_at,
+                        ttl_ms: entry.ttl_remaining_ms(),
+                        grace_ms: entry.grace_ms,
+                        compute_ms: entry.compute_ms,
+                        key,
+                    });
+                }
+            }
+        }
+        batch
+    }
+
+    async fn post(&self, node: &ClusterNode, path: &str, body: Value) -> Result<Value, String> {
+        post_json(
+            &node.address,
+            path,
+            &body,
+            self.auth_token.as_deref(),
+            self.tls.as_ref(),
+        )
+        .await
+    }
+
+    fn update(&self, change: impl FnOnce(&mut MigrationStatus)) {
+        self.cluster.migrations().update(self.id, change);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_migration_at_a_time() {
+        let migrations = Migrations::default();
+        let slots = SlotRange::parse_list("0-9,20").unwrap();
+
+        let first = migrations.begin("b", &slots).unwrap();
+        assert_eq!(first.slots_total, 11);
+        assert_eq!(first.slots, vec!["0-9", "20"]);
+        assert!(migrations.is_running());
+        assert!(migrations.begin("b", &slots).is_err());
+
+        migrations.update(first.id, |status| status.state = MigrationState::Completed);
+        let second = migrations.begin("c", &slots).unwrap();
+        assert_eq!(second.id, first.id + 1);
+        let ids: Vec<u64> = migrations.list().iter().map(|job| job.id).collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+    }
+}
@@ -0,0 +1,846 @@
+//! Cluster Module
+//!
+//! Shards the keyspace across several servers by hash slot. Nodes start
+//! from a node table and learn about each other by gossip over the cluster
+//! bus; requests for keys in slots served by another node are redirected
+//! there. Slots can be migrated between nodes while both keep serving, and
+//! a follower takes over the slots of its leader when a majority of the
+//! leaders agree the leader has failed.
+
+mod bus;
+mod client;
+mod gossip;
+mod migration;
+mod slots;
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::cache::current_timestamp_ms;
+
+pub use bus::bus_router;
+pub use gossip::{spawn_gossip_task, GossipMessage, NodeView, VoteRequest, VoteResponse};
+pub use migration::{
+    start_migration, MigrationState, MigrationStatus, Migrations, DEFAULT_MIGRATION_BATCH_SIZE,
+};
+pub use slots::{crc16, hash_tag, key_slot, SlotRange, SLOT_COUNT};
+
+/// Offset of a node's cluster bus port from its API port, as in Redis
+pub const BUS_PORT_OFFSET: u16 = 10_000;
+
+/// Default time without an answer before a node is suspected to have failed
+pub const DEFAULT_NODE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Membership events kept for `/cluster/nodes`
+const EVENT_HISTORY: usize = 64;
+
+// == Cluster Node ==
+/// A node of the cluster and the slots it serves.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterNode {
+    /// Unique name of the node
+    pub id: String,
+    /// `host:port` clients are redirected to
+    pub address: String,
+    /// Slots served by the node
+    #[serde(default)]
+    pub slots: Vec<SlotRange>,
+}
+
+impl ClusterNode {
+    /// Parses a `;`-separated list of nodes.
+    ///
+    /// Each node is `id|host:port|slots`, with comma-separated slot ranges,
+    /// e.g. `a|10.0.0.1:3000|0-8191;b|10.0.0.2:3000|8192-16383`.
+    pub fn parse_list(value: &str) -> Result<Vec<ClusterNode>, String> {
+        value
+            .split(';')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for ClusterNode {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = spec.split('|').map(str::trim).collect();
+        let [id, address, slots] = parts[..] else {
+            return Err(format!(
+                "invalid cluster node '{}', expected id|host:port|slots",
+                spec
+            ));
+        };
+
+        Ok(Self {
+            id: id.to_string(),
+            address: address.to_string(),
+            slots: SlotRange::parse_list(slots)?,
+        })
+    }
+}
+
+/// Returns the default cluster bus address of a node listening on
+/// `address`: the same host, with the port offset by `BUS_PORT_OFFSET`.
+pub fn default_bus_address(address: &str) -> String {
+    match address.rsplit_once(':') {
+        Some((host, port)) => match port
+            .parse::<u16>()
+            .ok()
+            .and_then(|port| port.checked_add(BUS_PORT_OFFSET))
+        {
+            Some(port) => format!("{}:{}", host, port),
+            None => address.to_string(),
+        },
+        None => address.to_string(),
+    }
+}
+
+// == Node Status ==
+/// Whether a node is believed to be up, following Redis Cluster: a node
+/// that stops answering is suspected (`pfail`) by each node on its own, and
+/// failed (`fail`) once a majority of the leaders suspect it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeHealth {
+    Online,
+    Pfail,
+    Fail,
+}
+
+/// A node as seen by this one, for `/cluster/nodes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeStatus {
+    /// The node and the slots it serves
+    pub node: ClusterNode,
+    /// `host:port` of the node's cluster bus
+    pub bus_address: String,
+    /// Epoch at which the node last gained slots
+    pub config_epoch: u64,
+    /// Id of the leader the node replicates, for followers
+    pub leader: Option<String>,
+    pub health: NodeHealth,
+    /// Milliseconds since the node last answered; None for this node
+    pub last_seen_ms: Option<u64>,
+    /// Leaders currently reporting the node as failing
+    pub fail_reports: usize,
+}
+
+/// Kinds of membership changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterEventKind {
+    /// A node was discovered
+    Joined,
+    /// A node stopped answering this one
+    Pfail,
+    /// A majority of the leaders agreed a node has failed
+    Fail,
+    /// A suspected or failed node is back
+    Online,
+    /// A follower took over the slots of its failed leader
+    Promoted,
+    /// A node claimed slots at a newer epoch
+    SlotsMoved,
+}
+
+/// A membership change seen by this node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClusterEvent {
+    /// Timestamp (Unix milliseconds)
+    pub at: u64,
+    /// Id of the node the event is about
+    pub node: String,
+    pub event: ClusterEventKind,
+    pub detail: String,
+}
+
+// == Slot Route ==
+/// Where a key's requests are served.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlotRoute {
+    /// This node serves the slot
+    Local,
+    /// Another node serves the slot
+    Moved { slot: u16, node: ClusterNode },
+    /// This node serves the slot but is migrating it to `node`: keys still
+    /// here are served locally, the others are asked for there
+    Migrating { slot: u16, node: ClusterNode },
+    /// No node serves the slot
+    Unassigned { slot: u16 },
+}
+
+// == Slot State ==
+/// A change to one slot's assignment, as made by CLUSTER SETSLOT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlotState {
+    /// This node's slot is being moved to the named node
+    Migrating(String),
+    /// The named node's slot is being moved to this node
+    Importing(String),
+    /// The named node serves the slot; any migration of it is over
+    Node(String),
+    /// Cancels a migration, leaving the owner unchanged
+    Stable,
+}
+
+// == Cluster ==
+/// The known nodes and their health, the owner of every slot and the slots
+/// being migrated.
+#[derive(Debug)]
+pub struct Cluster {
+    /// Id of this node
+    myself: String,
+    topology: RwLock<Topology>,
+    /// Slot migrations started on this node
+    migrations: Migrations,
+    /// Time without an answer before a node is suspected
+    node_timeout: Duration,
+    /// Bus addresses to gossip with until their nodes are known
+    meet: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Topology {
+    /// Index of this node in `nodes`
+    myself: usize,
+    /// Every known node; nodes are only ever added, so indexes are stable
+    nodes: Vec<NodeEntry>,
+    /// Index into `nodes` of each slot's owner
+    owners: Vec<Option<usize>>,
+    /// Slots of this node being moved, and the node receiving each
+    migrating: BTreeMap<u16, usize>,
+    /// Slots being moved to this node, and the node sending each
+    importing: BTreeMap<u16, usize>,
+    /// Highest epoch seen in the cluster
+    current_epoch: u64,
+    /// Epoch of the last failover vote this node granted
+    last_vote_epoch: u64,
+    /// When this node last voted to replace each failed leader
+    votes: HashMap<usize, Instant>,
+    /// When this follower may next ask to replace its failed leader
+    next_election: Option<Instant>,
+    /// Nodes this node cannot exchange messages with, to test partitions
+    unreachable: HashSet<usize>,
+    /// Oldest first
+    events: VecDeque<ClusterEvent>,
+}
+
+#[derive(Debug, Clone)]
+struct NodeEntry {
+    id: String,
+    address: String,
+    bus_address: String,
+    config_epoch: u64,
+    /// Client address of the node's leader, for followers
+    leader: Option<String>,
+    health: NodeHealth,
+    /// Last message from the node, or when it was discovered
+    last_seen: Instant,
+    /// When the node was marked failed
+    failed_at: Option<Instant>,
+    /// Leaders reporting the node as suspected or failed, and when
+    fail_reports: HashMap<usize, Instant>,
+}
+
+impl NodeEntry {
+    fn new(id: String, address: String, bus_address: String) -> Self {
+        Self {
+            id,
+            address,
+            bus_address,
+            config_epoch: 0,
+            leader: None,
+            health: NodeHealth::Online,
+            last_seen: Instant::now(),
+            failed_at: None,
+            fail_reports: HashMap::new(),
+        }
+    }
+}
+
+impl Cluster {
+    // == Constructor ==
+    /// Builds the slot table; `myself` must name one of `nodes`, and no
+    /// slot may be assigned twice.
+    ///
+    /// Every node's bus is assumed at its default address until gossip
+    /// says otherwise.
+    pub fn new(myself: &str, nodes: Vec<ClusterNode>) -> Result<Self, String> {
+        let mut owners: Vec<Option<usize>> = vec![None; SLOT_COUNT];
+        for (index, node) in nodes.iter().enumerate() {
+            if node.id.is_empty() {
+                return Err("every node needs an id".to_string());
+            }
+            if nodes[..index].iter().any(|other| other.id == node.id) {
+                return Err(format!("node '{}' is listed more than once", node.id));
+            }
+            for range in &node.slots {
+                for slot in range.start..=range.end {
+                    if let Some(owner) = owners[slot as usize] {
+                        return Err(format!(
+                            "slot {} is assigned to both '{}' and '{}'",
+                            slot, nodes[owner].id, node.id
+                        ));
+                    }
+                    owners[slot as usize] = Some(index);
+                }
+            }
+        }
+
+        let index = nodes
+            .iter()
+            .position(|node| node.id == myself)
+            .ok_or_else(|| format!("node '{}' is not in the node table", myself))?;
+        // Slot lists are derived from `owners` from here on
+        let nodes = nodes
+            .into_iter()
+            .map(|node| {
+                let bus_address = default_bus_address(&node.address);
+                NodeEntry::new(node.id, node.address, bus_address)
+            })
+            .collect();
+        Ok(Self {
+            myself: myself.to_string(),
+            topology: RwLock::new(Topology {
+                myself: index,
+                nodes,
+                owners,
+                migrating: BTreeMap::new(),
+                importing: BTreeMap::new(),
+                current_epoch: 0,
+                last_vote_epoch: 0,
+                votes: HashMap::new(),
+                next_election: None,
+                unreachable: HashSet::new(),
+                events: VecDeque::new(),
+            }),
+            migrations: Migrations::default(),
+            node_timeout: DEFAULT_NODE_TIMEOUT,
+            meet: Vec::new(),
+        })
+    }
+
+    /// Sets the port of this node's cluster bus, on the host of its address.
+    pub fn with_bus_port(mut self, port: u16) -> Self {
+        let topology = self.topology.get_mut().unwrap_or_else(|e| e.into_inner());
+        let myself = &mut topology.nodes[topology.myself];
+        let host = myself
+            .address
+            .rsplit_once(':')
+            .map_or(myself.address.as_str(), |(host, _)| host);
+        myself.bus_address = format!("{}:{}", host, port);
+        self
+    }
+
+    /// Sets the time without an answer before a node is suspected.
+    pub fn with_node_timeout(mut self, timeout: Duration) -> Self {
+        self.node_timeout = timeout;
+        self
+    }
+
+    /// Sets the bus addresses of nodes to join that are not in the table.
+    pub fn with_meet(mut self, seeds: Vec<String>) -> Self {
+        self.meet = seeds;
+        self
+    }
+
+    /// Makes this node a follower of the node listening on `address`.
+    pub fn with_leader(mut self, address: Option<String>) -> Self {
+        let topology = self.topology.get_mut().unwrap_or_else(|e| e.into_inner());
+        topology.nodes[topology.myself].leader = address;
+        self
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Topology> {
+        self.topology.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Topology> {
+        self.topology.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Id of this node.
+    pub fn myself_id(&self) -> &str {
+        &self.myself
+    }
+
+    /// This node and the slots it serves.
+    pub fn myself(&self) -> ClusterNode {
+        let topology = self.read();
+        topology.node(topology.myself)
+    }
+
+    /// Every known node with the slots it serves, in the order they were
+    /// configured or discovered.
+    pub fn nodes(&self) -> Vec<ClusterNode> {
+        let topology = self.read();
+        (0..topology.nodes.len())
+            .map(|index| topology.node(index))
+            .collect()
+    }
+
+    /// Returns the node named `id`, if it is known.
+    pub fn node(&self, id: &str) -> Option<ClusterNode> {
+        let topology = self.read();
+        topology.index_of(id).map(|index| topology.node(index))
+    }
+
+    /// Slot migrations started on this node.
+    pub fn migrations(&self) -> &Migrations {
+        &self.migrations
+    }
+
+    /// Time without an answer before a node is suspected.
+    pub fn node_timeout(&self) -> Duration {
+        self.node_timeout
+    }
+
+    // == Membership ==
+    /// Every known node with its health, in the same order as `nodes`.
+    pub fn node_statuses(&self) -> Vec<NodeStatus> {
+        let topology = self.read();
+        let now = Instant::now();
+        topology
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| NodeStatus {
+                node: topology.node(index),
+                bus_address: entry.bus_address.clone(),
+                config_epoch: entry.config_epoch,
+                leader: topology
+                    .leader_of(index)
+                    .map(|leader| topology.nodes[leader].id.clone()),
+                health: entry.health,
+                last_seen_ms: (index != topology.myself)
+                    .then(|| now.duration_since(entry.last_seen).as_millis() as u64),
+                fail_reports: entry.fail_reports.len(),
+            })
+            .collect()
+    }
+
+    /// Highest epoch seen in the cluster.
+    pub fn current_epoch(&self) -> u64 {
+        self.read().current_epoch
+    }
+
+    /// Recent membership changes, newest first.
+    pub fn events(&self) -> Vec<ClusterEvent> {
+        self.read().events.iter().rev().cloned().collect()
+    }
+
+    /// Number of nodes currently considered failed.
+    pub fn failed_nodes(&self) -> usize {
+        self.read()
+            .nodes
+            .iter()
+            .filter(|node| node.health == NodeHealth::Fail)
+            .count()
+    }
+
+    /// Cuts (or restores) the link between this node and the node `id`:
+    /// no gossip or votes are exchanged with it while it is unreachable.
+    /// Used to simulate network partitions.
+    pub fn set_unreachable(&self, id: &str, unreachable: bool) -> Result<(), String> {
+        let mut topology = self.write();
+        let index = topology
+            .index_of(id)
+            .ok_or_else(|| format!("unknown node '{}'", id))?;
+        if unreachable {
+            topology.unreachable.insert(index);
+        } else {
+            topology.unreachable.remove(&index);
+        }
+        Ok(())
+    }
+
+    /// Returns true if the link to the node `id` is cut.
+    pub fn is_unreachable(&self, id: &str) -> bool {
+        let topology = self.read();
+        topology
+            .index_of(id)
+            .is_some_and(|index| topology.unreachable.contains(&index))
+    }
+
+    // == Routing ==
+    /// Returns where requests for `key` are served. `asking` is set by
+    /// clients following an ASK redirect, so a slot being imported here is
+    /// served before it is assigned to this node.
+    pub fn route(&self, key: &str, asking: bool) -> SlotRoute {
+        let slot = key_slot(key);
+        let topology = self.read();
+        match topology.owners[slot as usize] {
+            Some(owner) if owner == topology.myself => match topology.migrating.get(&slot) {
+                Some(&target) => SlotRoute::Migrating {
+                    slot,
+                    node: topology.bare_node(target),
+                },
+                None => SlotRoute::Local,
+            },
+            _ if asking && topology.importing.contains_key(&slot) => SlotRoute::Local,
+            Some(owner) => SlotRoute::Moved {
+                slot,
+                node: topology.bare_node(owner),
+            },
+            None => SlotRoute::Unassigned { slot },
+        }
+    }
+
+    /// Returns the node serving `slot`, if any.
+    pub fn owner(&self, slot: u16) -> Option<ClusterNode> {
+        let topology = self.read();
+        topology.owners[slot as usize].map(|owner| topology.bare_node(owner))
+    }
+
+    /// Returns true if this node serves `slot` or is importing it, so it
+    /// may receive the slot's keys.
+    pub fn accepts(&self, slot: u16) -> bool {
+        let topology = self.read();
+        topology.owners[slot as usize] == Some(topology.myself)
+            || topology.importing.contains_key(&slot)
+    }
+
+    /// Returns contiguous runs of slots with the same owner, in slot order.
+    pub fn slot_ranges(&self) -> Vec<(SlotRange, ClusterNode)> {
+        let topology = self.read();
+        topology
+            .ranges()
+            .into_iter()
+            .map(|(range, owner)| (range, topology.bare_node(owner)))
+            .collect()
+    }
+
+    /// Number of slots served by any node.
+    pub fn slots_assigned(&self) -> usize {
+        self.read()
+            .owners
+            .iter()
+            .filter(|owner| owner.is_some())
+            .count()
+    }
+
+    /// Number of slots served by this node.
+    pub fn slots_served(&self) -> usize {
+        let topology = self.read();
+        topology
+            .owners
+            .iter()
+            .filter(|owner| **owner == Some(topology.myself))
+            .count()
+    }
+
+    /// Returns true if every slot is served, so every key has a node.
+    pub fn is_complete(&self) -> bool {
+        self.slots_assigned() == SLOT_COUNT
+    }
+
+    // == Slot Migration ==
+    /// Slots this node is migrating, with the id of the receiving node.
+    pub fn migrating(&self) -> BTreeMap<u16, String> {
+        let topology = self.read();
+        topology
+            .migrating
+            .iter()
+            .map(|(&slot, &node)| (slot, topology.nodes[node].id.clone()))
+            .collect()
+    }
+
+    /// Slots this node is importing, with the id of the sending node.
+    pub fn importing(&self) -> BTreeMap<u16, String> {
+        let topology = self.read();
+        topology
+            .importing
+            .iter()
+            .map(|(&slot, &node)| (slot, topology.nodes[node].id.clone()))
+            .collect()
+    }
+
+    /// Applies `state` to every slot in `slots`, or to none of them if any
+    /// change is invalid.
+    ///
+    /// A slot can only be migrated by its owner and imported by another
+    /// node. Assigning a slot ends its migration on this node; when this
+    /// node gains slots it moves to a new epoch, so its claim wins over the
+    /// previous owner's in gossip.
+    pub fn set_slots(&self, slots: &[SlotRange], state: &SlotState) -> Result<(), String> {
+        let mut topology = self.write();
+        let myself = topology.myself;
+        let other_node = |topology: &Topology, id: &str| match topology.index_of(id) {
+            Some(index) if index == myself => Err(format!("node '{}' is this node", id)),
+            Some(index) => Ok(index),
+            None => Err(format!("unknown node '{}'", id)),
+        };
+        let slots = slots.iter().flat_map(|range| range.start..=range.end);
+
+        match state {
+            SlotState::Migrating(id) => {
+                let target = other_node(&topology, id)?;
+                if let Some(slot) = slots
+                    .clone()
+                    .find(|&slot| topology.owners[slot as usize] != Some(myself))
+                {
+                    return Err(format!("slot {} is not served by this node", slot));
+                }
+                topology.migrating.extend(slots.map(|slot| (slot, target)));
+            }
+            SlotState::Importing(id) => {
+                let source = other_node(&topology, id)?;
+                if let Some(slot) = slots
+                    .clone()
+                    .find(|&slot| topology.owners[slot as usize] == Some(myself))
+                {
+                    return Err(format!("slot {} is already served by this node", slot));
+                }
+                topology.importing.extend(slots.map(|slot| (slot, source)));
+            }
+            SlotState::Node(id) => {
+                let owner = topology
+                    .index_of(id)
+                    .ok_or_else(|| format!("unknown node '{}'", id))?;
+                let mut gained = false;
+                for slot in slots {
+                    gained |= owner == myself && topology.owners[slot as usize] != Some(myself);
+                    topology.owners[slot as usize] = Some(owner);
+                    topology.migrating.remove(&slot);
+                    topology.importing.remove(&slot);
+                }
+                if gained {
+                    topology.current_epoch += 1;
+                    topology.nodes[myself].config_epoch = topology.current_epoch;
+                }
+            }
+            SlotState::Stable => {
+                for slot in slots {
+                    topology.migrating.remove(&slot);
+                    topology.importing.remove(&slot);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Topology {
+    fn index_of(&self, id: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.id == id)
+    }
+
+    /// The node at `index` with its current slot ranges.
+    fn node(&self, index: usize) -> ClusterNode {
+        ClusterNode {
+            slots: self
+                .ranges()
+                .into_iter()
+                .filter(|(_, owner)| *owner == index)
+                .map(|(range, _)| range)
+                .collect(),
+            ..self.bare_node(index)
+        }
+    }
+
+    /// The node at `index`, without its slots.
+    fn bare_node(&self, index: usize) -> ClusterNode {
+        let entry = &self.nodes[index];
+        ClusterNode {
+            id: entry.id.clone(),
+            address: entry.address.clone(),
+            slots: Vec::new(),
+        }
+    }
+
+    /// Index of the known node the node at `index` replicates.
+    fn leader_of(&self, index: usize) -> Option<usize> {
+        let address = self.nodes[index].leader.as_deref()?;
+        self.nodes.iter().position(|node| node.address == address)
+    }
+
+    /// Number of slots served by each node, by index.
+    fn slot_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.nodes.len()];
+        for owner in self.owners.iter().flatten() {
+            counts[*owner] += 1;
+        }
+        counts
+    }
+
+    /// Records a membership change and logs it.
+    fn record(&mut self, index: usize, event: ClusterEventKind, detail: String) {
+        let node = self.nodes[index].id.clone();
+        match event {
+            ClusterEventKind::Pfail | ClusterEventKind::Fail => {
+                warn!("Cluster node '{}' {}", node, detail)
+            }
+            _ => info!("Cluster node '{}' {}", node, detail),
+        }
+        self.events.push_back(ClusterEvent {
+            at: current_timestamp_ms(),
+            node,
+            event,
+            detail,
+        });
+        if self.events.len() > EVENT_HISTORY {
+            self.events.pop_front();
+        }
+    }
+
+    /// Contiguous runs of slots with the same owner, in slot order.
+    fn ranges(&self) -> Vec<(SlotRange, usize)> {
+        let mut ranges: Vec<(SlotRange, usize)> = Vec::new();
+        for (slot, owner) in self.owners.iter().enumerate() {
+            let Some(owner) = *owner else {
+                continue;
+            };
+            let slot = slot as u16;
+            match ranges.last_mut() {
+                Some((range, last)) if *last == owner && range.end + 1 == slot => {
+                    range.end = slot;
+                }
+                _ => ranges.push((
+                    SlotRange {
+                        start: slot,
+                        end: slot,
+                    },
+                    owner,
+                )),
+            }
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cluster(myself: &str) -> Cluster {
+        let nodes = ClusterNode::parse_list(
+            "a|127.0.0.1:7000|0-5460; b|127.0.0.1:7001|5461-10922; c|127.0.0.1:7002|10923-16383",
+        )
+        .unwrap();
+        Cluster::new(myself, nodes).unwrap()
+    }
+
+    #[test]
+    fn test_parse_cluster_nodes() {
+        let nodes = ClusterNode::parse_list("a|10.0.0.1:3000|0-100,200;b|10.0.0.2:3000|").unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].address, "10.0.0.1:3000");
+        assert_eq!(nodes[0].slots.len(), 2);
+        assert!(nodes[1].slots.is_empty());
+
+        assert!(ClusterNode::parse_list("a|10.0.0.1:3000").is_err());
+        assert!(ClusterNode::parse_list("a|10.0.0.1:3000|0-99999").is_err());
+    }
+
+    #[test]
+    fn test_route_keys() {
+        let cluster = cluster("c");
+        // "foo" hashes to 12182, "bar" to 5061
+        assert_eq!(cluster.route("foo", false), SlotRoute::Local);
+        match cluster.route("bar", false) {
+            SlotRoute::Moved { slot, node } => {
+                assert_eq!(slot, 5061);
+                assert_eq!(node.id, "a");
+            }
+            other => panic!("unexpected route {:?}", other),
+        }
+        assert_eq!(cluster.route("{foo}.bar", false), SlotRoute::Local);
+        assert_eq!(cluster.slots_served(), 5461);
+        assert!(cluster.is_complete());
+    }
+
+    #[test]
+    fn test_unassigned_slots() {
+        let nodes = ClusterNode::parse_list("a|127.0.0.1:7000|0-100,102").unwrap();
+        let cluster = Cluster::new("a", nodes).unwrap();
+
+        assert!(!cluster.is_complete());
+        assert_eq!(cluster.slots_assigned(), 102);
+        assert!(cluster.owner(101).is_none());
+        assert_eq!(
+            cluster.route("foo", false),
+            SlotRoute::Unassigned { slot: 12182 }
+        );
+
+        let ranges: Vec<String> = cluster
+            .slot_ranges()
+            .iter()
+            .map(|(range, _)| range.to_string())
+            .collect();
+        assert_eq!(ranges, vec!["0-100", "102"]);
+    }
+
+    #[test]
+    fn test_invalid_node_tables() {
+        let overlapping = ClusterNode::parse_list("a|h:1|0-10;b|h:2|10-20").unwrap();
+        assert!(Cluster::new("a", overlapping).is_err());
+
+        let duplicate = ClusterNode::parse_list("a|h:1|0;a|h:2|1").unwrap();
+        assert!(Cluster::new("a", duplicate).is_err());
+
+        let nodes = ClusterNode::parse_list("a|h:1|0").unwrap();
+        assert!(Cluster::new("z", nodes).is_err());
+    }
+
+    #[test]
+    fn test_slot_migration_states() {
+        let source = cluster("c");
+        let target = cluster("a");
+        let slot = [SlotRange {
+            start: 12182,
+            end: 12182,
+        }];
+
+        // Only the owner can migrate a slot, and only another node import it
+        assert!(source
+            .set_slots(&slot, &SlotState::Importing("a".to_string()))
+            .is_err());
+        assert!(target
+            .set_slots(&slot, &SlotState::Migrating("c".to_string()))
+            .is_err());
+        assert!(source
+            .set_slots(&slot, &SlotState::Migrating("c".to_string()))
+            .is_err());
+
+        source
+            .set_slots(&slot, &SlotState::Migrating("a".to_string()))
+            .unwrap();
+        target
+            .set_slots(&slot, &SlotState::Importing("c".to_string()))
+            .unwrap();
+        assert!(matches!(
+            source.route("foo", false),
+            SlotRoute::Migrating { slot: 12182, ref node } if node.id == "a"
+        ));
+        assert_eq!(source.migrating().get(&12182).map(String::as_str), Some("a"));
+
+        // The target only serves the slot to clients sent there by ASK
+        assert!(matches!(target.route("foo", false), SlotRoute::Moved { .. }));
+        assert_eq!(target.route("foo", true), SlotRoute::Local);
+        assert!(target.accepts(12182));
+
+        for cluster in [&source, &target] {
+            cluster
+                .set_slots(&slot, &SlotState::Node("a".to_string()))
+                .unwrap();
+            assert!(cluster.migrating().is_empty());
+            assert!(cluster.importing().is_empty());
+        }
+        assert_eq!(target.route("foo", false), SlotRoute::Local);
+        assert!(matches!(source.route("foo", false), SlotRoute::Moved { .. }));
+        assert_eq!(target.slots_served(), 5462);
+        assert_eq!(
+            target.myself().slots,
+            SlotRange::parse_list("0-5460,12182").unwrap()
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Hash Slots
+//!
+//! Keys map to one of 16384 slots by the CRC16 of the key, as in Redis
+//! Cluster. A `{tag}` in the key hashes only the tag, so related keys can
+//! be kept on one node.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Number of hash slots the keyspace is divided into
+pub const SLOT_COUNT: usize = 16_384;
+
+// == Key Slot ==
+/// Returns the hash slot of `key`.
+pub fn key_slot(key: &str) -> u16 {
+    crc16(hash_tag(key).as_bytes()) % SLOT_COUNT as u16
+}
+
+/// Returns the part of `key` that is hashed: the text between the first
+/// `{` and the next `}` if it is not empty, otherwise the whole key.
+pub fn hash_tag(key: &str) -> &str {
+    if let Some(open) = key.find('{') {
+        if let Some(len) = key[open + 1..].find('}') {
+            if len > 0 {
+                return &key[open + 1..open + 1 + len];
+            }
+        }
+    }
+    key
+}
+
+/// CRC16-CCITT (XMODEM), the checksum Redis Cluster uses for key slots.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+// == Slot Range ==
+/// An inclusive range of slots, written `start-end` or a single `slot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SlotRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl SlotRange {
+    /// Returns true if `slot` is in the range.
+    pub fn contains(&self, slot: u16) -> bool {
+        self.start <= slot && slot <= self.end
+    }
+
+    /// Number of slots in the range.
+    pub fn count(&self) -> usize {
+        (self.end - self.start) as usize + 1
+    }
+
+    /// Parses a comma-separated list of ranges, e.g. `0-5460,10923`.
+    pub fn parse_list(value: &str) -> Result<Vec<SlotRange>, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for SlotRange {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid slot range '{}', expected start-end within 0-{}",
+                spec,
+                SLOT_COUNT - 1
+            )
+        };
+        let (start, end) = spec.split_once('-').unwrap_or((spec, spec));
+        let start: u16 = start.trim().parse().map_err(|_| invalid())?;
+        let end: u16 = end.trim().parse().map_err(|_| invalid())?;
+        if start > end || end as usize >= SLOT_COUNT {
+            return Err(invalid());
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl TryFrom<String> for SlotRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<SlotRange> for String {
+    fn from(range: SlotRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for SlotRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_slot_matches_redis() {
+        assert_eq!(crc16(b"123456789"), 0x31C3);
+        assert_eq!(key_slot("foo"), 12182);
+        assert_eq!(key_slot("bar"), 5061);
+        assert_eq!(key_slot(""), 0);
+    }
+
+    #[test]
+    fn test_hash_tags() {
+        assert_eq!(hash_tag("{user1000}.following"), "user1000");
+        assert_eq!(
+            key_slot("{user1000}.following"),
+            key_slot("{user1000}.followers")
+        );
+        // Empty or unterminated tags hash the whole key
+        assert_eq!(hash_tag("foo{}{bar}"), "foo{}{bar}");
+        assert_eq!(hash_tag("foo{bar"), "foo{bar");
+        assert_eq!(hash_tag("foo{{bar}}zap"), "{bar");
+    }
+
+    #[test]
+    fn test_parse_slot_ranges() {
+        let ranges = SlotRange::parse_list("0-5460, 10923").unwrap();
+        assert_eq!(
+            ranges[0],
+            SlotRange {
+                start: 0,
+                end: 5460
+            }
+        );
+        assert_eq!(ranges[0].count(), 5461);
+        assert_eq!(ranges[1].to_string(), "10923");
+        assert!(ranges[0].contains(5460));
+        assert!(!ranges[0].contains(5461));
+
+        for invalid in ["10-5", "0-16384", "a-b", "-1"] {
+            assert!(invalid.parse::<SlotRange>().is_err(), "{}", invalid);
+        }
+    }
+}
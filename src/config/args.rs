@@ -0,0 +1,218 @@
+//! Command Line Arguments
+//!
+//! Every setting can be given as a flag; the matching environment variable
+//! is used when the flag is absent, which gives flags precedence over the
+//! environment. The environment is passed in rather than read by clap, so
+//! it can be replaced, e.g. in tests.
+
+use std::ffi::OsString;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
+
+use super::LogFormat;
+use crate::ratelimit::RateLimitRule;
+
+/// A lightweight in-memory cache server with TTL and LRU eviction.
+///
+/// Settings are taken from flags, then environment variables, then the
+/// config file, then built-in defaults.
+#[derive(Debug, Default, Parser)]
+#[command(name = "mini_redis", version)]
+pub struct Args {
+    /// TOML config file
+    #[arg(long, env = "CONFIG_FILE", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// HTTP server port
+    #[arg(long, env = "SERVER_PORT")]
+    pub port: Option<u16>,
+
+    /// Address to bind the HTTP server to; repeat or comma-separate to
+    /// listen on several
+    #[arg(
+        long = "bind",
+        env = "BIND_ADDRESS",
+        value_name = "ADDR",
+        value_delimiter = ','
+    )]
+    pub bind_addresses: Option<Vec<IpAddr>>,
+
+    /// Unix domain socket to also serve the HTTP API on
+    #[arg(long, env = "UNIX_SOCKET", value_name = "PATH")]
+    pub unix_socket: Option<PathBuf>,
+
+    /// Octal permissions of the Unix domain socket, e.g. 660
+    #[arg(long, env = "UNIX_SOCKET_MODE", value_name = "MODE", value_parser = parse_mode)]
+    pub unix_socket_mode: Option<u32>,
+
+    /// Maximum number of cache entries
+    #[arg(long, env = "MAX_ENTRIES")]
+    pub max_entries: Option<usize>,
+
+    /// Default TTL in seconds
+    #[arg(long, env = "DEFAULT_TTL", value_name = "SECONDS")]
+    pub default_ttl: Option<u64>,
+
+    /// Expired entry cleanup interval in seconds
+    #[arg(long, env = "CLEANUP_INTERVAL", value_name = "SECONDS")]
+    pub cleanup_interval: Option<u64>,
+
+    /// Comma-separated extra databases, `name=max_entries/default_ttl`
+    #[arg(long, env = "DATABASES", value_name = "DATABASES")]
+    pub databases: Option<String>,
+
+    /// Log output format
+    #[arg(long, env = "LOG_FORMAT", value_enum)]
+    pub log_format: Option<LogFormat>,
+
+    /// Slow log threshold in microseconds
+    #[arg(long, env = "SLOWLOG_THRESHOLD_US", value_name = "MICROS")]
+    pub slowlog_threshold_us: Option<u64>,
+
+    /// Maximum slow log entries
+    #[arg(long, env = "SLOWLOG_MAX_LEN")]
+    pub slowlog_max_len: Option<usize>,
+
+    /// Comma-separated bearer tokens
+    #[arg(long, env = "AUTH_TOKENS", value_name = "TOKENS")]
+    pub auth_tokens: Option<String>,
+
+    /// Serve /health without a token
+    #[arg(long, env = "AUTH_EXEMPT_HEALTH", value_name = "BOOL")]
+    pub auth_exempt_health: Option<bool>,
+
+    /// `;`-separated `name|token|permissions|patterns` users
+    #[arg(long, env = "ACL_USERS", value_name = "USERS")]
+    pub acl_users: Option<String>,
+
+    /// PEM certificate chain for TLS
+    #[arg(long = "tls-cert", env = "TLS_CERT_PATH", value_name = "PATH")]
+    pub tls_cert_path: Option<PathBuf>,
+
+    /// PEM private key for TLS
+    #[arg(long = "tls-key", env = "TLS_KEY_PATH", value_name = "PATH")]
+    pub tls_key_path: Option<PathBuf>,
+
+    /// PEM CA bundle for client certificates (mutual TLS)
+    #[arg(
+        long = "tls-client-ca",
+        env = "TLS_CLIENT_CA_PATH",
+        value_name = "PATH"
+    )]
+    pub tls_client_ca_path: Option<PathBuf>,
+
+    /// Per-client rate limit, `rate/burst` in requests per second
+    #[arg(long, env = "RATE_LIMIT", value_name = "RATE")]
+    pub rate_limit: Option<RateLimitRule>,
+
+    /// Comma-separated per-route limits, `route=rate/burst` or `route=off`
+    #[arg(long, env = "RATE_LIMIT_ROUTES", value_name = "ROUTES")]
+    pub rate_limit_routes: Option<String>,
+
+    /// Leader to replicate from, `host:port`; the server is a read-only follower
+    #[arg(long, env = "REPLICA_OF", value_name = "HOST:PORT")]
+    pub replica_of: Option<String>,
+
+    /// Bearer token presented to the leader
+    #[arg(long, env = "REPLICA_AUTH_TOKEN", value_name = "TOKEN")]
+    pub replica_auth_token: Option<String>,
+
+    /// PEM CA bundle for the leader's certificate; connects to it over TLS
+    #[arg(
+        long = "replica-tls-ca",
+        env = "REPLICA_TLS_CA_PATH",
+        value_name = "PATH"
+    )]
+    pub replica_tls_ca_path: Option<PathBuf>,
+
+    /// Write commands kept for followers resuming after a disconnect
+    #[arg(long, env = "REPL_BACKLOG_SIZE", value_name = "COMMANDS")]
+    pub repl_backlog_size: Option<usize>,
+
+    /// Id of this node in the cluster node table
+    #[arg(long, env = "CLUSTER_NODE_ID", value_name = "ID")]
+    pub cluster_node_id: Option<String>,
+
+    /// `;`-separated `id|host:port|slots` cluster nodes; enables cluster mode
+    #[arg(long, env = "CLUSTER_NODES", value_name = "NODES")]
+    pub cluster_nodes: Option<String>,
+
+    /// Bearer token presented to other nodes when migrating slots
+    #[arg(long, env = "CLUSTER_AUTH_TOKEN", value_name = "TOKEN")]
+    pub cluster_auth_token: Option<String>,
+
+    /// PEM CA bundle for other nodes' certificates; migrates slots over TLS
+    #[arg(
+        long = "cluster-tls-ca",
+        env = "CLUSTER_TLS_CA_PATH",
+        value_name = "PATH"
+    )]
+    pub cluster_tls_ca_path: Option<PathBuf>,
+
+    /// Port of the cluster bus carrying gossip between nodes (default: port + 10000)
+    #[arg(long, env = "CLUSTER_BUS_PORT", value_name = "PORT")]
+    pub cluster_bus_port: Option<u16>,
+
+    /// Milliseconds without an answer before a node is suspected to have failed
+    #[arg(long, env = "CLUSTER_NODE_TIMEOUT", value_name = "MS")]
+    pub cluster_node_timeout: Option<u64>,
+
+    /// Comma-separated cluster bus addresses of nodes to join, `host:port`
+    #[arg(long, env = "CLUSTER_MEET", value_name = "ADDRESSES")]
+    pub cluster_meet: Option<String>,
+
+    /// Origin URL fetched on a miss, with `{key}` standing for the key; enables origin mode
+    #[arg(long, env = "ORIGIN_URL", value_name = "URL")]
+    pub origin_url: Option<String>,
+
+    /// Milliseconds to wait for the origin before answering 502
+    #[arg(long, env = "ORIGIN_TIMEOUT", value_name = "MS")]
+    pub origin_timeout: Option<u64>,
+}
+
+impl Args {
+    /// Parses `args`, looking up the environment variable of each setting
+    /// missing from them with `env`.
+    pub fn parse_with_env<I, T>(
+        args: I,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let command = Args::command().mut_args(|arg| arg.env(None));
+        let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let given = match command.clone().try_get_matches_from(&args) {
+            // The help of the full command also lists the variables
+            Err(e) if e.kind() == ErrorKind::DisplayHelp => return Args::try_parse_from(&args),
+            given => given?,
+        };
+        for arg in Args::command().get_arguments() {
+            let (Some(name), Some(long)) = (arg.get_env(), arg.get_long()) else {
+                continue;
+            };
+            if given.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+                continue;
+            }
+            if let Some(value) = name.to_str().and_then(&env) {
+                args.push(format!("--{}={}", long, value).into());
+            }
+        }
+        let matches = command.try_get_matches_from(args)?;
+        Args::from_arg_matches(&matches)
+    }
+}
+
+/// Parses octal permission bits, with or without a `0o` prefix.
+fn parse_mode(value: &str) -> Result<u32, String> {
+    let digits = value.strip_prefix("0o").unwrap_or(value);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o777 => Ok(mode),
+        _ => Err(format!("'{}' is not an octal permission mode", value)),
+    }
+}
@@ -0,0 +1,267 @@
+//! Config File
+//!
+//! TOML representation of the settings. Every key is optional; missing
+//! keys fall back to the defaults.
+//!
+//! ```toml
+//! port = 6380
+//! bind_address = ["127.0.0.1", "::1"]
+//! unix_socket = "/run/mini_redis.sock"
+//! unix_socket_mode = 0o660
+//! max_entries = 50000
+//! log_format = "json"
+//! rate_limit = "100/200"
+//! rate_limit_routes = ["/set=20/40", "/health=off"]
+//!
+//! [[acl_users]]
+//! name = "billing"
+//! token = "s3cret"
+//! permissions = ["read", "write"]
+//! key_patterns = ["billing:*"]
+//!
+//! [[databases]]
+//! name = "staging"
+//! max_entries = 10000
+//! default_ttl = 60
+//!
+//! [[cluster_nodes]]
+//! id = "a"
+//! address = "10.0.0.1:6380"
+//! slots = ["0-8191"]
+//! ```
+
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use toml_edit::{value, DocumentMut};
+
+use super::{ConfigError, LogFormat, Tunables};
+use crate::acl::AclUser;
+use crate::cache::DatabaseConfig;
+use crate::cluster::ClusterNode;
+
+/// Settings read from a TOML config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub port: Option<u16>,
+    pub bind_address: Option<OneOrMany<IpAddr>>,
+    pub unix_socket: Option<PathBuf>,
+    pub unix_socket_mode: Option<u32>,
+    pub max_entries: Option<usize>,
+    pub default_ttl: Option<u64>,
+    pub cleanup_interval: Option<u64>,
+    pub databases: Option<Vec<DatabaseConfig>>,
+    pub log_format: Option<LogFormat>,
+    pub slowlog_threshold_us: Option<u64>,
+    pub slowlog_max_len: Option<usize>,
+    pub auth_tokens: Option<Vec<String>>,
+    pub auth_exempt_health: Option<bool>,
+    pub acl_users: Option<Vec<AclUser>>,
+    pub tls_cert_path: Option<PathBuf>,
+    pub tls_key_path: Option<PathBuf>,
+    pub tls_client_ca_path: Option<PathBuf>,
+    pub rate_limit: Option<String>,
+    pub rate_limit_routes: Option<Vec<String>>,
+    pub replica_of: Option<String>,
+    pub replica_auth_token: Option<String>,
+    pub replica_tls_ca_path: Option<PathBuf>,
+    pub repl_backlog_size: Option<usize>,
+    pub cluster_node_id: Option<String>,
+    pub cluster_nodes: Option<Vec<ClusterNode>>,
+    pub cluster_auth_token: Option<String>,
+    pub cluster_tls_ca_path: Option<PathBuf>,
+    pub cluster_bus_port: Option<u16>,
+    pub cluster_node_timeout: Option<u64>,
+    pub cluster_meet: Option<Vec<String>>,
+    pub origin_url: Option<String>,
+    pub origin_timeout: Option<u64>,
+}
+
+/// A key that takes either a single value or an array of values.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> From<OneOrMany<T>> for Vec<T> {
+    fn from(value: OneOrMany<T>) -> Self {
+        match value {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+impl ConfigFile {
+    /// Reads and parses the file at `path`.
+    pub fn read(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Writes the current runtime settings into the file at `path`.
+    ///
+    /// Only the tunable keys are replaced; comments, formatting and other
+    /// settings are preserved. The file is created if missing and replaced
+    /// atomically, so a crash cannot leave it half-written. Values too large
+    /// for a TOML integer are errors and leave the file unchanged.
+    pub fn rewrite(path: &Path, tunables: &Tunables) -> Result<(), ConfigError> {
+        let settings = [
+            ("max_entries", integer("max_entries", tunables.max_entries)?),
+            ("default_ttl", integer("default_ttl", tunables.default_ttl)?),
+            (
+                "cleanup_interval",
+                integer("cleanup_interval", tunables.cleanup_interval)?,
+            ),
+            (
+                "slowlog_threshold_us",
+                integer("slowlog_threshold_us", tunables.slowlog_threshold_us)?,
+            ),
+            (
+                "slowlog_max_len",
+                integer("slowlog_max_len", tunables.slowlog_max_len)?,
+            ),
+        ];
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(source) => {
+                return Err(ConfigError::Read {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+        let mut doc: DocumentMut = contents.parse().map_err(|source| ConfigError::Edit {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        for (key, setting) in settings {
+            doc[key] = value(setting);
+        }
+
+        let write_error = |source| ConfigError::Write {
+            path: path.to_path_buf(),
+            source,
+        };
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, doc.to_string()).map_err(write_error)?;
+        std::fs::rename(&tmp, path).map_err(write_error)
+    }
+}
+
+/// Converts a setting to a TOML integer, which is a signed 64-bit number.
+fn integer(setting: &'static str, number: impl TryInto<i64>) -> Result<i64, ConfigError> {
+    number
+        .try_into()
+        .map_err(|_| ConfigError::invalid(setting, "too large to write to the config file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acl::Permission;
+
+    #[test]
+    fn test_parse_config_file() {
+        let file: ConfigFile = toml::from_str(
+            r#"
+            port = 6380
+            bind_address = "::1"
+            log_format = "json"
+            rate_limit_routes = ["/set=20/40"]
+
+            [[acl_users]]
+            name = "billing"
+            token = "s3cret"
+            permissions = ["read", "write"]
+            key_patterns = ["billing:*"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(file.port, Some(6380));
+        assert_eq!(
+            file.bind_address,
+            Some(OneOrMany::One("::1".parse().unwrap()))
+        );
+        assert_eq!(file.log_format, Some(LogFormat::Json));
+        assert_eq!(file.max_entries, None);
+        let users = file.acl_users.unwrap();
+        assert_eq!(
+            users[0].permissions,
+            vec![Permission::Read, Permission::Write]
+        );
+    }
+
+    #[test]
+    fn test_rewrite_preserves_other_settings() {
+        let path =
+            std::env::temp_dir().join(format!("mini_redis_rewrite_{}.toml", std::process::id()));
+        std::fs::write(&path, "# Production cache\nport = 6380\nmax_entries = 10\n").unwrap();
+
+        let tunables = Tunables {
+            max_entries: 500,
+            default_ttl: 60,
+            cleanup_interval: 5,
+            slowlog_threshold_us: 2_000,
+            slowlog_max_len: 64,
+        };
+        ConfigFile::rewrite(&path, &tunables).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# Production cache\n"));
+        let file: ConfigFile = toml::from_str(&contents).unwrap();
+        assert_eq!(file.port, Some(6380));
+        assert_eq!(file.max_entries, Some(500));
+        assert_eq!(file.cleanup_interval, Some(5));
+        assert_eq!(file.slowlog_max_len, Some(64));
+
+        let huge = Tunables {
+            default_ttl: u64::MAX,
+            ..tunables
+        };
+        assert!(matches!(
+            ConfigFile::rewrite(&path, &huge),
+            Err(ConfigError::Invalid {
+                setting: "default_ttl",
+                ..
+            })
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_bind_address_list() {
+        let file: ConfigFile = toml::from_str(
+            r#"
+            bind_address = ["127.0.0.1", "::1"]
+            unix_socket_mode = 0o600
+            "#,
+        )
+        .unwrap();
+
+        let addrs: Vec<IpAddr> = file.bind_address.unwrap().into();
+        assert_eq!(addrs.len(), 2);
+        assert_eq!(file.unix_socket_mode, Some(0o600));
+    }
+
+    #[test]
+    fn test_unknown_keys_rejected() {
+        assert!(toml::from_str::<ConfigFile>("max_entires = 10").is_err());
+        assert!(toml::from_str::<ConfigFile>("max_entries = \"ten\"").is_err());
+    }
+}
@@ -0,0 +1,976 @@
+//! Configuration Module
+//!
+//! Loads server configuration from command-line flags, environment
+//! variables and an optional TOML config file, in that order of precedence,
+//! falling back to defaults for anything left unset.
+
+mod args;
+mod file;
+
+use std::ffi::OsString;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::acl::AclUser;
+use crate::cache::{DatabaseConfig, DEFAULT_DATABASE};
+use crate::cluster::{Cluster, ClusterNode, BUS_PORT_OFFSET};
+use crate::monitor::{DEFAULT_SLOWLOG_MAX_LEN, DEFAULT_SLOWLOG_THRESHOLD_US};
+use crate::origin::Origin;
+use crate::ratelimit::{RateLimitRule, RouteRateLimit};
+use crate::tls::TlsSettings;
+
+pub use args::Args;
+pub use file::ConfigFile;
+
+/// Default permissions of the Unix domain socket: owner and group only
+pub const DEFAULT_UNIX_SOCKET_MODE: u32 = 0o660;
+
+/// Default number of write commands kept for partial resynchronization
+pub const DEFAULT_REPL_BACKLOG_SIZE: usize = 10_000;
+
+/// Default milliseconds without an answer before a cluster node is suspected
+pub const DEFAULT_CLUSTER_NODE_TIMEOUT_MS: u64 = 15_000;
+
+/// Default milliseconds to wait for the origin on a miss
+pub const DEFAULT_ORIGIN_TIMEOUT_MS: u64 = 5_000;
+
+// == Config Error ==
+/// Errors that prevent the server from starting with the given settings.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// Invalid command-line flags or environment variables
+    #[error(transparent)]
+    Args(#[from] clap::Error),
+
+    /// The config file could not be read
+    #[error("Failed to read config file {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// The config file is not valid TOML or has unknown keys
+    #[error("Invalid config file {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    /// The config file could not be written by CONFIG REWRITE
+    #[error("Failed to write config file {}: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// The config file could not be edited in place
+    #[error("Invalid config file {}: {source}", path.display())]
+    Edit {
+        path: PathBuf,
+        source: toml_edit::TomlError,
+    },
+
+    /// A setting has a value the server cannot run with
+    #[error("Invalid {setting}: {message}")]
+    Invalid {
+        setting: &'static str,
+        message: String,
+    },
+}
+
+impl ConfigError {
+    fn invalid(setting: &'static str, message: impl Into<String>) -> Self {
+        ConfigError::Invalid {
+            setting,
+            message: message.into(),
+        }
+    }
+}
+
+// == Log Format ==
+/// Output format of the server logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogFormat::Text => f.write_str("text"),
+            LogFormat::Json => f.write_str("json"),
+        }
+    }
+}
+
+// == Tunables ==
+/// Settings that can be changed while the server runs and persisted back
+/// to the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Tunables {
+    /// Maximum number of cache entries
+    pub max_entries: usize,
+    /// Default TTL in seconds
+    pub default_ttl: u64,
+    /// Cleanup task interval in seconds
+    pub cleanup_interval: u64,
+    /// Slow log threshold in microseconds
+    pub slowlog_threshold_us: u64,
+    /// Maximum slow log entries
+    pub slowlog_max_len: usize,
+}
+
+/// Server configuration parameters.
+///
+/// Every value can be set by flag, environment variable or config file,
+/// with sensible defaults.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of entries the cache can hold
+    pub max_entries: usize,
+    /// Default TTL in seconds for entries without explicit TTL
+    pub default_ttl: u64,
+    /// HTTP server port
+    pub server_port: u16,
+    /// Addresses the HTTP server listens on, one listener per address
+    pub bind_addresses: Vec<IpAddr>,
+    /// Unix domain socket the HTTP API is also served on
+    pub unix_socket: Option<PathBuf>,
+    /// Permission bits of the Unix domain socket file
+    pub unix_socket_mode: u32,
+    /// Background cleanup task interval in seconds
+    pub cleanup_interval: u64,
+    /// Databases besides the default one
+    pub databases: Vec<DatabaseConfig>,
+    /// Log output format
+    pub log_format: LogFormat,
+    /// Minimum operation duration in microseconds to be recorded in the slow log
+    pub slowlog_threshold_us: u64,
+    /// Maximum number of entries kept in the slow log
+    pub slowlog_max_len: usize,
+    /// Bearer tokens accepted by the API; authentication is disabled when empty
+    pub auth_tokens: Vec<String>,
+    /// Whether `/health` stays reachable without credentials (for load balancers)
+    pub auth_exempt_health: bool,
+    /// Users with restricted permissions and key patterns
+    pub acl_users: Vec<AclUser>,
+    /// PEM certificate chain; TLS is enabled when set together with the key
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM private key for the certificate
+    pub tls_key_path: Option<PathBuf>,
+    /// PEM CA bundle used to require and verify client certificates
+    pub tls_client_ca_path: Option<PathBuf>,
+    /// Default per-client request rate; unlimited when None
+    pub rate_limit: Option<RateLimitRule>,
+    /// Per-route rates overriding `rate_limit`
+    pub route_rate_limits: Vec<RouteRateLimit>,
+    /// Leader (`host:port`) this server follows; None for a leader
+    pub replica_of: Option<String>,
+    /// Bearer token sent to the leader when it requires authentication
+    pub replica_auth_token: Option<String>,
+    /// PEM CA bundle the leader's certificate is verified against; the
+    /// leader is reached over TLS when set
+    pub replica_tls_ca_path: Option<PathBuf>,
+    /// Number of write commands kept for followers to resume from
+    pub repl_backlog_size: usize,
+    /// Id of this node in `cluster_nodes`
+    pub cluster_node_id: Option<String>,
+    /// Node table of the cluster; cluster mode is off when empty
+    pub cluster_nodes: Vec<ClusterNode>,
+    /// Bearer token sent to other nodes when migrating slots and on the bus
+    pub cluster_auth_token: Option<String>,
+    /// PEM CA bundle the other nodes' certificates are verified against;
+    /// their admin endpoints are reached over TLS when set
+    pub cluster_tls_ca_path: Option<PathBuf>,
+    /// Port of the cluster bus; `server_port + 10000` when None
+    pub cluster_bus_port: Option<u16>,
+    /// Milliseconds without an answer before a node is suspected to fail
+    pub cluster_node_timeout: u64,
+    /// Cluster bus addresses of nodes to join at startup
+    pub cluster_meet: Vec<String>,
+    /// Origin URL template fetched on a miss; origin mode is off when None
+    pub origin_url: Option<String>,
+    /// Milliseconds to wait for the origin
+    pub origin_timeout: u64,
+    /// Config file the settings were loaded from, if any
+    pub config_file: Option<PathBuf>,
+}
+
+impl Config {
+    /// Loads the configuration from the process arguments, environment
+    /// variables and config file.
+    ///
+    /// See [`Config::load_from`] for precedence and validation.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(std::env::args_os())
+    }
+
+    /// Loads the configuration from environment variables (and the config
+    /// file named by `CONFIG_FILE`), ignoring command-line flags.
+    ///
+    /// # Environment Variables
+    /// - `CONFIG_FILE` - TOML config file (default: none)
+    /// - `MAX_ENTRIES` - Maximum cache entries (default: 1000)
+    /// - `DEFAULT_TTL` - Default TTL in seconds (default: 300)
+    /// - `SERVER_PORT` - HTTP server port (default: 3000)
+    /// - `BIND_ADDRESS` - Comma-separated HTTP bind addresses (default: 0.0.0.0)
+    /// - `UNIX_SOCKET` - Unix domain socket path (default: none)
+    /// - `UNIX_SOCKET_MODE` - Octal socket file permissions (default: 660)
+    /// - `CLEANUP_INTERVAL` - Cleanup frequency in seconds (default: 1)
+    /// - `DATABASES` - Comma-separated `name=max_entries/default_ttl` (default: none)
+    /// - `LOG_FORMAT` - `text` or `json` (default: text)
+    /// - `SLOWLOG_THRESHOLD_US` - Slow log threshold in microseconds (default: 10000)
+    /// - `SLOWLOG_MAX_LEN` - Slow log capacity (default: 128)
+    /// - `AUTH_TOKENS` - Comma-separated bearer tokens (default: none, auth disabled)
+    /// - `AUTH_EXEMPT_HEALTH` - Leave `/health` unauthenticated (default: true)
+    /// - `ACL_USERS` - `;`-separated `name|token|permissions|patterns` (default: none)
+    /// - `TLS_CERT_PATH` / `TLS_KEY_PATH` - PEM certificate and key (default: none, plain HTTP)
+    /// - `TLS_CLIENT_CA_PATH` - PEM CA bundle for mutual TLS (default: none)
+    /// - `RATE_LIMIT` - Per-client `rate/burst` in requests per second (default: none)
+    /// - `RATE_LIMIT_ROUTES` - Comma-separated `route=rate/burst` or `route=off` (default: none)
+    /// - `REPLICA_OF` - Leader `host:port` to follow (default: none, the server is a leader)
+    /// - `REPLICA_AUTH_TOKEN` - Bearer token for the leader (default: none)
+    /// - `REPLICA_TLS_CA_PATH` - PEM CA bundle for a TLS leader (default: none, plain HTTP)
+    /// - `REPL_BACKLOG_SIZE` - Write commands kept for partial resync (default: 10000)
+    /// - `CLUSTER_NODE_ID` - Id of this node in the cluster (default: none)
+    /// - `CLUSTER_NODES` - `;`-separated `id|host:port|slots` (default: none, cluster mode off)
+    /// - `CLUSTER_AUTH_TOKEN` - Bearer token for other nodes (default: none)
+    /// - `CLUSTER_TLS_CA_PATH` - PEM CA bundle for nodes serving TLS (default: none, plain HTTP)
+    /// - `CLUSTER_BUS_PORT` - Port of the cluster bus (default: PORT + 10000)
+    /// - `CLUSTER_NODE_TIMEOUT` - Milliseconds before a silent node is suspected (default: 15000)
+    /// - `CLUSTER_MEET` - Comma-separated bus addresses of nodes to join (default: none)
+    /// - `ORIGIN_URL` - Origin URL with a `{key}` placeholder, fetched on a miss (default: none)
+    /// - `ORIGIN_TIMEOUT` - Milliseconds to wait for the origin (default: 5000)
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::load_from(["mini_redis"])
+    }
+
+    /// Loads the configuration from the given command-line arguments.
+    ///
+    /// Each setting is taken from its flag, then its environment variable,
+    /// then the config file (`--config`), then the default. Values that fail
+    /// to parse or validate are errors rather than falling back to defaults.
+    pub fn load_from<I, T>(args: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        Self::load_from_env(args, |name| std::env::var(name).ok())
+    }
+
+    /// Loads the configuration from the given command-line arguments, with
+    /// `env` in place of the process environment.
+    pub fn load_from_env<I, T>(
+        args: I,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args = Args::parse_with_env(args, env)?;
+        let file = match &args.config {
+            Some(path) => ConfigFile::read(path)?,
+            None => ConfigFile::default(),
+        };
+        let config = Self::merge(args, file)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Layers flags/environment over the config file over the defaults.
+    fn merge(args: Args, file: ConfigFile) -> Result<Self, ConfigError> {
+        let defaults = Config::default();
+
+        let auth_tokens = match args.auth_tokens {
+            Some(tokens) => parse_list(&tokens),
+            None => file.auth_tokens.unwrap_or_default(),
+        };
+        let acl_users = match args.acl_users {
+            Some(users) => {
+                AclUser::parse_list(&users).map_err(|e| ConfigError::invalid("acl_users", e))?
+            }
+            None => file.acl_users.unwrap_or_default(),
+        };
+        let databases = match args.databases {
+            Some(databases) => DatabaseConfig::parse_list(&databases)
+                .map_err(|e| ConfigError::invalid("databases", e))?,
+            None => file.databases.unwrap_or_default(),
+        };
+        let cluster_nodes = match args.cluster_nodes {
+            Some(nodes) => ClusterNode::parse_list(&nodes)
+                .map_err(|e| ConfigError::invalid("cluster_nodes", e))?,
+            None => file.cluster_nodes.unwrap_or_default(),
+        };
+        let rate_limit = match (args.rate_limit, file.rate_limit) {
+            (Some(rule), _) => Some(rule),
+            (None, Some(rule)) => Some(
+                rule.parse()
+                    .map_err(|e| ConfigError::invalid("rate_limit", e))?,
+            ),
+            (None, None) => None,
+        };
+        let route_rate_limits = match (args.rate_limit_routes, file.rate_limit_routes) {
+            (Some(routes), _) => RouteRateLimit::parse_list(&routes),
+            (None, Some(routes)) => routes.iter().map(|r| r.parse()).collect(),
+            (None, None) => Ok(Vec::new()),
+        }
+        .map_err(|e| ConfigError::invalid("rate_limit_routes", e))?;
+
+        Ok(Self {
+            max_entries: args
+                .max_entries
+                .or(file.max_entries)
+                .unwrap_or(defaults.max_entries),
+            default_ttl: args
+                .default_ttl
+                .or(file.default_ttl)
+                .unwrap_or(defaults.default_ttl),
+            server_port: args.port.or(file.port).unwrap_or(defaults.server_port),
+            bind_addresses: args
+                .bind_addresses
+                .or(file.bind_address.map(Into::into))
+                .unwrap_or(defaults.bind_addresses),
+            unix_socket: args.unix_socket.or(file.unix_socket),
+            unix_socket_mode: args
+                .unix_socket_mode
+                .or(file.unix_socket_mode)
+                .unwrap_or(defaults.unix_socket_mode),
+            cleanup_interval: args
+                .cleanup_interval
+                .or(file.cleanup_interval)
+                .unwrap_or(defaults.cleanup_interval),
+            databases,
+            log_format: args
+                .log_format
+                .or(file.log_format)
+                .unwrap_or(defaults.log_format),
+            slowlog_threshold_us: args
+                .slowlog_threshold_us
+                .or(file.slowlog_threshold_us)
+                .unwrap_or(defaults.slowlog_threshold_us),
+            slowlog_max_len: args
+                .slowlog_max_len
+                .or(file.slowlog_max_len)
+                .unwrap_or(defaults.slowlog_max_len),
+            auth_tokens,
+            auth_exempt_health: args
+                .auth_exempt_health
+                .or(file.auth_exempt_health)
+                .unwrap_or(defaults.auth_exempt_health),
+            acl_users,
+            tls_cert_path: args.tls_cert_path.or(file.tls_cert_path),
+            tls_key_path: args.tls_key_path.or(file.tls_key_path),
+            tls_client_ca_path: args.tls_client_ca_path.or(file.tls_client_ca_path),
+            rate_limit,
+            route_rate_limits,
+            replica_of: args.replica_of.or(file.replica_of),
+            replica_auth_token: args.replica_auth_token.or(file.replica_auth_token),
+            replica_tls_ca_path: args.replica_tls_ca_path.or(file.replica_tls_ca_path),
+            repl_backlog_size: args
+                .repl_backlog_size
+                .or(file.repl_backlog_size)
+                .unwrap_or(defaults.repl_backlog_size),
+            cluster_node_id: args.cluster_node_id.or(file.cluster_node_id),
+            cluster_nodes,
+            cluster_auth_token: args.cluster_auth_token.or(file.cluster_auth_token),
+            cluster_tls_ca_path: args.cluster_tls_ca_path.or(file.cluster_tls_ca_path),
+            cluster_bus_port: args.cluster_bus_port.or(file.cluster_bus_port),
+            cluster_node_timeout: args
+                .cluster_node_timeout
+                .or(file.cluster_node_timeout)
+                .unwrap_or(defaults.cluster_node_timeout),
+            cluster_meet: args
+                .cluster_meet
+                .map(|meet| parse_list(&meet))
+                .or(file.cluster_meet)
+                .unwrap_or_default(),
+            origin_url: args.origin_url.or(file.origin_url),
+            origin_timeout: args
+                .origin_timeout
+                .or(file.origin_timeout)
+                .unwrap_or(defaults.origin_timeout),
+            config_file: args.config,
+        })
+    }
+
+    /// Checks settings that parse but cannot work.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_entries == 0 {
+            return Err(ConfigError::invalid("max_entries", "must be at least 1"));
+        }
+        if self.cleanup_interval == 0 {
+            return Err(ConfigError::invalid(
+                "cleanup_interval",
+                "must be at least 1 second",
+            ));
+        }
+        if self.bind_addresses.is_empty() && self.unix_socket.is_none() {
+            return Err(ConfigError::invalid(
+                "bind_address",
+                "at least one address is needed unless unix_socket is set",
+            ));
+        }
+        for (i, addr) in self.bind_addresses.iter().enumerate() {
+            if self.bind_addresses[..i].contains(addr) {
+                return Err(ConfigError::invalid(
+                    "bind_address",
+                    format!("{} is listed more than once", addr),
+                ));
+            }
+        }
+        if self.unix_socket_mode > 0o777 {
+            return Err(ConfigError::invalid(
+                "unix_socket_mode",
+                format!("{:o} is not a valid permission mode", self.unix_socket_mode),
+            ));
+        }
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err(ConfigError::invalid(
+                "tls",
+                "tls_cert_path and tls_key_path must be set together",
+            ));
+        }
+        if self.tls_client_ca_path.is_some() && self.tls_cert_path.is_none() {
+            return Err(ConfigError::invalid(
+                "tls",
+                "tls_client_ca_path requires tls_cert_path and tls_key_path",
+            ));
+        }
+        for (i, database) in self.databases.iter().enumerate() {
+            if !DatabaseConfig::is_valid_name(&database.name) {
+                return Err(ConfigError::invalid(
+                    "databases",
+                    format!(
+                        "'{}' is not a valid name (letters, digits, '-' and '_')",
+                        database.name
+                    ),
+                ));
+            }
+            if database.name == DEFAULT_DATABASE
+                || self.databases[..i].iter().any(|d| d.name == database.name)
+            {
+                return Err(ConfigError::invalid(
+                    "databases",
+                    format!("'{}' is defined more than once", database.name),
+                ));
+            }
+            if database.max_entries == Some(0) {
+                return Err(ConfigError::invalid(
+                    "databases",
+                    format!("max_entries of '{}' must be at least 1", database.name),
+                ));
+            }
+        }
+        if let Some(leader) = &self.replica_of {
+            if !is_host_port(leader) {
+                return Err(ConfigError::invalid(
+                    "replica_of",
+                    format!("'{}' is not a host:port address", leader),
+                ));
+            }
+        }
+        if self.replica_tls_ca_path.is_some() && self.replica_of.is_none() {
+            return Err(ConfigError::invalid(
+                "replica_tls_ca_path",
+                "requires replica_of",
+            ));
+        }
+        if self.repl_backlog_size == 0 {
+            return Err(ConfigError::invalid(
+                "repl_backlog_size",
+                "must be at least 1",
+            ));
+        }
+        if self.cluster_node_id.is_some() == self.cluster_nodes.is_empty() {
+            return Err(ConfigError::invalid(
+                "cluster",
+                "cluster_node_id and cluster_nodes must be set together",
+            ));
+        }
+        for node in &self.cluster_nodes {
+            if !is_host_port(&node.address) {
+                return Err(ConfigError::invalid(
+                    "cluster_nodes",
+                    format!(
+                        "address '{}' of node '{}' is not a host:port address",
+                        node.address, node.id
+                    ),
+                ));
+            }
+        }
+        self.cluster()
+            .transpose()
+            .map_err(|e| ConfigError::invalid("cluster_nodes", e))?;
+        if self.cluster_node_id.is_some() {
+            if self.cluster_bus_port().is_none() {
+                return Err(ConfigError::invalid(
+                    "cluster_bus_port",
+                    "port + 10000 is out of range, set the bus port explicitly",
+                ));
+            }
+            if self.cluster_node_timeout == 0 {
+                return Err(ConfigError::invalid(
+                    "cluster_node_timeout",
+                    "must be at least 1 millisecond",
+                ));
+            }
+            // Migrations reach the other nodes on the port served here
+            if self.tls_cert_path.is_some() && self.cluster_tls_ca_path.is_none() {
+                return Err(ConfigError::invalid(
+                    "cluster_tls_ca_path",
+                    "required in cluster mode when tls_cert_path is set",
+                ));
+            }
+        } else if self.cluster_tls_ca_path.is_some() {
+            return Err(ConfigError::invalid(
+                "cluster_tls_ca_path",
+                "requires cluster_node_id and cluster_nodes",
+            ));
+        }
+        if let Some(address) = self.cluster_meet.iter().find(|a| !is_host_port(a)) {
+            return Err(ConfigError::invalid(
+                "cluster_meet",
+                format!("'{}' is not a host:port address", address),
+            ));
+        }
+        if let Some(template) = &self.origin_url {
+            Origin::check_template(template)
+                .map_err(|e| ConfigError::invalid("origin_url", e))?;
+            if self.origin_timeout == 0 {
+                return Err(ConfigError::invalid(
+                    "origin_timeout",
+                    "must be at least 1 millisecond",
+                ));
+            }
+        }
+        for user in &self.acl_users {
+            if user.name.is_empty() || user.token.is_empty() {
+                return Err(ConfigError::invalid(
+                    "acl_users",
+                    "every user needs a name and a token",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the settings that can be changed at runtime, as configured
+    /// at startup.
+    pub fn tunables(&self) -> Tunables {
+        Tunables {
+            max_entries: self.max_entries,
+            default_ttl: self.default_ttl,
+            cleanup_interval: self.cleanup_interval,
+            slowlog_threshold_us: self.slowlog_threshold_us,
+            slowlog_max_len: self.slowlog_max_len,
+        }
+    }
+
+    /// Returns true if requests must carry a valid bearer token.
+    pub fn auth_enabled(&self) -> bool {
+        !self.auth_tokens.is_empty() || !self.acl_users.is_empty()
+    }
+
+    /// Returns the slot table when cluster mode is enabled.
+    pub fn cluster(&self) -> Option<Result<Cluster, String>> {
+        let myself = self.cluster_node_id.as_deref()?;
+        Some(
+            Cluster::new(myself, self.cluster_nodes.clone()).map(|cluster| {
+                let cluster = cluster
+                    .with_node_timeout(Duration::from_millis(self.cluster_node_timeout))
+                    .with_meet(self.cluster_meet.clone())
+                    .with_leader(self.replica_of.clone());
+                match self.cluster_bus_port() {
+                    Some(port) => cluster.with_bus_port(port),
+                    None => cluster,
+                }
+            }),
+        )
+    }
+
+    /// Returns the port of the cluster bus, or None if the default is out
+    /// of range.
+    pub fn cluster_bus_port(&self) -> Option<u16> {
+        self.cluster_bus_port
+            .or_else(|| self.server_port.checked_add(BUS_PORT_OFFSET))
+    }
+
+    /// Returns the TLS files to serve with, or None for plain HTTP.
+    pub fn tls_settings(&self) -> Option<TlsSettings> {
+        Some(TlsSettings {
+            cert_path: self.tls_cert_path.clone()?,
+            key_path: self.tls_key_path.clone()?,
+            client_ca_path: self.tls_client_ca_path.clone(),
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_entries: 1000,
+            default_ttl: 300,
+            server_port: 3000,
+            bind_addresses: vec![IpAddr::V4(Ipv4Addr::UNSPECIFIED)],
+            unix_socket: None,
+            unix_socket_mode: DEFAULT_UNIX_SOCKET_MODE,
+            cleanup_interval: 1,
+            databases: Vec::new(),
+            log_format: LogFormat::Text,
+            slowlog_threshold_us: DEFAULT_SLOWLOG_THRESHOLD_US,
+            slowlog_max_len: DEFAULT_SLOWLOG_MAX_LEN,
+            auth_tokens: Vec::new(),
+            auth_exempt_health: true,
+            acl_users: Vec::new(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_client_ca_path: None,
+            rate_limit: None,
+            route_rate_limits: Vec::new(),
+            replica_of: None,
+            replica_auth_token: None,
+            replica_tls_ca_path: None,
+            repl_backlog_size: DEFAULT_REPL_BACKLOG_SIZE,
+            cluster_node_id: None,
+            cluster_nodes: Vec::new(),
+            cluster_auth_token: None,
+            cluster_tls_ca_path: None,
+            cluster_bus_port: None,
+            cluster_node_timeout: DEFAULT_CLUSTER_NODE_TIMEOUT_MS,
+            cluster_meet: Vec::new(),
+            origin_url: None,
+            origin_timeout: DEFAULT_ORIGIN_TIMEOUT_MS,
+            config_file: None,
+        }
+    }
+}
+
+/// Returns true if `address` is `host:port` with a numeric port.
+fn is_host_port(address: &str) -> bool {
+    address
+        .rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+}
+
+/// Splits a comma-separated list, trimming whitespace and dropping empty items.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// Writes `contents` to a config file unique to the calling test.
+    fn config_file(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!(
+            "mini_redis_config_{}_{}.toml",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_config_default() {
+        let config = Config::default();
+        assert_eq!(config.max_entries, 1000);
+        assert_eq!(config.default_ttl, 300);
+        assert_eq!(config.server_port, 3000);
+        assert_eq!(config.cleanup_interval, 1);
+        assert_eq!(config.slowlog_threshold_us, 10_000);
+        assert_eq!(config.slowlog_max_len, 128);
+        assert!(!config.auth_enabled());
+        assert!(config.auth_exempt_health);
+        assert!(config.tls_settings().is_none());
+        assert_eq!(config.bind_addresses, vec![IpAddr::from([0, 0, 0, 0])]);
+        assert_eq!(config.unix_socket, None);
+        assert_eq!(config.unix_socket_mode, 0o660);
+        assert_eq!(config.log_format, LogFormat::Text);
+        assert_eq!(config.replica_of, None);
+        assert_eq!(config.repl_backlog_size, DEFAULT_REPL_BACKLOG_SIZE);
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_list("a, b,,c "), vec!["a", "b", "c"]);
+        assert!(parse_list("").is_empty());
+    }
+
+    #[test]
+    fn test_config_from_env_defaults() {
+        let config = Config::load_from_env(["mini_redis"], |_| None).unwrap();
+        assert_eq!(config.max_entries, 1000);
+        assert_eq!(config.default_ttl, 300);
+        assert_eq!(config.server_port, 3000);
+        assert_eq!(config.cleanup_interval, 1);
+    }
+
+    #[test]
+    fn test_flags_override_file() {
+        let path = config_file(
+            "flags",
+            "port = 7000\nmax_entries = 50\ndefault_ttl = 60\nlog_format = \"json\"\n",
+        );
+
+        let config = Config::load_from([
+            "mini_redis",
+            "--config",
+            path.to_str().unwrap(),
+            "--port",
+            "8000",
+            "--bind",
+            "::1",
+            "--bind",
+            "127.0.0.1",
+            "--unix-socket-mode",
+            "600",
+        ])
+        .unwrap();
+
+        assert_eq!(config.server_port, 8000);
+        assert_eq!(config.max_entries, 50);
+        assert_eq!(config.default_ttl, 60);
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(
+            config.bind_addresses,
+            vec![
+                "::1".parse::<IpAddr>().unwrap(),
+                IpAddr::from([127, 0, 0, 1])
+            ]
+        );
+        assert_eq!(config.unix_socket_mode, 0o600);
+        assert_eq!(config.config_file, Some(path));
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let path = config_file("env", "slowlog_threshold_us = 5\nport = 7000\n");
+        let env = |name: &str| match name {
+            "SLOWLOG_THRESHOLD_US" => Some("7".to_string()),
+            "SERVER_PORT" => Some("8000".to_string()),
+            "BIND_ADDRESS" => Some("127.0.0.1,::1".to_string()),
+            _ => None,
+        };
+
+        let config = Config::load_from_env(
+            [
+                "mini_redis",
+                "--config",
+                path.to_str().unwrap(),
+                "--port",
+                "9000",
+            ],
+            env,
+        )
+        .unwrap();
+
+        assert_eq!(config.slowlog_threshold_us, 7);
+        // Flags still win over the environment
+        assert_eq!(config.server_port, 9000);
+        assert_eq!(
+            config.bind_addresses,
+            vec![
+                IpAddr::from([127, 0, 0, 1]),
+                "::1".parse::<IpAddr>().unwrap()
+            ]
+        );
+        assert!(matches!(
+            Config::load_from_env(["mini_redis"], |name| (name == "MAX_ENTRIES")
+                .then(|| "abc".to_string())),
+            Err(ConfigError::Args(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_values_are_errors() {
+        assert!(matches!(
+            Config::load_from(["mini_redis", "--max-entries", "abc"]),
+            Err(ConfigError::Args(_))
+        ));
+        assert!(matches!(
+            Config::load_from(["mini_redis", "--max-entries", "0"]),
+            Err(ConfigError::Invalid {
+                setting: "max_entries",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Config::load_from(["mini_redis", "--tls-cert", "cert.pem"]),
+            Err(ConfigError::Invalid { setting: "tls", .. })
+        ));
+        assert!(matches!(
+            Config::load_from(["mini_redis", "--acl-users", "broken"]),
+            Err(ConfigError::Invalid {
+                setting: "acl_users",
+                ..
+            })
+        ));
+        for leader in ["localhost", ":3000", "leader:http"] {
+            assert!(matches!(
+                Config::load_from(["mini_redis", "--replica-of", leader]),
+                Err(ConfigError::Invalid {
+                    setting: "replica_of",
+                    ..
+                })
+            ));
+        }
+        assert!(matches!(
+            Config::load_from(["mini_redis", "--replica-tls-ca", "ca.pem"]),
+            Err(ConfigError::Invalid {
+                setting: "replica_tls_ca_path",
+                ..
+            })
+        ));
+        for (node_id, nodes) in [
+            (None, "a|127.0.0.1:7000|0-16383"),
+            (Some("b"), "a|127.0.0.1:7000|0-16383"),
+            (Some("a"), "a|localhost|0-16383"),
+            (Some("a"), "a|h:1|0-100;b|h:2|100-200"),
+        ] {
+            let mut args = vec!["mini_redis", "--cluster-nodes", nodes];
+            if let Some(node_id) = node_id {
+                args.extend(["--cluster-node-id", node_id]);
+            }
+            assert!(
+                matches!(
+                    Config::load_from(args),
+                    Err(ConfigError::Invalid { setting, .. })
+                        if setting == "cluster" || setting == "cluster_nodes"
+                ),
+                "{}",
+                nodes
+            );
+        }
+        assert!(matches!(
+            Config::load_from(["mini_redis", "--cluster-tls-ca", "ca.pem"]),
+            Err(ConfigError::Invalid {
+                setting: "cluster_tls_ca_path",
+                ..
+            })
+        ));
+        let tls_cluster = [
+            "mini_redis",
+            "--cluster-node-id",
+            "a",
+            "--cluster-nodes",
+            "a|127.0.0.1:7000|0-16383",
+            "--tls-cert",
+            "cert.pem",
+            "--tls-key",
+            "key.pem",
+        ];
+        assert!(matches!(
+            Config::load_from(tls_cluster),
+            Err(ConfigError::Invalid {
+                setting: "cluster_tls_ca_path",
+                ..
+            })
+        ));
+        let config = Config::load_from(
+            tls_cluster
+                .into_iter()
+                .chain(["--cluster-tls-ca", "ca.pem"]),
+        )
+        .unwrap();
+        assert_eq!(config.cluster_tls_ca_path, Some(PathBuf::from("ca.pem")));
+        for origin in ["http://origin/items", "https://origin/{key}", "origin/{key}"] {
+            assert!(matches!(
+                Config::load_from(["mini_redis", "--origin-url", origin]),
+                Err(ConfigError::Invalid {
+                    setting: "origin_url",
+                    ..
+                })
+            ));
+        }
+        assert!(Config::load_from(["mini_redis", "--origin-url", "http://origin:8080/items/{key}"])
+            .is_ok());
+        for databases in ["a/b=10", "0", "x,x", "x=0"] {
+            assert!(matches!(
+                Config::load_from(["mini_redis", "--databases", databases]),
+                Err(ConfigError::Invalid {
+                    setting: "databases",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_cluster_nodes_from_file() {
+        let path = config_file(
+            "cluster",
+            r#"
+            cluster_node_id = "b"
+            cluster_node_timeout = 5000
+            cluster_meet = ["10.0.0.3:13000"]
+
+            [[cluster_nodes]]
+            id = "a"
+            address = "10.0.0.1:3000"
+            slots = ["0-8191"]
+
+            [[cluster_nodes]]
+            id = "b"
+            address = "10.0.0.2:3000"
+            slots = ["8192-16383"]
+            "#,
+        );
+
+        let config = Config::load_from(["mini_redis", "--config", path.to_str().unwrap()]).unwrap();
+        let cluster = config.cluster().unwrap().unwrap();
+        assert_eq!(cluster.myself().address, "10.0.0.2:3000");
+        assert!(cluster.is_complete());
+        assert!(Config::default().cluster().is_none());
+
+        assert_eq!(cluster.node_timeout(), Duration::from_millis(5000));
+        let buses: Vec<String> = cluster
+            .node_statuses()
+            .into_iter()
+            .map(|status| status.bus_address)
+            .collect();
+        assert_eq!(buses, vec!["10.0.0.1:13000", "10.0.0.2:13000"]);
+        assert!(cluster.gossip_targets().contains(&"10.0.0.3:13000".to_string()));
+
+        let mut invalid = config.clone();
+        invalid.server_port = 60_000;
+        assert!(invalid.validate().is_err());
+        invalid.cluster_bus_port = Some(7000);
+        assert!(invalid.validate().is_ok());
+        invalid.cluster_meet = vec!["nowhere".to_string()];
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_invalid_config_file() {
+        let path = config_file("unknown", "max_entires = 10\n");
+        assert!(matches!(
+            Config::load_from(["mini_redis", "--config", path.to_str().unwrap()]),
+            Err(ConfigError::Parse { .. })
+        ));
+
+        assert!(matches!(
+            Config::load_from(["mini_redis", "--config", "/nonexistent/mini_redis.toml"]),
+            Err(ConfigError::Read { .. })
+        ));
+    }
+}
@@ -3,7 +3,7 @@
 //! Provides unified error handling using thiserror.
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -26,10 +26,50 @@ pub enum CacheError {
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
+    /// Missing or invalid credentials
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// Authenticated but not permitted
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// Write sent to a read-only follower
+    #[error("Read-only follower: {0}")]
+    ReadOnly(String),
+
+    /// Key belongs to a slot served by another cluster node
+    #[error("MOVED {slot} {address}")]
+    Moved {
+        slot: u16,
+        address: String,
+        location: String,
+    },
+
+    /// Key is in a slot being migrated and is no longer on this node
+    #[error("ASK {slot} {address}")]
+    Ask {
+        slot: u16,
+        address: String,
+        location: String,
+    },
+
+    /// Key belongs to a slot no cluster node serves
+    #[error("Cluster down: {0}")]
+    ClusterDown(String),
+
+    /// Client exceeded its request rate
+    #[error("Rate limit exceeded, retry after {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+
     /// Cache is full and eviction failed
     #[error("Cache full: {0}")]
     CacheFull(String),
 
+    /// A loader or origin failed to fetch or write a value
+    #[error("Load failed: {0}")]
+    Load(String),
+
     /// Internal server error
     #[error("Internal error: {0}")]
     Internal(String),
@@ -42,15 +82,49 @@ impl IntoResponse for CacheError {
             CacheError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             CacheError::Expired(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             CacheError::InvalidRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            CacheError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
+            CacheError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg.clone()),
+            CacheError::ReadOnly(_) => (StatusCode::FORBIDDEN, self.to_string()),
+            CacheError::Moved { .. } | CacheError::Ask { .. } => {
+                (StatusCode::TEMPORARY_REDIRECT, self.to_string())
+            }
+            CacheError::ClusterDown(_) => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+            CacheError::RateLimited { .. } => (StatusCode::TOO_MANY_REQUESTS, self.to_string()),
             CacheError::CacheFull(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg.clone()),
+            CacheError::Load(_) => (StatusCode::BAD_GATEWAY, self.to_string()),
             CacheError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": message
-        }));
+        });
+        if let CacheError::Moved { slot, address, .. } | CacheError::Ask { slot, address, .. } =
+            &self
+        {
+            body["slot"] = json!(slot);
+            body["address"] = json!(address);
+        }
 
-        (status, body).into_response()
+        let mut response = (status, Json(body)).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        match self {
+            CacheError::RateLimited { retry_after_secs } => {
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+            }
+            CacheError::Moved { location, .. } | CacheError::Ask { location, .. } => {
+                if let Ok(location) = HeaderValue::try_from(location) {
+                    response.headers_mut().insert(header::LOCATION, location);
+                }
+            }
+            _ => {}
+        }
+        response
     }
 }
 
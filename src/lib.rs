@@ -2,12 +2,22 @@
 //!
 //! Provides Redis-like functionality with TTL expiration and LRU eviction.
 
+pub mod acl;
 pub mod api;
 pub mod cache;
+pub mod client;
+pub mod cluster;
 pub mod config;
 pub mod error;
+pub mod listener;
 pub mod models;
+pub mod monitor;
+pub mod origin;
+pub mod ratelimit;
+mod random;
+pub mod replication;
 pub mod tasks;
+pub mod tls;
 
 pub use api::AppState;
 pub use config::Config;
@@ -0,0 +1,317 @@
+//! Listeners
+//!
+//! Binds the TCP and Unix domain socket listeners the API is served on, and
+//! serves the router over plain TCP and Unix domain sockets, counting open
+//! connections. TCP listeners are served with `tls::serve_tls` instead when
+//! TLS is configured.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ConnectInfo;
+use axum::Router;
+use hyper::body::Incoming;
+use hyper::Request;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+use crate::monitor::ServerMetrics;
+
+#[cfg(unix)]
+pub use self::unix::{bind_unix, serve_unix, UnixSocket};
+
+/// Pause after a failed accept (e.g. file descriptor exhaustion)
+pub(crate) const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Pending connection queue length, matching the standard library
+const BACKLOG: i32 = 1024;
+
+// == TCP ==
+/// Binds a TCP listener on `addr`.
+///
+/// IPv6 listeners only accept IPv6 connections, so `::` and `0.0.0.0` can
+/// be bound side by side on the same port.
+pub fn bind_tcp(addr: SocketAddr) -> io::Result<TcpListener> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(BACKLOG)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// Serves `app` over plain HTTP on `listener` until `shutdown` completes.
+///
+/// Like `axum::serve`, in-flight connections are allowed to finish after
+/// shutdown and the peer address is available to handlers as
+/// `ConnectInfo<SocketAddr>`. Open connections are counted in `metrics`.
+pub async fn serve_tcp<F>(
+    listener: TcpListener,
+    app: Router,
+    metrics: Arc<ServerMetrics>,
+    shutdown: F,
+) -> io::Result<()>
+where
+    F: Future<Output = ()>,
+{
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(connection) => connection,
+                Err(e) => {
+                    warn!("Failed to accept connection: {}", e);
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let service =
+            TowerToHyperService::new(app.clone().map_request(move |mut req: Request<Incoming>| {
+                req.extensions_mut().insert(ConnectInfo(peer));
+                req
+            }));
+        let watcher = graceful.watcher();
+        let connected = metrics.connection_opened();
+
+        tokio::spawn(async move {
+            let _connected = connected;
+            let builder = Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            if let Err(e) = watcher.watch(connection.into_owned()).await {
+                debug!("Connection with {} closed with error: {}", peer, e);
+            }
+        });
+    }
+
+    info!("Waiting for open connections to finish");
+    graceful.shutdown().await;
+    Ok(())
+}
+
+// == Unix Domain Socket ==
+#[cfg(unix)]
+mod unix {
+    use std::ffi::OsString;
+    use std::fs::{self, Permissions};
+    use std::future::Future;
+    use std::io;
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use axum::Router;
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto::Builder;
+    use hyper_util::server::graceful::GracefulShutdown;
+    use hyper_util::service::TowerToHyperService;
+    use tokio::net::UnixListener;
+    use tracing::{debug, info, warn};
+
+    use super::ACCEPT_ERROR_BACKOFF;
+    use crate::monitor::ServerMetrics;
+
+    /// A Unix domain socket bound by `bind_unix`, to be served with
+    /// `serve_unix`.
+    #[derive(Debug)]
+    pub struct UnixSocket {
+        listener: UnixListener,
+        path: PathBuf,
+    }
+
+    impl UnixSocket {
+        /// Returns the path of the socket file.
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    /// Binds a Unix domain socket at `path` with permissions `mode`.
+    ///
+    /// A socket file left behind by a previous run is replaced, but not one
+    /// another server is still accepting on, and never a non-socket file.
+    /// The socket is bound inside a private directory and moved to `path`
+    /// once it has its permissions, so it is never reachable with looser
+    /// ones.
+    pub fn bind_unix(path: &Path, mode: u32) -> io::Result<UnixSocket> {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        format!("{} is in use by another process", path.display()),
+                    ));
+                }
+                fs::remove_file(path)?;
+            }
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let file_name = path.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a file path", path.display()),
+            )
+        })?;
+        let mut private = OsString::from(".");
+        private.push(file_name);
+        private.push(format!(".{}", std::process::id()));
+        let private = path.with_file_name(private);
+        fs::DirBuilder::new().mode(0o700).create(&private)?;
+
+        let staged = private.join("sock");
+        let bound = UnixListener::bind(&staged).and_then(|listener| {
+            fs::set_permissions(&staged, Permissions::from_mode(mode))?;
+            fs::rename(&staged, path)?;
+            Ok(listener)
+        });
+        let _ = fs::remove_file(&staged);
+        let cleaned = fs::remove_dir(&private);
+        let listener = bound?;
+        cleaned?;
+
+        Ok(UnixSocket {
+            listener,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Serves `app` on a Unix domain socket until `shutdown` completes.
+    ///
+    /// In-flight connections are allowed to finish, then the socket file
+    /// is removed. Requests carry no `ConnectInfo`, so rate limits apply
+    /// to Unix socket clients as a single client unless they authenticate
+    /// as an ACL user. Open connections are counted in `metrics`.
+    pub async fn serve_unix<F>(
+        socket: UnixSocket,
+        app: Router,
+        metrics: Arc<ServerMetrics>,
+        shutdown: F,
+    ) -> io::Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        let UnixSocket { listener, path } = socket;
+        let graceful = GracefulShutdown::new();
+        tokio::pin!(shutdown);
+
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("Failed to accept Unix socket connection: {}", e);
+                        tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                        continue;
+                    }
+                },
+                _ = &mut shutdown => break,
+            };
+
+            let service = TowerToHyperService::new(app.clone());
+            let watcher = graceful.watcher();
+            let connected = metrics.connection_opened();
+
+            tokio::spawn(async move {
+                let _connected = connected;
+                let builder = Builder::new(TokioExecutor::new());
+                let connection =
+                    builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+                if let Err(e) = watcher.watch(connection.into_owned()).await {
+                    debug!("Unix socket connection closed with error: {}", e);
+                }
+            });
+        }
+
+        info!("Waiting for open Unix socket connections to finish");
+        graceful.shutdown().await;
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[tokio::test]
+    async fn test_bind_ipv4_and_ipv6_on_same_port() {
+        let v4 = bind_tcp(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))).unwrap();
+        let port = v4.local_addr().unwrap().port();
+
+        // Hosts without IPv6 cannot run the second half
+        match bind_tcp(SocketAddr::from((Ipv6Addr::UNSPECIFIED, port))) {
+            Ok(v6) => assert_eq!(v6.local_addr().unwrap().port(), port),
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::AddrNotAvailable),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_unix_replaces_stale_socket_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path =
+            std::env::temp_dir().join(format!("mini_redis_bind_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let listener = bind_unix(&path, 0o600).unwrap();
+        assert_eq!(listener.path(), path);
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // The private directory it was bound in is gone
+        let private = format!(".mini_redis_bind_{}.sock.", std::process::id());
+        let leftovers = std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with(&private)
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        // Still accepting: refuse to take it over
+        let err = bind_unix(&path, 0o600).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        // Left behind by a stopped server: replace it
+        drop(listener);
+        let listener = bind_unix(&path, 0o660).unwrap();
+        drop(listener);
+        std::fs::remove_file(&path).unwrap();
+
+        // Never replace regular files
+        std::fs::write(&path, "data").unwrap();
+        let err = bind_unix(&path, 0o600).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -5,86 +5,265 @@
 //! # Requirements
 //! - Validates: Requirements 4.1, 8.4
 
-mod api;
-mod cache;
-mod config;
-mod error;
-mod models;
-mod tasks;
-
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use tokio::signal;
-use tracing::{info, warn};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use api::{create_router, AppState};
-use config::Config;
-use tasks::spawn_cleanup_task;
+use mini_redis::api::create_router;
+use mini_redis::cluster::{bus_router, spawn_gossip_task};
+use mini_redis::config::{ConfigError, LogFormat};
+use mini_redis::listener::{bind_tcp, serve_tcp};
+#[cfg(unix)]
+use mini_redis::listener::{bind_unix, serve_unix};
+use mini_redis::replication::spawn_follower_task;
+use mini_redis::tasks::spawn_cleanup_task_with_interval;
+use mini_redis::tls::{client_config, serve_tls, ReloadableTlsConfig};
+use mini_redis::{AppState, Config};
 
 /// Main entry point for the Mini Redis cache server.
 ///
 /// # Startup Sequence
-/// 1. Initialize tracing subscriber for logging
-/// 2. Load configuration from environment variables
+/// 1. Load configuration from flags, environment variables and config file
+/// 2. Initialize tracing subscriber for logging
 /// 3. Create cache store with configured parameters
-/// 4. Start background TTL cleanup task
+/// 4. Start background TTL cleanup tasks, replication on followers and
+///    gossip in cluster mode
 /// 5. Create Axum router with all endpoints
-/// 6. Start HTTP server on configured port
-/// 7. Handle graceful shutdown on SIGINT/SIGTERM
+/// 6. Start HTTP (or HTTPS, when TLS is configured) servers on each bind
+///    address, and on the Unix domain socket when configured; in cluster
+///    mode, start the cluster bus on each bind address
+/// 7. Handle graceful shutdown on SIGINT/SIGTERM, TLS reload on SIGHUP
 ///
 /// # Requirements
 /// - Validates: Requirements 4.1, 8.4
 #[tokio::main]
 async fn main() {
+    // Load configuration; invalid settings abort startup
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(ConfigError::Args(e)) => e.exit(),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Initialize tracing subscriber with env filter
     // Defaults to "info" level, can be overridden with RUST_LOG env var
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "mini_redis=info,tower_http=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "mini_redis=info,tower_http=info".into());
+    let registry = tracing_subscriber::registry().with(filter);
+    match config.log_format {
+        LogFormat::Text => registry.with(tracing_subscriber::fmt::layer()).init(),
+        LogFormat::Json => registry
+            .with(tracing_subscriber::fmt::layer().json())
+            .init(),
+    }
 
     info!("Starting Mini Redis Cache Server");
-
-    // Load configuration from environment variables
-    let config = Config::from_env();
+    if let Some(path) = &config.config_file {
+        info!("Configuration file: {}", path.display());
+    }
     info!(
         "Configuration loaded: max_entries={}, default_ttl={}s, port={}, cleanup_interval={}s",
         config.max_entries, config.default_ttl, config.server_port, config.cleanup_interval
     );
+    if config.auth_enabled() {
+        info!(
+            "Authentication enabled with {} token(s), /health exempt: {}",
+            config.auth_tokens.len(),
+            config.auth_exempt_health
+        );
+    } else {
+        warn!("Authentication disabled: set AUTH_TOKENS before exposing the server");
+    }
 
     // Create application state with cache store
-    let state = AppState::from_config(&config);
+    let mut state = AppState::from_config(&config);
     info!("Cache store initialized");
 
-    // Start background cleanup task
-    let cleanup_handle = spawn_cleanup_task(state.cache.clone(), config.cleanup_interval);
+    if state.databases.len() > 1 {
+        info!("{} databases configured", state.databases.len());
+    }
+    if let Some(cluster) = &state.cluster {
+        info!(
+            "Cluster mode: node '{}' serves {} of {} assigned slots across {} nodes",
+            cluster.myself().id,
+            cluster.slots_served(),
+            cluster.slots_assigned(),
+            cluster.nodes().len()
+        );
+        if !cluster.is_complete() {
+            warn!("Not every hash slot is assigned; keys in unassigned slots are rejected");
+        }
+    }
+
+    // Slot migrations reach nodes serving TLS with the cluster CA
+    state.cluster_tls = config.cluster_tls_ca_path.as_ref().map(|ca_path| {
+        let tls = client_config(ca_path, config.tls_settings().as_ref())
+            .unwrap_or_else(|e| panic!("Failed to load cluster TLS configuration: {}", e));
+        Arc::new(tls)
+    });
+
+    // Start a background cleanup task per database
+    let mut background_handles: Vec<_> = state
+        .databases
+        .iter()
+        .map(|(_, store)| {
+            spawn_cleanup_task_with_interval(store.clone(), state.cleanup_interval.subscribe())
+        })
+        .collect();
     info!("Background cleanup task started");
 
+    // Followers replicate from their leader until shutdown
+    if let Some(follower) = &state.follower {
+        let tls = config.replica_tls_ca_path.as_ref().map(|ca_path| {
+            let tls = client_config(ca_path, config.tls_settings().as_ref())
+                .unwrap_or_else(|e| panic!("Failed to load replica TLS configuration: {}", e));
+            Arc::new(tls)
+        });
+        background_handles.push(spawn_follower_task(
+            follower.clone(),
+            state.databases.clone(),
+            config.replica_auth_token.clone(),
+            tls,
+        ));
+    }
+    let replication = state.replication.clone();
+
+    // Cluster nodes gossip until shutdown; a follower stops replicating
+    // once it takes over from a failed leader
+    if let Some(cluster) = &state.cluster {
+        background_handles.push(spawn_gossip_task(
+            cluster.clone(),
+            state.follower.clone(),
+            config.cluster_auth_token.clone(),
+        ));
+    }
+    let bus = state
+        .cluster
+        .clone()
+        .map(|cluster| bus_router(cluster, config.cluster_auth_token.clone()));
+
     // Create router with all endpoints
+    let metrics = state.metrics.clone();
     let app = create_router(state);
 
-    // Bind to configured port
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.server_port));
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    info!("Server listening on http://{}", addr);
+    // Broadcast the shutdown signal to every listener
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal(background_handles).await;
+        replication.disconnect_followers();
+        let _ = shutdown_tx.send(true);
+    });
 
-    // Start server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal(cleanup_handle))
-        .await
-        .unwrap();
+    let tls = config.tls_settings().map(|settings| {
+        let tls = ReloadableTlsConfig::new(settings)
+            .unwrap_or_else(|e| panic!("Failed to load TLS configuration: {}", e));
+        let tls = Arc::new(tls);
+        if tls.settings().client_ca_path.is_some() {
+            info!("Client certificate verification enabled (mutual TLS)");
+        }
+        #[cfg(unix)]
+        mini_redis::tasks::spawn_tls_reload_task(tls.clone());
+        tls
+    });
+
+    // Bind to each configured address and start its server
+    let mut servers = JoinSet::new();
+    for ip in &config.bind_addresses {
+        let addr = SocketAddr::new(*ip, config.server_port);
+        let listener =
+            bind_tcp(addr).unwrap_or_else(|e| panic!("Failed to bind {}: {}", addr, e));
+        let shutdown = shutdown_requested(shutdown_rx.clone());
+
+        match &tls {
+            Some(tls) => {
+                info!("Server listening on https://{}", addr);
+                servers.spawn(serve_tls(
+                    listener,
+                    app.clone(),
+                    tls.clone(),
+                    metrics.clone(),
+                    shutdown,
+                ));
+            }
+            None => {
+                info!("Server listening on http://{}", addr);
+                servers.spawn(serve_tcp(listener, app.clone(), metrics.clone(), shutdown));
+            }
+        }
+    }
+
+    // Serve the cluster bus on its own port of each bind address
+    if let (Some(bus), Some(port)) = (&bus, config.cluster_bus_port()) {
+        for ip in &config.bind_addresses {
+            let addr = SocketAddr::new(*ip, port);
+            let listener =
+                bind_tcp(addr).unwrap_or_else(|e| panic!("Failed to bind {}: {}", addr, e));
+            info!("Cluster bus listening on {}", addr);
+            let bus = bus.clone();
+            let shutdown = shutdown_requested(shutdown_rx.clone());
+            servers.spawn(async move {
+                axum::serve(listener, bus)
+                    .with_graceful_shutdown(shutdown)
+                    .await
+            });
+        }
+    }
+
+    // Serve plain HTTP on the Unix domain socket for local clients
+    if let Some(path) = &config.unix_socket {
+        #[cfg(unix)]
+        {
+            let listener = bind_unix(path, config.unix_socket_mode)
+                .unwrap_or_else(|e| panic!("Failed to bind {}: {}", path.display(), e));
+            info!(
+                "Server listening on unix:{} (mode {:o})",
+                path.display(),
+                config.unix_socket_mode
+            );
+            servers.spawn(serve_unix(
+                listener,
+                app.clone(),
+                metrics.clone(),
+                shutdown_requested(shutdown_rx.clone()),
+            ));
+        }
+        #[cfg(not(unix))]
+        warn!(
+            "Unix domain sockets are not supported on this platform, ignoring {}",
+            path.display()
+        );
+    }
+
+    // Run until every server has shut down
+    while let Some(result) = servers.join_next().await {
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Server error: {}", e),
+            Err(e) => error!("Server task failed: {}", e),
+        }
+    }
 
     info!("Server shutdown complete");
 }
 
+/// Resolves once the shutdown signal has been broadcast on `rx`.
+async fn shutdown_requested(mut rx: watch::Receiver<bool>) {
+    let _ = rx.wait_for(|&stop| stop).await;
+}
+
 /// Waits for shutdown signal (Ctrl+C or SIGTERM).
 ///
-/// On shutdown signal, aborts the cleanup task and allows graceful shutdown.
-async fn shutdown_signal(cleanup_handle: tokio::task::JoinHandle<()>) {
+/// On shutdown signal, aborts the cleanup, replication and gossip tasks and allows
+/// graceful shutdown.
+async fn shutdown_signal(background_handles: Vec<tokio::task::JoinHandle<()>>) {
     let ctrl_c = async {
         signal::ctrl_c()
             .await
@@ -111,7 +290,9 @@ async fn shutdown_signal(cleanup_handle: tokio::task::JoinHandle<()>) {
         }
     }
 
-    // Abort the cleanup task
-    cleanup_handle.abort();
-    warn!("Cleanup task aborted");
+    // Abort the cleanup, replication and gossip tasks
+    for handle in background_handles {
+        handle.abort();
+    }
+    warn!("Background tasks aborted");
 }
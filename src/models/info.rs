@@ -0,0 +1,146 @@
+//! INFO report model
+//!
+//! Sectioned key/value report returned by GET /info, renderable as JSON or
+//! in the Redis INFO text format.
+
+use std::borrow::Cow;
+
+use serde_json::{Map, Value};
+
+// == Info Section ==
+/// A named group of INFO fields.
+#[derive(Debug, Clone)]
+pub struct InfoSection {
+    /// Section name in lowercase (e.g. "server")
+    pub name: &'static str,
+    /// Fields in insertion order
+    pub fields: Vec<(Cow<'static, str>, Value)>,
+}
+
+impl InfoSection {
+    /// Creates an empty section.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Appends a field to the section.
+    pub fn field(mut self, name: impl Into<Cow<'static, str>>, value: impl Into<Value>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+}
+
+// == Info Report ==
+/// Full INFO report made of ordered sections.
+#[derive(Debug, Clone, Default)]
+pub struct InfoReport {
+    /// Sections in display order
+    pub sections: Vec<InfoSection>,
+}
+
+impl InfoReport {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a section to the report.
+    pub fn push(&mut self, section: InfoSection) {
+        self.sections.push(section);
+    }
+
+    /// Renders the report as a JSON object of section objects.
+    pub fn to_json(&self) -> Value {
+        let sections = self
+            .sections
+            .iter()
+            .map(|section| {
+                let fields: Map<String, Value> = section
+                    .fields
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.clone()))
+                    .collect();
+                (section.name.to_string(), Value::Object(fields))
+            })
+            .collect();
+        Value::Object(sections)
+    }
+
+    /// Renders the report in the Redis INFO text format.
+    ///
+    /// Each section starts with a `# Name` header followed by `field:value`
+    /// lines, all CRLF terminated, with a blank line between sections.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for (i, section) in self.sections.iter().enumerate() {
+            if i > 0 {
+                out.push_str("\r\n");
+            }
+            out.push_str("# ");
+            out.push_str(&capitalize(section.name));
+            out.push_str("\r\n");
+            for (name, value) in &section.fields {
+                out.push_str(name);
+                out.push(':');
+                out.push_str(&text_value(value));
+                out.push_str("\r\n");
+            }
+        }
+        out
+    }
+}
+
+/// Formats a JSON value the way Redis INFO prints it.
+fn text_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(b) => if *b { "1" } else { "0" }.to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Uppercases the first character of a section name.
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_report() -> InfoReport {
+        let mut report = InfoReport::new();
+        report.push(
+            InfoSection::new("server")
+                .field("version", "0.1.0")
+                .field("uptime_in_seconds", 42),
+        );
+        report.push(InfoSection::new("persistence").field("enabled", false));
+        report
+    }
+
+    #[test]
+    fn test_info_report_text_format() {
+        let text = sample_report().to_text();
+        assert_eq!(
+            text,
+            "# Server\r\nversion:0.1.0\r\nuptime_in_seconds:42\r\n\r\n# Persistence\r\nenabled:0\r\n"
+        );
+    }
+
+    #[test]
+    fn test_info_report_json_format() {
+        let json = sample_report().to_json();
+        assert_eq!(json["server"]["version"], "0.1.0");
+        assert_eq!(json["server"]["uptime_in_seconds"], 42);
+        assert_eq!(json["persistence"]["enabled"], false);
+    }
+}
@@ -3,11 +3,21 @@
 //! This module defines the DTOs (Data Transfer Objects) used for
 //! serializing/deserializing HTTP request and response bodies.
 
+pub mod info;
 pub mod requests;
 pub mod responses;
 
 // Re-export commonly used types
-pub use requests::SetRequest;
+pub use info::{InfoReport, InfoSection};
+pub use requests::{
+    ConfigPatchRequest, CountQuery, FlushQuery, InfoQuery, KeyPath, MigrateRequest, MoveRequest,
+    RestoreEntry, RestoreKey, RestoreRequest, ScanQuery, SetRequest, SetSlotRequest, SetSlotState,
+    SwapDbRequest, SyncQuery,
+};
 pub use responses::{
-    DeleteResponse, ErrorResponse, GetResponse, HealthResponse, SetResponse, StatsResponse,
+    BigKeysResponse, ClusterNodeInfo, ClusterNodesResponse, ClusterSlotRange, ClusterSlotsResponse,
+    ConfigResponse, ConfigRewriteResponse, DatabaseInfo, DatabasesResponse, DeleteResponse,
+    ErrorResponse, FlushResponse, GetResponse, HealthResponse, HotKeysResponse, KeySlotResponse,
+    MigrationsResponse, MoveResponse, RestoreResponse, ScanResponse, SetResponse, SetSlotResponse,
+    SlowLogResetResponse, SlowLogResponse, StatsResponse, SwapDbResponse, TtlResponse,
 };
@@ -2,7 +2,10 @@
 //!
 //! Defines the structure of incoming HTTP request bodies.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::MAX_GRACE;
+use crate::cluster::SlotRange;
 
 /// Request body for the SET operation (PUT /set)
 ///
@@ -10,21 +13,62 @@ use serde::Deserialize;
 /// - `key`: The cache key to store the value under
 /// - `value`: The value to store
 /// - `ttl`: Optional TTL in seconds (uses default if not specified)
+/// - `grace`: Optional seconds the value is still served as stale once it
+///   expires
+/// - `compute_ms`: Optional milliseconds the value took to compute, to
+///   refresh it early
 ///
 /// # Requirements
 /// - Validates: Requirement 4.2
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SetRequest {
     /// The cache key
     pub key: String,
     /// The value to store
     pub value: String,
     /// Optional TTL in seconds
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub ttl: Option<u64>,
+    /// Optional stale-grace window in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grace: Option<u64>,
+    /// Optional milliseconds the value took to compute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute_ms: Option<u64>,
 }
 
 impl SetRequest {
+    /// Creates a request storing `value` under `key` with the default TTL
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            ttl: None,
+            grace: None,
+            compute_ms: None,
+        }
+    }
+
+    /// Sets the TTL in seconds
+    pub fn with_ttl(mut self, ttl: u64) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Keeps serving the value for `grace` seconds after it expires,
+    /// flagged as stale
+    pub fn with_grace(mut self, grace: u64) -> Self {
+        self.grace = Some(grace);
+        self
+    }
+
+    /// Records how long the value took to compute, so readers are asked
+    /// at random to refresh it shortly before it expires
+    pub fn with_compute_ms(mut self, compute_ms: u64) -> Self {
+        self.compute_ms = Some(compute_ms);
+        self
+    }
+
     /// Validates the request data
     ///
     /// Returns an error message if validation fails, None if valid.
@@ -35,6 +79,276 @@ impl SetRequest {
         if self.key.len() > 256 {
             return Some("Key exceeds maximum length of 256 characters".to_string());
         }
+        if self.grace.is_some_and(|grace| grace > MAX_GRACE) {
+            return Some(format!("Grace exceeds maximum of {} seconds", MAX_GRACE));
+        }
+        None
+    }
+}
+
+/// Query parameters for list endpoints (GET /admin/slowlog, /admin/hotkeys, /admin/bigkeys)
+///
+/// # Fields
+/// - `count`: Maximum number of entries to return (default: 10)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CountQuery {
+    /// Maximum number of entries to return
+    #[serde(default)]
+    pub count: Option<usize>,
+}
+
+impl CountQuery {
+    /// Default number of entries returned, as in Redis `SLOWLOG GET`
+    pub const DEFAULT_COUNT: usize = 10;
+
+    /// Returns the requested count, or the default if not specified
+    pub fn count(&self) -> usize {
+        self.count.unwrap_or(Self::DEFAULT_COUNT)
+    }
+}
+
+/// Query parameters for iterating over keys (GET /scan, /db/:db/scan)
+///
+/// # Fields
+/// - `cursor`: Where to continue, from the previous page (default: 0, the start)
+/// - `match`: Glob pattern keys must match (default: all keys)
+/// - `count`: Keys to look at in this page (default: 10)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScanQuery {
+    /// Cursor returned by the previous page, 0 to start
+    #[serde(default)]
+    pub cursor: u64,
+    /// Glob pattern keys must match
+    #[serde(default, rename = "match")]
+    pub pattern: Option<String>,
+    /// Number of keys to look at
+    #[serde(default)]
+    pub count: Option<usize>,
+}
+
+impl ScanQuery {
+    /// Default number of keys looked at per page, as in Redis `SCAN`
+    pub const DEFAULT_COUNT: usize = 10;
+
+    /// Returns the requested count, or the default if not specified
+    pub fn count(&self) -> usize {
+        self.count.unwrap_or(Self::DEFAULT_COUNT)
+    }
+}
+
+/// Path parameters of routes taking a key (`/get/:key`, `/db/:db/get/:key`, ...)
+///
+/// Deserialized by name so the optional `:db` segment is ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyPath {
+    /// The cache key
+    pub key: String,
+}
+
+impl KeyPath {
+    /// Creates a KeyPath for `key`
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { key: key.into() }
+    }
+}
+
+/// Request body for moving a key between databases (POST /db/:db/move/:key)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MoveRequest {
+    /// Database to move the key to
+    pub db: String,
+}
+
+/// Request body for swapping two databases (POST /admin/swapdb)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwapDbRequest {
+    /// First database
+    pub db1: String,
+    /// Second database
+    pub db2: String,
+}
+
+/// Query parameters for the INFO endpoint (GET /info)
+///
+/// # Fields
+/// - `section`: Single section to return (default: all sections)
+/// - `format`: `json` (default) or `text` for the Redis `key:value` format
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InfoQuery {
+    /// Section to return
+    #[serde(default)]
+    pub section: Option<String>,
+    /// Output format
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// Query parameters for flushing (POST /admin/flush, /db/:db/flush)
+///
+/// # Fields
+/// - `reset_stats`: Also reset hit, miss and eviction counters (default: false)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FlushQuery {
+    /// Reset the statistics along with the entries
+    #[serde(default)]
+    pub reset_stats: bool,
+}
+
+/// Query parameters of the replication stream (GET /admin/replication/sync)
+///
+/// # Fields
+/// - `replid`, `offset`: Position of a follower resuming after a disconnect
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SyncQuery {
+    /// Replication id the follower last synced with
+    #[serde(default)]
+    pub replid: Option<String>,
+    /// Offset of the last command the follower applied
+    #[serde(default)]
+    pub offset: Option<u64>,
+}
+
+/// Slot change requested from POST /admin/cluster/setslot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SetSlotState {
+    /// This node's slots are being moved to `node`
+    Migrating,
+    /// The slots of `node` are being moved to this node
+    Importing,
+    /// `node` serves the slots
+    Node,
+    /// Cancels a migration
+    Stable,
+}
+
+/// Request body for changing slot assignments (POST /admin/cluster/setslot)
+///
+/// # Fields
+/// - `slots`: Slot ranges to change, e.g. `["0-99", "200"]`
+/// - `state`: `migrating`, `importing`, `node` or `stable`
+/// - `node`: Node id, required unless `state` is `stable`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SetSlotRequest {
+    /// Slot ranges to change
+    pub slots: Vec<SlotRange>,
+    /// New state of the slots
+    pub state: SetSlotState,
+    /// Node the state refers to
+    #[serde(default)]
+    pub node: Option<String>,
+}
+
+/// A migrated key stored by POST /admin/cluster/restore
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestoreEntry {
+    /// Database holding the key
+    pub db: String,
+    /// The cache key
+    pub key: String,
+    /// The stored value
+    pub value: String,
+    /// Remaining TTL in milliseconds, None if the key never expires
+    #[serde(default)]
+    pub ttl_ms: Option<u64>,
+    /// Stale-grace window after expiry in milliseconds
+    #[serde(default)]
+    pub grace_ms: Option<u64>,
+    /// Milliseconds the value took to compute
+    #[serde(default)]
+    pub compute_ms: Option<u64>,
+}
+
+/// A key removed by POST /admin/cluster/restore
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestoreKey {
+    /// Database holding the key
+    pub db: String,
+    /// The cache key
+    pub key: String,
+    /// Value the key was sent with; if given, the key is only deleted while
+    /// it still holds this value, so a newer write is kept
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+/// Request body for receiving migrated keys (POST /admin/cluster/restore)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestoreRequest {
+    /// Keys to store, replacing any current value
+    #[serde(default)]
+    pub entries: Vec<RestoreEntry>,
+    /// Only store entries whose key is absent here, keeping values written
+    /// since the slot was handed over
+    #[serde(default)]
+    pub keep_existing: bool,
+    /// Keys deleted on the sending node after they were sent
+    #[serde(default)]
+    pub deleted: Vec<RestoreKey>,
+}
+
+/// Request body for migrating slots to another node (POST /admin/cluster/migrate)
+///
+/// # Fields
+/// - `target`: Id of the node receiving the slots
+/// - `slots`: Slot ranges to migrate, all served by this node
+/// - `batch_size`: Keys sent per request (default: 100)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MigrateRequest {
+    /// Id of the receiving node
+    pub target: String,
+    /// Slot ranges to migrate
+    pub slots: Vec<SlotRange>,
+    /// Keys sent per request
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+}
+
+/// Request body for changing settings at runtime (PATCH /admin/config)
+///
+/// Only the settings present are changed. Settings that need a restart
+/// (port, TLS, auth) are rejected as unknown fields.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigPatchRequest {
+    /// New cache capacity; shrinking evicts least recently used entries
+    pub max_entries: Option<usize>,
+    /// New default TTL in seconds for entries set from now on
+    pub default_ttl: Option<u64>,
+    /// New cleanup task interval in seconds
+    pub cleanup_interval: Option<u64>,
+    /// New slow log threshold in microseconds
+    pub slowlog_threshold_us: Option<u64>,
+    /// New slow log capacity (0 disables logging)
+    pub slowlog_max_len: Option<usize>,
+}
+
+impl ConfigPatchRequest {
+    /// Validates the request data
+    ///
+    /// Returns an error message if validation fails, None if valid.
+    pub fn validate(&self) -> Option<String> {
+        if self.max_entries == Some(0) {
+            return Some("max_entries must be at least 1".to_string());
+        }
+        if self.cleanup_interval == Some(0) {
+            return Some("cleanup_interval must be at least 1 second".to_string());
+        }
+        if self.max_entries.is_none()
+            && self.default_ttl.is_none()
+            && self.cleanup_interval.is_none()
+            && self.slowlog_threshold_us.is_none()
+            && self.slowlog_max_len.is_none()
+        {
+            return Some("No settings to change".to_string());
+        }
         None
     }
 }
@@ -65,6 +379,8 @@ mod tests {
             key: "".to_string(),
             value: "test".to_string(),
             ttl: None,
+            grace: None,
+            compute_ms: None,
         };
         assert!(req.validate().is_some());
     }
@@ -75,7 +391,38 @@ mod tests {
             key: "valid_key".to_string(),
             value: "test".to_string(),
             ttl: Some(60),
+            grace: None,
+            compute_ms: None,
         };
         assert!(req.validate().is_none());
     }
+
+    #[test]
+    fn test_validate_grace() {
+        let req = SetRequest::new("key", "value").with_grace(MAX_GRACE);
+        assert!(req.validate().is_none());
+        let req = SetRequest::new("key", "value").with_grace(u64::MAX);
+        assert!(req.validate().unwrap().contains("Grace"));
+    }
+
+    #[test]
+    fn test_count_query_default_count() {
+        let query: CountQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query.count(), CountQuery::DEFAULT_COUNT);
+
+        let query: CountQuery = serde_json::from_str(r#"{"count": 3}"#).unwrap();
+        assert_eq!(query.count(), 3);
+    }
+
+    #[test]
+    fn test_config_patch_validation() {
+        let patch: ConfigPatchRequest = serde_json::from_str(r#"{"max_entries": 10}"#).unwrap();
+        assert!(patch.validate().is_none());
+
+        let patch: ConfigPatchRequest = serde_json::from_str(r#"{"max_entries": 0}"#).unwrap();
+        assert!(patch.validate().is_some());
+
+        assert!(ConfigPatchRequest::default().validate().is_some());
+        assert!(serde_json::from_str::<ConfigPatchRequest>(r#"{"server_port": 1}"#).is_err());
+    }
 }
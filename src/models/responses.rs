@@ -2,18 +2,31 @@
 //!
 //! Defines the structure of outgoing HTTP response bodies.
 
-use serde::Serialize;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{BigKey, HotKey};
+use crate::cluster::{ClusterEvent, MigrationStatus, NodeHealth};
+use crate::config::Tunables;
+use crate::monitor::SlowLogEntry;
 
 /// Response body for the GET operation (GET /get/:key)
 ///
 /// # Requirements
 /// - Validates: Requirement 4.3
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GetResponse {
     /// The requested key
     pub key: String,
     /// The stored value
     pub value: String,
+    /// The value expired and is served within its grace window
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+    /// The caller should recompute the value and set it again
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub refresh: bool,
 }
 
 impl GetResponse {
@@ -22,6 +35,8 @@ impl GetResponse {
         Self {
             key: key.into(),
             value: value.into(),
+            stale: false,
+            refresh: false,
         }
     }
 }
@@ -30,7 +45,7 @@ impl GetResponse {
 ///
 /// # Requirements
 /// - Validates: Requirement 4.2
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SetResponse {
     /// Success message
     pub message: String,
@@ -53,7 +68,7 @@ impl SetResponse {
 ///
 /// # Requirements
 /// - Validates: Requirement 4.4
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DeleteResponse {
     /// Success message
     pub message: String,
@@ -76,7 +91,7 @@ impl DeleteResponse {
 ///
 /// # Requirements
 /// - Validates: Requirement 4.5, 6.4
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StatsResponse {
     /// Number of cache hits
     pub hits: u64,
@@ -88,6 +103,10 @@ pub struct StatsResponse {
     pub total_entries: usize,
     /// Hit rate (hits / (hits + misses))
     pub hit_rate: f64,
+    /// Requests rejected by access control
+    pub acl_denials: u64,
+    /// Requests rejected by the rate limiter
+    pub rate_limited: u64,
 }
 
 impl StatsResponse {
@@ -105,15 +124,36 @@ impl StatsResponse {
             evictions,
             total_entries,
             hit_rate,
+            acl_denials: 0,
+            rate_limited: 0,
         }
     }
 }
 
+/// Response body for the TTL lookup (GET /ttl/:key)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TtlResponse {
+    /// The looked up key
+    pub key: String,
+    /// Remaining time to live in milliseconds, None if the key never expires
+    pub ttl_ms: Option<u64>,
+}
+
+/// Response body for key iteration (GET /scan)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScanResponse {
+    /// Cursor of the next page, "0" once every key was visited. A string,
+    /// since it can exceed the integers JSON parsers read exactly.
+    pub cursor: String,
+    /// Keys of this page matching the pattern
+    pub keys: Vec<String>,
+}
+
 /// Response body for the health endpoint (GET /health)
 ///
 /// # Requirements
 /// - Validates: Requirement 4.6
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HealthResponse {
     /// Health status (e.g., "healthy")
     pub status: String,
@@ -131,11 +171,267 @@ impl HealthResponse {
     }
 }
 
+/// Response body for the slow log endpoint (GET /admin/slowlog)
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowLogResponse {
+    /// Most recent slow operations, newest first
+    pub entries: Vec<SlowLogEntry>,
+    /// Total number of entries currently retained
+    pub len: usize,
+    /// Logging threshold in microseconds
+    pub threshold_us: u64,
+    /// Maximum number of retained entries
+    pub max_len: usize,
+}
+
+/// Response body for the slow log reset endpoint (DELETE /admin/slowlog)
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowLogResetResponse {
+    /// Success message
+    pub message: String,
+    /// Number of entries removed
+    pub cleared: usize,
+}
+
+impl SlowLogResetResponse {
+    /// Creates a new SlowLogResetResponse
+    pub fn new(cleared: usize) -> Self {
+        Self {
+            message: format!("Slow log reset, {} entries cleared", cleared),
+            cleared,
+        }
+    }
+}
+
+/// Response body for the hot keys endpoint (GET /admin/hotkeys)
+#[derive(Debug, Clone, Serialize)]
+pub struct HotKeysResponse {
+    /// Most accessed keys, highest estimated count first
+    pub keys: Vec<HotKey>,
+}
+
+/// Response body for the big keys endpoint (GET /admin/bigkeys)
+#[derive(Debug, Clone, Serialize)]
+pub struct BigKeysResponse {
+    /// Largest live values, largest first
+    pub keys: Vec<BigKey>,
+}
+
+/// Response body for the flush endpoints (POST /db/:db/flush, POST /admin/flush)
+#[derive(Debug, Clone, Serialize)]
+pub struct FlushResponse {
+    /// Success message
+    pub message: String,
+    /// Number of entries removed
+    pub removed: usize,
+}
+
+impl FlushResponse {
+    /// Creates a new FlushResponse
+    pub fn new(removed: usize) -> Self {
+        Self {
+            message: format!("Flushed {} entries", removed),
+            removed,
+        }
+    }
+}
+
+/// Response body for the move endpoint (POST /db/:db/move/:key)
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveResponse {
+    /// The moved key
+    pub key: String,
+    /// Database the key was moved to
+    pub db: String,
+    /// False if the target database already held the key
+    pub moved: bool,
+}
+
+/// Response body for the swap endpoint (POST /admin/swapdb)
+#[derive(Debug, Clone, Serialize)]
+pub struct SwapDbResponse {
+    /// Success message
+    pub message: String,
+    /// Entries evicted to fit the swapped data into each database's capacity
+    pub evicted: usize,
+}
+
+/// One database in the database list (GET /admin/databases)
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseInfo {
+    /// Database name
+    pub name: String,
+    /// Current number of entries
+    pub keys: usize,
+    /// Maximum number of entries
+    pub max_entries: usize,
+    /// Default TTL in seconds
+    pub default_ttl: u64,
+    /// Cache hits
+    pub hits: u64,
+    /// Cache misses
+    pub misses: u64,
+    /// Evictions
+    pub evictions: u64,
+}
+
+/// Response body for the database list (GET /admin/databases)
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabasesResponse {
+    /// Databases in configuration order, default first
+    pub databases: Vec<DatabaseInfo>,
+}
+
+/// A run of slots served by one node (GET /cluster/slots)
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterSlotRange {
+    /// First slot of the range
+    pub start: u16,
+    /// Last slot of the range, inclusive
+    pub end: u16,
+    /// Id of the serving node
+    pub node: String,
+    /// Address of the serving node
+    pub address: String,
+}
+
+/// Response body for the slot table (GET /cluster/slots)
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterSlotsResponse {
+    /// Served slot ranges in slot order; unserved slots are left out
+    pub slots: Vec<ClusterSlotRange>,
+}
+
+/// One node in the node table (GET /cluster/nodes)
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterNodeInfo {
+    /// Node id
+    pub id: String,
+    /// Address clients are redirected to
+    pub address: String,
+    /// Address of the node's cluster bus
+    pub bus_address: String,
+    /// True for the node answering the request
+    pub myself: bool,
+    /// `leader` or `follower`
+    pub role: &'static str,
+    /// Id of the leader a follower replicates
+    pub leader: Option<String>,
+    /// `online`, `pfail` (suspected by this node) or `fail`
+    pub health: NodeHealth,
+    /// Epoch at which the node last gained slots
+    pub config_epoch: u64,
+    /// Milliseconds since the node last answered, None for this node
+    pub last_seen_ms: Option<u64>,
+    /// Leaders currently reporting the node as failing
+    pub fail_reports: usize,
+    /// Slot ranges served by the node, as `start-end`
+    pub slots: Vec<String>,
+}
+
+/// Response body for the node table (GET /cluster/nodes)
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterNodesResponse {
+    /// Nodes in configuration order, then in the order they were discovered
+    pub nodes: Vec<ClusterNodeInfo>,
+    /// Highest epoch seen in the cluster
+    pub current_epoch: u64,
+    /// Slots this node is migrating, with the receiving node
+    pub migrating: BTreeMap<u16, String>,
+    /// Slots this node is importing, with the sending node
+    pub importing: BTreeMap<u16, String>,
+    /// Recent membership changes, newest first
+    pub events: Vec<ClusterEvent>,
+}
+
+/// Response body for slot assignment changes (POST /admin/cluster/setslot)
+#[derive(Debug, Clone, Serialize)]
+pub struct SetSlotResponse {
+    /// Success message
+    pub message: String,
+    /// Number of slots changed
+    pub slots: usize,
+}
+
+/// Response body for received keys (POST /admin/cluster/restore)
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreResponse {
+    /// Number of keys stored
+    pub restored: usize,
+    /// Number of keys deleted
+    pub deleted: usize,
+}
+
+/// Response body for the migration list (GET /admin/cluster/migrations)
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationsResponse {
+    /// The running and recent migrations, newest first
+    pub migrations: Vec<MigrationStatus>,
+}
+
+/// Response body for the key slot lookup (GET /cluster/keyslot/:key)
+#[derive(Debug, Clone, Serialize)]
+pub struct KeySlotResponse {
+    /// The looked up key
+    pub key: String,
+    /// Hash slot of the key
+    pub slot: u16,
+    /// Id of the node serving the slot, if any
+    pub node: Option<String>,
+    /// Address of the node serving the slot, if any
+    pub address: Option<String>,
+}
+
+/// Response body for the config endpoints (GET/PATCH /admin/config)
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigResponse {
+    /// Settings that can be changed with PATCH, at their current values
+    #[serde(flatten)]
+    pub tunables: Tunables,
+    /// HTTP server port
+    pub server_port: u16,
+    /// Addresses the HTTP server listens on
+    pub bind_addresses: Vec<String>,
+    /// Unix domain socket the API is also served on
+    pub unix_socket: Option<String>,
+    /// Log output format
+    pub log_format: String,
+    /// Whether requests need a bearer token
+    pub auth_enabled: bool,
+    /// Whether the server is served over TLS
+    pub tls_enabled: bool,
+    /// Default per-client rate limit as `rate/burst`
+    pub rate_limit: Option<String>,
+    /// Leader this server replicates from, None for a leader
+    pub replica_of: Option<String>,
+    /// Config file used at startup and by CONFIG REWRITE
+    pub config_file: Option<String>,
+}
+
+/// Response body for the config rewrite endpoint (POST /admin/config/rewrite)
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigRewriteResponse {
+    /// Success message
+    pub message: String,
+    /// File that was written
+    pub path: String,
+}
+
+impl ConfigRewriteResponse {
+    /// Creates a new ConfigRewriteResponse
+    pub fn new(path: String) -> Self {
+        Self {
+            message: format!("Configuration written to {}", path),
+            path,
+        }
+    }
+}
+
 /// Error response body for all error conditions
 ///
 /// # Requirements
 /// - Validates: Requirement 7.5
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ErrorResponse {
     /// Error message describing what went wrong
     pub error: String,
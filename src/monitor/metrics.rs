@@ -0,0 +1,194 @@
+//! Server Metrics Module
+//!
+//! Process-wide counters that are not tied to cache contents.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::cache::current_timestamp_ms;
+
+// == Server Metrics ==
+/// Uptime, connection and request counters shared by all handlers.
+///
+/// Counters are atomics so middleware can update them without taking the
+/// cache lock.
+#[derive(Debug)]
+pub struct ServerMetrics {
+    /// Monotonic start instant, used for uptime
+    started_at: Instant,
+    /// Start time (Unix milliseconds)
+    started_at_ms: u64,
+    /// Client connections currently open
+    connected_clients: AtomicUsize,
+    /// Client connections accepted since start
+    total_connections: AtomicU64,
+    /// Requests currently being served
+    active_requests: AtomicUsize,
+    /// Requests received since start
+    total_requests: AtomicU64,
+    /// Requests rejected by access control
+    acl_denials: AtomicU64,
+    /// Requests rejected by the rate limiter
+    rate_limited: AtomicU64,
+}
+
+impl ServerMetrics {
+    // == Constructor ==
+    /// Creates metrics with the start time set to now.
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            started_at_ms: current_timestamp_ms(),
+            connected_clients: AtomicUsize::new(0),
+            total_connections: AtomicU64::new(0),
+            active_requests: AtomicUsize::new(0),
+            total_requests: AtomicU64::new(0),
+            acl_denials: AtomicU64::new(0),
+            rate_limited: AtomicU64::new(0),
+        }
+    }
+
+    // == Uptime ==
+    /// Returns the time elapsed since the server started.
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    // == Started At ==
+    /// Returns the server start time (Unix milliseconds).
+    pub fn started_at_ms(&self) -> u64 {
+        self.started_at_ms
+    }
+
+    // == Connections ==
+    /// Marks a client connection as open until the returned guard is
+    /// dropped.
+    pub fn connection_opened(self: &Arc<Self>) -> ConnectionGuard {
+        self.connected_clients.fetch_add(1, Ordering::Relaxed);
+        self.total_connections.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard(self.clone())
+    }
+
+    /// Returns the number of client connections currently open.
+    pub fn connected_clients(&self) -> usize {
+        self.connected_clients.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of client connections accepted since start.
+    pub fn total_connections(&self) -> u64 {
+        self.total_connections.load(Ordering::Relaxed)
+    }
+
+    // == Request Started ==
+    /// Marks the start of a request.
+    pub fn request_started(&self) {
+        self.active_requests.fetch_add(1, Ordering::Relaxed);
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // == Request Finished ==
+    /// Marks the end of a request started with `request_started`.
+    pub fn request_finished(&self) {
+        self.active_requests.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    // == Active Requests ==
+    /// Returns the number of requests currently being served.
+    pub fn active_requests(&self) -> usize {
+        self.active_requests.load(Ordering::Relaxed)
+    }
+
+    // == Total Requests ==
+    /// Returns the number of requests received since start.
+    pub fn total_requests(&self) -> u64 {
+        self.total_requests.load(Ordering::Relaxed)
+    }
+
+    // == ACL Denials ==
+    /// Records a request rejected by access control.
+    pub fn record_acl_denial(&self) {
+        self.acl_denials.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of requests rejected by access control.
+    pub fn acl_denials(&self) -> u64 {
+        self.acl_denials.load(Ordering::Relaxed)
+    }
+
+    // == Rate Limited ==
+    /// Records a request rejected by the rate limiter.
+    pub fn record_rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of requests rejected by the rate limiter.
+    pub fn rate_limited(&self) -> u64 {
+        self.rate_limited.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for ServerMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// == Connection Guard ==
+/// Counts a client connection as open while alive; held by the task
+/// serving the connection.
+#[derive(Debug)]
+pub struct ConnectionGuard(Arc<ServerMetrics>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.connected_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// == Unit Tests ==
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_request_counters() {
+        let metrics = ServerMetrics::new();
+
+        metrics.request_started();
+        metrics.request_started();
+        metrics.request_finished();
+
+        assert_eq!(metrics.active_requests(), 1);
+        assert_eq!(metrics.total_requests(), 2);
+
+        metrics.record_acl_denial();
+        assert_eq!(metrics.acl_denials(), 1);
+
+        metrics.record_rate_limited();
+        assert_eq!(metrics.rate_limited(), 1);
+    }
+
+    #[test]
+    fn test_metrics_connection_counters() {
+        let metrics = Arc::new(ServerMetrics::new());
+
+        let first = metrics.connection_opened();
+        let second = metrics.connection_opened();
+        assert_eq!(metrics.connected_clients(), 2);
+        drop(first);
+        assert_eq!(metrics.connected_clients(), 1);
+        drop(second);
+        assert_eq!(metrics.connected_clients(), 0);
+        assert_eq!(metrics.total_connections(), 2);
+    }
+
+    #[test]
+    fn test_metrics_start_time() {
+        let before = current_timestamp_ms();
+        let metrics = ServerMetrics::new();
+
+        assert!(metrics.started_at_ms() >= before);
+        assert!(metrics.uptime() < Duration::from_secs(1));
+    }
+}
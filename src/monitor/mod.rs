@@ -0,0 +1,13 @@
+//! Monitoring Module
+//!
+//! Diagnostics collected while the server runs, independent of cache contents.
+//!
+//! # Components
+//! - Slow Log: Records operations exceeding a latency threshold
+//! - Server Metrics: Uptime, connection and request counters
+
+mod metrics;
+mod slowlog;
+
+pub use metrics::{ConnectionGuard, ServerMetrics};
+pub use slowlog::{SlowLog, SlowLogEntry, DEFAULT_SLOWLOG_MAX_LEN, DEFAULT_SLOWLOG_THRESHOLD_US};
@@ -0,0 +1,242 @@
+//! Slow Log Module
+//!
+//! Bounded in-memory log of operations whose latency exceeded a threshold,
+//! modelled on the Redis SLOWLOG.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::cache::current_timestamp_ms;
+
+/// Default latency threshold in microseconds (same as Redis `slowlog-log-slower-than`)
+pub const DEFAULT_SLOWLOG_THRESHOLD_US: u64 = 10_000;
+
+/// Default maximum number of retained entries (same as Redis `slowlog-max-len`)
+pub const DEFAULT_SLOWLOG_MAX_LEN: usize = 128;
+
+// == Slow Log Entry ==
+/// A single recorded slow operation.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowLogEntry {
+    /// Monotonically increasing entry id
+    pub id: u64,
+    /// Time the operation completed (Unix milliseconds)
+    pub timestamp: u64,
+    /// Operation duration in microseconds
+    pub duration_us: u64,
+    /// Operation name (e.g. "get", "set")
+    pub operation: String,
+    /// Key the operation targeted, if any
+    pub key: Option<String>,
+    /// Size in bytes of the value read or written, if any
+    pub value_size: Option<usize>,
+}
+
+// == Slow Log ==
+/// Bounded log of slow operations.
+///
+/// Entries are stored newest first; once `max_len` is reached the oldest
+/// entry is dropped for each new one.
+#[derive(Debug)]
+pub struct SlowLog {
+    /// Recorded entries, newest at the front
+    entries: VecDeque<SlowLogEntry>,
+    /// Id assigned to the next recorded entry
+    next_id: u64,
+    /// Minimum duration in microseconds for an operation to be logged
+    threshold_us: u64,
+    /// Maximum number of retained entries
+    max_len: usize,
+}
+
+impl SlowLog {
+    // == Constructor ==
+    /// Creates a new empty slow log.
+    ///
+    /// # Arguments
+    /// * `threshold_us` - Minimum duration in microseconds to record an operation
+    /// * `max_len` - Maximum number of entries kept (0 disables logging)
+    pub fn new(threshold_us: u64, max_len: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(max_len.min(DEFAULT_SLOWLOG_MAX_LEN)),
+            next_id: 0,
+            threshold_us,
+            max_len,
+        }
+    }
+
+    // == Is Slow ==
+    /// Returns true if an operation of this duration would be recorded.
+    pub fn is_slow(&self, duration: Duration) -> bool {
+        self.max_len > 0 && duration.as_micros() >= u128::from(self.threshold_us)
+    }
+
+    // == Record ==
+    /// Records an operation if its duration meets the threshold.
+    ///
+    /// Returns true if the operation was logged.
+    pub fn record(
+        &mut self,
+        operation: &str,
+        key: Option<&str>,
+        value_size: Option<usize>,
+        duration: Duration,
+    ) -> bool {
+        if !self.is_slow(duration) {
+            return false;
+        }
+
+        let entry = SlowLogEntry {
+            id: self.next_id,
+            timestamp: current_timestamp_ms(),
+            duration_us: u64::try_from(duration.as_micros()).unwrap_or(u64::MAX),
+            operation: operation.to_string(),
+            key: key.map(str::to_string),
+            value_size,
+        };
+        self.next_id += 1;
+
+        self.entries.push_front(entry);
+        self.entries.truncate(self.max_len);
+        true
+    }
+
+    // == Latest ==
+    /// Returns up to `count` of the most recent entries, newest first.
+    pub fn latest(&self, count: usize) -> Vec<SlowLogEntry> {
+        self.entries.iter().take(count).cloned().collect()
+    }
+
+    // == Reset ==
+    /// Removes all entries, returning how many were cleared.
+    ///
+    /// Entry ids keep increasing across resets, as in Redis.
+    pub fn reset(&mut self) -> usize {
+        let cleared = self.entries.len();
+        self.entries.clear();
+        cleared
+    }
+
+    // == Length ==
+    /// Returns the number of retained entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // == Is Empty ==
+    /// Returns true if no entries are retained.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // == Threshold ==
+    /// Returns the logging threshold in microseconds.
+    pub fn threshold_us(&self) -> u64 {
+        self.threshold_us
+    }
+
+    /// Changes the logging threshold for operations recorded from now on.
+    pub fn set_threshold_us(&mut self, threshold_us: u64) {
+        self.threshold_us = threshold_us;
+    }
+
+    // == Max Length ==
+    /// Returns the maximum number of retained entries.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Changes the capacity, dropping the oldest entries beyond it.
+    pub fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+        self.entries.truncate(max_len);
+    }
+}
+
+impl Default for SlowLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOWLOG_THRESHOLD_US, DEFAULT_SLOWLOG_MAX_LEN)
+    }
+}
+
+// == Unit Tests ==
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slowlog_ignores_fast_operations() {
+        let mut log = SlowLog::new(1_000, 10);
+
+        let logged = log.record("get", Some("key1"), Some(5), Duration::from_micros(999));
+
+        assert!(!logged);
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn test_slowlog_records_slow_operations() {
+        let mut log = SlowLog::new(1_000, 10);
+
+        assert!(log.record("set", Some("key1"), Some(42), Duration::from_millis(5)));
+
+        let entries = log.latest(10);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, "set");
+        assert_eq!(entries[0].key.as_deref(), Some("key1"));
+        assert_eq!(entries[0].value_size, Some(42));
+        assert_eq!(entries[0].duration_us, 5_000);
+    }
+
+    #[test]
+    fn test_slowlog_is_bounded_and_newest_first() {
+        let mut log = SlowLog::new(0, 3);
+
+        for i in 0..5 {
+            log.record("get", Some(&format!("key{}", i)), None, Duration::ZERO);
+        }
+
+        assert_eq!(log.len(), 3);
+        let ids: Vec<u64> = log.latest(10).iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![4, 3, 2]);
+        assert_eq!(log.latest(1)[0].key.as_deref(), Some("key4"));
+    }
+
+    #[test]
+    fn test_slowlog_reset_keeps_ids_increasing() {
+        let mut log = SlowLog::new(0, 10);
+        log.record("get", None, None, Duration::ZERO);
+        log.record("get", None, None, Duration::ZERO);
+
+        assert_eq!(log.reset(), 2);
+        assert!(log.is_empty());
+
+        log.record("del", Some("key"), None, Duration::ZERO);
+        assert_eq!(log.latest(1)[0].id, 2);
+    }
+
+    #[test]
+    fn test_slowlog_reconfigure() {
+        let mut log = SlowLog::new(0, 10);
+        for i in 0..5 {
+            log.record("get", Some(&format!("key{}", i)), None, Duration::from_micros(10));
+        }
+
+        log.set_max_len(2);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.latest(1)[0].key.as_deref(), Some("key4"));
+
+        log.set_threshold_us(1_000);
+        assert!(!log.record("get", None, None, Duration::from_micros(10)));
+    }
+
+    #[test]
+    fn test_slowlog_zero_max_len_disables_logging() {
+        let mut log = SlowLog::new(0, 0);
+
+        assert!(!log.record("get", None, None, Duration::from_secs(1)));
+        assert!(log.is_empty());
+    }
+}
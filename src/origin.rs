@@ -0,0 +1,388 @@
+//! Origin Mode
+//!
+//! Puts the server in front of a slower HTTP origin as a read-through cache.
+//! A GET that misses fetches the key from the origin, stores the value for as
+//! long as the origin's `Cache-Control` allows and answers with it.
+//! Concurrent misses on the same key wait for one upstream request, and a
+//! write to the key while it is fetched wins over the fetched value.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use axum::http::{header, HeaderMap, Request, StatusCode, Uri};
+use http_body_util::{BodyExt, Empty, Limited};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{debug, warn};
+
+use crate::cache::{CacheEntry, CacheStore, SetOptions, MAX_GRACE, MAX_VALUE_SIZE};
+use crate::client::{describe, encode_segment};
+use crate::error::{CacheError, Result};
+
+/// Placeholder of the origin URL replaced by the percent-encoded key
+pub const KEY_PLACEHOLDER: &str = "{key}";
+
+/// Longest lifetime taken from an origin response, in seconds
+pub const MAX_ORIGIN_TTL: u64 = 365 * 24 * 60 * 60; // 1 year
+
+type HttpClient = hyper_util::client::legacy::Client<HttpConnector, Empty<Bytes>>;
+
+/// Outcome of one upstream request, shared by every miss waiting on it
+type Flight = Arc<OnceCell<std::result::Result<Option<String>, String>>>;
+
+/// What a store held for a key: creation time, expiry and value
+type Held = Option<(u64, Option<u64>, String)>;
+
+fn held(entry: Option<&CacheEntry>) -> Held {
+    entry.map(|entry| (entry.created_at, entry.expires_at, entry.value.clone()))
+}
+
+/// How long the origin lets a value be cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// Cache for this many seconds
+    Ttl(u64),
+    /// No lifetime given: cache with the database's default TTL
+    Default,
+    /// `no-store`, `no-cache`, `private` or no lifetime left: do not cache
+    Uncacheable,
+}
+
+impl Freshness {
+    /// Reads `Cache-Control` and `Age`. `s-maxage` wins over `max-age`, and
+    /// the age of the response is taken off its lifetime, which is capped
+    /// at `MAX_ORIGIN_TTL`. `Expires` is not read.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut max_age = None;
+        let mut s_maxage = None;
+        for (name, argument) in cache_control(headers) {
+            match name.as_str() {
+                "no-store" | "no-cache" | "private" => return Freshness::Uncacheable,
+                "max-age" => max_age = argument.as_deref().and_then(delta_seconds),
+                "s-maxage" => s_maxage = argument.as_deref().and_then(delta_seconds),
+                _ => {}
+            }
+        }
+
+        let Some(lifetime) = s_maxage.or(max_age) else {
+            return Freshness::Default;
+        };
+        let age = headers
+            .get(header::AGE)
+            .and_then(|age| age.to_str().ok()?.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        match lifetime.saturating_sub(age) {
+            0 => Freshness::Uncacheable,
+            ttl => Freshness::Ttl(ttl.min(MAX_ORIGIN_TTL)),
+        }
+    }
+}
+
+/// Returns the `stale-while-revalidate` seconds of a response, capped at
+/// `MAX_GRACE`, used as the grace window of the value.
+pub fn stale_grace(headers: &HeaderMap) -> Option<u64> {
+    cache_control(headers)
+        .into_iter()
+        .find(|(name, _)| name == "stale-while-revalidate")
+        .and_then(|(_, argument)| delta_seconds(&argument?))
+        .map(|grace| grace.min(MAX_GRACE))
+}
+
+/// Parses a `Cache-Control` number of seconds. Numbers too large for a
+/// u64 are read as u64::MAX rather than ignored.
+fn delta_seconds(argument: &str) -> Option<u64> {
+    if argument.is_empty() || !argument.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(argument.parse().unwrap_or(u64::MAX))
+}
+
+/// The `Cache-Control` directives of a response, with lowercase names and
+/// unquoted arguments.
+fn cache_control(headers: &HeaderMap) -> Vec<(String, Option<String>)> {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| match directive.split_once('=') {
+            Some((name, argument)) => (
+                name.trim().to_ascii_lowercase(),
+                Some(argument.trim().trim_matches('"').to_string()),
+            ),
+            None => (directive.trim().to_ascii_lowercase(), None),
+        })
+        .collect()
+}
+
+/// A value the origin answered with
+struct Fetched {
+    value: String,
+    freshness: Freshness,
+    /// Stale-grace window in seconds
+    grace: Option<u64>,
+    /// Milliseconds the origin took to answer
+    compute_ms: u64,
+}
+
+// == Origin ==
+/// The origin keys are fetched from on a miss
+pub struct Origin {
+    template: String,
+    timeout: Duration,
+    http: HttpClient,
+    /// Upstream requests in progress, by database and key
+    flights: Mutex<HashMap<(String, String), Flight>>,
+}
+
+impl Origin {
+    /// Creates an origin fetching `template` with `{key}` replaced by the
+    /// key, giving up after `timeout`.
+    pub fn new(template: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            template: template.into(),
+            timeout,
+            http: hyper_util::client::legacy::Client::builder(TokioExecutor::new())
+                .build(HttpConnector::new()),
+            flights: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks that `template` is an `http://` URL with a `{key}` placeholder.
+    pub fn check_template(template: &str) -> std::result::Result<(), String> {
+        if !template.contains(KEY_PLACEHOLDER) {
+            return Err(format!(
+                "'{}' has no {} placeholder",
+                template, KEY_PLACEHOLDER
+            ));
+        }
+        let uri: Uri = template
+            .replace(KEY_PLACEHOLDER, "key")
+            .parse()
+            .map_err(|e| format!("'{}' is not a URL: {}", template, e))?;
+        if uri.scheme_str() != Some("http") || uri.host().is_none() {
+            return Err(format!("'{}' is not an http:// URL", template));
+        }
+        Ok(())
+    }
+
+    /// Returns the URL of `key` at the origin.
+    pub fn url_for(&self, key: &str) -> String {
+        self.template.replace(KEY_PLACEHOLDER, &encode_segment(key))
+    }
+
+    /// Fetches `key` of `database` from the origin, stores it in `store`
+    /// when the origin allows caching, and returns it, or None if the
+    /// origin does not have it. A `stale-while-revalidate` lifetime becomes
+    /// the grace window of the stored value, and the time the origin took
+    /// to answer its compute time, so hot keys are refreshed early.
+    ///
+    /// Concurrent calls for the same key share one upstream request and
+    /// store the value once. If the key is written or removed while the
+    /// request runs, the fetched value is returned but not stored.
+    /// Followers pass no store, as they only take writes from their leader.
+    pub async fn load(
+        &self,
+        database: &str,
+        key: &str,
+        store: Option<&RwLock<CacheStore>>,
+    ) -> Result<Option<String>> {
+        let id = (database.to_string(), key.to_string());
+        let flight = self.lock().entry(id.clone()).or_default().clone();
+
+        let result = flight
+            .get_or_init(|| async {
+                let before = match store {
+                    Some(store) => held(store.read().await.stored_entry(key)),
+                    None => None,
+                };
+                let result = self.fetch(key).await;
+                if let (Ok(Some(fetched)), Some(store)) = (&result, store) {
+                    let ttl = match fetched.freshness {
+                        Freshness::Ttl(ttl) => Some(Some(ttl)),
+                        Freshness::Default => Some(None),
+                        Freshness::Uncacheable => None,
+                    };
+                    if let Some(ttl) = ttl {
+                        let options = SetOptions {
+                            grace: fetched.grace,
+                            compute_ms: Some(fetched.compute_ms),
+                        };
+                        let mut store = store.write().await;
+                        if held(store.stored_entry(key)) != before {
+                            debug!("'{}' changed while it was fetched, not caching", key);
+                        } else if let Err(e) =
+                            store.set_with(key.to_string(), fetched.value.clone(), ttl, options)
+                        {
+                            warn!("Could not cache '{}' from the origin: {}", key, e);
+                        }
+                    }
+                }
+
+                // Later misses start a new request
+                let mut flights = self.lock();
+                if flights
+                    .get(&id)
+                    .is_some_and(|current| Arc::ptr_eq(current, &flight))
+                {
+                    flights.remove(&id);
+                }
+                result.map(|fetched| fetched.map(|fetched| fetched.value))
+            })
+            .await;
+
+        result.clone().map_err(CacheError::Load)
+    }
+
+    /// Requests `key` from the origin. 404 and 410 mean the origin does not
+    /// have it; any other status except 2xx is an error, as is a body larger
+    /// than `MAX_VALUE_SIZE`.
+    async fn fetch(&self, key: &str) -> std::result::Result<Option<Fetched>, String> {
+        let started = Instant::now();
+        let url = self.url_for(key);
+        let uri: Uri = url
+            .parse()
+            .map_err(|e| format!("'{}' is not a URL: {}", url, e))?;
+        let request = Request::get(uri)
+            .body(Empty::new())
+            .map_err(|e| e.to_string())?;
+
+        let (status, headers, body) = tokio::time::timeout(self.timeout, async {
+            let response = self
+                .http
+                .request(request)
+                .await
+                .map_err(|e| format!("request to {}: {}", url, describe(&e)))?;
+            let (parts, body) = response.into_parts();
+            let body = Limited::new(body, MAX_VALUE_SIZE)
+                .collect()
+                .await
+                .map_err(|e| format!("response from {}: {}", url, e))?
+                .to_bytes();
+            Ok::<_, String>((parts.status, parts.headers, body))
+        })
+        .await
+        .map_err(|_| format!("{} did not answer within {:?}", url, self.timeout))??;
+
+        debug!("Origin answered {} for {}", status, url);
+        match status {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Ok(None),
+            status if status.is_success() => {
+                let value = String::from_utf8(body.to_vec())
+                    .map_err(|_| format!("{} answered a value that is not UTF-8", url))?;
+                Ok(Some(Fetched {
+                    value,
+                    freshness: Freshness::from_headers(&headers),
+                    grace: stale_grace(&headers),
+                    compute_ms: started.elapsed().as_millis() as u64,
+                }))
+            }
+            status => Err(format!("{} answered {}", url, status)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<(String, String), Flight>> {
+        self.flights.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// == Unit Tests ==
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn freshness(headers: &[(&'static str, &str)]) -> Freshness {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(*name, value.parse().unwrap());
+        }
+        Freshness::from_headers(&map)
+    }
+
+    #[test]
+    fn test_freshness_from_cache_control() {
+        assert_eq!(freshness(&[]), Freshness::Default);
+        assert_eq!(
+            freshness(&[("cache-control", "public")]),
+            Freshness::Default
+        );
+        assert_eq!(
+            freshness(&[("cache-control", "public, max-age=60")]),
+            Freshness::Ttl(60)
+        );
+        assert_eq!(
+            freshness(&[("cache-control", "max-age=60, s-maxage=\"600\"")]),
+            Freshness::Ttl(600)
+        );
+        assert_eq!(
+            freshness(&[("cache-control", "max-age=60"), ("age", "45")]),
+            Freshness::Ttl(15)
+        );
+        assert_eq!(
+            freshness(&[("cache-control", "max-age=60"), ("age", "90")]),
+            Freshness::Uncacheable
+        );
+        assert_eq!(
+            freshness(&[("cache-control", "max-age=0")]),
+            Freshness::Uncacheable
+        );
+        assert_eq!(
+            freshness(&[
+                ("cache-control", "max-age=60"),
+                ("cache-control", "No-Store")
+            ]),
+            Freshness::Uncacheable
+        );
+        assert_eq!(
+            freshness(&[("cache-control", "private, max-age=60")]),
+            Freshness::Uncacheable
+        );
+
+        // Lifetimes beyond the cap, or beyond u64, are cut down to it
+        assert_eq!(
+            freshness(&[("cache-control", "max-age=18446744073709551615")]),
+            Freshness::Ttl(MAX_ORIGIN_TTL)
+        );
+        assert_eq!(
+            freshness(&[("cache-control", "s-maxage=99999999999999999999")]),
+            Freshness::Ttl(MAX_ORIGIN_TTL)
+        );
+    }
+
+    #[test]
+    fn test_stale_grace() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(stale_grace(&headers), None);
+        headers.append(
+            header::CACHE_CONTROL,
+            "max-age=60, Stale-While-Revalidate=30".parse().unwrap(),
+        );
+        assert_eq!(stale_grace(&headers), Some(30));
+        assert_eq!(Freshness::from_headers(&headers), Freshness::Ttl(60));
+
+        let mut headers = HeaderMap::new();
+        headers.append(
+            header::CACHE_CONTROL,
+            "stale-while-revalidate=18446744073709551615".parse().unwrap(),
+        );
+        assert_eq!(stale_grace(&headers), Some(MAX_GRACE));
+    }
+
+    #[test]
+    fn test_origin_templates() {
+        assert!(Origin::check_template("http://origin:8080/items/{key}").is_ok());
+        assert!(Origin::check_template("http://origin/lookup?id={key}").is_ok());
+        assert!(Origin::check_template("http://origin/items").is_err());
+        assert!(Origin::check_template("https://origin/{key}").is_err());
+        assert!(Origin::check_template("/items/{key}").is_err());
+
+        let origin = Origin::new("http://origin/items/{key}", Duration::from_secs(1));
+        assert_eq!(
+            origin.url_for("user:1 a/b"),
+            "http://origin/items/user%3A1%20a%2Fb"
+        );
+    }
+}
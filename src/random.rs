@@ -0,0 +1,36 @@
+//! Random Numbers
+//!
+//! Cheap, non-cryptographic randomness for jitter and probabilistic
+//! decisions, without an extra dependency. std seeds `RandomState` keys
+//! randomly once per thread and changes them for every new instance, so
+//! hashing nothing with a new one yields a different, unpredictable-enough
+//! number each time. Not suitable for anything security-sensitive.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Returns a random u64.
+pub(crate) fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Returns a random number in (0, 1].
+pub(crate) fn random_unit() -> f64 {
+    let bits = random_u64() >> 11;
+    (bits + 1) as f64 / (1u64 << 53) as f64
+}
+
+// == Unit Tests ==
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_numbers() {
+        assert_ne!(random_u64(), random_u64());
+        for _ in 0..1000 {
+            let unit = random_unit();
+            assert!(unit > 0.0 && unit <= 1.0);
+        }
+    }
+}
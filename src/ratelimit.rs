@@ -0,0 +1,370 @@
+//! Rate Limiting
+//!
+//! Token-bucket limits per client, with optional per-route overrides,
+//! checked by the rate limit middleware before requests reach the handlers.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::acl::glob_match;
+
+/// Number of tracked buckets above which full (idle) buckets are dropped
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Minimum time between two prunes, so a large active set is not rescanned
+/// on every request
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+// == Rate Limit Rule ==
+/// A sustained request rate with a burst allowance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitRule {
+    /// Tokens added per second
+    pub per_second: f64,
+    /// Bucket capacity, i.e. the largest burst allowed
+    pub burst: u32,
+}
+
+impl RateLimitRule {
+    /// Creates a rule allowing `per_second` requests with bursts of `burst`.
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        Self { per_second, burst }
+    }
+}
+
+impl FromStr for RateLimitRule {
+    type Err = String;
+
+    /// Parses `rate` or `rate/burst`, e.g. `100` or `100/200`. The burst
+    /// defaults to the rate rounded up.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid rate limit '{}', expected rate or rate/burst", s);
+        let (rate, burst) = match s.trim().split_once('/') {
+            Some((rate, burst)) => (rate, Some(burst)),
+            None => (s.trim(), None),
+        };
+
+        let per_second: f64 = rate.trim().parse().map_err(|_| invalid())?;
+        if !per_second.is_finite() || per_second <= 0.0 {
+            return Err(invalid());
+        }
+        let burst = match burst {
+            Some(burst) => burst.trim().parse().map_err(|_| invalid())?,
+            None => per_second.ceil() as u32,
+        };
+        if burst == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Self::new(per_second, burst))
+    }
+}
+
+impl fmt::Display for RateLimitRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.per_second, self.burst)
+    }
+}
+
+// == Route Rate Limit ==
+/// A limit for routes matching a pattern, overriding the default limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteRateLimit {
+    /// Glob over route templates, e.g. `/get/:key` or `/admin/*`
+    pub route: String,
+    /// The limit to apply, or None to leave matching routes unlimited
+    pub rule: Option<RateLimitRule>,
+}
+
+impl RouteRateLimit {
+    /// Parses a comma-separated list of `route=rate/burst` entries, where
+    /// the limit may also be `off`, e.g. `/set=20/40,/admin/*=1/5,/health=off`.
+    pub fn parse_list(value: &str) -> Result<Vec<RouteRateLimit>, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for RouteRateLimit {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let Some((route, rule)) = spec.split_once('=') else {
+            return Err(format!(
+                "invalid route rate limit '{}', expected route=rate/burst",
+                spec
+            ));
+        };
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(format!("route rate limit '{}' needs a route", spec));
+        }
+
+        let rule = match rule.trim() {
+            "off" => None,
+            rule => Some(rule.parse()?),
+        };
+        Ok(Self {
+            route: route.to_string(),
+            rule,
+        })
+    }
+}
+
+// == Token Bucket ==
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(rule: &RateLimitRule, now: Instant) -> Self {
+        Self {
+            tokens: rule.burst as f64,
+            refilled_at: now,
+        }
+    }
+
+    fn refill(&mut self, rule: &RateLimitRule, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rule.per_second).min(rule.burst as f64);
+        self.refilled_at = now;
+    }
+
+    /// Takes one token, or returns how long until one is available.
+    fn try_take(&mut self, rule: &RateLimitRule, now: Instant) -> Result<(), Duration> {
+        self.refill(rule, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / rule.per_second,
+            ))
+        }
+    }
+}
+
+// == Rate Limiter ==
+/// Per-client token buckets for a default limit and per-route overrides.
+///
+/// Each client has one bucket for the default limit, shared by every route
+/// without an override, and one bucket per matching override.
+#[derive(Debug)]
+pub struct RateLimiter {
+    default: Option<RateLimitRule>,
+    routes: Vec<RouteRateLimit>,
+    buckets: Mutex<BucketTable>,
+}
+
+/// Buckets keyed by (client, index of the matching route override)
+#[derive(Debug)]
+struct BucketTable {
+    buckets: HashMap<(String, Option<usize>), TokenBucket>,
+    pruned_at: Instant,
+}
+
+impl RateLimiter {
+    // == Constructor ==
+    /// Creates a limiter; the first matching route override wins.
+    pub fn new(default: Option<RateLimitRule>, routes: Vec<RouteRateLimit>) -> Self {
+        Self {
+            default,
+            routes,
+            buckets: Mutex::new(BucketTable {
+                buckets: HashMap::new(),
+                pruned_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// A limiter that allows everything.
+    pub fn disabled() -> Self {
+        Self::new(None, Vec::new())
+    }
+
+    /// Returns true if any limit is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.default.is_some() || self.routes.iter().any(|r| r.rule.is_some())
+    }
+
+    // == Rule Lookup ==
+    /// Returns the bucket slot and limit that apply to `route`.
+    fn rule_for(&self, route: &str) -> (Option<usize>, Option<RateLimitRule>) {
+        match self.routes.iter().position(|r| glob_match(&r.route, route)) {
+            Some(index) => (Some(index), self.routes[index].rule),
+            None => (None, self.default),
+        }
+    }
+
+    // == Check ==
+    /// Consumes a token for `client` on `route`.
+    ///
+    /// Returns the time until the next token when the bucket is empty.
+    pub fn check(&self, client: &str, route: &str) -> Result<(), Duration> {
+        let (slot, rule) = self.rule_for(route);
+        let Some(rule) = rule else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut table = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if table.buckets.len() >= PRUNE_THRESHOLD
+            && now.saturating_duration_since(table.pruned_at) >= PRUNE_INTERVAL
+        {
+            self.prune(&mut table.buckets, now);
+            table.pruned_at = now;
+        }
+
+        table
+            .buckets
+            .entry((client.to_string(), slot))
+            .or_insert_with(|| TokenBucket::full(&rule, now))
+            .try_take(&rule, now)
+    }
+
+    /// Drops buckets that have refilled completely, which behave exactly
+    /// like freshly created ones.
+    fn prune(&self, buckets: &mut HashMap<(String, Option<usize>), TokenBucket>, now: Instant) {
+        buckets.retain(|(_, slot), bucket| {
+            let rule = match slot {
+                Some(index) => self.routes[*index].rule,
+                None => self.default,
+            };
+            rule.is_some_and(|rule| {
+                bucket.refill(&rule, now);
+                bucket.tokens < rule.burst as f64
+            })
+        });
+    }
+
+    /// Returns the number of tracked buckets.
+    pub fn tracked_buckets(&self) -> usize {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .buckets
+            .len()
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        assert_eq!(
+            "100/200".parse::<RateLimitRule>().unwrap(),
+            RateLimitRule::new(100.0, 200)
+        );
+        assert_eq!(
+            "0.5".parse::<RateLimitRule>().unwrap(),
+            RateLimitRule::new(0.5, 1)
+        );
+        assert!("0".parse::<RateLimitRule>().is_err());
+        assert!("10/0".parse::<RateLimitRule>().is_err());
+        assert!("fast".parse::<RateLimitRule>().is_err());
+    }
+
+    #[test]
+    fn test_parse_route_list() {
+        let routes = RouteRateLimit::parse_list("/set=20/40, /admin/*=1 ,/health=off").unwrap();
+
+        assert_eq!(routes.len(), 3);
+        assert_eq!(routes[0].route, "/set");
+        assert_eq!(routes[0].rule, Some(RateLimitRule::new(20.0, 40)));
+        assert_eq!(routes[1].rule, Some(RateLimitRule::new(1.0, 1)));
+        assert_eq!(routes[2].rule, None);
+        assert!(RouteRateLimit::parse_list("/set").is_err());
+        assert!(RouteRateLimit::parse_list("=1/2").is_err());
+    }
+
+    #[test]
+    fn test_burst_then_reject() {
+        let limiter = RateLimiter::new(Some(RateLimitRule::new(1.0, 3)), Vec::new());
+
+        for _ in 0..3 {
+            assert!(limiter.check("client", "/get/:key").is_ok());
+        }
+        let retry_after = limiter.check("client", "/get/:key").unwrap_err();
+        assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(1));
+
+        // Other clients have their own bucket
+        assert!(limiter.check("other", "/get/:key").is_ok());
+    }
+
+    #[test]
+    fn test_tokens_refill() {
+        let rule = RateLimitRule::new(10.0, 1);
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(&rule, start);
+
+        assert!(bucket.try_take(&rule, start).is_ok());
+        assert!(bucket.try_take(&rule, start).is_err());
+        assert!(bucket
+            .try_take(&rule, start + Duration::from_millis(100))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_route_overrides() {
+        let limiter = RateLimiter::new(
+            Some(RateLimitRule::new(1.0, 1)),
+            RouteRateLimit::parse_list("/health=off,/admin/*=1/2").unwrap(),
+        );
+
+        for _ in 0..10 {
+            assert!(limiter.check("client", "/health").is_ok());
+        }
+        assert!(limiter.check("client", "/admin/slowlog").is_ok());
+        assert!(limiter.check("client", "/admin/hotkeys").is_ok());
+        assert!(limiter.check("client", "/admin/bigkeys").is_err());
+
+        // The default bucket is separate from the override buckets
+        assert!(limiter.check("client", "/set").is_ok());
+        assert!(limiter.check("client", "/get/:key").is_err());
+    }
+
+    #[test]
+    fn test_prune_drops_full_buckets() {
+        let limiter = RateLimiter::new(Some(RateLimitRule::new(1.0, 2)), Vec::new());
+        limiter.check("busy", "/set").unwrap();
+        limiter.check("busy", "/set").unwrap();
+        limiter.check("idle", "/set").unwrap();
+
+        let later = Instant::now() + Duration::from_millis(1500);
+        let mut table = limiter.buckets.lock().unwrap();
+        limiter.prune(&mut table.buckets, later);
+
+        assert_eq!(table.buckets.len(), 1);
+        assert!(table.buckets.contains_key(&("busy".to_string(), None)));
+    }
+
+    #[test]
+    fn test_disabled_limiter() {
+        let limiter = RateLimiter::disabled();
+
+        assert!(!limiter.is_enabled());
+        for _ in 0..100 {
+            assert!(limiter.check("client", "/set").is_ok());
+        }
+        assert_eq!(limiter.tracked_buckets(), 0);
+    }
+}
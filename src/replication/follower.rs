@@ -0,0 +1,489 @@
+//! Follower Side
+//!
+//! Connects to the leader, loads its snapshot or resumes from the last
+//! applied offset, then applies the streamed commands. The link is retried
+//! until the server shuts down.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use axum::http::{header, Request, StatusCode};
+use http_body_util::{BodyExt, Empty};
+use hyper::client::conn::http1::SendRequest;
+use hyper_util::rt::TokioIo;
+use rustls::ClientConfig;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tokio_rustls::TlsConnector;
+use tracing::{debug, info, warn};
+
+use super::protocol::{Command, Message};
+use crate::cache::{CacheEntry, CacheStore, Databases};
+use crate::error::{CacheError, Result};
+
+/// Pause between connection attempts
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The link is considered down after this long without a message; the
+/// leader pings every second
+const LINK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Route serving the replication stream on the leader
+pub const SYNC_PATH: &str = "/admin/replication/sync";
+
+// == Follower Status ==
+/// State of the link to the leader, shared with `/info`.
+#[derive(Debug)]
+pub struct FollowerStatus {
+    leader: String,
+    link: Mutex<Link>,
+    /// Set when a cluster failover makes this server a leader
+    promoted: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+struct Link {
+    up: bool,
+    /// Replid of the snapshot being loaded; it becomes `replid` once
+    /// complete, so an interrupted load is never resumed
+    loading: Option<String>,
+    replid: Option<String>,
+    offset: u64,
+    leader_offset: u64,
+    last_io: Option<Instant>,
+    full_syncs: u64,
+    partial_syncs: u64,
+}
+
+/// Snapshot of the follower's link reported in the replication section of
+/// `/info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowerInfo {
+    pub leader: String,
+    pub link_up: bool,
+    pub syncing: bool,
+    pub offset: u64,
+    pub leader_offset: u64,
+    pub last_io_secs: Option<u64>,
+    pub full_syncs: u64,
+    pub partial_syncs: u64,
+}
+
+impl FollowerStatus {
+    // == Constructor ==
+    /// Creates the status of a follower of `leader` (`host:port`).
+    pub fn new(leader: impl Into<String>) -> Self {
+        Self {
+            leader: leader.into(),
+            link: Mutex::new(Link::default()),
+            promoted: AtomicBool::new(false),
+        }
+    }
+
+    /// The leader's address.
+    pub fn leader(&self) -> &str {
+        &self.leader
+    }
+
+    /// Stops replicating and makes the server writable, once it has taken
+    /// over from a failed leader.
+    pub fn promote(&self) {
+        self.promoted.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true once the server has been promoted to leader.
+    pub fn is_promoted(&self) -> bool {
+        self.promoted.load(Ordering::SeqCst)
+    }
+
+    /// Returns the current link state.
+    pub fn info(&self) -> FollowerInfo {
+        let link = self.lock();
+        FollowerInfo {
+            leader: self.leader.clone(),
+            link_up: link.up,
+            syncing: link.loading.is_some(),
+            offset: link.offset,
+            leader_offset: link.leader_offset.max(link.offset),
+            last_io_secs: link.last_io.map(|at| at.elapsed().as_secs()),
+            full_syncs: link.full_syncs,
+            partial_syncs: link.partial_syncs,
+        }
+    }
+
+    /// The replid and offset to resume from, if a sync has completed.
+    fn resume_point(&self) -> Option<(String, u64)> {
+        let link = self.lock();
+        link.replid.clone().map(|replid| (replid, link.offset))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Link> {
+        self.link.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// == Follower Task ==
+/// Spawns the task replicating the leader into `databases`.
+///
+/// The leader is reached over TLS when `tls` is given, and over plain HTTP
+/// otherwise. The task reconnects after every failure and runs until
+/// aborted or the server is promoted.
+pub fn spawn_follower_task(
+    status: Arc<FollowerStatus>,
+    databases: Arc<Databases>,
+    auth_token: Option<String>,
+    tls: Option<Arc<ClientConfig>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Replicating from leader {}", status.leader());
+        let tls = tls.map(TlsConnector::from);
+        while !status.is_promoted() {
+            match follow(&status, &databases, auth_token.as_deref(), tls.as_ref()).await {
+                Ok(()) => info!("Leader {} closed the replication link", status.leader()),
+                Err(e) => warn!("Replication link to {} failed: {}", status.leader(), e),
+            }
+            {
+                let mut link = status.lock();
+                link.up = false;
+                link.loading = None;
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+        info!("Promoted to leader, stopped replicating from {}", status.leader());
+    })
+}
+
+/// Runs one connection to the leader until it closes or fails.
+async fn follow(
+    status: &FollowerStatus,
+    databases: &Databases,
+    auth_token: Option<&str>,
+    tls: Option<&TlsConnector>,
+) -> std::result::Result<(), String> {
+    let stream = TcpStream::connect(status.leader())
+        .await
+        .map_err(|e| format!("connect: {}", e))?;
+    let mut sender = match tls {
+        Some(tls) => {
+            let name = crate::tls::server_name(status.leader()).map_err(|e| e.to_string())?;
+            let stream = tls
+                .connect(name, stream)
+                .await
+                .map_err(|e| format!("TLS handshake: {}", e))?;
+            handshake(stream).await?
+        }
+        None => handshake(stream).await?,
+    };
+
+    let uri = match status.resume_point() {
+        Some((replid, offset)) => format!("{}?replid={}&offset={}", SYNC_PATH, replid, offset),
+        None => SYNC_PATH.to_string(),
+    };
+    let mut request = Request::get(uri).header(header::HOST, status.leader());
+    if let Some(token) = auth_token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let request = request
+        .body(Empty::<Bytes>::new())
+        .map_err(|e| e.to_string())?;
+
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| format!("request: {}", e))?;
+    if response.status() != StatusCode::OK {
+        return Err(format!("leader answered {}", response.status()));
+    }
+
+    let mut body = response.into_body();
+    let mut buffer = Vec::new();
+    let mut database = None;
+    loop {
+        let frame = tokio::time::timeout(LINK_TIMEOUT, body.frame())
+            .await
+            .map_err(|_| format!("no message for {:?}", LINK_TIMEOUT))?;
+        let Some(frame) = frame else {
+            return Ok(());
+        };
+        let Ok(data) = frame.map_err(|e| e.to_string())?.into_data() else {
+            continue;
+        };
+        buffer.extend_from_slice(&data);
+
+        let mut consumed = 0;
+        while let Some(end) = buffer[consumed..].iter().position(|&b| b == b'\n') {
+            let line = &buffer[consumed..consumed + end];
+            consumed += end + 1;
+            if status.is_promoted() {
+                return Ok(());
+            }
+            let message: Message =
+                serde_json::from_slice(line).map_err(|e| format!("invalid message: {}", e))?;
+            handle(status, databases, message, &mut database).await;
+        }
+        buffer.drain(..consumed);
+    }
+}
+
+/// Starts an HTTP/1 connection over `io`, driven by a background task.
+async fn handshake<T>(io: T) -> std::result::Result<SendRequest<Empty<Bytes>>, String>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(io))
+        .await
+        .map_err(|e| format!("handshake: {}", e))?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!("Replication connection closed: {}", e);
+        }
+    });
+    Ok(sender)
+}
+
+/// Applies one message, tracking the snapshot database in `database`.
+async fn handle(
+    status: &FollowerStatus,
+    databases: &Databases,
+    message: Message,
+    database: &mut Option<Arc<tokio::sync::RwLock<CacheStore>>>,
+) {
+    status.lock().last_io = Some(Instant::now());
+
+    match message {
+        Message::FullResync { replid, offset } => {
+            info!("Full resync from {} at offset {}", status.leader(), offset);
+            let removed = databases.flush_all(false).await;
+            debug!("Dropped {} entries before loading the snapshot", removed);
+            let mut link = status.lock();
+            link.loading = Some(replid);
+            link.replid = None;
+            link.offset = offset;
+            link.leader_offset = offset;
+            link.full_syncs += 1;
+        }
+        Message::Database {
+            name,
+            max_entries,
+            default_ttl,
+        } => {
+            *database = databases.get(&name).cloned();
+            match database {
+                Some(store) => {
+                    let mut store = store.write().await;
+                    store.set_default_ttl(default_ttl);
+                    store.set_max_entries(max_entries);
+                }
+                None => warn!(
+                    "Leader database '{}' is not configured here, skipping its keys",
+                    name
+                ),
+            }
+        }
+        Message::Entry {
+            key,
+            value,
+            expires_at,
+            grace_ms,
+            compute_ms,
+        } => {
+            if let Some(store) = database {
+                let entry = CacheEntry {
+                    grace_ms,
+                    compute_ms,
+                    ..CacheEntry::with_expires_at(value, expires_at)
+                };
+                if let Err(e) = store.write().await.insert_entry(key, entry) {
+                    warn!("Failed to load replicated key: {}", e);
+                }
+            }
+        }
+        Message::SnapshotEnd => {
+            let mut link = status.lock();
+            link.replid = link.loading.take();
+            link.up = true;
+            info!("Snapshot loaded, streaming from offset {}", link.offset);
+        }
+        Message::Continue { replid, offset } => {
+            info!("Resumed replication at offset {}", offset);
+            let mut link = status.lock();
+            link.replid = Some(replid);
+            link.up = true;
+            link.partial_syncs += 1;
+        }
+        Message::Command { offset, command } => {
+            if let Err(e) = apply(databases, command).await {
+                warn!("Failed to apply replicated command {}: {}", offset, e);
+            }
+            status.lock().offset = offset;
+        }
+        Message::Ping { offset } => status.lock().leader_offset = offset,
+    }
+}
+
+// == Apply ==
+/// Applies a leader's command to the local databases.
+///
+/// Deletions of keys that are already gone (e.g. expired locally) succeed.
+/// Changed limits normally evict nothing, since the leader sends its own
+/// evictions first.
+pub async fn apply(databases: &Databases, command: Command) -> Result<()> {
+    let store = |name: &str| {
+        databases
+            .get(name)
+            .cloned()
+            .ok_or_else(|| CacheError::InvalidRequest(format!("Unknown database '{}'", name)))
+    };
+
+    match command {
+        Command::Set {
+            db,
+            key,
+            value,
+            expires_at,
+            grace_ms,
+            compute_ms,
+        } => store(&db)?.write().await.insert_entry(
+            key,
+            CacheEntry {
+                grace_ms,
+                compute_ms,
+                ..CacheEntry::with_expires_at(value, expires_at)
+            },
+        ),
+        Command::Del { db, key } | Command::Expire { db, key } | Command::Evict { db, key } => {
+            match store(&db)?.write().await.delete(&key) {
+                Err(CacheError::NotFound(_)) | Ok(()) => Ok(()),
+                Err(e) => Err(e),
+            }
+        }
+        Command::Flush { db } => databases.flush(&db, false).await.map(|_| ()),
+        Command::SwapDb { db1, db2 } => databases.swap_contents(&db1, &db2).await,
+        Command::Limits {
+            db,
+            max_entries,
+            default_ttl,
+        } => {
+            let store = store(&db)?;
+            let mut store = store.write().await;
+            store.set_default_ttl(default_ttl);
+            store.set_max_entries(max_entries);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::DEFAULT_DATABASE;
+    use tokio::sync::RwLock;
+
+    fn databases() -> Databases {
+        let mut databases = Databases::new(Arc::new(RwLock::new(CacheStore::new(10, 300))));
+        databases.add("staging", CacheStore::new(10, 60)).unwrap();
+        databases
+    }
+
+    fn set(db: &str, key: &str) -> Command {
+        Command::Set {
+            db: db.to_string(),
+            key: key.to_string(),
+            value: "value".to_string(),
+            expires_at: None,
+            grace_ms: None,
+            compute_ms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_commands() {
+        let databases = databases();
+        apply(&databases, set(DEFAULT_DATABASE, "a")).await.unwrap();
+        apply(&databases, set(DEFAULT_DATABASE, "b")).await.unwrap();
+        apply(&databases, set("staging", "c")).await.unwrap();
+
+        let expire = Command::Expire {
+            db: DEFAULT_DATABASE.to_string(),
+            key: "a".to_string(),
+        };
+        apply(&databases, expire.clone()).await.unwrap();
+        // Already gone locally
+        apply(&databases, expire).await.unwrap();
+
+        let swap = Command::SwapDb {
+            db1: DEFAULT_DATABASE.to_string(),
+            db2: "staging".to_string(),
+        };
+        apply(&databases, swap).await.unwrap();
+
+        let default = databases.default_store().read().await;
+        assert!(default.contains_key("c"));
+        assert_eq!(default.len(), 1);
+        assert_eq!(default.default_ttl(), 300);
+        drop(default);
+
+        let limits = Command::Limits {
+            db: "staging".to_string(),
+            max_entries: 5,
+            default_ttl: 30,
+        };
+        apply(&databases, limits).await.unwrap();
+        let staging = databases.get("staging").unwrap().read().await;
+        assert!(staging.contains_key("b"));
+        assert_eq!(staging.max_entries(), 5);
+        assert_eq!(staging.default_ttl(), 30);
+        drop(staging);
+
+        assert!(apply(&databases, set("missing", "d")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resume_point_after_sync() {
+        let status = FollowerStatus::new("leader:3000");
+        let databases = databases();
+        assert_eq!(status.resume_point(), None);
+
+        let mut database = None;
+        let messages = [
+            Message::FullResync {
+                replid: "abc".to_string(),
+                offset: 4,
+            },
+            Message::Database {
+                name: DEFAULT_DATABASE.to_string(),
+                max_entries: 20,
+                default_ttl: 100,
+            },
+            Message::Entry {
+                key: "key".to_string(),
+                value: "value".to_string(),
+                expires_at: None,
+                grace_ms: None,
+                compute_ms: None,
+            },
+            Message::SnapshotEnd,
+            Message::Command {
+                offset: 5,
+                command: set(DEFAULT_DATABASE, "next"),
+            },
+            Message::Ping { offset: 7 },
+        ];
+        for message in messages {
+            handle(&status, &databases, message, &mut database).await;
+        }
+
+        assert_eq!(status.resume_point(), Some(("abc".to_string(), 5)));
+        let info = status.info();
+        assert!(info.link_up);
+        assert_eq!(info.leader_offset, 7);
+        assert_eq!(info.full_syncs, 1);
+
+        let default = databases.default_store().read().await;
+        assert_eq!(default.len(), 2);
+        assert_eq!(default.max_entries(), 20);
+    }
+}
@@ -0,0 +1,232 @@
+//! Leader Side
+//!
+//! Opens a follower's stream: the backlogged commands when the follower can
+//! resume, otherwise a snapshot of every database, then live commands and
+//! a heartbeat.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::{Body, Bytes};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, warn};
+
+use super::log::{ReplicationLog, ReplicationSink};
+use super::protocol::Message;
+use crate::cache::{CacheEntry, Databases};
+
+/// How often the leader sends its offset to idle followers
+pub const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Lines buffered between the stream task and the connection
+const STREAM_BUFFER: usize = 1024;
+
+// == Sync Stream ==
+/// The start of a follower's stream and its subscription to what follows.
+pub struct SyncStream {
+    /// Lines sent before any live command
+    pub preamble: Vec<Bytes>,
+    /// Commands recorded after the preamble
+    pub receiver: broadcast::Receiver<Bytes>,
+    /// True if the preamble is a full snapshot
+    pub full: bool,
+}
+
+/// Starts a follower's sync, resuming from `resume` (replid and offset)
+/// when the backlog still covers it.
+pub async fn start_sync(
+    databases: &Databases,
+    log: &Arc<ReplicationLog>,
+    resume: Option<(&str, u64)>,
+) -> SyncStream {
+    // Stores record commands from the first sync on
+    for (name, store) in databases.iter() {
+        let mut store = store.write().await;
+        if !store.is_replicated() {
+            store.set_replication(ReplicationSink::new(name, log.clone()));
+        }
+    }
+
+    if let Some((replid, offset)) = resume {
+        if let Some((missed, receiver)) = log.resume(replid, offset) {
+            log.record_sync(false);
+            let mut preamble = Vec::with_capacity(missed.len() + 1);
+            preamble.push(
+                Message::Continue {
+                    replid: log.replid().to_string(),
+                    offset,
+                }
+                .to_line(),
+            );
+            preamble.extend(missed);
+            return SyncStream {
+                preamble,
+                receiver,
+                full: false,
+            };
+        }
+        log.record_partial_sync_error();
+    }
+
+    log.record_sync(true);
+    snapshot(databases, log).await
+}
+
+/// Copies every entry that can still be served, stale ones included, while
+/// holding all store locks, so the snapshot matches the offset the
+/// subscription starts at. The copies are encoded once the locks are
+/// released.
+async fn snapshot(databases: &Databases, log: &ReplicationLog) -> SyncStream {
+    let mut copies: Vec<(Message, Vec<(String, CacheEntry)>)> = Vec::with_capacity(databases.len());
+    let (offset, receiver) = {
+        let mut stores = Vec::with_capacity(databases.len());
+        for (name, store) in databases.iter() {
+            stores.push((name, store.read().await));
+        }
+        for (name, store) in &stores {
+            let database = Message::Database {
+                name: name.to_string(),
+                max_entries: store.max_entries(),
+                default_ttl: store.default_ttl(),
+            };
+            let entries = store
+                .servable_entries()
+                .map(|(key, entry)| (key.to_string(), entry.clone()))
+                .collect();
+            copies.push((database, entries));
+        }
+        log.subscribe()
+    };
+
+    let mut preamble = vec![Message::FullResync {
+        replid: log.replid().to_string(),
+        offset,
+    }
+    .to_line()];
+    for (database, entries) in copies {
+        preamble.push(database.to_line());
+        for (key, entry) in entries {
+            preamble.push(
+                Message::Entry {
+                    key,
+                    value: entry.value,
+                    expires_at: entry.expires_at,
+                    grace_ms: entry.grace_ms,
+                    compute_ms: entry.compute_ms,
+                }
+                .to_line(),
+            );
+        }
+    }
+    preamble.push(Message::SnapshotEnd.to_line());
+
+    SyncStream {
+        preamble,
+        receiver,
+        full: true,
+    }
+}
+
+// == Streaming ==
+/// Turns a sync into a response body that stays open, forwarding live
+/// commands and a ping every `PING_INTERVAL` until the follower hangs up or
+/// falls too far behind.
+pub fn stream_body(sync: SyncStream, log: Arc<ReplicationLog>, follower: String) -> Body {
+    let (tx, rx) = mpsc::channel::<Result<Bytes, Infallible>>(STREAM_BUFFER);
+
+    tokio::spawn(async move {
+        let _connected = log.follower_connected();
+        let SyncStream {
+            preamble,
+            mut receiver,
+            full,
+        } = sync;
+        info!(
+            "Follower {} connected ({} sync)",
+            follower,
+            if full { "full" } else { "partial" }
+        );
+
+        for line in preamble {
+            if tx.send(Ok(line)).await.is_err() {
+                return;
+            }
+        }
+
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        loop {
+            let line = tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(line) => line,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(
+                            "Follower {} fell {} commands behind, disconnecting",
+                            follower, skipped
+                        );
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+                _ = ping.tick() => Message::Ping { offset: log.offset() }.to_line(),
+                _ = log.disconnected() => return,
+            };
+            if tx.send(Ok(line)).await.is_err() {
+                info!("Follower {} disconnected", follower);
+                return;
+            }
+        }
+    });
+
+    Body::from_stream(ReceiverStream::new(rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{current_timestamp_ms, CacheStore};
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_snapshot_includes_stale_entries() {
+        let store = Arc::new(RwLock::new(CacheStore::new(10, 300)));
+        let databases = Databases::new(store.clone());
+        let expired_at = Some(current_timestamp_ms() - 1_000);
+        {
+            let mut store = store.write().await;
+            store
+                .set("live".to_string(), "value".to_string(), None)
+                .unwrap();
+            let stale = CacheEntry::with_expires_at("old".to_string(), expired_at);
+            store
+                .insert_entry("stale".to_string(), stale.with_grace_ms(60_000))
+                .unwrap();
+            let gone = CacheEntry::with_expires_at("old".to_string(), expired_at);
+            store
+                .insert_entry("gone".to_string(), gone.with_grace_ms(500))
+                .unwrap();
+        }
+
+        let log = Arc::new(ReplicationLog::new(100));
+        let sync = start_sync(&databases, &log, None).await;
+        assert!(sync.full);
+
+        let mut keys = Vec::new();
+        for line in &sync.preamble {
+            let message: Message = serde_json::from_slice(line).unwrap();
+            if let Message::Entry { key, grace_ms, .. } = message {
+                keys.push((key, grace_ms));
+            }
+        }
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                ("live".to_string(), None),
+                ("stale".to_string(), Some(60_000)),
+            ]
+        );
+    }
+}
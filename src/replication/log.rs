@@ -0,0 +1,307 @@
+//! Replication Log
+//!
+//! Numbers every write command, keeps the most recent ones in a backlog for
+//! followers resuming after a short disconnect, and broadcasts them to the
+//! connected followers. Stores only record commands once a follower has
+//! synced, so a server without followers pays nothing for replication.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::body::Bytes;
+use tokio::sync::{broadcast, watch};
+
+use super::protocol::{Command, Message};
+use crate::random::random_u64;
+
+/// Upper bound on commands buffered per connected follower; followers
+/// falling further behind are disconnected and resume from the backlog
+const MAX_BROADCAST_CAPACITY: usize = 65_536;
+
+// == Replication Log ==
+/// The leader's numbered command stream.
+#[derive(Debug)]
+pub struct ReplicationLog {
+    /// Identifies this log; offsets are only comparable within one replid
+    replid: String,
+    /// Maximum number of commands kept in the backlog
+    capacity: usize,
+    /// Offset of the last command and the serialized recent commands
+    backlog: Mutex<Backlog>,
+    /// Live commands for connected followers
+    sender: broadcast::Sender<Bytes>,
+    /// Set on shutdown to end the follower streams
+    closed: watch::Sender<bool>,
+    /// Currently connected followers
+    followers: AtomicUsize,
+    /// Followers sent a full snapshot
+    full_syncs: AtomicU64,
+    /// Followers resumed from the backlog
+    partial_syncs: AtomicU64,
+    /// Resume requests the backlog could not serve
+    partial_sync_errors: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Backlog {
+    offset: u64,
+    lines: VecDeque<Bytes>,
+}
+
+/// Counters reported in the replication section of `/info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationLogInfo {
+    pub replid: String,
+    pub offset: u64,
+    pub backlog_size: usize,
+    pub backlog_len: usize,
+    pub backlog_first_offset: u64,
+    pub connected_followers: usize,
+    pub full_syncs: u64,
+    pub partial_syncs: u64,
+    pub partial_sync_errors: u64,
+}
+
+impl ReplicationLog {
+    // == Constructor ==
+    /// Creates an empty log keeping up to `capacity` commands.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.clamp(1, MAX_BROADCAST_CAPACITY));
+        Self {
+            replid: new_replid(),
+            capacity,
+            backlog: Mutex::new(Backlog::default()),
+            sender,
+            closed: watch::channel(false).0,
+            followers: AtomicUsize::new(0),
+            full_syncs: AtomicU64::new(0),
+            partial_syncs: AtomicU64::new(0),
+            partial_sync_errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the id followers present to resume this log.
+    pub fn replid(&self) -> &str {
+        &self.replid
+    }
+
+    /// Returns the offset of the last recorded command.
+    pub fn offset(&self) -> u64 {
+        self.lock().offset
+    }
+
+    // == Append ==
+    /// Records a command under the next offset and sends it to the
+    /// connected followers.
+    pub fn append(&self, command: Command) {
+        let mut backlog = self.lock();
+        backlog.offset += 1;
+        let line = Message::Command {
+            offset: backlog.offset,
+            command,
+        }
+        .to_line();
+
+        if backlog.lines.len() >= self.capacity {
+            backlog.lines.pop_front();
+        }
+        backlog.lines.push_back(line.clone());
+        // Nobody may be listening between followers
+        let _ = self.sender.send(line);
+    }
+
+    // == Subscribe ==
+    /// Subscribes to commands after the current offset, which is returned.
+    ///
+    /// Used for full resyncs; callers hold every store lock so the snapshot
+    /// they take matches the offset.
+    pub fn subscribe(&self) -> (u64, broadcast::Receiver<Bytes>) {
+        let backlog = self.lock();
+        (backlog.offset, self.sender.subscribe())
+    }
+
+    /// Returns the backlogged commands after `offset` and a subscription to
+    /// the ones that follow, or None if the follower cannot resume.
+    pub fn resume(
+        &self,
+        replid: &str,
+        offset: u64,
+    ) -> Option<(Vec<Bytes>, broadcast::Receiver<Bytes>)> {
+        let backlog = self.lock();
+        let first = backlog.offset - backlog.lines.len() as u64;
+        if replid != self.replid || offset < first || offset > backlog.offset {
+            return None;
+        }
+
+        let missed = backlog
+            .lines
+            .iter()
+            .skip((offset - first) as usize)
+            .cloned()
+            .collect();
+        Some((missed, self.sender.subscribe()))
+    }
+
+    // == Statistics ==
+    /// Counts a follower sync; `full` for snapshots, otherwise a resume.
+    pub fn record_sync(&self, full: bool) {
+        let counter = if full {
+            &self.full_syncs
+        } else {
+            &self.partial_syncs
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a resume request the backlog could not serve.
+    pub fn record_partial_sync_error(&self) {
+        self.partial_sync_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Marks a follower as connected until the returned guard is dropped.
+    pub fn follower_connected(self: &Arc<Self>) -> FollowerGuard {
+        self.followers.fetch_add(1, Ordering::Relaxed);
+        FollowerGuard(self.clone())
+    }
+
+    /// Ends every follower stream, so graceful shutdown does not wait on
+    /// connections that never finish by themselves.
+    pub fn disconnect_followers(&self) {
+        self.closed.send_replace(true);
+    }
+
+    /// Resolves once `disconnect_followers` has been called.
+    pub async fn disconnected(&self) {
+        let _ = self.closed.subscribe().wait_for(|&closed| closed).await;
+    }
+
+    /// Returns the current counters.
+    pub fn info(&self) -> ReplicationLogInfo {
+        let (offset, backlog_len) = {
+            let backlog = self.lock();
+            (backlog.offset, backlog.lines.len())
+        };
+        ReplicationLogInfo {
+            replid: self.replid.clone(),
+            offset,
+            backlog_size: self.capacity,
+            backlog_len,
+            backlog_first_offset: offset - backlog_len as u64 + 1,
+            connected_followers: self.followers.load(Ordering::Relaxed),
+            full_syncs: self.full_syncs.load(Ordering::Relaxed),
+            partial_syncs: self.partial_syncs.load(Ordering::Relaxed),
+            partial_sync_errors: self.partial_sync_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Backlog> {
+        self.backlog.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Decrements the connected follower count when the stream ends.
+pub struct FollowerGuard(Arc<ReplicationLog>);
+
+impl Drop for FollowerGuard {
+    fn drop(&mut self) {
+        self.0.followers.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns a random 32 character hex id.
+fn new_replid() -> String {
+    format!("{:016x}{:016x}", random_u64(), random_u64())
+}
+
+// == Replication Sink ==
+/// Connects a store to the replication log under its database name.
+#[derive(Debug, Clone)]
+pub struct ReplicationSink {
+    db: String,
+    log: Arc<ReplicationLog>,
+}
+
+impl ReplicationSink {
+    /// Creates a sink recording commands of database `db` into `log`.
+    pub fn new(db: impl Into<String>, log: Arc<ReplicationLog>) -> Self {
+        Self { db: db.into(), log }
+    }
+
+    /// Name of the database the store holds.
+    pub fn db(&self) -> &str {
+        &self.db
+    }
+
+    /// Appends the command built from this sink's database name.
+    pub fn record(&self, command: impl FnOnce(String) -> Command) {
+        self.log.append(command(self.db.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn del(key: &str) -> Command {
+        Command::Del {
+            db: "0".to_string(),
+            key: key.to_string(),
+        }
+    }
+
+    fn offsets(lines: &[Bytes]) -> Vec<u64> {
+        lines
+            .iter()
+            .map(|line| match serde_json::from_slice(line).unwrap() {
+                Message::Command { offset, .. } => offset,
+                other => panic!("unexpected message {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_backlog_keeps_latest_commands() {
+        let log = ReplicationLog::new(3);
+        for i in 0..5 {
+            log.append(del(&format!("key{}", i)));
+        }
+
+        let info = log.info();
+        assert_eq!(info.offset, 5);
+        assert_eq!(info.backlog_len, 3);
+        assert_eq!(info.backlog_first_offset, 3);
+
+        // Offsets 3..=5 are kept, so a follower at 2 can resume
+        let (missed, _) = log.resume(log.replid(), 2).unwrap();
+        assert_eq!(offsets(&missed), vec![3, 4, 5]);
+        let (missed, _) = log.resume(log.replid(), 5).unwrap();
+        assert!(missed.is_empty());
+
+        assert!(log.resume(log.replid(), 1).is_none());
+        assert!(log.resume(log.replid(), 6).is_none());
+        assert!(log.resume("another-log", 4).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_new_commands() {
+        let log = ReplicationLog::new(10);
+        log.append(del("before"));
+
+        let (offset, mut receiver) = log.subscribe();
+        assert_eq!(offset, 1);
+        log.append(del("after"));
+
+        let line = receiver.recv().await.unwrap();
+        assert_eq!(offsets(&[line]), vec![2]);
+    }
+
+    #[test]
+    fn test_follower_guard_counts_connections() {
+        let log = Arc::new(ReplicationLog::new(10));
+        let guard = log.follower_connected();
+        assert_eq!(log.info().connected_followers, 1);
+        drop(guard);
+        assert_eq!(log.info().connected_followers, 0);
+    }
+}
@@ -0,0 +1,22 @@
+//! Replication Module
+//!
+//! Leader-follower replication over the HTTP API. A follower requests
+//! `GET /admin/replication/sync` from its leader and receives a snapshot,
+//! or only the commands it missed if the leader's backlog still holds
+//! them, followed by every write command as it happens.
+//!
+//! # Components
+//! - `protocol`: Messages and commands of the replication stream
+//! - `log`: The leader's numbered command log and backlog
+//! - `leader`: Snapshots and the streaming response
+//! - `follower`: The task applying a leader's stream
+
+mod follower;
+mod leader;
+mod log;
+mod protocol;
+
+pub use follower::{apply, spawn_follower_task, FollowerInfo, FollowerStatus, SYNC_PATH};
+pub use leader::{start_sync, stream_body, SyncStream, PING_INTERVAL};
+pub use log::{FollowerGuard, ReplicationLog, ReplicationLogInfo, ReplicationSink};
+pub use protocol::{Command, Message};
@@ -0,0 +1,120 @@
+//! Replication Protocol
+//!
+//! The leader streams newline-delimited JSON messages to each follower:
+//! either a snapshot of every database or a `continue` marker, then every
+//! write command with its offset, and a `ping` every second carrying the
+//! leader's current offset.
+
+use axum::body::Bytes;
+use serde::{Deserialize, Serialize};
+
+// == Command ==
+/// A change to one database, replayed by followers in offset order.
+///
+/// Expirations and evictions are sent as commands too, so followers
+/// converge on the leader's data set instead of deciding on their own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Command {
+    /// A key was set; `expires_at` is absolute (Unix milliseconds),
+    /// `grace_ms` is the stale-grace window after it and `compute_ms` the
+    /// time the value took to compute
+    Set {
+        db: String,
+        key: String,
+        value: String,
+        expires_at: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        grace_ms: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compute_ms: Option<u64>,
+    },
+    /// A key was deleted
+    Del { db: String, key: String },
+    /// A key's TTL elapsed
+    Expire { db: String, key: String },
+    /// A key was evicted to make room
+    Evict { db: String, key: String },
+    /// Every key of a database was removed
+    Flush { db: String },
+    /// Two databases swapped their contents
+    SwapDb { db1: String, db2: String },
+    /// A database's capacity or default TTL changed
+    Limits {
+        db: String,
+        max_entries: usize,
+        default_ttl: u64,
+    },
+}
+
+// == Message ==
+/// One line of the replication stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// Start of a snapshot of the leader's data set as of `offset`
+    FullResync { replid: String, offset: u64 },
+    /// Snapshot: the following entries belong to this database
+    Database {
+        name: String,
+        max_entries: usize,
+        default_ttl: u64,
+    },
+    /// Snapshot: one live key
+    Entry {
+        key: String,
+        value: String,
+        expires_at: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        grace_ms: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compute_ms: Option<u64>,
+    },
+    /// End of the snapshot
+    SnapshotEnd,
+    /// The follower's offset is still in the backlog; the commands after
+    /// it follow
+    Continue { replid: String, offset: u64 },
+    /// A write command
+    Command { offset: u64, command: Command },
+    /// Heartbeat with the leader's current offset
+    Ping { offset: u64 },
+}
+
+impl Message {
+    /// Serializes the message as one line of the stream.
+    pub fn to_line(&self) -> Bytes {
+        let mut line = serde_json::to_vec(self).expect("replication messages serialize");
+        line.push(b'\n');
+        Bytes::from(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = Message::Command {
+            offset: 7,
+            command: Command::Set {
+                db: "0".to_string(),
+                key: "key".to_string(),
+                value: "value".to_string(),
+                expires_at: Some(1_700_000_000_000),
+                grace_ms: Some(30_000),
+                compute_ms: Some(250),
+            },
+        };
+
+        let line = message.to_line();
+        assert!(line.ends_with(b"\n"));
+        assert!(line.starts_with(br#"{"type":"command","offset":7,"command":{"op":"set""#));
+        let parsed: Message = serde_json::from_slice(&line).unwrap();
+        assert_eq!(parsed, message);
+
+        let parsed: Message = serde_json::from_str(r#"{"type":"snapshot_end"}"#).unwrap();
+        assert_eq!(parsed, Message::SnapshotEnd);
+    }
+}
@@ -8,7 +8,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use tokio::task::JoinHandle;
 use tracing::{debug, info};
 
@@ -42,17 +42,42 @@ pub fn spawn_cleanup_task(
     cache: Arc<RwLock<CacheStore>>,
     cleanup_interval_secs: u64,
 ) -> JoinHandle<()> {
-    let interval = Duration::from_secs(cleanup_interval_secs);
+    let (_, interval) = watch::channel(cleanup_interval_secs);
+    spawn_cleanup_task_with_interval(cache, interval)
+}
 
+/// Spawns the cleanup task with an interval that can change while it runs.
+///
+/// A new value on `interval` cancels the pending sleep and reschedules the
+/// next cleanup run using the new interval. Once the sender is dropped the
+/// last interval is kept.
+pub fn spawn_cleanup_task_with_interval(
+    cache: Arc<RwLock<CacheStore>>,
+    mut interval: watch::Receiver<u64>,
+) -> JoinHandle<()> {
     tokio::spawn(async move {
+        let mut interval_secs = *interval.borrow_and_update();
+        let mut watching = true;
         info!(
             "Starting TTL cleanup task with interval of {} seconds",
-            cleanup_interval_secs
+            interval_secs
         );
 
         loop {
-            // Sleep for the configured interval
-            tokio::time::sleep(interval).await;
+            tokio::select! {
+                // Sleep for the configured interval
+                _ = tokio::time::sleep(Duration::from_secs(interval_secs)) => {}
+                changed = interval.changed(), if watching => {
+                    match changed {
+                        Ok(()) => {
+                            interval_secs = *interval.borrow_and_update();
+                            info!("TTL cleanup task rescheduled to every {} seconds", interval_secs);
+                        }
+                        Err(_) => watching = false,
+                    }
+                    continue;
+                }
+            }
 
             // Acquire write lock and cleanup expired entries
             let removed = {
@@ -134,6 +159,30 @@ mod tests {
         handle.abort();
     }
 
+    #[tokio::test]
+    async fn test_cleanup_task_reschedules_on_interval_change() {
+        let cache = Arc::new(RwLock::new(CacheStore::new(100, 300)));
+        {
+            let mut cache_guard = cache.write().await;
+            cache_guard
+                .set("expire_soon".to_string(), "value".to_string(), Some(1))
+                .unwrap();
+        }
+
+        // Start with an interval far longer than the test
+        let (interval_tx, interval_rx) = watch::channel(3600);
+        let handle = spawn_cleanup_task_with_interval(cache.clone(), interval_rx);
+
+        interval_tx.send(1).unwrap();
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+
+        // Without the reschedule no run would have happened yet
+        assert!(cache.read().await.stats().cleanup_runs >= 1);
+        assert_eq!(cache.read().await.len(), 0);
+
+        handle.abort();
+    }
+
     #[tokio::test]
     async fn test_cleanup_task_can_be_aborted() {
         let cache = Arc::new(RwLock::new(CacheStore::new(100, 300)));
@@ -4,10 +4,15 @@
 //!
 //! # Tasks
 //! - TTL Cleanup: Removes expired cache entries at configured intervals
+//! - TLS Reload: Reloads the certificate and key on SIGHUP (Unix only)
 //!
 //! # Requirements
 //! - Validates: Requirements 2.3, 2.5, 8.5
 
 mod cleanup;
+#[cfg(unix)]
+mod tls_reload;
 
-pub use cleanup::spawn_cleanup_task;
+pub use cleanup::{spawn_cleanup_task, spawn_cleanup_task_with_interval};
+#[cfg(unix)]
+pub use tls_reload::spawn_tls_reload_task;
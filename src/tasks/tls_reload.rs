@@ -0,0 +1,35 @@
+//! TLS Reload Task
+//!
+//! Background task that reloads the TLS certificate and key on SIGHUP.
+
+use std::sync::Arc;
+
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::tls::ReloadableTlsConfig;
+
+/// Spawns a task that reloads `tls` from disk every time SIGHUP arrives.
+///
+/// Connections that already completed their handshake keep the old
+/// certificate; new connections use the reloaded one. A failed reload is
+/// logged and the previous certificate stays in use.
+///
+/// # Panics
+/// Panics if the SIGHUP handler cannot be installed.
+pub fn spawn_tls_reload_task(tls: Arc<ReloadableTlsConfig>) -> JoinHandle<()> {
+    let mut hangup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            match tls.reload() {
+                Ok(()) => info!(
+                    "Reloaded TLS certificate from {}",
+                    tls.settings().cert_path.display()
+                ),
+                Err(e) => error!("TLS reload failed, keeping previous certificate: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,394 @@
+//! TLS Termination
+//!
+//! Loads a rustls server configuration from PEM files and serves the router
+//! over TLS. The configuration can be swapped at runtime (on SIGHUP) without
+//! affecting connections that have already completed their handshake.
+
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use axum::extract::ConnectInfo;
+use axum::Router;
+use hyper::body::Incoming;
+use hyper::Request;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+use crate::listener::ACCEPT_ERROR_BACKOFF;
+use crate::monitor::ServerMetrics;
+
+/// Time allowed for a client to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// == TLS Error ==
+/// Errors raised while loading certificates and keys.
+#[derive(Debug, Error)]
+pub enum TlsError {
+    /// A certificate, key or CA file could not be read
+    #[error("Failed to read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+
+    /// The certificate file contained no PEM certificates
+    #[error("No certificates found in {}", .0.display())]
+    NoCertificates(PathBuf),
+
+    /// The key file contained no PEM private key
+    #[error("No private key found in {}", .0.display())]
+    NoPrivateKey(PathBuf),
+
+    /// The client CA bundle could not be turned into a verifier
+    #[error("Invalid client CA: {0}")]
+    ClientCa(String),
+
+    /// A host name cannot be checked against a server certificate
+    #[error("Invalid server name '{0}'")]
+    ServerName(String),
+
+    /// rustls rejected the certificate/key pair
+    #[error("TLS configuration error: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+// == TLS Settings ==
+/// Paths to the PEM files used for TLS termination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsSettings {
+    /// Server certificate chain, leaf first
+    pub cert_path: PathBuf,
+    /// Private key for the leaf certificate (PKCS#8, PKCS#1 or SEC1)
+    pub key_path: PathBuf,
+    /// CA bundle for verifying client certificates; enables mutual TLS
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl TlsSettings {
+    /// Builds a rustls server configuration from the configured files.
+    ///
+    /// Clients must present a certificate signed by the client CA when one
+    /// is configured. Both HTTP/2 and HTTP/1.1 are offered via ALPN.
+    pub fn load(&self) -> Result<ServerConfig, TlsError> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?;
+
+        let builder = match &self.client_ca_path {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(path)? {
+                    roots.add(cert)?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .map_err(|e| TlsError::ClientCa(e.to_string()))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder.with_single_cert(
+            load_certs(&self.cert_path)?,
+            load_private_key(&self.key_path)?,
+        )?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(config)
+    }
+}
+
+// == Client Config ==
+/// Builds a rustls client configuration for connecting to another server,
+/// such as a replication leader, over TLS.
+///
+/// The server must present a certificate issued by one of the CAs in
+/// `ca_path`. When `identity` is given, its certificate is presented to
+/// servers requiring mutual TLS. Only HTTP/1.1 is offered via ALPN.
+pub fn client_config(
+    ca_path: &Path,
+    identity: Option<&TlsSettings>,
+) -> Result<ClientConfig, TlsError> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(ca_path)? {
+        roots.add(cert)?;
+    }
+    let builder =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots);
+
+    let mut config = match identity {
+        Some(identity) => builder.with_client_auth_cert(
+            load_certs(&identity.cert_path)?,
+            load_private_key(&identity.key_path)?,
+        )?,
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// Returns the name the certificate of the server at `address`
+/// (`host:port`) must be valid for.
+pub fn server_name(address: &str) -> Result<ServerName<'static>, TlsError> {
+    let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    ServerName::try_from(host.to_string()).map_err(|_| TlsError::ServerName(host.to_string()))
+}
+
+/// Reads every PEM certificate in `path`.
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let pem = read_file(path)?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|source| TlsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(path.to_path_buf()));
+    }
+    Ok(certs)
+}
+
+/// Reads the first PEM private key in `path`.
+fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, TlsError> {
+    let pem = read_file(path)?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|source| TlsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?
+        .ok_or_else(|| TlsError::NoPrivateKey(path.to_path_buf()))
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, TlsError> {
+    std::fs::read(path).map_err(|source| TlsError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+// == Reloadable TLS Config ==
+/// The active server configuration, replaceable while the server runs.
+///
+/// Each handshake uses the configuration current at accept time, so a
+/// reload only affects new connections.
+#[derive(Debug)]
+pub struct ReloadableTlsConfig {
+    settings: TlsSettings,
+    current: RwLock<Arc<ServerConfig>>,
+}
+
+impl ReloadableTlsConfig {
+    /// Loads the initial configuration, failing if the files are invalid.
+    pub fn new(settings: TlsSettings) -> Result<Self, TlsError> {
+        let config = settings.load()?;
+        Ok(Self {
+            settings,
+            current: RwLock::new(Arc::new(config)),
+        })
+    }
+
+    /// Re-reads the certificate, key and client CA from disk.
+    ///
+    /// On error the previous configuration stays in use.
+    pub fn reload(&self) -> Result<(), TlsError> {
+        let config = self.settings.load()?;
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(config);
+        Ok(())
+    }
+
+    /// Returns the settings the configuration is loaded from.
+    pub fn settings(&self) -> &TlsSettings {
+        &self.settings
+    }
+
+    /// Returns an acceptor for the current configuration.
+    pub fn acceptor(&self) -> TlsAcceptor {
+        let config = self.current.read().unwrap_or_else(|e| e.into_inner());
+        TlsAcceptor::from(config.clone())
+    }
+}
+
+// == Serve TLS ==
+/// Serves `app` over TLS on `listener` until `shutdown` completes.
+///
+/// After shutdown no new connections are accepted and in-flight
+/// connections are allowed to finish, like `axum::serve`. The peer address
+/// is available to handlers as `ConnectInfo<SocketAddr>`. Open connections
+/// are counted in `metrics` from the end of the handshake.
+pub async fn serve_tls<F>(
+    listener: TcpListener,
+    app: Router,
+    tls: Arc<ReloadableTlsConfig>,
+    metrics: Arc<ServerMetrics>,
+    shutdown: F,
+) -> io::Result<()>
+where
+    F: Future<Output = ()>,
+{
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(connection) => connection,
+                Err(e) => {
+                    warn!("Failed to accept connection: {}", e);
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let acceptor = tls.acceptor();
+        let service =
+            TowerToHyperService::new(app.clone().map_request(move |mut req: Request<Incoming>| {
+                req.extensions_mut().insert(ConnectInfo(peer));
+                req
+            }));
+        let watcher = graceful.watcher();
+        let metrics = metrics.clone();
+
+        tokio::spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        debug!("TLS handshake with {} failed: {}", peer, e);
+                        return;
+                    }
+                    Err(_) => {
+                        debug!("TLS handshake with {} timed out", peer);
+                        return;
+                    }
+                };
+
+            let _connected = metrics.connection_opened();
+            let builder = Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            if let Err(e) = watcher.watch(connection.into_owned()).await {
+                debug!("Connection with {} closed with error: {}", peer, e);
+            }
+        });
+    }
+
+    info!("Waiting for open TLS connections to finish");
+    graceful.shutdown().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a fresh self-signed certificate and key to a temporary directory.
+    fn self_signed(dir: &Path) -> TlsSettings {
+        let rcgen::CertifiedKey { cert, key_pair } =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        std::fs::create_dir_all(dir).unwrap();
+        let settings = TlsSettings {
+            cert_path: dir.join("cert.pem"),
+            key_path: dir.join("key.pem"),
+            client_ca_path: None,
+        };
+        std::fs::write(&settings.cert_path, cert.pem()).unwrap();
+        std::fs::write(&settings.key_path, key_pair.serialize_pem()).unwrap();
+        settings
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mini_redis_tls_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_load_self_signed() {
+        let settings = self_signed(&temp_dir("load"));
+
+        let config = settings.load().unwrap();
+        assert_eq!(config.alpn_protocols.len(), 2);
+    }
+
+    #[test]
+    fn test_load_missing_files() {
+        let settings = TlsSettings {
+            cert_path: PathBuf::from("/nonexistent/cert.pem"),
+            key_path: PathBuf::from("/nonexistent/key.pem"),
+            client_ca_path: None,
+        };
+
+        assert!(matches!(settings.load(), Err(TlsError::Io { .. })));
+    }
+
+    #[test]
+    fn test_load_rejects_empty_pem() {
+        let dir = temp_dir("empty");
+        let mut settings = self_signed(&dir);
+        let empty = dir.join("empty.pem");
+        std::fs::write(&empty, "").unwrap();
+
+        settings.key_path = empty.clone();
+        assert!(matches!(settings.load(), Err(TlsError::NoPrivateKey(_))));
+
+        settings.cert_path = empty;
+        assert!(matches!(settings.load(), Err(TlsError::NoCertificates(_))));
+    }
+
+    #[test]
+    fn test_reload_keeps_previous_config_on_error() {
+        let dir = temp_dir("reload");
+        let settings = self_signed(&dir);
+        let tls = ReloadableTlsConfig::new(settings.clone()).unwrap();
+        let before = tls.acceptor().config().clone();
+
+        std::fs::write(&settings.key_path, "not a key").unwrap();
+        assert!(tls.reload().is_err());
+        assert!(Arc::ptr_eq(&before, tls.acceptor().config()));
+
+        self_signed(&dir);
+        tls.reload().unwrap();
+        assert!(!Arc::ptr_eq(&before, tls.acceptor().config()));
+    }
+
+    #[test]
+    fn test_client_config() {
+        let settings = self_signed(&temp_dir("client"));
+
+        let config = client_config(&settings.cert_path, None).unwrap();
+        assert_eq!(config.alpn_protocols, vec![b"http/1.1".to_vec()]);
+        assert!(!config.client_auth_cert_resolver.has_certs());
+        let config = client_config(&settings.cert_path, Some(&settings)).unwrap();
+        assert!(config.client_auth_cert_resolver.has_certs());
+        assert!(matches!(
+            client_config(&settings.key_path, None),
+            Err(TlsError::NoCertificates(_))
+        ));
+    }
+
+    #[test]
+    fn test_server_name() {
+        assert_eq!(
+            server_name("localhost:3000").unwrap(),
+            ServerName::try_from("localhost").unwrap()
+        );
+        assert_eq!(
+            server_name("[::1]:3000").unwrap(),
+            ServerName::try_from("::1").unwrap()
+        );
+        assert!(server_name("bad host:3000").is_err());
+    }
+}
@@ -7,11 +7,17 @@
 
 use axum::{
     body::Body,
+    extract::ConnectInfo,
     http::{Request, StatusCode},
     Router,
 };
-use mini_redis::{api::create_router, cache::CacheStore, AppState};
+use mini_redis::{
+    api::create_router,
+    cache::{CacheStore, DatabaseConfig},
+    AppState, Config,
+};
 use serde_json::Value;
+use std::net::SocketAddr;
 use std::thread::sleep;
 use std::time::Duration;
 use tower::ServiceExt;
@@ -402,3 +408,792 @@ async fn test_ttl_expiration_via_api() {
 
     assert_eq!(get_response.status(), StatusCode::NOT_FOUND);
 }
+
+// == SLOWLOG Endpoint Tests ==
+
+#[tokio::test]
+async fn test_slowlog_records_and_resets() {
+    // A zero threshold logs every operation
+    let config = Config {
+        slowlog_threshold_us: 0,
+        ..Config::default()
+    };
+    let app = create_router(AppState::from_config(&config));
+
+    let _ = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/set")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"key":"slow_key","value":"slow_value"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/slowlog?count=10")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["len"].as_u64().unwrap(), 1);
+    assert_eq!(json["entries"][0]["operation"].as_str().unwrap(), "set");
+    assert_eq!(json["entries"][0]["key"].as_str().unwrap(), "slow_key");
+    assert_eq!(json["entries"][0]["value_size"].as_u64().unwrap(), 10);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/admin/slowlog")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["cleared"].as_u64().unwrap(), 1);
+}
+
+// == HOTKEYS / BIGKEYS Endpoint Tests ==
+
+#[tokio::test]
+async fn test_hotkeys_and_bigkeys_endpoints() {
+    let app = create_test_app();
+
+    for (key, value) in [("hot", "v".to_string()), ("big", "x".repeat(4096))] {
+        let body = serde_json::json!({ "key": key, "value": value }).to_string();
+        let _ = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri("/set")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+    for _ in 0..3 {
+        let _ = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/get/hot")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/admin/hotkeys?count=1")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["keys"][0]["key"].as_str().unwrap(), "hot");
+    assert_eq!(json["keys"][0]["count"].as_u64().unwrap(), 4);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/admin/bigkeys")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["keys"][0]["key"].as_str().unwrap(), "big");
+    assert_eq!(json["keys"][0]["size"].as_u64().unwrap(), 4096);
+}
+
+// == INFO Endpoint Tests ==
+
+#[tokio::test]
+async fn test_info_endpoint_json() {
+    let app = create_test_app();
+
+    let _ = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/set")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"key":"info_key","value":"info_value","ttl":60}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/info")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["keyspace"]["keys"].as_u64().unwrap(), 1);
+    assert_eq!(json["keyspace"]["expires"].as_u64().unwrap(), 1);
+    assert!(json["memory"]["used_memory"].as_u64().unwrap() > 0);
+    assert_eq!(json["clients"]["active_requests"].as_u64().unwrap(), 1);
+    assert_eq!(json["clients"]["total_requests"].as_u64().unwrap(), 2);
+    assert!(!json["persistence"]["enabled"].as_bool().unwrap());
+}
+
+#[tokio::test]
+async fn test_info_endpoint_unknown_section() {
+    let app = create_test_app();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/info?section=bogus")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// == KEY INTROSPECTION Endpoint Tests ==
+
+#[tokio::test]
+async fn test_key_info_endpoint() {
+    let app = create_test_app();
+
+    let _ = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/set")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"key":"debug_key","value":"12345","ttl":60}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/key/debug_key")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["encoding"].as_str().unwrap(), "int");
+    assert_eq!(json["access_count"].as_u64().unwrap(), 0);
+    assert_eq!(json["eviction_rank"].as_u64().unwrap(), 0);
+    assert!(json["ttl_ms"].as_u64().unwrap() <= 60_000);
+
+    // Introspection must not count as a hit
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/stats")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["hits"].as_u64().unwrap(), 0);
+    assert_eq!(json["misses"].as_u64().unwrap(), 0);
+}
+
+// == AUTHENTICATION Tests ==
+
+fn create_auth_app(exempt_health: bool) -> Router {
+    let config = Config {
+        auth_tokens: vec!["token-a".to_string(), "token-b".to_string()],
+        auth_exempt_health: exempt_health,
+        ..Config::default()
+    };
+    create_router(AppState::from_config(&config))
+}
+
+#[tokio::test]
+async fn test_auth_rejects_missing_and_invalid_tokens() {
+    let app = create_auth_app(true);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/get/some_key")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let json = body_to_json(response.into_body()).await;
+    assert!(json.get("error").is_some());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/get/some_key")
+                .header("authorization", "Bearer wrong")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_auth_accepts_any_configured_token() {
+    let app = create_auth_app(true);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/set")
+                .header("content-type", "application/json")
+                .header("authorization", "Bearer token-b")
+                .body(Body::from(r#"{"key":"auth_key","value":"auth_value"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_auth_health_exemption() {
+    let request = || {
+        Request::builder()
+            .method("GET")
+            .uri("/health")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = create_auth_app(true).oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = create_auth_app(false).oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+// == ACL Tests ==
+
+fn create_acl_app() -> Router {
+    let config = Config {
+        auth_tokens: vec!["root-token".to_string()],
+        acl_users: mini_redis::acl::AclUser::parse_list(
+            "billing|billing-token|read,write|billing:*;viewer|viewer-token|read|*",
+        )
+        .unwrap(),
+        ..Config::default()
+    };
+    create_router(AppState::from_config(&config))
+}
+
+fn set_request(token: &str, key: &str) -> Request<Body> {
+    Request::builder()
+        .method("PUT")
+        .uri("/set")
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::from(format!(r#"{{"key":"{}","value":"v"}}"#, key)))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_acl_enforces_key_patterns() {
+    let app = create_acl_app();
+
+    let response = app
+        .clone()
+        .oneshot(set_request("billing-token", "billing:invoice:1"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(set_request("billing-token", "users:1"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/del/users:1")
+                .header("authorization", "Bearer billing-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Denials are counted in /stats
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/stats")
+                .header("authorization", "Bearer root-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["acl_denials"].as_u64().unwrap(), 2);
+    assert_eq!(json["total_entries"].as_u64().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_acl_enforces_permissions() {
+    let app = create_acl_app();
+
+    let response = app
+        .clone()
+        .oneshot(set_request("viewer-token", "anything"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/slowlog")
+                .header("authorization", "Bearer billing-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/slowlog")
+                .header("authorization", "Bearer root-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_acl_whoami() {
+    let app = create_acl_app();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/acl/whoami")
+                .header("authorization", "Bearer billing-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["name"].as_str().unwrap(), "billing");
+    assert_eq!(json["permissions"], serde_json::json!(["read", "write"]));
+    assert_eq!(json["key_patterns"], serde_json::json!(["billing:*"]));
+    assert!(json.get("token").is_none());
+}
+
+// == Rate Limit Tests ==
+
+fn create_rate_limited_app() -> Router {
+    let config = Config {
+        rate_limit: Some("1/2".parse().unwrap()),
+        route_rate_limits: mini_redis::ratelimit::RouteRateLimit::parse_list("/health=off")
+            .unwrap(),
+        ..Config::default()
+    };
+    create_router(AppState::from_config(&config))
+}
+
+fn get_from(ip: [u8; 4], uri: &str) -> Request<Body> {
+    Request::builder()
+        .method("GET")
+        .uri(uri)
+        .extension(ConnectInfo(SocketAddr::from((ip, 40000))))
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_rate_limit_rejects_after_burst() {
+    let app = create_rate_limited_app();
+    let client = [10, 0, 0, 1];
+
+    for _ in 0..2 {
+        let response = app.clone().oneshot(get_from(client, "/stats")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = app.clone().oneshot(get_from(client, "/stats")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "1");
+    let json = body_to_json(response.into_body()).await;
+    assert!(json["error"].as_str().unwrap().contains("Rate limit"));
+
+    // Unlimited routes and other clients are unaffected
+    let response = app.clone().oneshot(get_from(client, "/health")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(get_from([10, 0, 0, 2], "/stats"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["rate_limited"], 1);
+}
+
+// == Runtime Config Tests ==
+
+fn patch_config(body: &str) -> Request<Body> {
+    Request::builder()
+        .method("PATCH")
+        .uri("/admin/config")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_config_get_and_patch() {
+    let app = create_test_app();
+    for i in 0..10 {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri("/set")
+                    .header("content-type", "application/json")
+                    .body(Body::from(format!(r#"{{"key":"key{}","value":"v"}}"#, i)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/admin/config")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["max_entries"], 100);
+    assert_eq!(json["default_ttl"], 300);
+
+    // Shrinking the capacity evicts down to the new limit
+    let response = app
+        .clone()
+        .oneshot(patch_config(r#"{"max_entries":4,"cleanup_interval":15}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["max_entries"], 4);
+    assert_eq!(json["cleanup_interval"], 15);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/stats")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["total_entries"], 4);
+    assert_eq!(json["evictions"], 6);
+
+    // Unknown and invalid settings are rejected
+    let response = app
+        .clone()
+        .oneshot(patch_config(r#"{"server_port":7000}"#))
+        .await
+        .unwrap();
+    assert!(response.status().is_client_error());
+
+    let response = app
+        .oneshot(patch_config(r#"{"max_entries":0}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_config_rewrite() {
+    let rewrite = || {
+        Request::builder()
+            .method("POST")
+            .uri("/admin/config/rewrite")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Without a config file there is nothing to rewrite
+    let response = create_test_app().oneshot(rewrite()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let path = std::env::temp_dir().join(format!(
+        "mini_redis_config_rewrite_{}.toml",
+        std::process::id()
+    ));
+    std::fs::write(&path, "# Cache settings\nport = 6380\n").unwrap();
+    let config = Config {
+        config_file: Some(path.clone()),
+        ..Config::default()
+    };
+    let app = create_router(AppState::from_config(&config));
+
+    let response = app
+        .clone()
+        .oneshot(patch_config(r#"{"default_ttl":60}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.oneshot(rewrite()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(contents.starts_with("# Cache settings\nport = 6380\n"));
+    assert!(contents.contains("default_ttl = 60"));
+}
+
+// == Database Tests ==
+
+fn create_databases_app() -> Router {
+    let config = Config {
+        databases: DatabaseConfig::parse_list("staging=2/60,archive").unwrap(),
+        ..Config::default()
+    };
+    create_router(AppState::from_config(&config))
+}
+
+fn json_request(method: &str, uri: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_databases_are_isolated() {
+    let app = create_databases_app();
+
+    let response = app
+        .clone()
+        .oneshot(json_request(
+            "PUT",
+            "/db/staging/set",
+            r#"{"key":"k","value":"staged"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The default database does not see the key
+    let response = app.clone().oneshot(get("/get/k")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app.clone().oneshot(get("/db/staging/get/k")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["value"], "staged");
+
+    // The header selects a database for unprefixed routes
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/get/k")
+                .header("x-database", "staging")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.clone().oneshot(get("/db/staging/stats")).await.unwrap();
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["total_entries"], 1);
+    assert_eq!(json["hits"], 2);
+
+    let response = app.oneshot(get("/db/missing/get/k")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_flushdb_and_flushall() {
+    let app = create_databases_app();
+    for uri in ["/set", "/db/staging/set", "/db/archive/set"] {
+        let response = app
+            .clone()
+            .oneshot(json_request("PUT", uri, r#"{"key":"k","value":"v"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(json_request("POST", "/db/staging/flush", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["removed"], 1);
+
+    let response = app.clone().oneshot(get("/get/k")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(json_request("POST", "/admin/flush?reset_stats=true", ""))
+        .await
+        .unwrap();
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["removed"], 2);
+
+    let response = app.clone().oneshot(get("/db/archive/get/k")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Limits survive the flush, counters were reset
+    let response = app.oneshot(get("/admin/databases")).await.unwrap();
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["databases"][0]["hits"], 0);
+    assert_eq!(json["databases"][1]["max_entries"], 2);
+}
+
+#[tokio::test]
+async fn test_move_and_swapdb() {
+    let app = create_databases_app();
+    let response = app
+        .clone()
+        .oneshot(json_request("PUT", "/set", r#"{"key":"k","value":"v"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(json_request("POST", "/db/0/move/k", r#"{"db":"archive"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    assert_eq!(json["moved"], true);
+
+    let response = app.clone().oneshot(get("/get/k")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .clone()
+        .oneshot(json_request(
+            "POST",
+            "/admin/swapdb",
+            r#"{"db1":"0","db2":"archive"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.clone().oneshot(get("/get/k")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.oneshot(get("/admin/databases")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_to_json(response.into_body()).await;
+    let databases = json["databases"].as_array().unwrap();
+    assert_eq!(databases.len(), 3);
+    assert_eq!(databases[0]["name"], "0");
+    assert_eq!(databases[0]["keys"], 1);
+    assert_eq!(databases[1]["max_entries"], 2);
+    assert_eq!(databases[1]["default_ttl"], 60);
+}
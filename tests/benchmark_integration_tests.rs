@@ -0,0 +1,109 @@
+//! Integration Tests for mini-redis-benchmark
+//!
+//! Runs short benchmarks in-process and against a server on a local port,
+//! and checks the JSON reports.
+
+mod common;
+
+use serde_json::Value;
+use tokio::process::Command;
+
+use common::start_server;
+
+// == Helper Functions ==
+
+/// Runs the benchmark with JSON output and returns the report.
+async fn benchmark(args: &[&str]) -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_mini-redis-benchmark"))
+        .args(args)
+        .args(["--format", "json"])
+        .env_remove("MINI_REDIS_URL")
+        .env_remove("MINI_REDIS_AUTH_TOKEN")
+        .output()
+        .await
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+// == Benchmark Tests ==
+
+#[tokio::test]
+async fn test_in_process_benchmark() {
+    let report = benchmark(&[
+        "--in-process",
+        "-n",
+        "2000",
+        "-c",
+        "4",
+        "-k",
+        "200",
+        "-r",
+        "0.8",
+    ])
+    .await;
+
+    assert_eq!(report["target"], "in-process");
+    assert_eq!(report["requests"], 2000);
+    assert_eq!(report["errors"], 0);
+    // Every key was written before measuring
+    assert_eq!(report["hit_rate"], 1.0);
+    let get = report["latency"]["get"]["count"].as_u64().unwrap();
+    let set = report["latency"]["set"]["count"].as_u64().unwrap();
+    assert_eq!(get + set, 2000);
+    assert!((1400..1800).contains(&get), "{}", get);
+    let p50 = report["latency"]["all"]["p50_us"].as_f64().unwrap();
+    let p99 = report["latency"]["all"]["p99_us"].as_f64().unwrap();
+    assert!(p50 <= p99);
+}
+
+#[tokio::test]
+async fn test_server_benchmark() {
+    let (url, state) = start_server().await;
+
+    let report = benchmark(&[
+        "--url",
+        &url,
+        "-n",
+        "500",
+        "-c",
+        "8",
+        "-k",
+        "50",
+        "--distribution",
+        "uniform",
+        "-v",
+        "10-100",
+    ])
+    .await;
+
+    assert_eq!(report["target"], url);
+    assert_eq!(report["requests"], 500);
+    assert_eq!(report["errors"], 0);
+    assert_eq!(report["workload"]["distribution"], "uniform");
+    assert_eq!(report["workload"]["zipf_exponent"], Value::Null);
+    assert_eq!(state.cache.read().await.len(), 50);
+}
+
+#[tokio::test]
+async fn test_benchmark_without_preload_misses() {
+    let report = benchmark(&[
+        "--in-process",
+        "-n",
+        "1000",
+        "-k",
+        "100000",
+        "-r",
+        "1",
+        "--no-preload",
+    ])
+    .await;
+
+    assert_eq!(report["requests"], 1000);
+    assert_eq!(report["hit_rate"], 0.0);
+    assert_eq!(report["latency"]["set"]["count"], 0);
+}
@@ -0,0 +1,149 @@
+//! Integration Tests for mini-redis-cli
+//!
+//! Runs the CLI binary against a server on a local port, in one-shot and
+//! pipe mode.
+
+mod common;
+
+use std::process::{Output, Stdio};
+
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use common::start_server;
+
+// == Helper Functions ==
+
+fn cli(url: &str) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_mini-redis-cli"));
+    command
+        .arg("--url")
+        .arg(url)
+        .env_remove("MINI_REDIS_AUTH_TOKEN");
+    command
+}
+
+/// Runs one command and returns its output.
+async fn run(url: &str, args: &[&str]) -> Output {
+    cli(url).args(args).output().await.unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string()
+}
+
+// == One-Shot Tests ==
+
+#[tokio::test]
+async fn test_one_shot_commands() {
+    let (url, state) = start_server().await;
+
+    let output = run(&url, &["set", "foo", "bar baz", "--ttl", "60"]).await;
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "OK");
+    assert!(state.cache.read().await.contains_key("foo"));
+
+    assert_eq!(stdout(&run(&url, &["get", "foo"]).await), r#""bar baz""#);
+    assert_eq!(
+        stdout(&run(&url, &["--format", "raw", "get", "foo"]).await),
+        "bar baz"
+    );
+    let json: Value =
+        serde_json::from_str(&stdout(&run(&url, &["-f", "json", "get", "foo"]).await)).unwrap();
+    assert_eq!(json["value"], "bar baz");
+
+    assert_eq!(stdout(&run(&url, &["ttl", "foo"]).await), "(integer) 60");
+    assert_eq!(
+        stdout(&run(&url, &["ttl", "missing"]).await),
+        "(integer) -2"
+    );
+    assert_eq!(stdout(&run(&url, &["get", "missing"]).await), "(nil)");
+    assert_eq!(
+        stdout(&run(&url, &["del", "foo", "missing"]).await),
+        "(integer) 1"
+    );
+    assert!(stdout(&run(&url, &["stats"]).await).contains("hits: "));
+    assert_eq!(stdout(&run(&url, &["ping"]).await), "PONG");
+
+    // Errors go to stderr with a failing exit status
+    let output = run(&url, &["set", "", "value"]).await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("(error) "));
+    let output = run(&url, &["frobnicate"]).await;
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn test_one_shot_scan() {
+    let (url, state) = start_server().await;
+    for i in 0..25 {
+        state
+            .cache
+            .write()
+            .await
+            .set(format!("user:{}", i), "v".to_string(), None)
+            .unwrap();
+    }
+    state
+        .cache
+        .write()
+        .await
+        .set("item:1".to_string(), "v".to_string(), None)
+        .unwrap();
+
+    let mut keys = Vec::new();
+    let mut cursor = "0".to_string();
+    loop {
+        let output = run(
+            &url,
+            &[
+                "-f", "json", "scan", &cursor, "match", "user:*", "count", "10",
+            ],
+        )
+        .await;
+        let page: Value = serde_json::from_str(&stdout(&output)).unwrap();
+        keys.extend(page["keys"].as_array().unwrap().clone());
+        cursor = page["cursor"].as_str().unwrap().to_string();
+        if cursor == "0" {
+            break;
+        }
+    }
+    assert_eq!(keys.len(), 25);
+}
+
+// == Pipe Tests ==
+
+#[tokio::test]
+async fn test_pipe_mode_loads_stdin() {
+    let (url, state) = start_server().await;
+
+    let mut input = String::new();
+    for i in 0..500 {
+        input.push_str(&format!("set key:{} 'value {}'\n", i, i));
+    }
+    input.push_str("\nset broken\ndel key:0\n");
+
+    let mut child = cli(&url)
+        .arg("--pipe")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes()).await.unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().await.unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "errors: 1, replies: 501");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 502: usage: set"));
+
+    // Commands run concurrently, so the DEL may come before the SET of key:0
+    let mut store = state.cache.write().await;
+    assert!(store.len() >= 499);
+    assert_eq!(store.get("key:499").unwrap(), "value 499");
+}
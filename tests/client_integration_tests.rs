@@ -0,0 +1,224 @@
+//! Integration Tests for the Client
+//!
+//! Runs servers on local ports and checks the typed client against them:
+//! commands, retries while a server is starting, cluster redirects, and
+//! consistent-hash sharding over several servers.
+
+mod common;
+
+use std::time::Duration;
+
+use mini_redis::cache::CacheStore;
+use mini_redis::client::{Client, ClientError, ClientOptions, RetryPolicy, ShardedClient};
+use mini_redis::cluster::{ClusterNode, SlotRange};
+use mini_redis::models::SetRequest;
+use mini_redis::{AppState, Config};
+use tokio::net::TcpListener;
+
+use common::{serve_on, start_server};
+
+// == Client Tests ==
+
+#[tokio::test]
+async fn test_client_commands() {
+    let (url, state) = start_server().await;
+    let client = Client::new(&url).unwrap();
+
+    assert_eq!(client.health().await.unwrap().status, "healthy");
+    let response = client
+        .set(&SetRequest::new("user:1 name", "alice").with_ttl(60))
+        .await
+        .unwrap();
+    assert_eq!(response.key, "user:1 name");
+    assert!(state.cache.read().await.contains_key("user:1 name"));
+
+    let value = client.get("user:1 name").await.unwrap().unwrap();
+    assert_eq!(value.value, "alice");
+    assert!(client.get("missing").await.unwrap().is_none());
+
+    assert!(client.delete("user:1 name").await.unwrap());
+    assert!(!client.delete("user:1 name").await.unwrap());
+    let stats = client.stats().await.unwrap();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 1);
+
+    // Errors carry the server's message
+    match client.set(&SetRequest::new("", "value")).await {
+        Err(ClientError::Server {
+            status: 400,
+            message,
+        }) => {
+            assert!(message.contains("empty"), "{}", message)
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_client_retries_until_the_server_is_up() {
+    // Reserve a port, then free it so the first attempts are refused
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let options = ClientOptions {
+        retry: RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(100),
+        },
+        ..ClientOptions::default()
+    };
+    let client = Client::with_options(&addr.to_string(), options).unwrap();
+
+    let no_retries = ClientOptions {
+        retry: RetryPolicy::none(),
+        ..ClientOptions::default()
+    };
+    let impatient = Client::with_options(&addr.to_string(), no_retries).unwrap();
+    assert!(matches!(
+        impatient.health().await,
+        Err(ClientError::Connection { .. })
+    ));
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let listener = TcpListener::bind(addr).await.unwrap();
+        serve_on(listener, AppState::new(CacheStore::new(100, 300)));
+    });
+    client.set(&SetRequest::new("key", "value")).await.unwrap();
+    assert_eq!(client.get("key").await.unwrap().unwrap().value, "value");
+}
+
+#[tokio::test]
+async fn test_client_follows_cluster_redirects() {
+    let listeners = [
+        TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        TcpListener::bind("127.0.0.1:0").await.unwrap(),
+    ];
+    let table: Vec<ClusterNode> = listeners
+        .iter()
+        .zip(["a", "b"])
+        .zip(["0-8191", "8192-16383"])
+        .map(|((listener, id), slots)| ClusterNode {
+            id: id.to_string(),
+            address: listener.local_addr().unwrap().to_string(),
+            slots: SlotRange::parse_list(slots).unwrap(),
+        })
+        .collect();
+    let mut states = Vec::new();
+    for (listener, node) in listeners.into_iter().zip(&table) {
+        let config = Config {
+            cluster_node_id: Some(node.id.clone()),
+            cluster_nodes: table.clone(),
+            ..Config::default()
+        };
+        let state = AppState::from_config(&config);
+        serve_on(listener, state.clone());
+        states.push(state);
+    }
+
+    // "foo" hashes to slot 12182, served by b
+    let client = Client::new(&table[0].address).unwrap();
+    client.set(&SetRequest::new("foo", "bar")).await.unwrap();
+    assert!(states[1].cache.read().await.contains_key("foo"));
+    assert!(!states[0].cache.read().await.contains_key("foo"));
+    assert_eq!(client.get("foo").await.unwrap().unwrap().value, "bar");
+}
+
+#[tokio::test]
+async fn test_client_sends_token_only_to_trusted_hosts() {
+    let listeners = [
+        TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        TcpListener::bind("127.0.0.1:0").await.unwrap(),
+    ];
+    let table: Vec<ClusterNode> = listeners
+        .iter()
+        .zip(["a", "b"])
+        .zip(["0-8191", "8192-16383"])
+        .map(|((listener, id), slots)| ClusterNode {
+            id: id.to_string(),
+            address: listener.local_addr().unwrap().to_string(),
+            slots: SlotRange::parse_list(slots).unwrap(),
+        })
+        .collect();
+    for (listener, node) in listeners.into_iter().zip(&table) {
+        let config = Config {
+            cluster_node_id: Some(node.id.clone()),
+            cluster_nodes: table.clone(),
+            auth_tokens: vec!["s3cret".to_string()],
+            ..Config::default()
+        };
+        serve_on(listener, AppState::from_config(&config));
+    }
+
+    // "foo" hashes to slot 12182, so a redirects to b
+    let untrusting = ClientOptions {
+        auth_token: Some("s3cret".to_string()),
+        ..ClientOptions::default()
+    };
+    let client = Client::with_options(&table[0].address, untrusting.clone()).unwrap();
+    assert!(matches!(
+        client.set(&SetRequest::new("foo", "bar")).await,
+        Err(ClientError::Server { status: 401, .. })
+    ));
+
+    let trusting = ClientOptions {
+        trusted_hosts: vec![table[1].address.clone()],
+        ..untrusting
+    };
+    let client = Client::with_options(&table[0].address, trusting).unwrap();
+    client.set(&SetRequest::new("foo", "bar")).await.unwrap();
+    assert_eq!(client.get("foo").await.unwrap().unwrap().value, "bar");
+}
+
+// == Sharding Tests ==
+
+#[tokio::test]
+async fn test_sharded_client_spreads_keys() {
+    let mut servers = Vec::new();
+    for _ in 0..3 {
+        servers.push(start_server().await);
+    }
+    let urls: Vec<String> = servers.iter().map(|(url, _)| url.clone()).collect();
+    let mut client = ShardedClient::new(&urls).unwrap();
+
+    let keys: Vec<String> = (0..300).map(|i| format!("key:{}", i)).collect();
+    for key in &keys {
+        client.set(&SetRequest::new(key, key)).await.unwrap();
+    }
+    // Every key is on exactly the server the ring picked
+    for (url, state) in &servers {
+        let store = state.cache.read().await;
+        let held = keys.iter().filter(|key| store.contains_key(key)).count();
+        assert!(held > 50, "{} holds {} keys", url, held);
+        for key in &keys {
+            assert_eq!(store.contains_key(key), client.server_for(key) == Some(url));
+        }
+    }
+    for key in &keys {
+        assert_eq!(client.get(key).await.unwrap().unwrap().value, *key);
+    }
+    let total: u64 = client
+        .stats()
+        .await
+        .into_iter()
+        .map(|(_, stats)| stats.unwrap().total_entries as u64)
+        .sum();
+    assert_eq!(total, 300);
+
+    // A fourth server only takes over part of the keys
+    let (url, _) = start_server().await;
+    let before: Vec<String> = keys
+        .iter()
+        .map(|key| client.server_for(key).unwrap().to_string())
+        .collect();
+    assert!(client.add_server(&url).unwrap());
+    let moved = keys
+        .iter()
+        .zip(&before)
+        .filter(|(key, before)| client.server_for(key) != Some(before.as_str()))
+        .count();
+    assert!(moved > 0 && moved < 150, "{} of 300 keys moved", moved);
+}
@@ -0,0 +1,303 @@
+//! Integration Tests for Cluster Membership and Failover
+//!
+//! Runs nodes on local ports with their cluster bus and gossip task, and
+//! checks that nodes joined through one seed discover the whole cluster,
+//! and that a follower takes over when its leader is cut off from the
+//! other nodes by an injected partition.
+
+mod common;
+
+use std::net::SocketAddr;
+
+use mini_redis::cluster::{bus_router, key_slot, spawn_gossip_task, ClusterNode, SlotRange};
+use mini_redis::replication::spawn_follower_task;
+use mini_redis::{AppState, Config};
+use reqwest::{redirect::Policy, StatusCode};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+use common::{eventually, serve_on};
+
+// == Helper Functions ==
+
+/// Short enough for a failover to finish within a few seconds
+const NODE_TIMEOUT_MS: u64 = 300;
+
+struct Node {
+    addr: SocketAddr,
+    bus: SocketAddr,
+    state: AppState,
+}
+
+struct Listeners {
+    api: TcpListener,
+    bus: TcpListener,
+}
+
+impl Listeners {
+    async fn bind() -> Self {
+        Self {
+            api: TcpListener::bind("127.0.0.1:0").await.unwrap(),
+            bus: TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        }
+    }
+
+    fn address(&self) -> String {
+        self.api.local_addr().unwrap().to_string()
+    }
+}
+
+/// Starts a node with its API, cluster bus, gossip and, for followers,
+/// replication.
+async fn start_node(listeners: Listeners, config: Config) -> Node {
+    let config = Config {
+        cluster_bus_port: Some(listeners.bus.local_addr().unwrap().port()),
+        cluster_node_timeout: NODE_TIMEOUT_MS,
+        ..config
+    };
+    config.validate().unwrap();
+    let state = AppState::from_config(&config);
+    let cluster = state.cluster.clone().unwrap();
+
+    if let Some(follower) = &state.follower {
+        spawn_follower_task(follower.clone(), state.databases.clone(), None, None);
+    }
+    spawn_gossip_task(cluster.clone(), state.follower.clone(), None);
+
+    let addr = listeners.api.local_addr().unwrap();
+    let bus = listeners.bus.local_addr().unwrap();
+    serve_on(listeners.api, state.clone());
+    tokio::spawn(async move { axum::serve(listeners.bus, bus_router(cluster, None)).await });
+    Node { addr, bus, state }
+}
+
+fn table_entry(id: &str, listeners: &Listeners, slots: &str) -> ClusterNode {
+    ClusterNode {
+        id: id.to_string(),
+        address: listeners.address(),
+        slots: SlotRange::parse_list(slots).unwrap(),
+    }
+}
+
+async fn cluster_nodes(client: &reqwest::Client, node: &Node) -> Value {
+    client
+        .get(format!("http://{}/cluster/nodes", node.addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+/// Health of the node `id` as seen by `node`.
+async fn health(client: &reqwest::Client, node: &Node, id: &str) -> String {
+    let body = cluster_nodes(client, node).await;
+    body["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["id"] == id)
+        .map(|entry| entry["health"].as_str().unwrap().to_string())
+        .unwrap_or_default()
+}
+
+/// Cuts (or restores) every link between `isolated` and the other nodes.
+fn partition(nodes: &[(&str, &Node)], isolated: &str, cut: bool) {
+    for (id, node) in nodes {
+        let cluster = node.state.cluster.as_ref().unwrap();
+        if *id == isolated {
+            for (other, _) in nodes.iter().filter(|(other, _)| other != id) {
+                cluster.set_unreachable(other, cut).unwrap();
+            }
+        } else {
+            cluster.set_unreachable(isolated, cut).unwrap();
+        }
+    }
+}
+
+// == Membership Tests ==
+
+#[tokio::test]
+async fn test_nodes_discover_the_cluster_through_one_seed() {
+    let listeners = [
+        Listeners::bind().await,
+        Listeners::bind().await,
+        Listeners::bind().await,
+    ];
+    let seed = listeners[0].bus.local_addr().unwrap().to_string();
+    let slots = ["0-5460", "5461-10922", "10923-16383"];
+
+    // Every node only knows itself, and b and c know a's bus address
+    let mut nodes = Vec::new();
+    for (i, listeners) in listeners.into_iter().enumerate() {
+        let id = ["a", "b", "c"][i];
+        let config = Config {
+            cluster_node_id: Some(id.to_string()),
+            cluster_nodes: vec![table_entry(id, &listeners, slots[i])],
+            cluster_meet: if i == 0 {
+                Vec::new()
+            } else {
+                vec![seed.clone()]
+            },
+            ..Config::default()
+        };
+        nodes.push(start_node(listeners, config).await);
+    }
+
+    eventually("a complete slot table on every node", || async {
+        nodes.iter().all(|node| {
+            let cluster = node.state.cluster.as_ref().unwrap();
+            cluster.is_complete() && cluster.nodes().len() == 3
+        })
+    })
+    .await;
+
+    let client = reqwest::Client::new();
+    let body = cluster_nodes(&client, &nodes[2]).await;
+    let entries = body["nodes"].as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    for entry in entries {
+        assert_eq!(entry["health"], "online");
+        assert_eq!(entry["role"], "leader");
+    }
+    let b = entries.iter().find(|entry| entry["id"] == "b").unwrap();
+    assert_eq!(b["bus_address"], nodes[1].bus.to_string());
+    assert_eq!(b["slots"], json!(["5461-10922"]));
+    assert!(body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|event| event["event"] == "joined" && event["node"] == "b"));
+
+    // Redirects reach nodes that were only discovered by gossip
+    let key = "foo";
+    assert_eq!(key_slot(key), 12182);
+    let response = client
+        .put(format!("http://{}/set", nodes[0].addr))
+        .json(&json!({"key": key, "value": "bar"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(nodes[2].state.cache.read().await.contains_key(key));
+}
+
+// == Failover Tests ==
+
+#[tokio::test]
+async fn test_follower_takes_over_a_partitioned_leader() {
+    let listeners = [
+        Listeners::bind().await,
+        Listeners::bind().await,
+        Listeners::bind().await,
+        Listeners::bind().await,
+    ];
+    let table = vec![
+        table_entry("a", &listeners[0], "0-5460"),
+        table_entry("b", &listeners[1], "5461-10922"),
+        table_entry("c", &listeners[2], "10923-16383"),
+        table_entry("d", &listeners[3], ""),
+    ];
+    let leader = listeners[0].address();
+    // Bus ports are not the defaults, so every node meets the others
+    let buses: Vec<String> = listeners
+        .iter()
+        .map(|listeners| listeners.bus.local_addr().unwrap().to_string())
+        .collect();
+
+    let mut nodes = Vec::new();
+    for (i, listeners) in listeners.into_iter().enumerate() {
+        let config = Config {
+            cluster_node_id: Some(table[i].id.clone()),
+            cluster_nodes: table.clone(),
+            cluster_meet: buses.clone(),
+            // d replicates a
+            replica_of: (i == 3).then(|| leader.clone()),
+            ..Config::default()
+        };
+        nodes.push(start_node(listeners, config).await);
+    }
+    let [a, b, c, d] = &nodes[..] else {
+        unreachable!()
+    };
+    let named = [("a", a), ("b", b), ("c", c), ("d", d)];
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap();
+
+    // "bar" hashes to slot 5061, served by a
+    let response = client
+        .put(format!("http://{}/set", a.addr))
+        .json(&json!({"key": "bar", "value": "before"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    eventually("the key replicated to d", || async {
+        d.state.cache.read().await.contains_key("bar")
+    })
+    .await;
+    eventually("b knowing d follows a", || async {
+        let body = cluster_nodes(&client, b).await;
+        let entry = &body["nodes"][3];
+        entry["role"] == "follower" && entry["leader"] == "a"
+    })
+    .await;
+
+    partition(&named, "a", true);
+    eventually("a failed on b", || async {
+        health(&client, b, "a").await == "fail"
+    })
+    .await;
+    eventually("d serving a's slots", || async {
+        d.state.cluster.as_ref().unwrap().slots_served() == 5461
+    })
+    .await;
+    eventually("a's slots moved to d on c", || async {
+        c.state.cluster.as_ref().unwrap().owner(5061).unwrap().id == "d"
+    })
+    .await;
+
+    // d now takes writes for the slots and kept the replicated data
+    let response = client
+        .put(format!("http://{}/set", d.addr))
+        .json(&json!({"key": "bar", "value": "after"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = cluster_nodes(&client, d).await;
+    let myself = &body["nodes"][3];
+    assert_eq!(myself["role"], "leader");
+    assert_eq!(myself["slots"], json!(["0-5460"]));
+    assert!(body["current_epoch"].as_u64().unwrap() >= 1);
+    let events: Vec<&str> = body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["event"].as_str().unwrap())
+        .collect();
+    assert!(events.contains(&"promoted"), "{:?}", events);
+    assert!(events.contains(&"fail"), "{:?}", events);
+
+    // Once the partition heals, a learns it lost its slots
+    partition(&named, "a", false);
+    eventually("a redirecting to d", || async {
+        a.state.cluster.as_ref().unwrap().slots_served() == 0
+    })
+    .await;
+    eventually("a online again on b", || async {
+        health(&client, b, "a").await == "online"
+    })
+    .await;
+    let response = client
+        .get(format!("http://{}/get/bar", a.addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["address"], d.addr.to_string());
+}
@@ -0,0 +1,549 @@
+//! Integration Tests for Cluster Mode
+//!
+//! Runs a three-node cluster on local ports and checks that keys are stored
+//! on the node serving their slot, with requests to the other nodes
+//! redirected there, and that slots can be migrated between nodes while
+//! they are serving.
+
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use mini_redis::cluster::{key_slot, start_migration, ClusterNode, MigrationState, SlotRange};
+use mini_redis::tls::{client_config, serve_tls, ReloadableTlsConfig, TlsSettings};
+use mini_redis::{api::create_router, AppState, Config};
+use reqwest::{redirect::Policy, StatusCode};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+use common::serve_on;
+
+// == Helper Functions ==
+
+/// Slots of each node: a third of the keyspace each
+const SLOTS: [&str; 3] = ["0-5460", "5461-10922", "10923-16383"];
+
+struct Node {
+    addr: SocketAddr,
+    state: AppState,
+}
+
+/// Starts one server per entry of `slots`, named `a`, `b`, `c`...
+async fn start_cluster(slots: &[&str]) -> Vec<Node> {
+    let mut listeners = Vec::new();
+    let mut table = Vec::new();
+    for (i, slots) in slots.iter().enumerate() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        table.push(ClusterNode {
+            id: ((b'a' + i as u8) as char).to_string(),
+            address: listener.local_addr().unwrap().to_string(),
+            slots: SlotRange::parse_list(slots).unwrap(),
+        });
+        listeners.push(listener);
+    }
+
+    let mut nodes = Vec::new();
+    for (listener, node) in listeners.into_iter().zip(&table) {
+        let config = Config {
+            cluster_node_id: Some(node.id.clone()),
+            cluster_nodes: table.clone(),
+            ..Config::default()
+        };
+        config.validate().unwrap();
+        let state = AppState::from_config(&config);
+        let addr = listener.local_addr().unwrap();
+        serve_on(listener, state.clone());
+        nodes.push(Node { addr, state });
+    }
+    nodes
+}
+
+/// Index of the node serving `key` with the default `SLOTS`.
+fn owner(key: &str) -> usize {
+    match key_slot(key) {
+        0..=5460 => 0,
+        5461..=10922 => 1,
+        _ => 2,
+    }
+}
+
+async fn stored_on(node: &Node, key: &str) -> bool {
+    node.state.cache.read().await.contains_key(key)
+}
+
+// == Cluster Tests ==
+
+#[tokio::test]
+async fn test_keys_are_stored_on_their_slot_owner() {
+    let nodes = start_cluster(&SLOTS).await;
+    // Follows redirects, resending the body of PUT /set
+    let client = reqwest::Client::new();
+
+    for key in ["foo", "bar", "user:1", "user:2", "session:42"] {
+        let response = client
+            .put(format!("http://{}/set", nodes[0].addr))
+            .json(&json!({"key": key, "value": format!("value of {}", key)}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", key);
+
+        for (i, node) in nodes.iter().enumerate() {
+            assert_eq!(stored_on(node, key).await, i == owner(key), "{}", key);
+        }
+
+        // Any node answers through the redirect
+        let body: Value = client
+            .get(format!("http://{}/get/{}", nodes[1].addr, key))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["value"], format!("value of {}", key));
+    }
+}
+
+#[tokio::test]
+async fn test_redirect_names_the_serving_node() {
+    let nodes = start_cluster(&SLOTS).await;
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap();
+
+    // "foo" hashes to slot 12182, served by the third node
+    let response = client
+        .get(format!("http://{}/get/foo?x=1", nodes[0].addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(
+        response.headers()["location"],
+        format!("http://{}/get/foo?x=1", nodes[2].addr).as_str()
+    );
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], format!("MOVED 12182 {}", nodes[2].addr));
+    assert_eq!(body["slot"], 12182);
+    assert_eq!(body["address"], nodes[2].addr.to_string());
+
+    // Keys in the node's own slots are served directly
+    let response = client
+        .put(format!("http://{}/set", nodes[2].addr))
+        .json(&json!({"key": "foo", "value": "bar"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Hash tags keep related keys on one node
+    let owners: Vec<usize> = ["{user:7}:profile", "{user:7}:cart", "{user:7}"]
+        .iter()
+        .map(|key| owner(key))
+        .collect();
+    assert!(owners.iter().all(|&o| o == owners[0]));
+}
+
+#[tokio::test]
+async fn test_cluster_slots_endpoint() {
+    let nodes = start_cluster(&SLOTS).await;
+    let client = reqwest::Client::new();
+
+    for node in &nodes {
+        let body: Value = client
+            .get(format!("http://{}/cluster/slots", node.addr))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let slots = body["slots"].as_array().unwrap();
+        assert_eq!(slots.len(), 3);
+        for (i, range) in slots.iter().enumerate() {
+            assert_eq!(range["address"], nodes[i].addr.to_string());
+        }
+        assert_eq!(slots[1]["start"], 5461);
+        assert_eq!(slots[1]["end"], 10922);
+    }
+
+    let body: Value = client
+        .get(format!("http://{}/cluster/keyslot/foo", nodes[0].addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["slot"], 12182);
+    assert_eq!(body["node"], "c");
+
+    let info: Value = client
+        .get(format!("http://{}/info?section=cluster", nodes[1].addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(info["cluster"]["cluster_enabled"], true);
+    assert_eq!(info["cluster"]["cluster_state"], "ok");
+    assert_eq!(info["cluster"]["cluster_my_id"], "b");
+    assert_eq!(info["cluster"]["cluster_slots_served"], 5462);
+}
+
+#[tokio::test]
+async fn test_unassigned_slots_are_rejected() {
+    // Only the lower half of the keyspace is served
+    let nodes = start_cluster(&["0-8191"]).await;
+    let client = reqwest::Client::new();
+
+    // "bar" hashes to slot 5061, "foo" to 12182
+    let response = client
+        .put(format!("http://{}/set", nodes[0].addr))
+        .json(&json!({"key": "bar", "value": "served"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = client
+        .get(format!("http://{}/get/foo", nodes[0].addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let info: Value = client
+        .get(format!("http://{}/info?section=cluster", nodes[0].addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(info["cluster"]["cluster_state"], "fail");
+}
+
+// == Slot Migration Tests ==
+
+/// Hash tags whose slots are served by the first node of a two-node
+/// `["0-8191", "8192-16383"]` cluster.
+fn tags_on_first_node(count: usize) -> Vec<String> {
+    (0..)
+        .map(|i| format!("tag{}", i))
+        .filter(|tag| key_slot(tag) < 8192)
+        .take(count)
+        .collect()
+}
+
+async fn migrations(client: &reqwest::Client, node: &Node) -> Value {
+    client
+        .get(format!("http://{}/admin/cluster/migrations", node.addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_migrate_slots_under_traffic() {
+    let nodes = start_cluster(&["0-8191", "8192-16383"]).await;
+    let client = reqwest::Client::new();
+    let tags = tags_on_first_node(2);
+
+    for tag in &tags {
+        for i in 0..100 {
+            let ttl = if i % 2 == 0 { 3600 } else { 60 };
+            let response = client
+                .put(format!("http://{}/set", nodes[0].addr))
+                .json(&json!({"key": format!("{{{}}}:{}", tag, i), "value": i.to_string(), "ttl": ttl}))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+
+    // Reads must see the last write wherever the key lives at the time
+    let writer = {
+        let client = client.clone();
+        let addr = nodes[0].addr;
+        let key = format!("{{{}}}:live", tags[0]);
+        tokio::spawn(async move {
+            for i in 0..200 {
+                let response = client
+                    .put(format!("http://{}/set", addr))
+                    .json(&json!({"key": key, "value": i.to_string()}))
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body: Value = client
+                    .get(format!("http://{}/get/{}", addr, key))
+                    .send()
+                    .await
+                    .unwrap()
+                    .json()
+                    .await
+                    .unwrap();
+                assert_eq!(body["value"], i.to_string());
+            }
+        })
+    };
+
+    let slots: Vec<String> = tags.iter().map(|tag| key_slot(tag).to_string()).collect();
+    let response = client
+        .post(format!("http://{}/admin/cluster/migrate", nodes[0].addr))
+        .json(&json!({"target": "b", "slots": slots, "batch_size": 10}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let started: Value = response.json().await.unwrap();
+    assert_eq!(started["state"], "running");
+    assert_eq!(started["slots_total"], 2);
+
+    let mut status = Value::Null;
+    for _ in 0..100 {
+        status = migrations(&client, &nodes[0]).await["migrations"][0].clone();
+        if status["state"] != "running" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(status["state"], "completed", "{}", status);
+    assert_eq!(status["slots_done"], 2);
+    assert!(status["keys_migrated"].as_u64().unwrap() >= 200);
+    writer.await.unwrap();
+
+    // Every key now lives on the second node, with its TTL
+    for tag in &tags {
+        for i in 0..100 {
+            let key = format!("{{{}}}:{}", tag, i);
+            assert!(!stored_on(&nodes[0], &key).await, "{}", key);
+            let info = nodes[1].state.cache.read().await.inspect(&key).unwrap();
+            let ttl_ms = info.ttl_ms.unwrap();
+            assert_eq!(ttl_ms > 60_000, i % 2 == 0, "{}", key);
+        }
+        // Both nodes agree on the new owner
+        for node in &nodes {
+            let body: Value = client
+                .get(format!("http://{}/cluster/keyslot/{}", node.addr, tag))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(body["node"], "b");
+        }
+    }
+    let body: Value = client
+        .get(format!("http://{}/get/{{{}}}:live", nodes[0].addr, tags[0]))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["value"], "199");
+
+    let info: Value = client
+        .get(format!("http://{}/info?section=cluster", nodes[0].addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(info["cluster"]["cluster_slots_served"], 8190);
+    assert_eq!(info["cluster"]["cluster_slots_migrating"], 0);
+}
+
+#[tokio::test]
+async fn test_migrate_slots_over_tls() {
+    let dir = std::env::temp_dir().join(format!("mini_redis_cluster_tls_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let settings = TlsSettings {
+        cert_path: dir.join("node.pem"),
+        key_path: dir.join("node.key"),
+        client_ca_path: None,
+    };
+    std::fs::write(&settings.cert_path, cert.pem()).unwrap();
+    std::fs::write(&settings.key_path, key_pair.serialize_pem()).unwrap();
+
+    let mut listeners = Vec::new();
+    let mut table = Vec::new();
+    for (id, slots) in [("a", "0-8191"), ("b", "8192-16383")] {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        table.push(ClusterNode {
+            id: id.to_string(),
+            address: format!("localhost:{}", listener.local_addr().unwrap().port()),
+            slots: SlotRange::parse_list(slots).unwrap(),
+        });
+        listeners.push(listener);
+    }
+
+    // Both nodes serve only TLS, and verify each other against the CA
+    let mut states = Vec::new();
+    for (listener, node) in listeners.into_iter().zip(&table) {
+        let config = Config {
+            cluster_node_id: Some(node.id.clone()),
+            cluster_nodes: table.clone(),
+            tls_cert_path: Some(settings.cert_path.clone()),
+            tls_key_path: Some(settings.key_path.clone()),
+            cluster_tls_ca_path: Some(settings.cert_path.clone()),
+            ..Config::default()
+        };
+        config.validate().unwrap();
+        let state = AppState {
+            cluster_tls: Some(Arc::new(client_config(&settings.cert_path, None).unwrap())),
+            ..AppState::from_config(&config)
+        };
+        let tls = Arc::new(ReloadableTlsConfig::new(settings.clone()).unwrap());
+        tokio::spawn(serve_tls(
+            listener,
+            create_router(state.clone()),
+            tls,
+            state.metrics.clone(),
+            std::future::pending(),
+        ));
+        states.push(state);
+    }
+
+    let tag = &tags_on_first_node(1)[0];
+    for i in 0..10 {
+        states[0]
+            .cache
+            .write()
+            .await
+            .set(format!("{{{}}}:{}", tag, i), i.to_string(), None)
+            .unwrap();
+    }
+    let cluster = states[0].cluster.clone().unwrap();
+    start_migration(
+        cluster.clone(),
+        states[0].databases.clone(),
+        "b",
+        SlotRange::parse_list(&key_slot(tag).to_string()).unwrap(),
+        4,
+        None,
+        states[0].cluster_tls.clone(),
+    )
+    .unwrap();
+
+    let mut status = None;
+    for _ in 0..100 {
+        status = cluster.migrations().list().into_iter().next();
+        if status
+            .as_ref()
+            .is_some_and(|status| status.state != MigrationState::Running)
+        {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let status = status.unwrap();
+    assert_eq!(status.state, MigrationState::Completed, "{:?}", status);
+    assert_eq!(status.keys_migrated, 10);
+    for i in 0..10 {
+        let key = format!("{{{}}}:{}", tag, i);
+        assert!(states[1].cache.read().await.contains_key(&key), "{}", key);
+    }
+}
+
+#[tokio::test]
+async fn test_ask_redirects_during_migration() {
+    let nodes = start_cluster(&["0-8191", "8192-16383"]).await;
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap();
+    let tag = &tags_on_first_node(1)[0];
+    let slot = key_slot(tag);
+    let (kept, moved) = (format!("{{{}}}:kept", tag), format!("{{{}}}:moved", tag));
+
+    let response = client
+        .put(format!("http://{}/set", nodes[0].addr))
+        .json(&json!({"key": kept, "value": "here"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for (node, state, other) in [(&nodes[1], "importing", "a"), (&nodes[0], "migrating", "b")] {
+        let response = client
+            .post(format!("http://{}/admin/cluster/setslot", node.addr))
+            .json(&json!({"slots": [slot.to_string()], "state": state, "node": other}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Keys still on the source are served there
+    let response = client
+        .get(format!("http://{}/get/{}", nodes[0].addr, kept))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Others are asked for on the target
+    let response = client
+        .get(format!("http://{}/get/{}", nodes[0].addr, moved))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    let location =
+        reqwest::Url::parse(&format!("http://{}/get/{}?asking=1", nodes[1].addr, moved)).unwrap();
+    assert_eq!(response.headers()["location"], location.as_str());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], format!("ASK {} {}", slot, nodes[1].addr));
+
+    // The target only serves the slot to clients sent by ASK
+    let response = client
+        .get(format!("http://{}/get/{}", nodes[1].addr, moved))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], format!("MOVED {} {}", slot, nodes[0].addr));
+    let response = client.get(location).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let body: Value = client
+        .get(format!("http://{}/cluster/nodes", nodes[0].addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["migrating"][slot.to_string()], "b");
+
+    // Cancelling the migration serves the slot as before
+    let response = client
+        .post(format!("http://{}/admin/cluster/setslot", nodes[0].addr))
+        .json(&json!({"slots": [slot.to_string()], "state": "stable"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("http://{}/get/{}", nodes[0].addr, moved))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,50 @@
+//! Shared Test Fixtures
+//!
+//! Servers on local ports and polling helpers for the integration tests.
+//! Every test file compiles its own copy and uses only some of them.
+
+#![allow(dead_code)]
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use mini_redis::cache::CacheStore;
+use mini_redis::{api::create_router, AppState};
+use tokio::net::TcpListener;
+
+/// Serves the API of `state` on `listener` in the background.
+pub fn serve_on(listener: TcpListener, state: AppState) {
+    let app = create_router(state).into_make_service_with_connect_info::<SocketAddr>();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+}
+
+/// Serves the API of `state` on a free local port and returns its address.
+pub async fn serve(state: AppState) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    serve_on(listener, state);
+    addr
+}
+
+/// Starts a server with an empty store, returning its URL and state.
+pub async fn start_server() -> (String, AppState) {
+    let state = AppState::new(CacheStore::new(10_000, 300));
+    let addr = serve(state.clone()).await;
+    (format!("http://{}", addr), state)
+}
+
+/// Polls `check` until it returns true, failing after ten seconds.
+pub async fn eventually<F, Fut>(what: &str, mut check: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    for _ in 0..200 {
+        if check().await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("{} not reached in time", what);
+}
@@ -0,0 +1,273 @@
+//! Integration Tests for Origin Mode
+//!
+//! Serves a stand-in origin and a server in front of it on local ports, and
+//! checks that misses are fetched, cached as the origin's `Cache-Control`
+//! allows, coalesced into one upstream request per key, and never cached
+//! over a write made while they were fetched.
+
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use mini_redis::cache::MAX_VALUE_SIZE;
+use mini_redis::{AppState, Config};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+use common::serve;
+
+// == Helper Functions ==
+
+/// Answers `/items/:key` by key prefix after a short delay, counting the
+/// requests.
+async fn origin_item(
+    State(requests): State<Arc<AtomicUsize>>,
+    Path(key): Path<String>,
+) -> Response {
+    let request = requests.fetch_add(1, Ordering::SeqCst) + 1;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let value = format!("origin:{}", key);
+    if key.starts_with("swr") {
+        let value = format!("{}:{}", value, request);
+        let cache_control = "max-age=1, stale-while-revalidate=30";
+        ([(header::CACHE_CONTROL, cache_control)], value).into_response()
+    } else if key.starts_with("fresh") {
+        ([(header::CACHE_CONTROL, "public, max-age=60")], value).into_response()
+    } else if key.starts_with("nostore") {
+        ([(header::CACHE_CONTROL, "no-store")], value).into_response()
+    } else if key.starts_with("missing") {
+        StatusCode::NOT_FOUND.into_response()
+    } else if key.starts_with("broken") {
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    } else if key.starts_with("huge") {
+        "x".repeat(MAX_VALUE_SIZE + 1).into_response()
+    } else if key.starts_with("slow") {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        value.into_response()
+    } else {
+        value.into_response()
+    }
+}
+
+async fn start_origin() -> (SocketAddr, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let app = Router::new()
+        .route("/items/:key", get(origin_item))
+        .with_state(requests.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (addr, requests)
+}
+
+/// Starts a server fetching misses from `origin`.
+async fn start_server(origin: SocketAddr) -> (String, AppState) {
+    let config = Config {
+        origin_url: Some(format!("http://{}/items/{{key}}", origin)),
+        origin_timeout: 500,
+        ..Config::default()
+    };
+    let state = AppState::from_config(&config);
+    let addr = serve(state.clone()).await;
+    (format!("http://{}", addr), state)
+}
+
+async fn get_key(client: &reqwest::Client, url: &str, key: &str) -> (StatusCode, Value) {
+    let response = client
+        .get(format!("{}/get/{}", url, key))
+        .send()
+        .await
+        .unwrap();
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+    (status, response.json().await.unwrap())
+}
+
+async fn ttl_ms(state: &AppState, key: &str) -> Option<u64> {
+    state
+        .cache
+        .read()
+        .await
+        .entry(key)
+        .and_then(|entry| entry.ttl_remaining_ms())
+}
+
+// == Origin Tests ==
+
+#[tokio::test]
+async fn test_miss_is_fetched_and_cached() {
+    let (origin, requests) = start_origin().await;
+    let (url, state) = start_server(origin).await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = get_key(&client, &url, "fresh-1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"], "origin:fresh-1");
+
+    // Cached for the origin's max-age
+    let ttl = ttl_ms(&state, "fresh-1").await.unwrap();
+    assert!((55_000..=60_000).contains(&ttl), "{}", ttl);
+    let (status, body) = get_key(&client, &url, "fresh-1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"], "origin:fresh-1");
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // Without Cache-Control the database default TTL applies
+    let (status, _) = get_key(&client, &url, "plain").await;
+    assert_eq!(status, StatusCode::OK);
+    let ttl = ttl_ms(&state, "plain").await.unwrap();
+    assert!((295_000..=300_000).contains(&ttl), "{}", ttl);
+
+    // Keys are percent-encoded into the origin URL
+    let (status, body) = get_key(&client, &url, "fresh%20a%2Fb").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"], "origin:fresh a/b");
+}
+
+#[tokio::test]
+async fn test_uncacheable_values_are_not_stored() {
+    let (origin, requests) = start_origin().await;
+    let (url, state) = start_server(origin).await;
+    let client = reqwest::Client::new();
+
+    for _ in 0..2 {
+        let (status, body) = get_key(&client, &url, "nostore-1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["value"], "origin:nostore-1");
+    }
+    assert_eq!(ttl_ms(&state, "nostore-1").await, None);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_origin_errors() {
+    let (origin, _) = start_origin().await;
+    let (url, state) = start_server(origin).await;
+    let client = reqwest::Client::new();
+
+    let (status, _) = get_key(&client, &url, "missing-1").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, body) = get_key(&client, &url, "broken-1").await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(body["error"].as_str().unwrap().contains("500"), "{}", body);
+    let (status, body) = get_key(&client, &url, "slow-1").await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(
+        body["error"].as_str().unwrap().contains("within"),
+        "{}",
+        body
+    );
+    // Bodies too large to cache are not read to the end
+    let (status, _) = get_key(&client, &url, "huge-1").await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(state.cache.read().await.len(), 0);
+
+    // An origin that is down
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let down = listener.local_addr().unwrap();
+    drop(listener);
+    let (url, _) = start_server(down).await;
+    let (status, _) = get_key(&client, &url, "fresh-1").await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+}
+
+#[tokio::test]
+async fn test_concurrent_misses_are_coalesced() {
+    let (origin, requests) = start_origin().await;
+    let (url, state) = start_server(origin).await;
+    let client = reqwest::Client::new();
+
+    let mut gets = Vec::new();
+    for _ in 0..20 {
+        let client = client.clone();
+        let url = url.clone();
+        gets.push(tokio::spawn(async move {
+            get_key(&client, &url, "fresh-2").await
+        }));
+    }
+    for get in gets {
+        let (status, body) = get.await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["value"], "origin:fresh-2");
+    }
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    assert_eq!(state.cache.read().await.len(), 1);
+
+    // Misses on other keys are not held up by each other
+    let (a, b) = tokio::join!(
+        get_key(&client, &url, "nostore-a"),
+        get_key(&client, &url, "nostore-b")
+    );
+    assert_eq!((a.0, b.0), (StatusCode::OK, StatusCode::OK));
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_write_during_fetch_is_kept() {
+    let (origin, requests) = start_origin().await;
+    let (url, state) = start_server(origin).await;
+    let client = reqwest::Client::new();
+
+    let fetch = {
+        let (client, url) = (client.clone(), url.clone());
+        tokio::spawn(async move { get_key(&client, &url, "fresh-3").await })
+    };
+    while requests.load(Ordering::SeqCst) == 0 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    let response = client
+        .put(format!("{}/set", url))
+        .json(&json!({"key": "fresh-3", "value": "written"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The miss is answered with the origin's value, but the write stays
+    let (status, body) = fetch.await.unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"], "origin:fresh-3");
+    let (_, body) = get_key(&client, &url, "fresh-3").await;
+    assert_eq!(body["value"], "written");
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    assert!(ttl_ms(&state, "fresh-3").await.unwrap() > 60_000);
+}
+
+#[tokio::test]
+async fn test_stale_values_are_refreshed_in_the_background() {
+    let (origin, requests) = start_origin().await;
+    let (url, _) = start_server(origin).await;
+    let client = reqwest::Client::new();
+
+    let (_, body) = get_key(&client, &url, "swr-1").await;
+    assert_eq!(body["value"], "origin:swr-1:1");
+    assert_eq!(body["stale"], Value::Null);
+
+    // Expired but within stale-while-revalidate: served stale, and the
+    // server refreshes it instead of asking the client to
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let (status, body) = get_key(&client, &url, "swr-1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"], "origin:swr-1:1");
+    assert_eq!(body["stale"], true);
+    assert_eq!(body["refresh"], Value::Null);
+
+    for _ in 0..40 {
+        let (_, body) = get_key(&client, &url, "swr-1").await;
+        if body["value"] == "origin:swr-1:2" {
+            assert_eq!(body["stale"], Value::Null);
+            assert_eq!(requests.load(Ordering::SeqCst), 2);
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("stale value was not refreshed");
+}
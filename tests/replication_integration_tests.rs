@@ -0,0 +1,218 @@
+//! Integration Tests for Leader-Follower Replication
+//!
+//! Serves a leader and a follower on local ports and checks that writes to
+//! the leader reach the follower, both through the initial snapshot and the
+//! live command stream.
+
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use mini_redis::replication::spawn_follower_task;
+use mini_redis::tls::{client_config, serve_tls, ReloadableTlsConfig, TlsSettings};
+use mini_redis::{api::create_router, cache::CacheStore, AppState, Config};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+use common::{eventually, serve};
+
+// == Helper Functions ==
+
+/// Starts a follower of `leader`, returning its address and state.
+async fn start_follower(leader: SocketAddr) -> (SocketAddr, AppState) {
+    let config = Config {
+        replica_of: Some(leader.to_string()),
+        ..Config::default()
+    };
+    let state = AppState::from_config(&config);
+    spawn_follower_task(
+        state.follower.clone().unwrap(),
+        state.databases.clone(),
+        None,
+        None,
+    );
+    (serve(state.clone()).await, state)
+}
+
+async fn has_key(state: &AppState, key: &str) -> bool {
+    state.cache.write().await.get(key).is_ok()
+}
+
+async fn replication_info(client: &reqwest::Client, addr: SocketAddr) -> Value {
+    let info: Value = client
+        .get(format!("http://{}/info?section=replication", addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    info["replication"].clone()
+}
+
+// == Replication Tests ==
+
+#[tokio::test]
+async fn test_follower_replicates_leader() {
+    let leader_state = AppState::new(CacheStore::new(100, 300));
+    let leader = serve(leader_state).await;
+    let client = reqwest::Client::new();
+
+    // Written before the follower connects, so it arrives in the snapshot
+    let response = client
+        .put(format!("http://{}/set", leader))
+        .json(&json!({"key": "before", "value": "snapshot"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let (follower, follower_state) = start_follower(leader).await;
+    eventually("snapshot applied", || has_key(&follower_state, "before")).await;
+
+    // Live commands
+    client
+        .put(format!("http://{}/set", leader))
+        .json(&json!({"key": "after", "value": "stream"}))
+        .send()
+        .await
+        .unwrap();
+    client
+        .delete(format!("http://{}/del/before", leader))
+        .send()
+        .await
+        .unwrap();
+    eventually("live commands applied", || async {
+        has_key(&follower_state, "after").await && !has_key(&follower_state, "before").await
+    })
+    .await;
+
+    let response = client
+        .get(format!("http://{}/get/after", follower))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["value"], "stream");
+
+    // Followers are read-only
+    let response = client
+        .put(format!("http://{}/set", follower))
+        .json(&json!({"key": "local", "value": "write"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let info = replication_info(&client, follower).await;
+    assert_eq!(info["role"], "follower");
+    assert_eq!(info["leader_link_status"], "up");
+    let info = replication_info(&client, leader).await;
+    assert_eq!(info["role"], "leader");
+    assert_eq!(info["connected_followers"], 1);
+
+    // Flushes propagate
+    client
+        .post(format!("http://{}/admin/flush", leader))
+        .send()
+        .await
+        .unwrap();
+    eventually("flush replicated", || async {
+        follower_state.cache.read().await.is_empty()
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_sync_resumes_from_backlog() {
+    let leader_state = AppState::new(CacheStore::new(100, 300));
+    let leader = serve(leader_state).await;
+    let client = reqwest::Client::new();
+
+    // A first sync attaches the replication log to the stores
+    let (_, follower_state) = start_follower(leader).await;
+    client
+        .put(format!("http://{}/set", leader))
+        .json(&json!({"key": "key", "value": "value"}))
+        .send()
+        .await
+        .unwrap();
+    eventually("write replicated", || has_key(&follower_state, "key")).await;
+
+    let info = replication_info(&client, leader).await;
+    let replid = info["replid"].as_str().unwrap();
+    let mut response = client
+        .get(format!(
+            "http://{}/admin/replication/sync?replid={}&offset=0",
+            leader, replid
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let chunk = response.chunk().await.unwrap().unwrap();
+    let first = String::from_utf8_lossy(&chunk);
+    let first = first.lines().next().unwrap();
+    let message: Value = serde_json::from_str(first).unwrap();
+    assert_eq!(message["type"], "continue");
+    assert_eq!(message["offset"], 0);
+}
+
+#[tokio::test]
+async fn test_follower_replicates_tls_leader() {
+    let dir = std::env::temp_dir().join(format!("mini_redis_repl_tls_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let settings = TlsSettings {
+        cert_path: dir.join("leader.pem"),
+        key_path: dir.join("leader.key"),
+        client_ca_path: None,
+    };
+    std::fs::write(&settings.cert_path, cert.pem()).unwrap();
+    std::fs::write(&settings.key_path, key_pair.serialize_pem()).unwrap();
+
+    let leader_state = AppState::new(CacheStore::new(100, 300));
+    leader_state
+        .cache
+        .write()
+        .await
+        .set("before".to_string(), "snapshot".to_string(), None)
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let tls = Arc::new(ReloadableTlsConfig::new(settings.clone()).unwrap());
+    tokio::spawn(serve_tls(
+        listener,
+        create_router(leader_state.clone()),
+        tls,
+        leader_state.metrics.clone(),
+        std::future::pending(),
+    ));
+
+    // The leader's certificate is verified against the configured CA
+    let config = Config {
+        replica_of: Some(format!("localhost:{}", port)),
+        replica_tls_ca_path: Some(settings.cert_path.clone()),
+        ..Config::default()
+    };
+    let state = AppState::from_config(&config);
+    let tls = client_config(&settings.cert_path, None).unwrap();
+    spawn_follower_task(
+        state.follower.clone().unwrap(),
+        state.databases.clone(),
+        None,
+        Some(Arc::new(tls)),
+    );
+    eventually("snapshot applied", || has_key(&state, "before")).await;
+
+    leader_state
+        .cache
+        .write()
+        .await
+        .set("after".to_string(), "stream".to_string(), None)
+        .unwrap();
+    eventually("write replicated", || has_key(&state, "after")).await;
+}
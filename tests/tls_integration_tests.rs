@@ -0,0 +1,220 @@
+//! Integration Tests for TLS Termination
+//!
+//! Serves the router over TLS on a local port using certificates generated
+//! at test time, and talks to it with a rustls client.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use mini_redis::tls::{serve_tls, ReloadableTlsConfig, TlsSettings};
+use mini_redis::{api::create_router, cache::CacheStore, AppState};
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedKey, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+
+// == Helper Functions ==
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("mini_redis_tls_it_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes a new self-signed `localhost` certificate and key into `dir`,
+/// returning the settings pointing at them and the certificate.
+fn write_server_cert(dir: &std::path::Path) -> (TlsSettings, CertificateDer<'static>) {
+    let CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let settings = TlsSettings {
+        cert_path: dir.join("server.pem"),
+        key_path: dir.join("server.key"),
+        client_ca_path: None,
+    };
+    std::fs::write(&settings.cert_path, cert.pem()).unwrap();
+    std::fs::write(&settings.key_path, key_pair.serialize_pem()).unwrap();
+    (settings, cert.der().clone())
+}
+
+async fn start_tls_server(tls: Arc<ReloadableTlsConfig>) -> (SocketAddr, oneshot::Sender<()>) {
+    let state = AppState::new(CacheStore::new(100, 300));
+    let app = create_router(state.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop, stopped) = oneshot::channel::<()>();
+
+    tokio::spawn(serve_tls(listener, app, tls, state.metrics, async {
+        let _ = stopped.await;
+    }));
+    (addr, stop)
+}
+
+fn client_config(
+    trusted: &CertificateDer<'static>,
+    identity: Option<(CertificateDer<'static>, PrivateKeyDer<'static>)>,
+) -> Arc<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.add(trusted.clone()).unwrap();
+    let builder =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+    let mut config = match identity {
+        Some((cert, key)) => builder.with_client_auth_cert(vec![cert], key).unwrap(),
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Arc::new(config)
+}
+
+async fn connect(
+    config: Arc<ClientConfig>,
+    addr: SocketAddr,
+) -> std::io::Result<TlsStream<TcpStream>> {
+    let tcp = TcpStream::connect(addr).await?;
+    let name = ServerName::try_from("localhost").unwrap();
+    TlsConnector::from(config).connect(name, tcp).await
+}
+
+/// Sends GET /health on a kept-alive connection and returns the status line.
+async fn get_health(stream: &mut TlsStream<TcpStream>) -> std::io::Result<String> {
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nhost: localhost\r\n\r\n")
+        .await?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        response.extend_from_slice(&buf[..n]);
+
+        let text = String::from_utf8_lossy(&response).to_string();
+        let Some((head, body)) = text.split_once("\r\n\r\n") else {
+            continue;
+        };
+        let content_length = head
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("content-length")
+                    .then(|| value.trim().parse::<usize>().ok())?
+            })
+            .unwrap_or(0);
+        if body.len() >= content_length {
+            return Ok(head.lines().next().unwrap_or_default().to_string());
+        }
+    }
+}
+
+fn peer_certificate(stream: &TlsStream<TcpStream>) -> CertificateDer<'static> {
+    stream.get_ref().1.peer_certificates().unwrap()[0].clone()
+}
+
+// == TLS Tests ==
+
+#[tokio::test]
+async fn test_tls_serves_requests() {
+    let (settings, cert) = write_server_cert(&temp_dir("serve"));
+    let tls = Arc::new(ReloadableTlsConfig::new(settings).unwrap());
+    let (addr, _stop) = start_tls_server(tls).await;
+
+    let mut stream = connect(client_config(&cert, None), addr).await.unwrap();
+    let status = get_health(&mut stream).await.unwrap();
+
+    assert_eq!(status, "HTTP/1.1 200 OK");
+}
+
+#[tokio::test]
+async fn test_tls_rejects_plaintext() {
+    let (settings, _) = write_server_cert(&temp_dir("plaintext"));
+    let tls = Arc::new(ReloadableTlsConfig::new(settings).unwrap());
+    let (addr, _stop) = start_tls_server(tls).await;
+
+    let mut tcp = TcpStream::connect(addr).await.unwrap();
+    tcp.write_all(b"GET /health HTTP/1.1\r\nhost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = Vec::new();
+    let _ = tcp.read_to_end(&mut response).await;
+
+    assert!(!String::from_utf8_lossy(&response).contains("200 OK"));
+}
+
+#[tokio::test]
+async fn test_tls_reload_keeps_existing_connections() {
+    let dir = temp_dir("reload");
+    let (settings, old_cert) = write_server_cert(&dir);
+    let tls = Arc::new(ReloadableTlsConfig::new(settings).unwrap());
+    let (addr, _stop) = start_tls_server(tls.clone()).await;
+
+    let mut existing = connect(client_config(&old_cert, None), addr).await.unwrap();
+    assert_eq!(get_health(&mut existing).await.unwrap(), "HTTP/1.1 200 OK");
+
+    // Rotate the certificate on disk and reload, as SIGHUP would
+    let (_, new_cert) = write_server_cert(&dir);
+    tls.reload().unwrap();
+
+    // The connection made before the reload keeps working
+    assert_eq!(get_health(&mut existing).await.unwrap(), "HTTP/1.1 200 OK");
+    assert_eq!(peer_certificate(&existing), old_cert);
+
+    // New connections get the new certificate
+    let fresh = connect(client_config(&new_cert, None), addr).await.unwrap();
+    assert_eq!(peer_certificate(&fresh), new_cert);
+    assert!(connect(client_config(&old_cert, None), addr).await.is_err());
+}
+
+#[tokio::test]
+async fn test_mutual_tls_requires_client_certificate() {
+    let dir = temp_dir("mtls");
+    let (mut settings, server_cert) = write_server_cert(&dir);
+
+    // Client CA and a client certificate it issued
+    let ca_key = KeyPair::generate().unwrap();
+    let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+
+    let client_key = KeyPair::generate().unwrap();
+    let mut client_params = CertificateParams::new(vec!["client".to_string()]).unwrap();
+    client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let client_cert = client_params
+        .signed_by(&client_key, &ca_cert, &ca_key)
+        .unwrap();
+
+    let ca_path = dir.join("client-ca.pem");
+    std::fs::write(&ca_path, ca_cert.pem()).unwrap();
+    settings.client_ca_path = Some(ca_path);
+
+    let tls = Arc::new(ReloadableTlsConfig::new(settings).unwrap());
+    let (addr, _stop) = start_tls_server(tls).await;
+
+    // Without a client certificate the server aborts the handshake
+    let anonymous = match connect(client_config(&server_cert, None), addr).await {
+        Ok(mut stream) => get_health(&mut stream).await,
+        Err(e) => Err(e),
+    };
+    assert!(anonymous.is_err());
+
+    // With a certificate from the trusted CA the request succeeds
+    let identity = (
+        client_cert.der().clone(),
+        PrivatePkcs8KeyDer::from(client_key.serialize_der()).into(),
+    );
+    let mut stream = connect(client_config(&server_cert, Some(identity)), addr)
+        .await
+        .unwrap();
+    assert_eq!(get_health(&mut stream).await.unwrap(), "HTTP/1.1 200 OK");
+}
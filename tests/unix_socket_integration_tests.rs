@@ -0,0 +1,97 @@
+//! Integration Tests for the Unix Domain Socket Listener
+//!
+//! Serves the router on a Unix domain socket in a temporary directory and
+//! talks to it with raw HTTP/1.1 requests.
+
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use mini_redis::listener::{bind_unix, serve_unix};
+use mini_redis::{api::create_router, cache::CacheStore, AppState};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::sync::oneshot;
+
+// == Helper Functions ==
+
+fn socket_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "mini_redis_uds_it_{}_{}.sock",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Sends one request and returns the raw response.
+async fn request(path: &Path, request: &str) -> String {
+    let mut stream = UnixStream::connect(path).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+// == Unix Socket Tests ==
+
+#[tokio::test]
+async fn test_serves_api_over_unix_socket() {
+    let path = socket_path("serve");
+    let listener = bind_unix(&path, 0o600).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let state = AppState::new(CacheStore::new(100, 300));
+    let app = create_router(state.clone());
+    let (stop, stopped) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_unix(listener, app, state.metrics.clone(), async {
+        let _ = stopped.await;
+    }));
+
+    let body = r#"{"key":"local","value":"sidecar"}"#;
+    let response = request(
+        &path,
+        &format!(
+            "PUT /set HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ),
+    )
+    .await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+
+    let response = request(
+        &path,
+        "GET /get/local HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("sidecar"));
+
+    // The connection asking is the only one open
+    let response = request(
+        &path,
+        "GET /info?section=clients HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert!(
+        response.contains(r#""connected_clients":1"#),
+        "{}",
+        response
+    );
+    assert!(
+        response.contains(r#""total_connections_received":3"#),
+        "{}",
+        response
+    );
+
+    // Shutting down removes the socket file
+    stop.send(()).unwrap();
+    server.await.unwrap().unwrap();
+    assert!(!path.exists());
+}